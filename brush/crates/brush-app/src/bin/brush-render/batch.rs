@@ -0,0 +1,105 @@
+use anyhow::{Context, Result};
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+
+/// The PLY files directly in `dir`, in natural order so `epoch_10.ply` comes after
+/// `epoch_9.ply`.
+pub fn ply_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to list the directory {}", dir.display()))?;
+    let mut files = vec![];
+    for entry in entries {
+        let path = entry?.path();
+        let is_ply = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("ply"));
+        if is_ply && path.is_file() {
+            files.push(path);
+        }
+    }
+    anyhow::ensure!(!files.is_empty(), "No PLY files in {}", dir.display());
+    files.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+    Ok(files)
+}
+
+/// Compare strings with runs of digits compared by their numeric value.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        let (Some(ca), Some(cb)) = (a.chars().next(), b.chars().next()) else {
+            return a.len().cmp(&b.len());
+        };
+        if ca.is_ascii_digit() && cb.is_ascii_digit() {
+            let split = |s: &str| s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+            let (num_a, rest_a) = a.split_at(split(a));
+            let (num_b, rest_b) = b.split_at(split(b));
+            let (trimmed_a, trimmed_b) =
+                (num_a.trim_start_matches('0'), num_b.trim_start_matches('0'));
+            // Longer numbers are bigger, equally long ones compare like strings.
+            let order = trimmed_a
+                .len()
+                .cmp(&trimmed_b.len())
+                .then_with(|| trimmed_a.cmp(trimmed_b))
+                .then_with(|| num_a.len().cmp(&num_b.len()));
+            if order != Ordering::Equal {
+                return order;
+            }
+            (a, b) = (rest_a, rest_b);
+        } else {
+            if ca != cb {
+                return ca.cmp(&cb);
+            }
+            (a, b) = (&a[ca.len_utf8()..], &b[cb.len_utf8()..]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sorts_naturally() {
+        let mut names = vec![
+            "epoch_100.ply",
+            "epoch_20.ply",
+            "epoch_9.ply",
+            "epoch_0010.ply",
+            "a.ply",
+            "epoch_20b.ply",
+        ];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            [
+                "a.ply",
+                "epoch_9.ply",
+                "epoch_0010.ply",
+                "epoch_20.ply",
+                "epoch_20b.ply",
+                "epoch_100.ply"
+            ]
+        );
+        assert_eq!(natural_cmp("x2", "x02"), Ordering::Less);
+        assert_eq!(natural_cmp("x2", "x2"), Ordering::Equal);
+    }
+
+    #[test]
+    fn lists_ply_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().to_path_buf();
+        std::fs::create_dir_all(dir.join("nested.ply")).unwrap();
+        for name in ["s_10.ply", "s_2.PLY", "notes.txt"] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+        let files = ply_files(&dir).unwrap();
+        let names: Vec<_> = files
+            .iter()
+            .map(|f| f.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["s_2.PLY", "s_10.ply"]);
+
+        temp.close().unwrap();
+        assert!(ply_files(&dir).is_err());
+    }
+}
@@ -0,0 +1,96 @@
+use std::fmt;
+
+/// Summary of the render times of --benchmark, in milliseconds.
+#[derive(Debug, PartialEq)]
+pub struct TimingStats {
+    pub iterations: usize,
+    pub min: f64,
+    pub mean: f64,
+    pub median: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+impl TimingStats {
+    /// Percentiles use the nearest rank method. Returns `None` without samples.
+    pub fn new(samples: &[f64]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let n = sorted.len();
+        let percentile = |p: f64| {
+            let rank = (p / 100.0 * n as f64).ceil() as usize;
+            sorted[rank.clamp(1, n) - 1]
+        };
+        let median = if n % 2 == 0 {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+        } else {
+            sorted[n / 2]
+        };
+        Some(Self {
+            iterations: n,
+            min: sorted[0],
+            mean: sorted.iter().sum::<f64>() / n as f64,
+            median,
+            p95: percentile(95.0),
+            p99: percentile(99.0),
+        })
+    }
+
+    /// Rendering throughput at the median render time.
+    pub fn splats_per_second(&self, num_splats: u32) -> f64 {
+        num_splats as f64 / (self.median / 1000.0)
+    }
+}
+
+/// One `key=value` line per statistic, for CI scripts to parse.
+impl fmt::Display for TimingStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "iterations={}", self.iterations)?;
+        writeln!(f, "min_ms={:.3}", self.min)?;
+        writeln!(f, "mean_ms={:.3}", self.mean)?;
+        writeln!(f, "median_ms={:.3}", self.median)?;
+        writeln!(f, "p95_ms={:.3}", self.p95)?;
+        write!(f, "p99_ms={:.3}", self.p99)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_percentiles() {
+        // Shuffled 1..=100.
+        let samples: Vec<f64> = (0..100).map(|i| ((i * 37) % 100 + 1) as f64).collect();
+        let stats = TimingStats::new(&samples).unwrap();
+        assert_eq!(
+            stats,
+            TimingStats {
+                iterations: 100,
+                min: 1.0,
+                mean: 50.5,
+                median: 50.5,
+                p95: 95.0,
+                p99: 99.0,
+            }
+        );
+        assert_eq!(
+            stats.to_string(),
+            "iterations=100\nmin_ms=1.000\nmean_ms=50.500\nmedian_ms=50.500\np95_ms=95.000\np99_ms=99.000"
+        );
+    }
+
+    #[test]
+    fn handles_few_samples() {
+        assert_eq!(TimingStats::new(&[]), None);
+        let stats = TimingStats::new(&[2.0]).unwrap();
+        assert_eq!((stats.median, stats.p95, stats.p99), (2.0, 2.0, 2.0));
+        let stats = TimingStats::new(&[3.0, 1.0, 2.0]).unwrap();
+        assert_eq!((stats.mean, stats.median, stats.p99), (2.0, 2.0, 3.0));
+        assert_eq!(stats.min, 1.0);
+        assert_eq!(stats.splats_per_second(1000), 500_000.0);
+    }
+}
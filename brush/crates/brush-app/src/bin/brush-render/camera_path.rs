@@ -0,0 +1,491 @@
+use anyhow::{Context, Result};
+use brush_render::camera::{Camera, focal_to_fov, fov_to_focal, look_at_rotation};
+use glam::{Quat, UVec2, Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A single camera in a camera path file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    /// World space position as x y z.
+    pub position: [f32; 3],
+    /// Rotation quaternion as x y z w.
+    pub rotation: [f32; 4],
+    /// Horizontal field of view in degrees.
+    #[serde(alias = "fov_x")]
+    pub fov: f64,
+    /// Vertical field of view in degrees. Derived from the image aspect ratio when missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fov_y: Option<f64>,
+    /// Optional timestamp of this keyframe. Dynamic splats are rendered at this time unless
+    /// --time-range overrides it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<f32>,
+}
+
+/// A camera from a nerfstudio `camera_path.json`.
+#[derive(Deserialize)]
+struct NerfstudioCamera {
+    /// Row major 4x4 camera to world matrix, in OpenGL conventions.
+    camera_to_world: Vec<f32>,
+    /// Vertical field of view in degrees.
+    fov: f64,
+    /// Width / height of the image.
+    aspect: f64,
+}
+
+impl NerfstudioCamera {
+    fn to_keyframe(&self) -> Result<Keyframe> {
+        anyhow::ensure!(
+            self.camera_to_world.len() == 16,
+            "camera_to_world must have 16 elements"
+        );
+        let mut transform = glam::Mat4::from_cols_slice(&self.camera_to_world).transpose();
+        // Swap basis to match the Brush camera convention, like the nerfstudio dataset loader.
+        transform.y_axis *= -1.0;
+        transform.z_axis *= -1.0;
+        let (_, rotation, translation) = transform.to_scale_rotation_translation();
+
+        let fov_y = self.fov.to_radians();
+        let fov_x = 2.0 * (self.aspect * (fov_y * 0.5).tan()).atan();
+        Ok(Keyframe {
+            position: translation.into(),
+            rotation: rotation.to_array(),
+            fov: fov_x.to_degrees(),
+            fov_y: Some(self.fov),
+            time: None,
+        })
+    }
+}
+
+/// Camera path files are either a plain list of keyframes, an object with a `keyframes` list,
+/// or a nerfstudio `camera_path.json`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CameraPathFile {
+    List(Vec<Keyframe>),
+    Object { keyframes: Vec<Keyframe> },
+    Nerfstudio { camera_path: Vec<NerfstudioCamera> },
+}
+
+#[derive(Serialize)]
+struct CameraPathOut<'a> {
+    keyframes: &'a [Keyframe],
+}
+
+pub fn parse_camera_path(json: &str) -> Result<Vec<Keyframe>> {
+    let keyframes = match serde_json::from_str(json).context("Invalid camera path JSON")? {
+        CameraPathFile::List(keyframes) | CameraPathFile::Object { keyframes } => keyframes,
+        CameraPathFile::Nerfstudio { camera_path } => camera_path
+            .iter()
+            .map(NerfstudioCamera::to_keyframe)
+            .collect::<Result<_>>()?,
+    };
+    anyhow::ensure!(!keyframes.is_empty(), "Camera path has no keyframes");
+    Ok(keyframes)
+}
+
+pub fn camera_path_to_json(keyframes: &[Keyframe]) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&CameraPathOut { keyframes })?)
+}
+
+/// Keyframes for `num_frames` cameras evenly spaced on a circle around `center`, all looking
+/// at the center. The circle lies in the plane perpendicular to `up`, raised by `elevation`
+/// degrees.
+pub fn orbit(
+    center: Vec3,
+    radius: f32,
+    elevation: f32,
+    up: Vec3,
+    num_frames: u32,
+    fov: f64,
+) -> Result<Vec<Keyframe>> {
+    let up = up.normalize();
+    let (axis_a, axis_b) = up.any_orthonormal_pair();
+    let elevation = elevation.to_radians();
+
+    (0..num_frames)
+        .map(|i| {
+            let angle = std::f32::consts::TAU * i as f32 / num_frames as f32;
+            let dir = elevation.cos() * (angle.cos() * axis_a + angle.sin() * axis_b)
+                + elevation.sin() * up;
+            let eye = center + dir * radius;
+            Ok(Keyframe {
+                position: eye.into(),
+                rotation: look_at_rotation(eye, center, up)?.to_array(),
+                fov,
+                fov_y: None,
+                time: None,
+            })
+        })
+        .collect()
+}
+
+/// Range of orbit azimuths in degrees, parsed from `start:end:steps`. The end is exclusive,
+/// so `0:360:36` gives 36 frames 10° apart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AzimuthRange {
+    pub start: f32,
+    pub end: f32,
+    pub steps: u32,
+}
+
+impl std::str::FromStr for AzimuthRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').collect();
+        let [start, end, steps] = parts[..] else {
+            return Err(format!("Expected start:end:steps, got '{s}'"));
+        };
+        let angle = |v: &str| {
+            v.trim()
+                .parse::<f32>()
+                .map_err(|e| format!("Invalid angle '{v}': {e}"))
+        };
+        let steps = steps
+            .trim()
+            .parse::<u32>()
+            .map_err(|e| format!("Invalid step count '{steps}': {e}"))?;
+        if steps == 0 {
+            return Err("Step count must be at least 1".to_owned());
+        }
+        Ok(Self {
+            start: angle(start)?,
+            end: angle(end)?,
+            steps,
+        })
+    }
+}
+
+/// Keyframes for cameras at each azimuth of `azimuths` around `center`, see
+/// [`Camera::orbit`]. The orbit is rotated so `up` takes the place of +Y.
+pub fn orbit_azimuths(
+    center: Vec3,
+    radius: f32,
+    elevation: f32,
+    up: Vec3,
+    azimuths: AzimuthRange,
+    fov_x: f64,
+    fov_y: f64,
+) -> Vec<Keyframe> {
+    let to_up = Quat::from_rotation_arc(Vec3::Y, up.normalize());
+    (0..azimuths.steps)
+        .map(|i| {
+            let t = i as f32 / azimuths.steps as f32;
+            let azimuth = azimuths.start + (azimuths.end - azimuths.start) * t;
+            let camera = Camera::orbit(
+                Vec3::ZERO,
+                radius,
+                azimuth.to_radians(),
+                elevation.to_radians(),
+                fov_x,
+                fov_y,
+            );
+            Keyframe {
+                position: (center + to_up * camera.position).into(),
+                rotation: (to_up * camera.rotation).to_array(),
+                fov: fov_x.to_degrees(),
+                fov_y: Some(fov_y.to_degrees()),
+                time: None,
+            }
+        })
+        .collect()
+}
+
+/// Uniform Catmull-Rom spline through `p1` and `p2` at `t` in 0..1.
+fn catmull_rom(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: f32) -> Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+fn lerp_f64(a: f64, b: f64, t: f32) -> f64 {
+    a + (b - a) * t as f64
+}
+
+impl Keyframe {
+    /// Blend between `self` and `next` at `t`. The position follows a Catmull-Rom spline using
+    /// the neighbouring keyframes `prev` and `after`, the rotation is slerped and the
+    /// field of view & time are lerped.
+    fn interpolate(&self, next: &Self, prev: &Self, after: &Self, t: f32) -> Self {
+        let position = catmull_rom(
+            Vec3::from(prev.position),
+            Vec3::from(self.position),
+            Vec3::from(next.position),
+            Vec3::from(after.position),
+            t,
+        );
+        let rotation = Quat::from_array(self.rotation)
+            .normalize()
+            .slerp(Quat::from_array(next.rotation).normalize(), t);
+        let time = match (self.time, next.time) {
+            (Some(a), Some(b)) => Some(a + (b - a) * t),
+            _ => None,
+        };
+        let fov_y = match (self.fov_y, next.fov_y) {
+            (Some(a), Some(b)) => Some(lerp_f64(a, b, t)),
+            _ => None,
+        };
+        Self {
+            position: position.into(),
+            rotation: rotation.to_array(),
+            fov: lerp_f64(self.fov, next.fov, t),
+            fov_y,
+            time,
+        }
+    }
+
+    /// Camera for this keyframe. Without a vertical fov it is derived from the image aspect ratio.
+    pub fn to_camera(&self, img_size: UVec2, center_uv: Vec2) -> Camera {
+        let fov_x = self.fov.to_radians();
+        let fov_y = self.fov_y.map_or_else(
+            || focal_to_fov(fov_to_focal(fov_x, img_size.x), img_size.y),
+            f64::to_radians,
+        );
+        Camera::new(
+            Vec3::from(self.position),
+            Quat::from_array(self.rotation).normalize(),
+            fov_x,
+            fov_y,
+            center_uv,
+        )
+    }
+}
+
+/// Insert `frames_per_segment - 1` frames between each pair of keyframes, see
+/// [`Keyframe::interpolate`]. The keyframes themselves are always included.
+pub fn interpolate(keyframes: &[Keyframe], frames_per_segment: u32) -> Vec<Keyframe> {
+    let frames_per_segment = frames_per_segment.max(1);
+    let mut frames = vec![];
+    for i in 0..keyframes.len().saturating_sub(1) {
+        // Repeat the end points for the spline tangents at the start and end of the path.
+        let prev = &keyframes[i.saturating_sub(1)];
+        let after = &keyframes[(i + 2).min(keyframes.len() - 1)];
+        for step in 0..frames_per_segment {
+            let t = step as f32 / frames_per_segment as f32;
+            frames.push(keyframes[i].interpolate(&keyframes[i + 1], prev, after, t));
+        }
+    }
+    frames.extend(keyframes.last().cloned());
+    frames
+}
+
+/// Path for frame `index` of a sequence of `num_frames` frames.
+///
+/// A printf style `%d` or `%0Nd` token in `output` is replaced by the frame index, zero padded
+/// to N digits, and `%%` by a literal `%`. A `{}` is replaced by the index padded to 4 digits.
+/// A path without an extension is treated as a directory to write
+/// `frame_<index>.<default_ext>` into. Other paths are only accepted for a single frame, as
+/// every frame would overwrite the same file.
+pub fn frame_path(
+    output: &Path,
+    index: usize,
+    num_frames: usize,
+    default_ext: &str,
+) -> Result<PathBuf> {
+    let (path, has_token) = substitute_frame_index(&output.to_string_lossy(), index)?;
+    let output = PathBuf::from(path);
+    if has_token {
+        return Ok(output);
+    }
+    if output.extension().is_none() {
+        return Ok(output.join(format!("frame_{index:04}.{default_ext}")));
+    }
+    anyhow::ensure!(
+        num_frames <= 1,
+        "{} would be overwritten by each of the {num_frames} frames, add a frame number \
+         token like %04d to the file name",
+        output.display()
+    );
+    Ok(output)
+}
+
+/// Replace the frame number tokens of `pattern`, see [`frame_path`]. Also returns whether
+/// there were any.
+fn substitute_frame_index(pattern: &str, index: usize) -> Result<(String, bool)> {
+    let mut has_token = pattern.contains("{}");
+    let pattern = pattern.replace("{}", &format!("{index:04}"));
+
+    let mut path = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            path.push(c);
+            continue;
+        }
+        if chars.next_if_eq(&'%').is_some() {
+            path.push('%');
+            continue;
+        }
+        let mut width = String::new();
+        while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+            width.push(digit);
+        }
+        anyhow::ensure!(
+            chars.next() == Some('d') && (width.is_empty() || width.starts_with('0')),
+            "Unsupported frame number token in {pattern}, use %d or %0Nd (eg. %04d)"
+        );
+        let width: usize = if width.is_empty() { 0 } else { width.parse()? };
+        path.push_str(&format!("{index:0width$}"));
+        has_token = true;
+    }
+    Ok((path, has_token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_interpolate() {
+        let json = r#"{ "keyframes": [
+            { "position": [0, 0, 0], "rotation": [0, 0, 0, 1], "fov": 40, "time": 0 },
+            { "position": [2, 0, 0], "rotation": [0, 0, 0, 1], "fov": 60, "time": 1 }
+        ] }"#;
+        let keyframes = parse_camera_path(json).unwrap();
+        assert_eq!(keyframes.len(), 2);
+
+        let frames = interpolate(&keyframes, 2);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[1].position, [1.0, 0.0, 0.0]);
+        assert_eq!(frames[1].fov, 50.0);
+        assert_eq!(frames[1].time, Some(0.5));
+        assert_eq!(frames[2], keyframes[1]);
+
+        assert!(parse_camera_path("[]").is_err());
+    }
+
+    #[test]
+    fn spline_passes_through_keyframes() {
+        let keyframes: Vec<_> = [[0.0, 0.0, 0.0], [1.0, 1.0, 0.0], [2.0, 0.0, 0.0]]
+            .into_iter()
+            .map(|position| Keyframe {
+                position,
+                rotation: [0.0, 0.0, 0.0, 1.0],
+                fov: 60.0,
+                fov_y: None,
+                time: None,
+            })
+            .collect();
+        let frames = interpolate(&keyframes, 4);
+        assert_eq!(frames.len(), 9);
+        assert_eq!(frames[0], keyframes[0]);
+        assert_eq!(frames[4].position, keyframes[1].position);
+        assert_eq!(frames[8], keyframes[2]);
+        // The curve is smooth, not piecewise linear.
+        assert!(frames[3].position[1] > 0.75);
+    }
+
+    #[test]
+    fn parse_nerfstudio_path() {
+        let json = r#"{
+            "render_height": 480,
+            "render_width": 640,
+            "camera_path": [{
+                "camera_to_world": [1, 0, 0, 1, 0, 1, 0, 2, 0, 0, 1, 3, 0, 0, 0, 1],
+                "fov": 50,
+                "aspect": 1.0
+            }]
+        }"#;
+        let keyframes = parse_camera_path(json).unwrap();
+        assert_eq!(keyframes.len(), 1);
+        let keyframe = &keyframes[0];
+        assert_eq!(keyframe.position, [1.0, 2.0, 3.0]);
+        assert!((keyframe.fov - 50.0).abs() < 1e-6);
+        assert_eq!(keyframe.fov_y, Some(50.0));
+
+        // OpenGL cameras look down -Z with +Y up, Brush cameras down +Z with +Y down.
+        let rotation = Quat::from_array(keyframe.rotation);
+        assert!((rotation * Vec3::Z).distance(-Vec3::Z) < 1e-5);
+        assert!((rotation * Vec3::Y).distance(-Vec3::Y) < 1e-5);
+    }
+
+    #[test]
+    fn orbit_looks_at_center() {
+        let center = Vec3::new(1.0, 2.0, 3.0);
+        let frames = orbit(center, 5.0, 30.0, Vec3::Y, 8, 50.0).unwrap();
+        assert_eq!(frames.len(), 8);
+
+        for frame in &frames {
+            let eye = Vec3::from(frame.position);
+            assert!((eye.distance(center) - 5.0).abs() < 1e-4);
+            assert!((eye.y - center.y - 2.5).abs() < 1e-4);
+
+            let forward = Quat::from_array(frame.rotation) * Vec3::Z;
+            assert!(forward.dot((center - eye).normalize()) > 0.9999);
+        }
+
+        // Written paths can be read back.
+        let json = camera_path_to_json(&frames).unwrap();
+        assert_eq!(parse_camera_path(&json).unwrap(), frames);
+    }
+
+    #[test]
+    fn orbit_azimuth_range() {
+        let range: AzimuthRange = "0:360:4".parse().unwrap();
+        assert_eq!(
+            range,
+            AzimuthRange {
+                start: 0.0,
+                end: 360.0,
+                steps: 4
+            }
+        );
+        assert!("0:360".parse::<AzimuthRange>().is_err());
+        assert!("0:360:0".parse::<AzimuthRange>().is_err());
+
+        // With +Z up the orbit circles around the Z axis.
+        let center = Vec3::new(1.0, 2.0, 3.0);
+        let frames = orbit_azimuths(center, 2.0, 0.0, Vec3::Z, range, 0.8, 0.6);
+        assert_eq!(frames.len(), 4);
+        for frame in &frames {
+            let eye = Vec3::from(frame.position);
+            assert!((eye.distance(center) - 2.0).abs() < 1e-4);
+            assert!((eye.z - center.z).abs() < 1e-4);
+            let forward = Quat::from_array(frame.rotation) * Vec3::Z;
+            assert!(forward.dot((center - eye).normalize()) > 0.9999);
+        }
+    }
+
+    #[test]
+    fn sequence_frame_paths() {
+        assert_eq!(
+            frame_path(Path::new("out/frame_{}.png"), 7, 10, "png").unwrap(),
+            PathBuf::from("out/frame_0007.png")
+        );
+        assert_eq!(
+            frame_path(Path::new("out"), 3, 10, "png").unwrap(),
+            PathBuf::from("out/frame_0003.png")
+        );
+        // Plain file names only work for a single frame.
+        assert_eq!(
+            frame_path(Path::new("out/render.png"), 0, 1, "png").unwrap(),
+            PathBuf::from("out/render.png")
+        );
+        assert!(frame_path(Path::new("out/render.png"), 12, 20, "png").is_err());
+    }
+
+    #[test]
+    fn printf_frame_patterns() {
+        let path = |pattern: &str, index| frame_path(Path::new(pattern), index, 200, "png");
+        assert_eq!(
+            path("frames/img_%04d.png", 12).unwrap(),
+            PathBuf::from("frames/img_0012.png")
+        );
+        assert_eq!(
+            path("frames/img_%d.png", 123).unwrap(),
+            PathBuf::from("frames/img_123.png")
+        );
+        assert_eq!(
+            path("100%%_%02d.png", 7).unwrap(),
+            PathBuf::from("100%_07.png")
+        );
+        assert!(path("img_%4d.png", 1).is_err());
+        assert!(path("img_%s.png", 1).is_err());
+        assert!(path("img_%.png", 1).is_err());
+    }
+}
@@ -0,0 +1,169 @@
+use anyhow::{Context, Result};
+use brush_render::camera::Camera;
+use glam::UVec2;
+use std::path::{Path, PathBuf};
+
+/// A single registered image of a COLMAP reconstruction.
+pub struct ColmapView {
+    pub camera: Camera,
+    /// Resolution of the original image.
+    pub img_size: UVec2,
+}
+
+/// Build the camera for a COLMAP image.
+///
+/// COLMAP stores the world to camera transform, while Brush cameras store their pose in
+/// world space. Both look down +Z with +Y pointing down in the image, so the pose is just
+/// the inverse transform.
+pub fn view_from_colmap(cam: &colmap_reader::Camera, image: &colmap_reader::Image) -> ColmapView {
+    let img_size = UVec2::new(cam.width as u32, cam.height as u32);
+    let (focal_x, focal_y) = cam.focal();
+
+    let world_to_cam = glam::Affine3A::from_rotation_translation(image.quat, image.tvec);
+    let (_, rotation, position) = world_to_cam.inverse().to_scale_rotation_translation();
+
+    ColmapView {
+        camera: Camera::from_intrinsics(
+            position,
+            rotation,
+            glam::dvec2(focal_x, focal_y),
+            cam.principal_point(),
+            img_size,
+        ),
+        img_size,
+    }
+}
+
+fn find_file(dir: &Path, stem: &str) -> Result<(PathBuf, bool)> {
+    let bin = dir.join(format!("{stem}.bin"));
+    if bin.exists() {
+        return Ok((bin, true));
+    }
+    let txt = dir.join(format!("{stem}.txt"));
+    if txt.exists() {
+        return Ok((txt, false));
+    }
+    anyhow::bail!("No {stem}.bin or {stem}.txt found in {}", dir.display())
+}
+
+async fn open(path: &Path) -> Result<tokio::io::BufReader<tokio::fs::File>> {
+    let file = tokio::fs::File::open(path)
+        .await
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    Ok(tokio::io::BufReader::new(file))
+}
+
+/// Load the view of the image called `image_name` from a COLMAP sparse model directory
+/// (eg. `sparse/0`). The name can be either the full name stored by COLMAP or just its
+/// file name.
+pub async fn load_view(dir: &Path, image_name: &str) -> Result<ColmapView> {
+    let (cam_path, cam_binary) = find_file(dir, "cameras")?;
+    let (img_path, img_binary) = find_file(dir, "images")?;
+
+    let cameras = colmap_reader::read_cameras(open(&cam_path).await?, cam_binary)
+        .await
+        .with_context(|| format!("Failed to parse {}", cam_path.display()))?;
+    let images = colmap_reader::read_images(open(&img_path).await?, img_binary, false)
+        .await
+        .with_context(|| format!("Failed to parse {}", img_path.display()))?;
+
+    let image = images
+        .iter()
+        .find(|img| img.name == image_name)
+        .or_else(|| {
+            images.iter().find(|img| {
+                Path::new(&img.name)
+                    .file_name()
+                    .is_some_and(|n| n == image_name)
+            })
+        })
+        .with_context(|| format!("No image named '{image_name}' in {}", img_path.display()))?;
+
+    let cam = cameras
+        .iter()
+        .find(|cam| cam.id == image.camera_id)
+        .with_context(|| {
+            format!(
+                "Image '{}' references camera ID {} which doesn't exist",
+                image.name, image.camera_id
+            )
+        })?;
+
+    Ok(view_from_colmap(cam, image))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::{Quat, Vec3, uvec2, vec2, vec3};
+
+    fn pinhole() -> colmap_reader::Camera {
+        colmap_reader::Camera {
+            id: 1,
+            model: colmap_reader::CameraModel::Pinhole,
+            width: 640,
+            height: 480,
+            params: vec![500.0, 520.0, 320.0, 200.0],
+        }
+    }
+
+    #[test]
+    fn converts_colmap_pose_and_intrinsics() {
+        // Camera rotated 90° around Y, COLMAP translation t = -R * position.
+        let quat = Quat::from_rotation_y(std::f32::consts::FRAC_PI_2);
+        let tvec = vec3(1.0, 2.0, 3.0);
+        let image = colmap_reader::Image {
+            id: 1,
+            tvec,
+            quat,
+            camera_id: 1,
+            name: "frame.jpg".to_owned(),
+            points: None,
+        };
+        let view = view_from_colmap(&pinhole(), &image);
+        let cam = &view.camera;
+
+        assert_eq!(view.img_size, uvec2(640, 480));
+        // R^T = rotation of -90° around Y, so -R^T t = (3, -2, -1).
+        assert!(cam.position.abs_diff_eq(vec3(3.0, -2.0, -1.0), 1e-5));
+        assert!(cam.center_uv.abs_diff_eq(vec2(0.5, 200.0 / 480.0), 1e-6));
+        assert!(
+            cam.focal(view.img_size)
+                .abs_diff_eq(vec2(500.0, 520.0), 1e-3)
+        );
+
+        // A point in front of the COLMAP camera stays in front of the Brush camera.
+        let world = vec3(0.5, -0.25, 4.0);
+        let colmap_local = quat * world + tvec;
+        let brush_local = cam.world_to_local().transform_point3(world);
+        assert!(brush_local.abs_diff_eq(colmap_local, 1e-5));
+        assert!(
+            cam.world_to_local()
+                .transform_point3(cam.position)
+                .abs_diff_eq(Vec3::ZERO, 1e-5)
+        );
+    }
+
+    #[tokio::test]
+    async fn loads_text_model() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("cameras.txt"),
+            "# Camera list\n1 PINHOLE 640 480 500 520 320 200\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("images.txt"),
+            "# Image list\n\
+             1 1 0 0 0 0 0 0 1 a.jpg\n\n\
+             2 1 0 0 0 0 0 5 1 sub/b.jpg\n\n",
+        )
+        .unwrap();
+
+        let view = load_view(dir.path(), "b.jpg").await.unwrap();
+        assert_eq!(view.img_size, uvec2(640, 480));
+        assert!(view.camera.position.abs_diff_eq(vec3(0.0, 0.0, -5.0), 1e-6));
+
+        assert!(load_view(dir.path(), "missing.jpg").await.is_err());
+    }
+}
@@ -0,0 +1,159 @@
+use brush_render::{
+    color::{linear_to_srgb, srgb_to_linear},
+    tonemap::{apply_aces_tonemapping, apply_reinhard_tonemapping},
+};
+use burn::{
+    prelude::Backend,
+    tensor::{Tensor, s},
+};
+
+/// Color space of the rendered colors, see --color-space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorSpace {
+    /// The colors are already sRGB encoded, as for splats trained on sRGB images. They're
+    /// written unchanged
+    Srgb,
+    /// The colors are linear radiance. They're sRGB encoded for 8 and 16-bit outputs, and
+    /// stay linear in EXR and NumPy files
+    Linear,
+}
+
+/// Tone mapping of the linear colors before the output transfer function, see --tonemap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Tonemap {
+    /// Colors above 1 are clipped by the output format
+    None,
+    /// Compress the highlights with `L / (1 + L / white_point²)`
+    Reinhard,
+    /// The ACES filmic curve, which also adds some contrast
+    Aces,
+}
+
+/// Turns rendered colors into the values written to the output: --exposure, --tonemap and
+/// the sRGB transfer function, in that order. Runs on the GPU before the render is read back.
+#[derive(Debug, Clone, Copy)]
+pub struct OutputTransform {
+    pub color_space: ColorSpace,
+    /// Exposure in stops.
+    pub exposure: f32,
+    pub tonemap: Tonemap,
+    pub white_point: f32,
+    /// Whether the output stores sRGB encoded values. Otherwise it gets linear light.
+    pub encode: bool,
+}
+
+impl OutputTransform {
+    /// Transform the color channels of an `[H, W, 4]` RGBA render. Alpha is left alone.
+    pub fn apply<B: Backend>(self, img: Tensor<B, 3>) -> Tensor<B, 3> {
+        if self.color_space == ColorSpace::Srgb
+            && self.exposure == 0.0
+            && self.tonemap == Tonemap::None
+            && self.encode
+        {
+            return img;
+        }
+        // Exposure is a scale of the light, and tone mapping works on linear light, so both
+        // run before the transfer function.
+        let img = match self.color_space {
+            ColorSpace::Linear => img,
+            ColorSpace::Srgb => srgb_to_linear(img),
+        };
+        let img = if self.exposure == 0.0 {
+            img
+        } else {
+            let color = img.clone().slice(s![.., .., 0..3]) * self.exposure.exp2();
+            Tensor::cat(vec![color, img.slice(s![.., .., 3..])], 2)
+        };
+        let img = match self.tonemap {
+            Tonemap::None => img,
+            Tonemap::Reinhard => apply_reinhard_tonemapping(img, self.white_point),
+            Tonemap::Aces => apply_aces_tonemapping(img),
+        };
+        if self.encode {
+            linear_to_srgb(img)
+        } else {
+            img
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use brush_render::MainBackend;
+    use burn::backend::wgpu::WgpuDevice;
+
+    fn transform(rgba: [f32; 4], transform: OutputTransform) -> [f32; 4] {
+        let img = Tensor::<MainBackend, 1>::from_floats(rgba, &WgpuDevice::DefaultDevice)
+            .reshape([1, 1, 4]);
+        let values = transform.apply(img).into_data().into_vec::<f32>().unwrap();
+        std::array::from_fn(|i| values[i])
+    }
+
+    fn output(color_space: ColorSpace, exposure: f32, encode: bool) -> OutputTransform {
+        OutputTransform {
+            color_space,
+            exposure,
+            tonemap: Tonemap::None,
+            white_point: 1.0,
+            encode,
+        }
+    }
+
+    fn to_u8(v: f32) -> u8 {
+        (v.clamp(0.0, 1.0) * 255.0).round() as u8
+    }
+
+    #[test]
+    fn mid_gray_code_value() {
+        // 18% linear gray is sRGB code value 118.
+        let rgba = transform(
+            [0.18, 0.18, 0.18, 1.0],
+            output(ColorSpace::Linear, 0.0, true),
+        );
+        assert_eq!(rgba.map(to_u8), [118, 118, 118, 255]);
+
+        // EXR stays linear.
+        let rgba = transform(
+            [0.18, 0.18, 0.18, 1.0],
+            output(ColorSpace::Linear, 0.0, false),
+        );
+        assert_eq!(rgba, [0.18, 0.18, 0.18, 1.0]);
+    }
+
+    #[test]
+    fn exposure_in_stops() {
+        let rgba = transform(
+            [0.09, 0.18, 0.36, 0.5],
+            output(ColorSpace::Linear, 1.0, false),
+        );
+        for (v, expected) in rgba.iter().zip([0.18, 0.36, 0.72, 0.5]) {
+            assert!((v - expected).abs() < 1e-6);
+        }
+
+        // sRGB colors are unchanged without exposure, and doubled in linear light with one
+        // stop.
+        let gray = [0.5, 0.5, 0.5, 1.0];
+        assert_eq!(transform(gray, output(ColorSpace::Srgb, 0.0, true)), gray);
+        let rgba = transform(gray, output(ColorSpace::Srgb, 1.0, true));
+        let linear = ((0.5f32 + 0.055) / 1.055).powf(2.4);
+        let expected = 1.055 * (2.0 * linear).powf(1.0 / 2.4) - 0.055;
+        assert!((rgba[0] - expected).abs() < 1e-5);
+        assert_eq!(rgba[3], 1.0);
+    }
+
+    #[test]
+    fn tonemaps_linear_light() {
+        // --linear-output decodes sRGB colors, and Reinhard maps 1 to 0.5.
+        let rgba = transform(
+            [1.0, 0.0, 1.0, 0.25],
+            OutputTransform {
+                tonemap: Tonemap::Reinhard,
+                ..output(ColorSpace::Srgb, 0.0, false)
+            },
+        );
+        for (v, expected) in rgba.iter().zip([0.5, 0.0, 0.5, 0.25]) {
+            assert!((v - expected).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use brush_render::camera::Camera;
+use glam::{Mat3, Quat, Vec2, Vec3};
+
+/// File name suffixes of the faces, in the order of [`env_map_cameras`].
+pub const FACE_SUFFIXES: [&str; 6] = ["px", "nx", "py", "ny", "pz", "nz"];
+
+/// Column and row of each face in the horizontal cross layout:
+///
+/// ```text
+///       +Y
+///   -X  +Z  +X  -Z
+///       -Y
+/// ```
+const CROSS_CELLS: [(usize, usize); 6] = [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)];
+
+/// Forward and image down direction of each face in the OpenGL cubemap convention (also used
+/// by DDS and KTX), in the +X, -X, +Y, -Y, +Z, -Z order of the cubemap layers.
+const FACE_AXES: [(Vec3, Vec3); 6] = [
+    (Vec3::X, Vec3::NEG_Y),
+    (Vec3::NEG_X, Vec3::NEG_Y),
+    (Vec3::Y, Vec3::Z),
+    (Vec3::NEG_Y, Vec3::NEG_Z),
+    (Vec3::Z, Vec3::NEG_Y),
+    (Vec3::NEG_Z, Vec3::NEG_Y),
+];
+
+/// Cameras for the six world axis aligned 90° cubemap faces at `position`.
+///
+/// The cubemap convention is left-handed, so a camera can't produce the faces directly.
+/// These cameras see the faces mirrored, and their views have to be flipped horizontally
+/// with [`flip_horizontal`] to get the faces engines expect.
+pub fn env_map_cameras(position: Vec3) -> [Camera; 6] {
+    FACE_AXES.map(|(forward, down)| {
+        let right = down.cross(forward);
+        let rotation = Quat::from_mat3(&Mat3::from_cols(right, down, forward));
+        Camera::new(
+            position,
+            rotation,
+            std::f64::consts::FRAC_PI_2,
+            std::f64::consts::FRAC_PI_2,
+            Vec2::splat(0.5),
+        )
+    })
+}
+
+/// Mirror an image with `channels` interleaved channels left to right.
+pub fn flip_horizontal(data: &mut [f32], width: usize, channels: usize) {
+    for row in data.chunks_exact_mut(width * channels) {
+        for x in 0..width / 2 {
+            let mirror = width - 1 - x;
+            for c in 0..channels {
+                row.swap(x * channels + c, mirror * channels + c);
+            }
+        }
+    }
+}
+
+/// Arrange six square faces of `size` pixels into a 4x3 horizontal cross. The cells without a
+/// face are left at zero.
+pub fn cross_layout(faces: [&[f32]; 6], size: usize, channels: usize) -> Vec<f32> {
+    let width = size * 4;
+    let row = size * channels;
+    let mut out = vec![0.0; width * size * 3 * channels];
+    for (face, (col, cell_row)) in faces.into_iter().zip(CROSS_CELLS) {
+        for y in 0..size {
+            let start = ((cell_row * size + y) * width + col * size) * channels;
+            out[start..start + row].copy_from_slice(&face[y * row..(y + 1) * row]);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn faces_follow_cubemap_convention() {
+        for (camera, (forward, down)) in env_map_cameras(Vec3::ONE).iter().zip(FACE_AXES) {
+            assert!((camera.rotation * Vec3::Z).abs_diff_eq(forward, 1e-6));
+            assert!((camera.rotation * Vec3::Y).abs_diff_eq(down, 1e-6));
+            // After the horizontal flip the image right direction is mirrored, which makes
+            // the face left-handed like the cubemap convention.
+            let right = -(camera.rotation * Vec3::X);
+            assert!(right.cross(down).dot(forward) < 0.0);
+        }
+    }
+
+    #[test]
+    fn flips_and_arranges_faces() {
+        let mut data = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        flip_horizontal(&mut data, 3, 1);
+        assert_eq!(data, [3.0, 2.0, 1.0, 6.0, 5.0, 4.0]);
+
+        let faces: Vec<Vec<f32>> = (1..=6).map(|i| vec![i as f32]).collect();
+        let cross = cross_layout(std::array::from_fn(|i| faces[i].as_slice()), 1, 1);
+        #[rustfmt::skip]
+        assert_eq!(cross, [
+            0.0, 3.0, 0.0, 0.0,
+            2.0, 5.0, 1.0, 6.0,
+            0.0, 4.0, 0.0, 0.0,
+        ]);
+    }
+}
@@ -0,0 +1,71 @@
+use brush_render::camera::Camera;
+use glam::{Vec2, Vec3};
+
+/// Cameras sampling a thin lens of `aperture` radius around `camera`, focused at `focus`
+/// distance along the view direction. Averaging their renders gives depth of field.
+///
+/// The samples are spread evenly over the aperture disc on a golden angle spiral, so the
+/// result is deterministic. Each camera is moved within the lens plane, and its principal
+/// point is shifted so the plane at the focus distance projects to the same pixels for every
+/// sample. Unlike turning the cameras towards the focus point, this doesn't add any keystone
+/// distortion.
+pub fn aperture_cameras(camera: &Camera, aperture: f32, focus: f32, samples: u32) -> Vec<Camera> {
+    let golden_angle = std::f32::consts::PI * (3.0 - 5.0f32.sqrt());
+    // Image plane units per unit of lens offset, in normalized image coordinates.
+    let uv_per_offset = Vec2::new(
+        0.5 / (focus * (camera.fov_x as f32 * 0.5).tan()),
+        0.5 / (focus * (camera.fov_y as f32 * 0.5).tan()),
+    );
+    (0..samples)
+        .map(|i| {
+            let radius = aperture * ((i as f32 + 0.5) / samples as f32).sqrt();
+            let offset = Vec2::from_angle(i as f32 * golden_angle) * radius;
+            let mut sample = camera.clone();
+            sample.position += camera.rotation * Vec3::new(offset.x, offset.y, 0.0);
+            sample.center_uv += offset * uv_per_offset;
+            sample
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::{Quat, UVec2, Vec3Swizzles};
+
+    fn project(camera: &Camera, img_size: UVec2, point: Vec3) -> Vec2 {
+        let local = camera.world_to_local().transform_point3(point);
+        camera.focal(img_size) * local.xy() / local.z + camera.center(img_size)
+    }
+
+    #[test]
+    fn focus_plane_stays_fixed() {
+        let img_size = UVec2::new(640, 480);
+        let camera = Camera::new(
+            Vec3::new(1.0, 2.0, -3.0),
+            Quat::from_rotation_y(0.4) * Quat::from_rotation_x(-0.2),
+            1.0,
+            0.8,
+            Vec2::new(0.5, 0.45),
+        );
+        let cameras = aperture_cameras(&camera, 0.1, 4.0, 16);
+        assert_eq!(cameras.len(), 16);
+
+        let on_focus = camera
+            .local_to_world()
+            .transform_point3(Vec3::new(0.7, -0.3, 4.0));
+        let off_focus = camera
+            .local_to_world()
+            .transform_point3(Vec3::new(0.7, -0.3, 8.0));
+        let center = project(&camera, img_size, on_focus);
+        let mut max_blur: f32 = 0.0;
+        for sample in &cameras {
+            let offset = camera.world_to_local().transform_point3(sample.position);
+            assert!(offset.z.abs() < 1e-5 && offset.length() <= 0.1 + 1e-5);
+            assert!(project(sample, img_size, on_focus).distance(center) < 1e-2);
+            let blurred = project(sample, img_size, off_focus);
+            max_blur = max_blur.max(blurred.distance(project(&camera, img_size, off_focus)));
+        }
+        assert!(max_blur > 1.0);
+    }
+}
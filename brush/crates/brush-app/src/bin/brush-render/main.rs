@@ -0,0 +1,3345 @@
+#![recursion_limit = "256"]
+
+use anyhow::{Context, Result};
+use brush_render::{
+    MainBackend, RenderOptions, SplatForward,
+    bounding_box::{auto_camera_for_splats, splat_aabb},
+    camera::{
+        Camera, FisheyeModel, ProjectionMode, focal_to_fov, fov_from_focal_35mm, fov_to_focal,
+        look_at_rotation,
+    },
+    composite_over_background, env_map,
+    gaussian_splats::{SplatPrecision, SplatRenderMode, Splats},
+};
+use brush_serde::{DeserializeError, SplatMessage, load_splat_from_binary, load_splat_from_ply};
+use burn::{
+    backend::wgpu::{WgpuDevice, WgpuRuntime},
+    prelude::Backend,
+    tensor::{ElementConversion, Int, Tensor, TensorPrimitive, s},
+};
+use burn_cubecl::cubecl::Runtime;
+use clap::{Parser, ValueEnum};
+use glam::{EulerRot, Quat, UVec2, Vec2, Vec3, uvec2};
+use std::{
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+mod batch;
+mod benchmark;
+mod camera_path;
+mod colmap;
+mod color;
+mod cubemap;
+mod dof;
+mod meta;
+mod metrics;
+mod output;
+mod panorama;
+mod stats;
+mod transforms;
+mod watch;
+
+#[derive(Clone, Parser)]
+#[command(
+    author,
+    version,
+    about = "Render a PLY splat file to an image using Brush"
+)]
+struct Args {
+    /// Input PLY files, or `-` to read one from stdin. Files ending in `.splat` are read as
+    /// the compact antimatter15 format. Multiple files are merged into one scene, with the
+    /// spherical harmonics padded to the highest degree. A single directory renders each of
+    /// its PLY files separately into --out-dir, named after the files
+    #[arg(
+        value_name = "PLY_PATH",
+        required_unless_present_any = ["extra_inputs", "list_adapters"],
+        num_args = 1..
+    )]
+    input: Vec<PathBuf>,
+    /// More input PLY files to merge into the scene, like the positional inputs
+    #[arg(long = "input", value_name = "PLY_PATH", num_args = 1..)]
+    extra_inputs: Vec<PathBuf>,
+    /// Place an input in the scene with a translation, rotation quaternion and uniform scale,
+    /// as "tx ty tz qx qy qz qw s". Given once per input, in the order of the inputs
+    #[arg(long, value_name = "TRANSFORM", allow_hyphen_values = true)]
+    transform: Vec<InputTransform>,
+    /// Output image path. An .exr file stores the unclamped float RGBA values, a .pfm file
+    /// the float RGB values.
+    /// When rendering a sequence this is a directory, or a filename pattern where a printf
+    /// style `%04d` (or `{}`) is replaced by the frame index, eg. `frames/img_%04d.png`.
+    /// Use `-` to write a single image to stdout, which needs --output-format. Progress
+    /// messages are then printed to stderr
+    #[arg(
+        short,
+        long,
+        value_name = "IMAGE_PATH",
+        required_unless_present_any = ["out_dir", "print_aabb", "info", "list_adapters", "no_output"]
+    )]
+    output: Option<PathBuf>,
+    /// Directory to write the frames of a sequence into, as frame_0000.png, frame_0001.png, ...,
+    /// or the renders of a directory of PLY files
+    #[arg(long, value_name = "DIR", conflicts_with = "output")]
+    out_dir: Option<PathBuf>,
+    /// Format of the output image [default: picked from the output file extension].
+    /// EXR output also stores the depth map as a `depth.Z` channel when --depth is set
+    #[arg(long, value_enum, alias = "format")]
+    output_format: Option<output::ImageFormat>,
+    /// Write a JSON file with the inputs and their SHA-256, the splat count, render mode,
+    /// camera, image size, background, timings of each stage and the brush-render version.
+    /// Its `camera_args` reproduce the camera when passed back to brush-render
+    #[arg(long, value_name = "JSON_PATH")]
+    meta_out: Option<PathBuf>,
+    /// Compare the render against this reference image and print the PSNR and SSIM of the
+    /// RGB channels. The reference must have the same size as the output image
+    #[arg(
+        long,
+        value_name = "IMAGE_PATH",
+        conflicts_with_all = ["stereo", "panorama", "cubemap"]
+    )]
+    compare: Option<PathBuf>,
+    /// Render the view this many more times after writing it, waiting for the GPU after each
+    /// render, and print the min, mean, median, p95 and p99 render times in milliseconds and
+    /// the splats rendered per second as `key=value` lines. Loading isn't included
+    #[arg(
+        long,
+        alias = "bench",
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    benchmark: Option<u32>,
+    /// Untimed renders before the --benchmark renders, to compile the shaders and fill the
+    /// caches
+    #[arg(long, value_name = "N", default_value = "3", requires = "benchmark")]
+    benchmark_warmup: u32,
+    /// Only run the --benchmark renders, without writing any image
+    #[arg(
+        long,
+        requires = "benchmark",
+        conflicts_with_all = [
+            "output",
+            "out_dir",
+            "depth",
+            "alpha_output",
+            "normal_output",
+            "preview_out",
+            "compare",
+        ]
+    )]
+    no_output: bool,
+    /// Exit with an error when the PSNR against --compare is below this many dB
+    #[arg(long, value_name = "DB", requires = "compare")]
+    min_psnr: Option<f64>,
+    /// GPU to render on, as an index or part of the name from --list-adapters
+    /// [default: picked by wgpu]
+    #[arg(long, value_name = "INDEX_OR_NAME")]
+    adapter: Option<brush_process::AdapterPreference>,
+    /// List the available GPU adapters and exit
+    #[arg(long)]
+    list_adapters: bool,
+    /// Print timings of each stage (loading, rendering, read back, encoding) and render
+    /// statistics like the number of visible splats and intersections
+    #[arg(short, long)]
+    verbose: bool,
+    /// After rendering, print the number of loaded, rendered and visible splats, the tiles
+    /// with splats and the most splats in one tile, the peak GPU memory and the time of each
+    /// stage as `key=value` lines. Only supported for single renders
+    #[arg(long, conflicts_with_all = ["no_output", "print_aabb", "info", "watch"])]
+    print_stats: bool,
+    /// Print the --print-stats output as a single line of JSON instead
+    #[arg(long, requires = "print_stats")]
+    stats_json: bool,
+    /// Bits per channel of the output image. 16 bits is only supported for PNG, EXR, NumPy and
+    /// PFM files always store f32 values
+    #[arg(long, value_enum, default_value = "8")]
+    bit_depth: output::BitDepth,
+    /// Quality of lossy output formats (JPEG), from 1 to 100
+    #[arg(long, default_value = "90", value_parser = clap::value_parser!(u8).range(1..=100))]
+    quality: u8,
+    /// Color space of the rendered colors. Linear colors are sRGB encoded for all outputs
+    /// except EXR, which stays linear
+    #[arg(long, value_enum, default_value = "srgb")]
+    color_space: color::ColorSpace,
+    /// Exposure adjustment in stops, applied in linear light before the transfer function
+    #[arg(
+        long,
+        value_name = "STOPS",
+        default_value = "0",
+        allow_hyphen_values = true
+    )]
+    exposure: f32,
+    /// Tone map the linear colors after --exposure, to fit HDR renders into the range of
+    /// 8 and 16-bit outputs. Runs on the GPU
+    #[arg(long, value_enum, default_value = "none")]
+    tonemap: color::Tonemap,
+    /// Write linear light to every output format, skipping the implicit sRGB encoding.
+    /// Colors rendered in --color-space srgb are decoded, so eg. EXR files of splats trained
+    /// on sRGB images are saved in linear light
+    #[arg(long)]
+    linear_output: bool,
+    /// White point of --tonemap reinhard. 1 is the classic `L / (1 + L)`, larger values keep
+    /// more highlight contrast
+    #[arg(long, default_value = "1.0")]
+    white_point: f32,
+    /// Output width in pixels [default: 1920, or 4096 for panoramas]
+    #[arg(long)]
+    width: Option<u32>,
+    /// Output height in pixels [default: 1080, or 2048 for panoramas]
+    #[arg(long)]
+    height: Option<u32>,
+    /// Only render the X Y W H pixel rectangle of the full --width x --height frame. The
+    /// output is W x H pixels and matches that crop of the full render
+    #[arg(
+        long,
+        num_args = 4,
+        value_names = ["X", "Y", "W", "H"],
+        conflicts_with_all = ["panorama", "cubemap"]
+    )]
+    roi: Option<Vec<u32>>,
+    /// Camera projection. Defaults to orthographic when an orthographic view size is given,
+    /// and perspective otherwise
+    #[arg(long, value_enum, alias = "camera-model")]
+    mode: Option<Mode>,
+    /// Render with a fisheye camera, same as --mode fisheye
+    #[arg(long, conflicts_with_all = ["mode", "ortho_width", "ortho_height"])]
+    fisheye: bool,
+    /// Render with an orthographic camera, same as --mode orthographic. Needs --ortho-width
+    /// or --ortho-height
+    #[arg(long, conflicts_with_all = ["mode", "fisheye"])]
+    ortho: bool,
+    /// Field of view of the fisheye camera in degrees, up to 360 [default: 180]. The image
+    /// circle fits the shorter side of the image, pixels outside of it are transparent
+    #[arg(long, value_name = "DEGREES", conflicts_with = "fov_x")]
+    max_fov: Option<f32>,
+    /// How the angle of a ray from the optical axis of the fisheye maps to its distance from
+    /// the image center [default: equidistant]
+    #[arg(long, value_enum)]
+    fisheye_model: Option<FisheyeModel>,
+    /// Horizontal field of view in degrees [default: 60]. For fisheye cameras this is the
+    /// field of view of the image circle, same as --max-fov
+    #[arg(long)]
+    fov_x: Option<f64>,
+    /// Vertical field of view in degrees (defaults to match fov-x)
+    #[arg(long)]
+    fov_y: Option<f64>,
+    /// Horizontal focal length in pixels (overrides fov-x)
+    #[arg(long)]
+    focal_x: Option<f64>,
+    /// Vertical focal length in pixels (overrides fov-y)
+    #[arg(long)]
+    focal_y: Option<f64>,
+    /// Horizontal field of view as a 35mm equivalent focal length in millimeters, assuming
+    /// a 36mm wide sensor
+    #[arg(long, value_name = "MM", conflicts_with_all = ["fov_x", "focal_x"])]
+    focal_35mm: Option<f64>,
+    /// Render with an orthographic camera covering this many world units horizontally.
+    /// The fov and focal options are ignored in orthographic mode
+    #[arg(long)]
+    ortho_width: Option<f32>,
+    /// Orthographic view height in world units (defaults to match the aspect ratio of ortho-width)
+    #[arg(long)]
+    ortho_height: Option<f32>,
+    /// Camera center X in normalized [0..1] (0.5 is center)
+    #[arg(long, default_value = "0.5")]
+    center_x: f32,
+    /// Camera center Y in normalized [0..1] (0.5 is center)
+    #[arg(long, default_value = "0.5")]
+    center_y: f32,
+    /// Camera center X in pixels, eg. the cx of calibrated intrinsics
+    #[arg(long, value_name = "PIXELS", conflicts_with = "center_x")]
+    cx_px: Option<f32>,
+    /// Camera center Y in pixels, eg. the cy of calibrated intrinsics
+    #[arg(long, value_name = "PIXELS", conflicts_with = "center_y")]
+    cy_px: Option<f32>,
+    /// Camera position as x y z
+    #[arg(
+        long,
+        num_args = 3,
+        value_delimiter = ' ',
+        default_values_t = [0.0, 0.0, 0.0],
+        allow_hyphen_values = true
+    )]
+    cam_pos: Vec<f32>,
+    /// Camera rotation as quaternion x y z w
+    #[arg(
+        long,
+        num_args = 4,
+        value_delimiter = ' ',
+        default_values_t = [0.0, 0.0, 0.0, 1.0],
+        allow_hyphen_values = true
+    )]
+    cam_rot: Vec<f32>,
+    /// Camera rotation as roll pitch yaw Euler angles in degrees, instead of --cam-rot. They
+    /// use the ZYX convention of OpenCV and most SfM tools, R = Rz(yaw) * Ry(pitch) *
+    /// Rx(roll): roll around X, then pitch around Y, then yaw around Z, all around the fixed
+    /// world axes. Pass `--euler-order ypr` to read yaw pitch roll angles instead
+    #[arg(
+        long,
+        num_args = 3,
+        value_delimiter = ' ',
+        allow_hyphen_values = true,
+        conflicts_with_all = ["cam_rot", "look_at_target"]
+    )]
+    cam_euler: Option<Vec<f32>>,
+    /// Convention of the --cam-euler angles
+    #[arg(long, value_enum, default_value = "zyx", requires = "cam_euler")]
+    euler_order: EulerOrder,
+    /// Point the camera from --cam-pos at this x y z target instead of using --cam-rot
+    #[arg(
+        long,
+        visible_alias = "look-at",
+        num_args = 3,
+        value_delimiter = ' ',
+        allow_hyphen_values = true,
+        conflicts_with = "cam_rot"
+    )]
+    look_at_target: Option<Vec<f32>>,
+    /// World space up direction as x y z, used by --look-at-target and --orbit
+    #[arg(
+        long,
+        visible_alias = "up",
+        num_args = 3,
+        value_delimiter = ' ',
+        default_values_t = [0.0, 1.0, 0.0],
+        allow_hyphen_values = true
+    )]
+    world_up: Vec<f32>,
+    /// Place the camera to frame every splat, ignoring --cam-pos and --cam-rot: it looks
+    /// along +Z at the bounding sphere of the splat centers, far enough back for the sphere
+    /// to fit in the image at --fov-x
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "cam_euler", "look_at_target", "fov_y", "focal_y", "center_x", "center_y",
+            "cx_px", "cy_px", "camera_path", "orbit", "orbit_azimuths", "colmap_dir",
+            "transforms",
+        ]
+    )]
+    auto_camera: bool,
+    /// Render the view of a registered image from a COLMAP sparse model directory (eg.
+    /// `sparse/0`), using its pose, intrinsics and resolution. Needs --colmap-image-name
+    #[arg(
+        long,
+        value_name = "DIR",
+        requires = "colmap_image_name",
+        conflicts_with_all = [
+            "cam_pos", "cam_rot", "cam_euler", "look_at_target", "fov_x", "fov_y",
+            "focal_x", "focal_y", "focal_35mm", "center_x", "center_y", "cx_px", "cy_px",
+            "camera_path", "orbit", "orbit_azimuths",
+        ]
+    )]
+    colmap_dir: Option<PathBuf>,
+    /// Name of the COLMAP image to render, either as stored in images.bin/txt or just
+    /// its file name
+    #[arg(long, value_name = "NAME", requires = "colmap_dir")]
+    colmap_image_name: Option<String>,
+    /// Render the cameras of a nerfstudio transforms.json, using their pose, intrinsics and
+    /// resolution. Renders every frame as a sequence unless --frame-index is given.
+    /// Lens distortion is ignored
+    #[arg(
+        long,
+        value_name = "JSON_PATH",
+        conflicts_with_all = [
+            "cam_pos", "cam_rot", "cam_euler", "look_at_target", "fov_x", "fov_y",
+            "focal_x", "focal_y", "focal_35mm", "center_x", "center_y", "cx_px", "cy_px",
+            "camera_path", "orbit", "colmap_dir", "orbit_azimuths",
+        ]
+    )]
+    transforms: Option<PathBuf>,
+    /// Index of the frame of --transforms to render
+    #[arg(long, value_name = "N", requires = "transforms")]
+    frame_index: Option<usize>,
+    /// Background color as r g b in [0..1]
+    #[arg(
+        long,
+        conflicts_with = "background_image",
+        num_args = 3,
+        value_delimiter = ' ',
+        default_values_t = [0.0, 0.0, 0.0],
+        allow_hyphen_values = true
+    )]
+    background: Vec<f32>,
+    /// Composite the render over this image instead of a solid background. The image is
+    /// stretched over every view, eg. each eye of a stereo pair, and used as an environment
+    /// map for --panorama. Like --background, alpha stays the opacity of the splats
+    #[arg(long, value_name = "IMAGE_PATH")]
+    background_image: Option<PathBuf>,
+    /// Composite the render over a lat-long environment map, eg. an HDR .exr, sampled along
+    /// the view ray of every pixel. The map uses the layout of --panorama output
+    #[arg(long, value_name = "IMAGE_PATH", conflicts_with_all = ["background", "background_image"])]
+    env_map: Option<PathBuf>,
+    /// Render a 360° equirectangular panorama by rendering a cubemap around the camera and
+    /// resampling it. Unlike --mode equirectangular the splats themselves are projected
+    /// exactly, without distortion towards the poles
+    #[arg(long, conflicts_with_all = ["mode", "fisheye", "ortho", "stereo", "ortho_width", "ortho_height"])]
+    panorama: bool,
+    /// Render the six world axis aligned 90° views around --cam-pos as a cubemap, with the
+    /// faces oriented like OpenGL/DDS cubemaps so the output can be used as an environment
+    /// map. The camera rotation is ignored
+    #[arg(long, conflicts_with_all = ["panorama", "mode", "fisheye", "ortho", "stereo", "ortho_width", "ortho_height"])]
+    cubemap: bool,
+    /// How the faces of --cubemap are written
+    #[arg(long, value_enum, default_value = "cross", requires = "cubemap")]
+    cubemap_layout: CubemapLayout,
+    /// Size of the cubemap faces rendered for --panorama and --cubemap in pixels [default:
+    /// 1024 for cubemaps, for panoramas it matches the panorama resolution in the center of
+    /// each face]
+    #[arg(long)]
+    cube_face_size: Option<u32>,
+    /// Render a stereo pair for VR previews, with the eyes offset along the camera X axis.
+    /// The width and height are per eye
+    #[arg(long)]
+    stereo: bool,
+    /// Distance between the eyes of a stereo render in world units
+    #[arg(long, default_value = "0.064", requires = "stereo")]
+    ipd: f32,
+    /// Layout of the stereo images
+    #[arg(long, value_enum, default_value = "sbs", requires = "stereo")]
+    stereo_layout: StereoLayout,
+    /// Toe the stereo eyes in to converge at this distance in front of the camera, instead
+    /// of looking parallel
+    #[arg(long, value_name = "DIST", requires = "stereo")]
+    convergence_distance: Option<f32>,
+    /// Render with depth of field, through a lens with this aperture radius in world units.
+    /// Averages --dof-samples renders from points on the aperture
+    #[arg(
+        long,
+        value_name = "RADIUS",
+        requires = "dof_focus",
+        conflicts_with_all = ["panorama", "cubemap"]
+    )]
+    dof_aperture: Option<f32>,
+    /// Distance in front of the camera that's in focus with --dof-aperture
+    #[arg(long, value_name = "DIST", requires = "dof_aperture")]
+    dof_focus: Option<f32>,
+    /// Number of renders averaged for --dof-aperture. More samples give smoother blur
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "32",
+        requires = "dof_aperture",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    dof_samples: u32,
+    /// Write premultiplied alpha, where the color is already weighted by alpha, instead of
+    /// compositing the background into the color. Useful for compositing tools like Blender
+    /// or Nuke
+    #[arg(long, conflicts_with_all = ["background", "background_image", "env_map"])]
+    premultiplied_alpha: bool,
+    /// Render every camera of a JSON camera path instead of a single camera. The file holds a
+    /// list of keyframes with a position [x, y, z], rotation [x, y, z, w], fov (or fov_x and
+    /// fov_y) in degrees and an optional time, which animates dynamic splats. A nerfstudio
+    /// camera_path.json works as well
+    #[arg(long, value_name = "JSON_PATH", conflicts_with = "orbit")]
+    camera_path: Option<PathBuf>,
+    /// Render each camera of a JSON file as is, without interpolating between them. Uses the
+    /// same format as --camera-path. The splats are only loaded once for all cameras
+    #[arg(
+        long,
+        value_name = "JSON_PATH",
+        conflicts_with_all = ["camera_path", "orbit", "transforms", "colmap_dir"]
+    )]
+    cameras: Option<PathBuf>,
+    /// Number of frames rendered per segment between consecutive keyframes of the camera
+    /// path. Positions follow a Catmull-Rom spline and rotations are slerped
+    #[arg(long, default_value = "1", requires = "camera_path")]
+    frames_per_segment: u32,
+    /// Number of intermediate frames inserted between each pair of keyframes, an alternative
+    /// to --frames-per-segment
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "camera_path",
+        conflicts_with = "frames_per_segment"
+    )]
+    interpolate_frames: Option<u32>,
+    /// Render a turntable of this many frames orbiting --orbit-center, always looking at the center
+    #[arg(long, value_name = "N_FRAMES")]
+    orbit: Option<u32>,
+    /// Render a turntable at the azimuths start:end:steps in degrees around --orbit-center,
+    /// see --orbit-radius and --orbit-elevation. The end is exclusive, so 0:360:36 renders
+    /// 36 frames 10° apart
+    #[arg(
+        long,
+        value_name = "START:END:STEPS",
+        conflicts_with_all = ["orbit", "camera_path", "cameras"]
+    )]
+    orbit_azimuths: Option<camera_path::AzimuthRange>,
+    /// Distance of the orbiting camera to the orbit center
+    #[arg(long, default_value = "3")]
+    orbit_radius: f32,
+    /// Center of the orbit as x y z
+    #[arg(
+        long,
+        num_args = 3,
+        value_delimiter = ' ',
+        default_values_t = [0.0, 0.0, 0.0],
+        allow_hyphen_values = true
+    )]
+    orbit_center: Vec<f32>,
+    /// Elevation of the orbiting camera above the center in degrees
+    #[arg(long, default_value = "20", allow_hyphen_values = true)]
+    orbit_elevation: f32,
+    /// Write the cameras of the rendered sequence as a camera path JSON, which can be
+    /// edited and rendered again with --camera-path
+    #[arg(long, value_name = "JSON_PATH")]
+    write_path: Option<PathBuf>,
+    /// Only render splats whose center lies inside this axis aligned box, given as
+    /// min_x min_y min_z max_x max_y max_z
+    #[arg(
+        long,
+        num_args = 6,
+        value_delimiter = ' ',
+        allow_hyphen_values = true,
+        value_names = ["MIN_X", "MIN_Y", "MIN_Z", "MAX_X", "MAX_Y", "MAX_Z"]
+    )]
+    crop_box: Option<Vec<f32>>,
+    /// Subsample splats by taking every nth point
+    #[arg(long, conflicts_with = "subsample_random")]
+    subsample_points: Option<u32>,
+    /// Subsample splats by picking N of them at random, see --seed
+    #[arg(long, value_name = "N")]
+    subsample_random: Option<usize>,
+    /// Seed for --subsample-random, the same seed always picks the same splats
+    #[arg(long, default_value = "0")]
+    seed: u64,
+    /// Only use the spherical harmonics bands up to this degree. 0 renders view independent
+    /// base colors, which is faster for previews of large scenes
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(0..=4))]
+    max_sh_degree: Option<u32>,
+    /// Remove splats with an opacity below this threshold (0..1) before rendering. Applied
+    /// after the subsampling and crop options, and to the opacities at --time
+    #[arg(long, value_name = "T")]
+    min_opacity: Option<f32>,
+    /// Render dynamic (4D) splats at this time, normalized to 0..1 over the capture. Times
+    /// outside of that range clamp. Inputs without motion render as they are
+    #[arg(long, value_name = "T", allow_hyphen_values = true)]
+    time: Option<f32>,
+    /// Render dynamic splats at evenly spaced times from T0 to T1 as a frame sequence. Uses
+    /// the single camera and --frames, or spreads the times over the frames of
+    /// --camera-path, --orbit or --transforms so camera and time animate together
+    #[arg(
+        long,
+        num_args = 2,
+        value_delimiter = ' ',
+        allow_hyphen_values = true,
+        value_names = ["T0", "T1"],
+        conflicts_with_all = ["time", "auto_camera"]
+    )]
+    time_range: Option<Vec<f32>>,
+    /// Number of frames of a --time-range sequence from a single camera
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "time_range",
+        conflicts_with_all = ["camera_path", "cameras", "orbit", "orbit_azimuths", "transforms"],
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    frames: Option<u32>,
+    /// Distance of the near plane. Splats closer to the camera are not rendered
+    #[arg(long, value_name = "DISTANCE", default_value = "0.01")]
+    z_near: f32,
+    /// Also write a depth map (expected depth along the ray) to this path.
+    /// A .png is written as 16-bit grayscale, .raw/.f32 as raw little endian f32 values,
+    /// .npy as a NumPy array and .pfm as a grayscale Portable Float Map
+    #[arg(long, value_name = "PATH")]
+    depth: Option<PathBuf>,
+    /// Depth value written for pixels without any splat coverage
+    #[arg(long, default_value = "0")]
+    depth_far: f32,
+    /// Map the min/max depth of the scene to 0..1
+    #[arg(long)]
+    depth_normalize: bool,
+    /// Scale applied to depth before quantizing to a 16-bit PNG (1000 = millimeters).
+    /// Ignored when --depth-normalize is set, which always uses the full 16-bit range
+    #[arg(long, default_value = "1000")]
+    depth_scale: f32,
+    /// Also write the alpha matte (the accumulated opacity, independent of --background) to
+    /// this path as a grayscale image, using --bit-depth bits. A .npy or .pfm path gets the
+    /// float values
+    #[arg(long, value_name = "IMAGE_PATH")]
+    alpha_output: Option<PathBuf>,
+    /// Also write a normal map, blended from the splat orientations, to this path as an RGB
+    /// image. Normals n are encoded as 0.5 * n + 0.5, using --bit-depth bits. A .npy or .pfm
+    /// path gets the blended normals as floats
+    #[arg(long, value_name = "IMAGE_PATH")]
+    normal_output: Option<PathBuf>,
+    /// Space of the --normal-output normals
+    #[arg(long, value_enum, default_value = "world", requires = "normal_output")]
+    normal_space: NormalSpace,
+    /// Composite the image over a light and dark checkerboard, to judge the coverage of
+    /// transparent renders in regular image viewers. Needs the default transparent
+    /// background
+    #[arg(long, conflicts_with_all = ["background", "background_image", "env_map"])]
+    preview_checker: bool,
+    /// Size of the --preview-checker cells in output pixels
+    #[arg(
+        long,
+        value_name = "PIXELS",
+        default_value = "16",
+        value_parser = clap::value_parser!(u32).range(1..),
+        requires = "preview_checker"
+    )]
+    checker_size: u32,
+    /// Write the --preview-checker image to this path instead, and keep the alpha of the
+    /// image written to --output
+    #[arg(long, value_name = "IMAGE_PATH", requires = "preview_checker")]
+    preview_out: Option<PathBuf>,
+    /// Render mode, instead of the one stored in the PLY metadata. Normal and position modes
+    /// write their values as the RGB channels, unchanged by --color-space and --exposure, so
+    /// use a float format like .exr to keep negative values. Alpha only renders write the
+    /// alpha as a gray image
+    #[arg(long, value_enum)]
+    render_mode: Option<SplatRenderMode>,
+    /// Anti-alias the splats with the Mip-Splatting 2D filter instead of the dilation of
+    /// classic 3DGS. Reduces aliasing of thin structures, but scenes trained without it
+    /// render slightly dimmer. Mip render modes are always anti-aliased
+    #[arg(long)]
+    antialias: bool,
+    /// Render at N times the output resolution and box filter down to reduce aliasing
+    #[arg(long, value_name = "N", default_value = "1", value_parser = clap::value_parser!(u32).range(1..=16))]
+    supersample: u32,
+    /// Largest number of pixels rendered at once. Bigger (supersampled) images are rendered
+    /// in horizontal strips to limit GPU memory use
+    #[arg(long, value_name = "PIXELS", default_value = "16777216")]
+    max_render_pixels: u64,
+    /// Print the bounding box of the (cropped) splats and exit without rendering
+    #[arg(long)]
+    print_aabb: bool,
+    /// Number of standard deviations of each splat included in --print-aabb
+    #[arg(long, default_value = "3")]
+    aabb_sigma: f32,
+    /// Print the number of splats, SH degree, render mode, bounds of the means, opacity and
+    /// scale summaries and memory use of the (cropped) splats and exit without rendering
+    #[arg(long, conflicts_with = "print_aabb")]
+    info: bool,
+    /// Keep running and render again whenever an input file changes, eg. to follow the
+    /// snapshots of a training run. The output is overwritten, unless its name has a frame
+    /// number token like %04d, which is replaced by the number of the render
+    #[arg(long, conflicts_with = "no_output")]
+    watch: bool,
+    /// How often --watch checks the inputs, in milliseconds. Changes are only rendered once
+    /// the inputs stayed the same for a whole interval, so they're completely written
+    #[arg(long, value_name = "MS", default_value = "500", requires = "watch")]
+    watch_interval: u64,
+}
+
+/// One camera of a rendered sequence.
+struct SequenceFrame {
+    camera: Camera,
+    img_size: UVec2,
+    /// Time to evaluate dynamic splats at, see --time-range.
+    time: Option<f32>,
+    /// Printed with the progress after rendering the frame.
+    note: Option<String>,
+}
+
+/// Similarity transform placing one input PLY in the scene, see --transform.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct InputTransform {
+    translation: Vec3,
+    rotation: Quat,
+    scale: f32,
+}
+
+impl std::str::FromStr for InputTransform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split_whitespace()
+            .map(|v| {
+                v.parse::<f32>()
+                    .map_err(|e| format!("Invalid number '{v}': {e}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let [tx, ty, tz, qx, qy, qz, qw, scale] = values[..] else {
+            return Err(format!("Expected \"tx ty tz qx qy qz qw s\", got '{s}'"));
+        };
+        let rotation = Quat::from_xyzw(qx, qy, qz, qw);
+        if rotation.length_squared() < 1e-12 {
+            return Err("Rotation quaternion can't be zero".to_owned());
+        }
+        if scale <= 0.0 {
+            return Err("Scale must be positive".to_owned());
+        }
+        Ok(Self {
+            translation: Vec3::new(tx, ty, tz),
+            rotation: rotation.normalize(),
+            scale,
+        })
+    }
+}
+
+/// How the two eyes of a stereo render are written, see --stereo.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum StereoLayout {
+    /// One image with the left eye on the left half and the right eye on the right half
+    #[value(name = "sbs")]
+    SideBySide,
+    /// Separate images with `_L` and `_R` appended to the file names
+    Separate,
+}
+
+/// Space of the normals written by --normal-output.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum NormalSpace {
+    World,
+    /// +X right, +Y down and +Z forward, so surfaces facing the camera point towards -Z
+    Camera,
+}
+
+impl NormalSpace {
+    fn render_mode(self) -> SplatRenderMode {
+        match self {
+            Self::World => SplatRenderMode::NormalsWorldSpace,
+            Self::Camera => SplatRenderMode::NormalsCameraSpace,
+        }
+    }
+}
+
+/// How the angles of --cam-euler are applied. The resulting rotation is the camera to world
+/// rotation, for a camera looking down +Z with +X right and +Y down.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum EulerOrder {
+    /// Yaw pitch roll: R = Ry(yaw) * Rx(pitch) * Rz(roll), ie. yaw around Y first, then
+    /// pitch around the rotated X axis, then roll around the rotated Z axis
+    Ypr,
+    /// Roll pitch yaw in the ZYX convention of most SfM tools and OpenCV based pipelines:
+    /// R = Rz(yaw) * Ry(pitch) * Rx(roll)
+    Zyx,
+}
+
+/// How the faces of a cubemap are written, see --cubemap.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CubemapLayout {
+    /// One 4x3 image with the faces in a horizontal cross: +Y on top, -X +Z +X -Z in the
+    /// middle row and -Y at the bottom
+    Cross,
+    /// Six images with `_px`, `_nx`, `_py`, `_ny`, `_pz` and `_nz` appended to the file names
+    Separate,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Mode {
+    /// Pinhole camera using the fov/focal options
+    Perspective,
+    /// Parallel projection, see --ortho-width and --ortho-height
+    Orthographic,
+    /// 360° panorama in a latitude/longitude layout
+    Equirectangular,
+    /// Fisheye, see --max-fov and --fisheye-model
+    Fisheye,
+}
+
+impl Args {
+    /// All input PLY files, the positional ones first.
+    fn inputs(&self) -> impl Iterator<Item = &PathBuf> {
+        self.input.iter().chain(&self.extra_inputs)
+    }
+
+    /// The input directory when rendering each PLY file in it separately.
+    fn input_dir(&self) -> Option<&Path> {
+        match (self.input.as_slice(), self.extra_inputs.is_empty()) {
+            ([dir], true) if dir.is_dir() => Some(dir),
+            _ => None,
+        }
+    }
+
+    /// Whether the image is written to stdout instead of a file.
+    fn output_to_stdout(&self) -> bool {
+        self.output.as_deref() == Some(Path::new("-"))
+    }
+
+    /// Extension of sequence frames when the output path doesn't specify one.
+    fn default_extension(&self) -> &'static str {
+        self.output_format
+            .map_or("png", output::ImageFormat::extension)
+    }
+
+    /// Output path of frame `index` of a sequence of `num_frames` frames.
+    fn frame_output(&self, index: usize, num_frames: usize) -> Result<PathBuf> {
+        match (&self.out_dir, &self.output) {
+            (Some(dir), _) => {
+                Ok(dir.join(format!("frame_{index:04}.{}", self.default_extension())))
+            }
+            (None, Some(output)) => {
+                camera_path::frame_path(output, index, num_frames, self.default_extension())
+            }
+            (None, None) => unreachable!("Clap requires --output or --out-dir"),
+        }
+    }
+
+    /// Paths of the depth map, alpha matte, normal map and checkerboard preview of frame
+    /// `index` of a sequence.
+    fn frame_aux_outputs(&self, index: usize, num_frames: usize) -> Result<[Option<PathBuf>; 4]> {
+        let frame_path = |path: &Option<PathBuf>| {
+            path.as_ref()
+                .map(|p| camera_path::frame_path(p, index, num_frames, "png"))
+                .transpose()
+        };
+        Ok([
+            frame_path(&self.depth)?,
+            frame_path(&self.alpha_output)?,
+            frame_path(&self.normal_output)?,
+            frame_path(&self.preview_out)?,
+        ])
+    }
+
+    fn cube_face_size(&self) -> u32 {
+        self.cube_face_size.unwrap_or_else(|| {
+            if self.panorama {
+                (self.img_size().x as f32 / std::f32::consts::PI).ceil() as u32
+            } else {
+                1024
+            }
+        })
+    }
+
+    /// Principal point in normalized [0..1] coordinates.
+    fn center_uv(&self) -> Vec2 {
+        let img_size = self.img_size().as_vec2();
+        Vec2::new(
+            self.cx_px.map_or(self.center_x, |cx| cx / img_size.x),
+            self.cy_px.map_or(self.center_y, |cy| cy / img_size.y),
+        )
+    }
+
+    fn mode(&self) -> Mode {
+        let has_ortho_size = self.ortho_width.is_some() || self.ortho_height.is_some();
+        self.mode.unwrap_or(if has_ortho_size || self.ortho {
+            Mode::Orthographic
+        } else if self.fisheye {
+            Mode::Fisheye
+        } else {
+            Mode::Perspective
+        })
+    }
+
+    fn img_size(&self) -> UVec2 {
+        let default = if self.panorama || self.mode == Some(Mode::Equirectangular) {
+            uvec2(4096, 2048)
+        } else {
+            uvec2(1920, 1080)
+        };
+        uvec2(
+            self.width.unwrap_or(default.x),
+            self.height.unwrap_or(default.y),
+        )
+    }
+
+    /// The camera and image size rendering the --roi rectangle of the `img_size` view of
+    /// `camera`, or the view itself without --roi.
+    fn roi_view(&self, camera: Camera, img_size: UVec2) -> Result<(Camera, UVec2)> {
+        let Some(roi) = &self.roi else {
+            return Ok((camera, img_size));
+        };
+        let (offset, size) = (uvec2(roi[0], roi[1]), uvec2(roi[2], roi[3]));
+        anyhow::ensure!(
+            size.x > 0 && size.y > 0 && (offset + size).cmple(img_size).all(),
+            "--roi {} {} {} {} doesn't fit in the {}x{} image",
+            offset.x,
+            offset.y,
+            size.x,
+            size.y,
+            img_size.x,
+            img_size.y
+        );
+        let camera = camera
+            .sub_view(img_size, offset, size)
+            .context("--roi can't be used with equirectangular or fisheye cameras")?;
+        Ok((camera, size))
+    }
+}
+
+fn compute_fov(args: &Args) -> (f64, f64) {
+    let img_size = args.img_size();
+
+    let fov_x = if let Some(focal_x) = args.focal_x {
+        focal_to_fov(focal_x, img_size.x)
+    } else if let Some(focal_mm) = args.focal_35mm {
+        fov_from_focal_35mm(focal_mm)
+    } else {
+        args.fov_x
+            .filter(|_| args.mode() != Mode::Fisheye)
+            .unwrap_or(60.0)
+            .to_radians()
+    };
+
+    let fov_y = if let Some(focal_y) = args.focal_y {
+        focal_to_fov(focal_y, img_size.y)
+    } else if let Some(fov_y) = args.fov_y {
+        fov_y.to_radians()
+    } else {
+        let focal_x = fov_to_focal(fov_x, img_size.x);
+        focal_to_fov(focal_x, img_size.y)
+    };
+
+    (fov_x, fov_y)
+}
+
+fn compute_projection(args: &Args) -> Result<ProjectionMode> {
+    let img_size = args.img_size();
+    let aspect = img_size.y as f32 / img_size.x as f32;
+    let has_ortho_size = args.ortho_width.is_some() || args.ortho_height.is_some();
+
+    let mode = args.mode();
+
+    if has_ortho_size && mode != Mode::Orthographic {
+        anyhow::bail!("--ortho-width and --ortho-height only apply to orthographic rendering");
+    }
+    if (args.max_fov.is_some() || args.fisheye_model.is_some()) && mode != Mode::Fisheye {
+        anyhow::bail!("--max-fov and --fisheye-model only apply to fisheye rendering");
+    }
+
+    Ok(match mode {
+        Mode::Perspective => ProjectionMode::Perspective,
+        Mode::Equirectangular => ProjectionMode::Equirectangular,
+        Mode::Fisheye => {
+            let max_fov = args
+                .max_fov
+                .or(args.fov_x.map(|fov| fov as f32))
+                .unwrap_or(180.0);
+            anyhow::ensure!(
+                max_fov > 0.0 && max_fov <= 360.0,
+                "--max-fov must be between 0 and 360 degrees"
+            );
+            ProjectionMode::Fisheye {
+                max_fov_rad: max_fov.to_radians(),
+                model: args.fisheye_model.unwrap_or_default(),
+            }
+        }
+        Mode::Orthographic => match (args.ortho_width, args.ortho_height) {
+            (None, None) => {
+                anyhow::bail!("Orthographic rendering needs --ortho-width or --ortho-height")
+            }
+            (Some(width), height) => ProjectionMode::Orthographic {
+                width,
+                height: height.unwrap_or(width * aspect),
+            },
+            (None, Some(height)) => ProjectionMode::Orthographic {
+                width: height / aspect,
+                height,
+            },
+        },
+    })
+}
+
+/// `count` evenly spaced times from `start` to `end`, both included. A single time is `start`.
+fn time_steps(start: f32, end: f32, count: usize) -> Vec<f32> {
+    let steps = count.saturating_sub(1).max(1) as f32;
+    (0..count)
+        .map(|i| start + (end - start) * i as f32 / steps)
+        .collect()
+}
+
+/// The single camera described by the camera arguments.
+fn compute_camera(args: &Args) -> Result<Camera> {
+    let (fov_x, fov_y) = compute_fov(args);
+    let center_uv = args.center_uv();
+    let position = Vec3::from_slice(&args.cam_pos);
+
+    let rotation = if let Some(target) = &args.look_at_target {
+        look_at_rotation(
+            position,
+            Vec3::from_slice(target),
+            Vec3::from_slice(&args.world_up),
+        )?
+    } else if let Some(euler) = &args.cam_euler {
+        let [a, b, c] = [euler[0], euler[1], euler[2]].map(f32::to_radians);
+        match args.euler_order {
+            EulerOrder::Ypr => Quat::from_euler(EulerRot::YXZ, a, b, c),
+            // Given as roll pitch yaw.
+            EulerOrder::Zyx => Quat::from_euler(EulerRot::ZYX, c, b, a),
+        }
+    } else {
+        Quat::from_slice(&args.cam_rot)
+    };
+
+    Ok(Camera::new(position, rotation, fov_x, fov_y, center_uv))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let run_start = Instant::now();
+    let mut args = Args::parse();
+
+    if args.list_adapters {
+        for (i, adapter) in brush_process::list_adapters().iter().enumerate() {
+            println!("{}", brush_process::describe_adapter(i, adapter));
+        }
+        return Ok(());
+    }
+
+    let device =
+        brush_process::burn_init_setup_with(&args.adapter.clone().unwrap_or_default()).await?;
+    <MainBackend as Backend>::seed(&device, 42);
+
+    if args.print_aabb {
+        let (splats, _) = load_splats(&args, &device).await?;
+        let (min, max) = splat_aabb(
+            &splats.means.val(),
+            &splats.log_scales.val(),
+            args.aabb_sigma,
+        )
+        .await;
+        println!("AABB min: [{}, {}, {}]", min.x, min.y, min.z);
+        println!("AABB max: [{}, {}, {}]", max.x, max.y, max.z);
+        return Ok(());
+    }
+
+    if args.info {
+        let (splats, _) = load_splats(&args, &device).await?;
+        print!("{}", splats.statistics().await);
+        return Ok(());
+    }
+
+    let mut transform_views = if let Some(path) = &args.transforms {
+        let json = tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let (views, warnings) = transforms::parse_transforms(&json)?;
+        for warning in warnings {
+            warn(warning);
+        }
+        Some(views)
+    } else {
+        None
+    };
+
+    // Render dataset views at their original resolution unless overridden.
+    let dataset_view = if let (Some(dir), Some(name)) = (&args.colmap_dir, &args.colmap_image_name)
+    {
+        let view = colmap::load_view(dir, name).await?;
+        Some((view.camera, view.img_size))
+    } else if let (Some(views), Some(index)) = (&mut transform_views, args.frame_index) {
+        anyhow::ensure!(
+            index < views.len(),
+            "Frame index {index} is out of range, the transforms file has {} frames",
+            views.len()
+        );
+        let view = views.swap_remove(index);
+        transform_views = None;
+        Some((view.camera, view.img_size))
+    } else {
+        None
+    };
+    let dataset_camera = dataset_view.map(|(camera, img_size)| {
+        args.width.get_or_insert(img_size.x);
+        args.height.get_or_insert(img_size.y);
+        camera
+    });
+
+    if args.ortho_width.is_some_and(|w| w <= 0.0) || args.ortho_height.is_some_and(|h| h <= 0.0) {
+        anyhow::bail!("Orthographic view size must be positive");
+    }
+    if args.focal_35mm.is_some_and(|f| f <= 0.0) {
+        anyhow::bail!("--focal-35mm must be positive");
+    }
+    anyhow::ensure!(
+        args.white_point > 0.0 && args.white_point.is_finite(),
+        "--white-point must be positive"
+    );
+
+    let projection = compute_projection(&args)?;
+    if let (Some(aperture), Some(focus)) = (args.dof_aperture, args.dof_focus) {
+        anyhow::ensure!(
+            aperture >= 0.0 && focus > 0.0,
+            "--dof-aperture can't be negative and --dof-focus must be positive"
+        );
+        anyhow::ensure!(
+            projection == ProjectionMode::Perspective,
+            "Depth of field is only supported for perspective cameras"
+        );
+    }
+
+    // Check the depth format before doing any heavy lifting.
+    if let Some(depth_path) = &args.depth {
+        output::DepthFormat::from_path(depth_path)?;
+    }
+
+    if args.orbit_elevation.abs() >= 90.0 {
+        anyhow::bail!("Orbit elevation must be between -90 and 90 degrees");
+    }
+
+    let keyframes = if let Some(path) = args.camera_path.as_ref().or(args.cameras.as_ref()) {
+        let json = tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let keyframes = camera_path::parse_camera_path(&json)?;
+        if args.cameras.is_some() {
+            Some(keyframes)
+        } else {
+            let frames_per_segment = args
+                .interpolate_frames
+                .map_or(args.frames_per_segment, |n| n + 1);
+            Some(camera_path::interpolate(&keyframes, frames_per_segment))
+        }
+    } else if let Some(azimuths) = args.orbit_azimuths {
+        let (fov_x, fov_y) = compute_fov(&args);
+        Some(camera_path::orbit_azimuths(
+            Vec3::from_slice(&args.orbit_center),
+            args.orbit_radius,
+            args.orbit_elevation,
+            Vec3::from_slice(&args.world_up),
+            azimuths,
+            fov_x,
+            fov_y,
+        ))
+    } else if let Some(num_frames) = args.orbit {
+        let center = Vec3::new(
+            args.orbit_center[0],
+            args.orbit_center[1],
+            args.orbit_center[2],
+        );
+        let (fov_x, _) = compute_fov(&args);
+        Some(camera_path::orbit(
+            center,
+            args.orbit_radius,
+            args.orbit_elevation,
+            Vec3::from_slice(&args.world_up),
+            num_frames,
+            fov_x.to_degrees(),
+        )?)
+    } else {
+        None
+    };
+
+    let center_uv = args.center_uv();
+    let frames: Option<Vec<SequenceFrame>> = if let Some(keyframes) = &keyframes {
+        Some(
+            keyframes
+                .iter()
+                .map(|frame| SequenceFrame {
+                    camera: frame.to_camera(args.img_size(), center_uv),
+                    img_size: args.img_size(),
+                    time: frame.time,
+                    note: frame.time.map(|time| format!("t = {time}")),
+                })
+                .collect(),
+        )
+    } else {
+        transform_views.map(|views| {
+            views
+                .into_iter()
+                .map(|view| SequenceFrame {
+                    camera: view.camera,
+                    img_size: uvec2(
+                        args.width.unwrap_or(view.img_size.x),
+                        args.height.unwrap_or(view.img_size.y),
+                    ),
+                    time: None,
+                    note: Some(view.file_path),
+                })
+                .collect()
+        })
+    };
+
+    let frames = if let Some(range) = &args.time_range {
+        anyhow::ensure!(
+            range.iter().all(|t| t.is_finite()),
+            "--time-range must be finite numbers"
+        );
+        let mut frames = match frames {
+            Some(frames) => frames,
+            None => {
+                let num_frames = args.frames.context(
+                    "--time-range needs --frames, or a camera path or orbit to take the frames from",
+                )?;
+                let camera = compute_camera(&args)?;
+                (0..num_frames)
+                    .map(|_| SequenceFrame {
+                        camera: camera.clone(),
+                        img_size: args.img_size(),
+                        time: None,
+                        note: None,
+                    })
+                    .collect()
+            }
+        };
+        let times = time_steps(range[0], range[1], frames.len());
+        for (frame, time) in frames.iter_mut().zip(times) {
+            frame.time = Some(time);
+            frame.note = Some(match frame.note.take() {
+                Some(note) => format!("{note}, t = {time}"),
+                None => format!("t = {time}"),
+            });
+        }
+        Some(frames)
+    } else {
+        frames
+    };
+
+    let first_output = if let Some(frames) = &frames {
+        // Check the file name patterns before doing any heavy lifting.
+        args.frame_aux_outputs(0, frames.len())?;
+        Some(args.frame_output(0, frames.len())?)
+    } else if args.no_output || args.input_dir().is_some() {
+        None
+    } else if args.watch {
+        let output = args.output.as_deref().context("--watch needs --output")?;
+        Some(camera_path::frame_path(
+            output,
+            0,
+            1,
+            args.default_extension(),
+        )?)
+    } else {
+        Some(
+            args.output
+                .clone()
+                .context("--out-dir is only supported when rendering a sequence of cameras")?,
+        )
+    };
+    anyhow::ensure!(
+        frames.is_none() || args.meta_out.is_none(),
+        "--meta-out is only supported for single renders"
+    );
+    anyhow::ensure!(
+        frames.is_none() || !args.print_stats,
+        "--print-stats is only supported for single renders"
+    );
+    anyhow::ensure!(
+        frames.is_none() || args.compare.is_none(),
+        "--compare is only supported for single renders"
+    );
+    anyhow::ensure!(
+        frames.is_none() || args.benchmark.is_none(),
+        "--benchmark is only supported for single renders"
+    );
+    anyhow::ensure!(
+        frames.is_none() || !args.watch,
+        "--watch is only supported for single renders"
+    );
+    anyhow::ensure!(
+        !args.watch
+            || args
+                .inputs()
+                .all(|p| p != Path::new("-") && !args.output_to_stdout()),
+        "--watch needs input and output files, not stdin or stdout"
+    );
+    if args.output_to_stdout() {
+        anyhow::ensure!(
+            frames.is_none(),
+            "Sequences can't be written to stdout, use an output pattern or --out-dir"
+        );
+        anyhow::ensure!(
+            args.output_format.is_some(),
+            "Writing the image to stdout needs --output-format"
+        );
+        anyhow::ensure!(
+            !args.stereo || args.stereo_layout == StereoLayout::SideBySide,
+            "Separate stereo images can't be written to stdout"
+        );
+        anyhow::ensure!(
+            !args.cubemap || args.cubemap_layout == CubemapLayout::Cross,
+            "Separate cubemap faces can't be written to stdout"
+        );
+    }
+    if let Some(first_output) = &first_output {
+        let format = output::ImageFormat::resolve(first_output, args.output_format)?;
+        output::check_image_format(format, args.bit_depth)?;
+    }
+    if let Some(alpha_path) = &args.alpha_output {
+        let alpha_format = output::ImageFormat::from_path(alpha_path)?;
+        output::check_image_format(alpha_format, args.bit_depth)?;
+    }
+    if let Some(preview_path) = &args.preview_out {
+        let preview_format = output::ImageFormat::from_path(preview_path)?;
+        output::check_image_format(preview_format, args.bit_depth)?;
+    }
+    if let Some(normal_path) = &args.normal_output {
+        let normal_format = output::ImageFormat::from_path(normal_path)?;
+        output::check_image_format(normal_format, args.bit_depth)?;
+        anyhow::ensure!(
+            args.normal_space == NormalSpace::World || !(args.panorama || args.cubemap),
+            "Camera space normals of panoramas and cubemaps would change from face to face, \
+             use --normal-space world"
+        );
+    }
+
+    if let Some(path) = &args.write_path {
+        let keyframes = keyframes
+            .as_ref()
+            .context("--write-path needs --orbit, --orbit-azimuths, --camera-path or --cameras")?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, camera_path::camera_path_to_json(keyframes)?).await?;
+        status(
+            &args,
+            format_args!("Saved camera path to {}", path.display()),
+        );
+    }
+
+    // A panorama has the layout of an environment map, so an image stretched over it is
+    // sampled like one.
+    let env_map_path = args
+        .env_map
+        .as_ref()
+        .or(args.background_image.as_ref().filter(|_| args.panorama));
+    let background_image = if let Some(path) = env_map_path {
+        let image = image::open(path)
+            .with_context(|| format!("Failed to open environment map {}", path.display()))?
+            .into_rgb32f();
+        let (w, h) = image.dimensions();
+        let env_map = Tensor::<MainBackend, 1>::from_floats(image.as_raw().as_slice(), &device)
+            .reshape([h as usize, w as usize, 3]);
+        Some(BackgroundImage::EnvMap(env_map))
+    } else if let Some(path) = &args.background_image {
+        let image = image::open(path)
+            .with_context(|| format!("Failed to open background image {}", path.display()))?;
+        Some(BackgroundImage::Stretched(image))
+    } else {
+        None
+    };
+
+    if let Some(dir) = args.input_dir() {
+        anyhow::ensure!(
+            frames.is_none(),
+            "A directory of PLY files can only be rendered from a single camera"
+        );
+        anyhow::ensure!(
+            !args.auto_camera,
+            "--auto-camera can't be used with a directory of PLY files"
+        );
+        let camera = match dataset_camera {
+            Some(camera) => camera,
+            None => compute_camera(&args)?,
+        };
+        let camera = camera.with_projection(projection);
+        let (camera, img_size) = args.roi_view(camera, args.img_size())?;
+        let files = batch::ply_files(dir)?;
+        return render_batch(
+            &args,
+            &device,
+            &files,
+            &camera,
+            img_size,
+            background_image.as_ref(),
+        )
+        .await;
+    }
+
+    let (splats, inputs) = load_splats(&args, &device).await?;
+
+    if let Some(frames) = frames {
+        let sequence_start = Instant::now();
+        if let Some(range) = &args.time_range {
+            if splats.motion.is_none() {
+                warn(format_args!(
+                    "The inputs have no motion, every frame of --time-range looks the same"
+                ));
+            } else if range.iter().any(|t| !(0.0..=1.0).contains(t)) {
+                warn(format_args!("Clamping the times of --time-range to 0..1"));
+            }
+        }
+
+        for (i, frame) in frames.iter().enumerate() {
+            let frame_start = Instant::now();
+            let camera = frame.camera.clone().with_projection(projection);
+            let (camera, img_size) = args.roi_view(camera, frame.img_size)?;
+            let output = args.frame_output(i, frames.len())?;
+            let [depth, alpha, normals, preview] = args.frame_aux_outputs(i, frames.len())?;
+            // Evaluated on the device from the uploaded splats, nothing is read back.
+            let frame_splats = match frame.time {
+                Some(time) => splats.at_time(time),
+                None => splats.clone(),
+            };
+            render_camera(
+                &frame_splats,
+                &camera,
+                img_size,
+                &args,
+                &output,
+                depth.as_deref(),
+                alpha.as_deref(),
+                normals.as_deref(),
+                preview.as_deref(),
+                background_image.as_ref(),
+            )
+            .await?;
+
+            let ms = frame_start.elapsed().as_secs_f64() * 1000.0;
+            match &frame.note {
+                Some(note) => println!(
+                    "Rendered frame {}/{} in {ms:.1} ms ({note})",
+                    i + 1,
+                    frames.len()
+                ),
+                None => println!("Rendered frame {}/{} in {ms:.1} ms", i + 1, frames.len()),
+            }
+        }
+
+        let total = sequence_start.elapsed().as_secs_f64();
+        println!(
+            "Rendered {} frames in {total:.2} s ({:.1} ms per frame)",
+            frames.len(),
+            total * 1000.0 / frames.len().max(1) as f64
+        );
+    } else {
+        let camera = match dataset_camera {
+            Some(camera) => camera,
+            None if args.auto_camera => {
+                let (fov_x, _) = compute_fov(&args);
+                auto_camera_for_splats(&splats.means.val(), fov_x, args.img_size()).await
+            }
+            None => compute_camera(&args)?,
+        };
+        let camera = camera.with_projection(projection);
+        let (camera, img_size) = args.roi_view(camera, args.img_size())?;
+        let outputs = match &first_output {
+            Some(output) => {
+                render_camera(
+                    &splats,
+                    &camera,
+                    img_size,
+                    &args,
+                    output,
+                    args.depth.as_deref(),
+                    args.alpha_output.as_deref(),
+                    args.normal_output.as_deref(),
+                    args.preview_out.as_deref(),
+                    background_image.as_ref(),
+                )
+                .await?
+            }
+            None => vec![],
+        };
+        let psnr = outputs.first().and_then(|o| o.psnr);
+
+        if let Some(iterations) = args.benchmark {
+            let times = benchmark_renders(&splats, &camera, img_size, &args, iterations)?;
+            if let Some(stats) = benchmark::TimingStats::new(&times) {
+                status(&args, format_args!("{stats}"));
+                status(
+                    &args,
+                    format_args!(
+                        "splats_per_second={:.0}",
+                        stats.splats_per_second(splats.num_splats())
+                    ),
+                );
+            }
+        }
+
+        if args.print_stats {
+            let memory = WgpuRuntime::client(&device).memory_usage();
+            let summary = stats::RenderSummary::new(
+                &inputs,
+                splats.num_splats(),
+                &outputs,
+                &memory,
+                run_start.elapsed().as_secs_f64() * 1000.0,
+            );
+            if args.stats_json {
+                status(&args, format_args!("{}", serde_json::to_string(&summary)?));
+            } else {
+                status(&args, format_args!("{summary}"));
+            }
+        }
+
+        if let Some(path) = &args.meta_out {
+            let meta = meta::RenderMeta {
+                version: env!("CARGO_PKG_VERSION"),
+                inputs,
+                num_splats: splats.num_splats(),
+                render_mode: splats.render_mode,
+                antialiased: RenderOptions {
+                    antialias: args.antialias,
+                    ..Default::default()
+                }
+                .antialiased(splats.render_mode),
+                camera_args: meta::camera_args(&camera, img_size),
+                camera: camera.clone(),
+                img_size,
+                background: [args.background[0], args.background[1], args.background[2]],
+                outputs,
+            };
+            let json = serde_json::to_string_pretty(&meta)?;
+            tokio::fs::write(path, json)
+                .await
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            status(&args, format_args!("Saved metadata to {}", path.display()));
+        }
+
+        if let (Some(psnr), Some(min_psnr)) = (psnr, args.min_psnr) {
+            anyhow::ensure!(
+                psnr >= min_psnr,
+                "PSNR of {psnr:.2} dB is below --min-psnr {min_psnr} dB"
+            );
+        }
+
+        if args.watch {
+            let paths: Vec<PathBuf> = args.inputs().cloned().collect();
+            let output = args.output.as_deref().context("--watch needs --output")?;
+            let interval = std::time::Duration::from_millis(args.watch_interval);
+            let mut changes = watch::ChangeDetector::new(watch::Snapshot::of(&paths));
+            status(&args, format_args!("Watching the inputs for changes"));
+            for index in 1.. {
+                changes.wait(&paths, interval).await;
+                // The file might still be half written, so try again instead of giving up.
+                let splats = match load_splats(&args, &device).await {
+                    Ok((splats, _)) => splats,
+                    Err(err) => {
+                        warn(format_args!("Failed to load the changed inputs: {err:#}"));
+                        changes.retry();
+                        continue;
+                    }
+                };
+                let path = camera_path::frame_path(output, index, 1, args.default_extension())?;
+                let rendered = render_camera(
+                    &splats,
+                    &camera,
+                    img_size,
+                    &args,
+                    &path,
+                    args.depth.as_deref(),
+                    args.alpha_output.as_deref(),
+                    args.normal_output.as_deref(),
+                    args.preview_out.as_deref(),
+                    background_image.as_ref(),
+                )
+                .await;
+                if let Err(err) = rendered {
+                    warn(format_args!("Failed to render the changed inputs: {err:#}"));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Load and merge the input splats, applying the subsampling and crop options.
+async fn load_splats(
+    args: &Args,
+    device: &WgpuDevice,
+) -> Result<(Splats<MainBackend>, Vec<meta::InputMeta>)> {
+    anyhow::ensure!(
+        args.inputs().count() == 1 || args.subsample_random.is_none(),
+        "--subsample-random only supports a single input PLY"
+    );
+    anyhow::ensure!(
+        args.inputs().filter(|p| *p == Path::new("-")).count() <= 1,
+        "Stdin can only be used as one input"
+    );
+
+    let num_inputs = args.inputs().count();
+    anyhow::ensure!(
+        args.transform.is_empty() || args.transform.len() == num_inputs,
+        "Got {} --transform values for {num_inputs} inputs, give one per input",
+        args.transform.len()
+    );
+
+    let mut inputs = vec![];
+    let mut input_metas = vec![];
+    for (i, path) in args.inputs().enumerate() {
+        let (mut splats, input_meta) = load_input(args, path, device).await?;
+        input_metas.push(input_meta);
+        if let Some(transform) = args.transform.get(i) {
+            splats = splats.transform(transform.translation, transform.rotation, transform.scale);
+        }
+        inputs.push(splats);
+    }
+    let mut splats = if inputs.len() == 1 {
+        inputs.swap_remove(0)
+    } else {
+        let render_mode = inputs[0].render_mode;
+        if inputs.iter().any(|s| s.render_mode != render_mode) {
+            warn(format_args!(
+                "The input PLYs use different render modes, rendering all of them as {render_mode:?}"
+            ));
+            for input in &mut inputs {
+                input.render_mode = render_mode;
+            }
+        }
+        let total = inputs.len();
+        let merged = Splats::concat(&inputs).context("Failed to merge the input PLYs")?;
+        status(
+            args,
+            format_args!("Merged {total} inputs into {} splats", merged.num_splats()),
+        );
+        merged
+    };
+
+    if let Some(max_degree) = args.max_sh_degree {
+        splats = splats.with_max_sh_degree(max_degree);
+    }
+
+    if let Some(crop_box) = &args.crop_box {
+        let min = Vec3::from_slice(&crop_box[0..3]);
+        let max = Vec3::from_slice(&crop_box[3..6]);
+        anyhow::ensure!(
+            min.cmple(max).all(),
+            "Crop box minimum must not be larger than its maximum"
+        );
+        let total = splats.num_splats();
+        splats = splats.crop_aabb(min, max).await;
+        anyhow::ensure!(splats.num_splats() > 0, "No splats inside the crop box");
+        status(
+            args,
+            format_args!("Cropped to {} of {total} splats", splats.num_splats()),
+        );
+    }
+
+    if let Some(time) = args.time {
+        anyhow::ensure!(time.is_finite(), "--time must be a finite number");
+        if splats.motion.is_none() {
+            warn(format_args!(
+                "The inputs have no motion, ignoring --time and rendering them as they are"
+            ));
+        } else {
+            if !(0.0..=1.0).contains(&time) {
+                warn(format_args!("Clamping --time {time} to the range 0..1"));
+            }
+            splats = splats.at_time(time);
+            verbose(args, format_args!("Evaluated the splats at time {time}"));
+        }
+    }
+
+    if let Some(threshold) = args.min_opacity {
+        anyhow::ensure!(
+            (0.0..=1.0).contains(&threshold),
+            "Minimum opacity must be between 0 and 1"
+        );
+        let total = splats.num_splats();
+        splats = splats.prune_by_opacity(threshold).await;
+        anyhow::ensure!(
+            splats.num_splats() > 0,
+            "No splats with an opacity of at least {threshold}"
+        );
+        status(
+            args,
+            format_args!(
+                "Removed {} of {total} splats below opacity {threshold}",
+                total - splats.num_splats()
+            ),
+        );
+    }
+
+    // The splats are only rendered from here on, so they can be stored as f16 where the
+    // device supports it. Dynamic splats stay f32, as they're moved for every frame.
+    if splats.motion.is_none() {
+        splats = splats.with_precision(SplatPrecision::preferred::<MainBackend>(device));
+    }
+    verbose(
+        args,
+        format_args!(
+            "Rendering {} splats with SH degree {}, stored as {:?}",
+            splats.num_splats(),
+            splats.sh_degree(),
+            splats.precision()
+        ),
+    );
+    Ok((splats, input_metas))
+}
+
+/// Parse a PLY, or a `.splat` file when `binary` is set. `.splat` files are always loaded
+/// whole, --subsample-points only applies to PLYs.
+async fn parse_splats<T: tokio::io::AsyncRead + Send + Unpin>(
+    reader: T,
+    binary: bool,
+    subsample_points: Option<u32>,
+) -> Result<SplatMessage, DeserializeError> {
+    if binary {
+        load_splat_from_binary(reader).await
+    } else {
+        load_splat_from_ply(reader, subsample_points).await
+    }
+}
+
+/// Parse a single input PLY (or stdin for `-`) and upload it to the GPU.
+/// The file is hashed for --meta-out.
+async fn load_input(
+    args: &Args,
+    path: &Path,
+    device: &WgpuDevice,
+) -> Result<(Splats<MainBackend>, meta::InputMeta)> {
+    let parse_start = Instant::now();
+    let binary = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("splat"));
+    let mut sha256 = None;
+    let message = if path == Path::new("-") {
+        let mut data = Vec::new();
+        std::io::stdin()
+            .read_to_end(&mut data)
+            .context("Failed to read PLY from stdin")?;
+        if args.meta_out.is_some() {
+            sha256 = Some(meta::sha256_hex(&data));
+        }
+        parse_splats(std::io::Cursor::new(data), binary, args.subsample_points).await
+    } else if args.meta_out.is_some() {
+        // Read the whole file to hash it, instead of streaming it into the parser.
+        let data = tokio::fs::read(path)
+            .await
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        sha256 = Some(meta::sha256_hex(&data));
+        parse_splats(std::io::Cursor::new(data), binary, args.subsample_points).await
+    } else {
+        let file = tokio::fs::File::open(path)
+            .await
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        parse_splats(file, binary, args.subsample_points).await
+    }
+    .with_context(|| format!("Failed to parse splats from {}", path.display()))?;
+    let parse_ms = parse_start.elapsed().as_secs_f64() * 1000.0;
+    verbose(
+        args,
+        format_args!(
+            "Parsed {} splats from {} in {:.1} ms",
+            message.data.num_splats(),
+            path.display(),
+            parse_ms
+        ),
+    );
+
+    let render_mode = match args.render_mode {
+        Some(mode) => {
+            let missing = message.data.missing_for_render_mode(mode);
+            anyhow::ensure!(
+                missing.is_empty(),
+                "{} has no {} properties, which --render-mode {} needs",
+                path.display(),
+                missing.join(", "),
+                mode.to_possible_value().expect("Not skipped").get_name()
+            );
+            mode
+        }
+        None => message.meta.render_mode.unwrap_or(SplatRenderMode::Default),
+    };
+    let data = match args.subsample_random {
+        Some(count) => {
+            let subset = message.data.random_subset(count, args.seed);
+            status(
+                args,
+                format_args!(
+                    "Randomly picked {} of {} splats",
+                    subset.num_splats(),
+                    message.data.num_splats()
+                ),
+            );
+            subset
+        }
+        None => message.data,
+    };
+    let upload_start = Instant::now();
+    let splats = data.into_splats::<MainBackend>(device, render_mode);
+    let upload_ms = upload_start.elapsed().as_secs_f64() * 1000.0;
+    verbose(
+        args,
+        format_args!("Uploaded splats to the GPU in {upload_ms:.1} ms"),
+    );
+    let input_meta = meta::InputMeta {
+        path: path.to_path_buf(),
+        sha256,
+        num_splats: splats.num_splats(),
+        parse_ms,
+        upload_ms,
+    };
+    Ok((splats, input_meta))
+}
+
+/// Rendered image (and optionally the alpha weighted depth and normals) read back from the
+/// GPU.
+struct RenderedView {
+    rgba: Vec<f32>,
+    depth: Option<Vec<f32>>,
+    /// Blended normals, 3 channels per pixel.
+    normals: Option<Vec<f32>>,
+    /// Statistics of each render pass, one per strip.
+    stats: Vec<RenderStats>,
+}
+
+impl RenderedView {
+    /// Mirror the view left to right.
+    fn flip_horizontal(&mut self, width: usize) {
+        cubemap::flip_horizontal(&mut self.rgba, width, 4);
+        if let Some(depth) = &mut self.depth {
+            cubemap::flip_horizontal(depth, width, 1);
+        }
+        if let Some(normals) = &mut self.normals {
+            cubemap::flip_horizontal(normals, width, 3);
+        }
+    }
+
+    /// Arrange six cubemap faces of `size` pixels in a horizontal cross.
+    fn cube_cross(views: Vec<Self>, size: usize) -> Self {
+        let rgba =
+            cubemap::cross_layout(std::array::from_fn(|i| views[i].rgba.as_slice()), size, 4);
+        let depth = views
+            .iter()
+            .map(|v| v.depth.as_deref())
+            .collect::<Option<Vec<_>>>()
+            .map(|depths| cubemap::cross_layout(std::array::from_fn(|i| depths[i]), size, 1));
+        let normals = views
+            .iter()
+            .map(|v| v.normals.as_deref())
+            .collect::<Option<Vec<_>>>()
+            .map(|normals| cubemap::cross_layout(std::array::from_fn(|i| normals[i]), size, 3));
+        Self {
+            rgba,
+            depth,
+            normals,
+            stats: views.into_iter().flat_map(|v| v.stats).collect(),
+        }
+    }
+
+    /// Average views of the same size, eg. the samples of a depth of field render. The sums
+    /// are accumulated in f32, and only quantized when writing the image.
+    fn average(views: Vec<Self>) -> Self {
+        let mean = |images: Vec<&[f32]>| -> Vec<f32> {
+            let scale = 1.0 / images.len() as f32;
+            let mut sum = vec![0.0; images[0].len()];
+            for image in images {
+                for (s, v) in sum.iter_mut().zip(image) {
+                    *s += v;
+                }
+            }
+            sum.iter().map(|s| s * scale).collect()
+        };
+        let rgba = mean(views.iter().map(|v| v.rgba.as_slice()).collect());
+        let depth = views
+            .iter()
+            .map(|v| v.depth.as_deref())
+            .collect::<Option<Vec<_>>>()
+            .map(mean);
+        let normals = views
+            .iter()
+            .map(|v| v.normals.as_deref())
+            .collect::<Option<Vec<_>>>()
+            .map(mean);
+        Self {
+            rgba,
+            depth,
+            normals,
+            stats: views.into_iter().flat_map(|v| v.stats).collect(),
+        }
+    }
+
+    /// Place views of `width` pixels wide next to each other in one image.
+    fn side_by_side(mut views: Vec<Self>, width: usize) -> Self {
+        if views.len() == 1 {
+            return views.swap_remove(0);
+        }
+        let interleave = |images: Vec<&[f32]>, channels: usize| -> Vec<f32> {
+            let row = width * channels;
+            let rows = images[0].len() / row;
+            (0..rows)
+                .flat_map(|y| {
+                    images
+                        .iter()
+                        .flat_map(move |img| &img[y * row..(y + 1) * row])
+                })
+                .copied()
+                .collect()
+        };
+        let rgba = interleave(views.iter().map(|v| v.rgba.as_slice()).collect(), 4);
+        let depth = views
+            .iter()
+            .map(|v| v.depth.as_deref())
+            .collect::<Option<Vec<_>>>()
+            .map(|depths| interleave(depths, 1));
+        let normals = views
+            .iter()
+            .map(|v| v.normals.as_deref())
+            .collect::<Option<Vec<_>>>()
+            .map(|normals| interleave(normals, 3));
+        Self {
+            rgba,
+            depth,
+            normals,
+            stats: views.into_iter().flat_map(|v| v.stats).collect(),
+        }
+    }
+}
+
+/// Timings and buffer sizes of a single render pass, printed with --verbose.
+struct RenderStats {
+    img_size: UVec2,
+    num_visible: u32,
+    num_intersections: u32,
+    /// Tiles with at least one splat.
+    tiles_used: u32,
+    max_splats_per_tile: u32,
+    /// Size of the projected splat and intersection buffers.
+    buffer_bytes: u64,
+    render_ms: f64,
+    readback_ms: f64,
+}
+
+/// Whether renders in `mode` are colors, as opposed to eg. normals or positions.
+fn is_color(mode: SplatRenderMode) -> bool {
+    matches!(mode, SplatRenderMode::Default | SplatRenderMode::Mip)
+}
+
+/// Render a view of the splats, composited over the `[H, W, 3]` `background_image` if given,
+/// and with the colors transformed by `transform`. When `normals` is set the normals are
+/// rendered in that mode as well. The splats per tile are only read back for the statistics
+/// with `tile_stats`, otherwise they're zero.
+async fn render_view(
+    splats: &Splats<MainBackend>,
+    camera: &Camera,
+    img_size: UVec2,
+    background: Vec3,
+    background_image: Option<Tensor<MainBackend, 3>>,
+    transform: color::OutputTransform,
+    options: RenderOptions,
+    normals: Option<SplatRenderMode>,
+    tile_stats: bool,
+) -> Result<RenderedView> {
+    let render = |mode: SplatRenderMode, background: Vec3, bwd_info: bool, options| {
+        MainBackend::render_splats(
+            camera,
+            img_size,
+            splats.means.val().into_primitive().tensor(),
+            splats.log_scales.val().into_primitive().tensor(),
+            splats.rotations.val().into_primitive().tensor(),
+            splats.sh_coeffs.val().into_primitive().tensor(),
+            splats.raw_opacities.val().into_primitive().tensor(),
+            mode,
+            background,
+            bwd_info,
+            options,
+        )
+    };
+    let render_start = Instant::now();
+    let (img, aux) = render(splats.render_mode, background, true, options);
+
+    // Reading back the counts waits for the render to finish, which separates the render
+    // and read back timings.
+    let num_visible = aux.num_visible().into_scalar_async().await?.elem::<u32>();
+    let num_intersections = aux
+        .num_intersections()
+        .into_scalar_async()
+        .await?
+        .elem::<u32>();
+    let render_ms = render_start.elapsed().as_secs_f64() * 1000.0;
+
+    let projected_splats: Tensor<MainBackend, 2> =
+        Tensor::from_primitive(TensorPrimitive::Float(aux.projected_splats.clone()));
+    let compact_gid_from_isect: Tensor<MainBackend, 1, Int> =
+        Tensor::from_primitive(aux.compact_gid_from_isect.clone());
+    // The tile ids of the intersections take up as much as the splat ids.
+    let buffer_bytes = 4
+        * (projected_splats.shape().num_elements() as u64
+            + 2 * compact_gid_from_isect.dims()[0] as u64);
+
+    let readback_start = Instant::now();
+    let img = Tensor::<MainBackend, 3>::from_primitive(TensorPrimitive::Float(img));
+    // Normals, positions and alpha only renders have no color channels with alpha.
+    let img = match img.dims()[2] {
+        4 => img,
+        3 => {
+            let alpha_options = RenderOptions {
+                z_near: options.z_near,
+                antialias: aux.antialiased,
+                ..Default::default()
+            };
+            let (alpha, _) = render(SplatRenderMode::AlphaOnly, Vec3::ZERO, false, alpha_options);
+            let alpha = Tensor::<MainBackend, 3>::from_primitive(TensorPrimitive::Float(alpha));
+            Tensor::cat(vec![img, alpha], 2)
+        }
+        1 => Tensor::cat(vec![img.clone(), img.clone(), img.clone(), img], 2),
+        c => anyhow::bail!("Expected 4-channel output, got {c}"),
+    };
+    // Normals and positions aren't colors.
+    let img = if is_color(splats.render_mode) {
+        let img = match background_image {
+            Some(background) => composite_over_background(img, background),
+            None => img,
+        };
+        transform.apply(img)
+    } else {
+        img
+    };
+    let rgba = img.into_data_async().await?.into_vec()?;
+
+    let depth = match aux.depth() {
+        Some(depth) => Some(depth.into_data_async().await?.into_vec()?),
+        None if options.depth => anyhow::bail!("Renderer didn't output depth"),
+        None => None,
+    };
+    let normals = match normals {
+        Some(mode) => {
+            let normal_options = RenderOptions {
+                z_near: options.z_near,
+                antialias: aux.antialiased,
+                ..Default::default()
+            };
+            let (normals, _) = render(mode, Vec3::ZERO, false, normal_options);
+            let normals = Tensor::<MainBackend, 3>::from_primitive(TensorPrimitive::Float(normals));
+            Some(normals.into_data_async().await?.into_vec()?)
+        }
+        None => None,
+    };
+    let readback_ms = readback_start.elapsed().as_secs_f64() * 1000.0;
+
+    // Read back after the timings, as only the statistics need the intersections per tile.
+    let (tiles_used, max_splats_per_tile) = if tile_stats {
+        let tile_counts: Vec<u32> = aux
+            .calc_tile_depth()
+            .into_data_async()
+            .await?
+            .iter::<u32>()
+            .collect();
+        (
+            tile_counts.iter().filter(|&&count| count > 0).count() as u32,
+            tile_counts.iter().copied().max().unwrap_or(0),
+        )
+    } else {
+        (0, 0)
+    };
+    let stats = RenderStats {
+        img_size,
+        num_visible,
+        num_intersections,
+        tiles_used,
+        max_splats_per_tile,
+        buffer_bytes,
+        render_ms,
+        readback_ms,
+    };
+    Ok(RenderedView {
+        rgba,
+        depth,
+        normals,
+        stats: vec![stats],
+    })
+}
+
+/// Render `camera` `iterations` times and return the wall clock time of each render in
+/// milliseconds. Every render waits for the GPU to finish, so the times include the kernel
+/// launches. The kernels are already compiled by the render written to disk.
+fn benchmark_renders(
+    splats: &Splats<MainBackend>,
+    camera: &Camera,
+    img_size: UVec2,
+    args: &Args,
+    iterations: u32,
+) -> Result<Vec<f64>> {
+    let device = splats.device();
+    let background = Vec3::new(args.background[0], args.background[1], args.background[2]);
+    // The warmup renders compile the shaders, so their times are dropped.
+    let times = (0..args.benchmark_warmup + iterations)
+        .map(|_| {
+            let start = Instant::now();
+            let _ = MainBackend::render_splats(
+                camera,
+                img_size,
+                splats.means.val().into_primitive().tensor(),
+                splats.log_scales.val().into_primitive().tensor(),
+                splats.rotations.val().into_primitive().tensor(),
+                splats.sh_coeffs.val().into_primitive().tensor(),
+                splats.raw_opacities.val().into_primitive().tensor(),
+                splats.render_mode,
+                background,
+                false,
+                RenderOptions {
+                    z_near: args.z_near,
+                    antialias: args.antialias,
+                    ..Default::default()
+                },
+            );
+            MainBackend::sync(&device)
+                .map_err(|e| anyhow::anyhow!("Failed to wait for the GPU: {e:?}"))?;
+            Ok(start.elapsed().as_secs_f64() * 1000.0)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(times[args.benchmark_warmup as usize..].to_vec())
+}
+
+/// Render a view, splitting it into horizontal strips of at most `max_pixels` pixels.
+async fn render_view_in_strips(
+    splats: &Splats<MainBackend>,
+    camera: &Camera,
+    img_size: UVec2,
+    background: Vec3,
+    background_image: Option<&BackgroundImage>,
+    transform: color::OutputTransform,
+    options: RenderOptions,
+    normals: Option<SplatRenderMode>,
+    tile_stats: bool,
+    max_pixels: u64,
+) -> Result<RenderedView> {
+    let background_image =
+        background_image.map(|image| image.view(camera, img_size, &splats.device()));
+    let strip_rows = (max_pixels / img_size.x as u64).clamp(1, img_size.y as u64) as u32;
+    if strip_rows == img_size.y {
+        return render_view(
+            splats,
+            camera,
+            img_size,
+            background,
+            background_image,
+            transform,
+            options,
+            normals,
+            tile_stats,
+        )
+        .await;
+    }
+
+    let mut view = RenderedView {
+        rgba: Vec::with_capacity(img_size.element_product() as usize * 4),
+        depth: options.depth.then(Vec::new),
+        normals: normals.map(|_| Vec::new()),
+        stats: vec![],
+    };
+    for y in (0..img_size.y).step_by(strip_rows as usize) {
+        let size = uvec2(img_size.x, strip_rows.min(img_size.y - y));
+        let strip_camera = camera
+            .sub_view(img_size, uvec2(0, y), size)
+            .context("Panoramas can't be rendered in strips, raise --max-render-pixels")?;
+        // Strips span the whole width, so they're contiguous in the row major output.
+        let strip = render_view(
+            splats,
+            &strip_camera,
+            size,
+            background,
+            background_image
+                .clone()
+                .map(|image| image.slice(s![y as usize..(y + size.y) as usize])),
+            transform,
+            options,
+            normals,
+            tile_stats,
+        )
+        .await?;
+        view.rgba.extend(strip.rgba);
+        if let (Some(depth), Some(strip_depth)) = (&mut view.depth, strip.depth) {
+            depth.extend(strip_depth);
+        }
+        if let (Some(normals), Some(strip_normals)) = (&mut view.normals, strip.normals) {
+            normals.extend(strip_normals);
+        }
+        view.stats.extend(strip.stats);
+    }
+    Ok(view)
+}
+
+/// Render the cubemap faces around `camera` and resample them to an equirectangular view.
+async fn render_panorama(
+    splats: &Splats<MainBackend>,
+    camera: &Camera,
+    face_size: u32,
+    img_size: UVec2,
+    background: Vec3,
+    background_image: Option<&BackgroundImage>,
+    transform: color::OutputTransform,
+    options: RenderOptions,
+    normals: Option<SplatRenderMode>,
+    tile_stats: bool,
+    max_render_pixels: u64,
+) -> Result<RenderedView> {
+    let mut faces = Vec::with_capacity(6);
+    for face in camera.cube_faces() {
+        let view = render_view_in_strips(
+            splats,
+            &face,
+            UVec2::splat(face_size),
+            background,
+            background_image,
+            transform,
+            options,
+            normals,
+            tile_stats,
+            max_render_pixels,
+        )
+        .await?;
+        faces.push(view);
+    }
+    let resample = |faces: [&[f32]; 6], channels, ray_distance| {
+        panorama::equirect_from_cube(
+            faces,
+            face_size,
+            channels,
+            img_size.x,
+            img_size.y,
+            ray_distance,
+        )
+    };
+    let rgba = resample(std::array::from_fn(|i| faces[i].rgba.as_slice()), 4, false);
+    let depth = faces
+        .iter()
+        .map(|f| f.depth.as_deref())
+        .collect::<Option<Vec<_>>>()
+        .map(|depths| resample(std::array::from_fn(|i| depths[i]), 1, true));
+    let normals = faces
+        .iter()
+        .map(|f| f.normals.as_deref())
+        .collect::<Option<Vec<_>>>()
+        .map(|normals| resample(std::array::from_fn(|i| normals[i]), 3, false));
+    Ok(RenderedView {
+        rgba,
+        depth,
+        normals,
+        stats: faces.into_iter().flat_map(|f| f.stats).collect(),
+    })
+}
+
+/// Render one view of the splats and write the image (and optionally the depth map, alpha
+/// matte, normal map and checkerboard preview) to disk. With --stereo this renders a pair
+/// of views instead.
+///
+/// Returns the metadata of every written image.
+async fn render_camera(
+    splats: &Splats<MainBackend>,
+    camera: &Camera,
+    img_size: UVec2,
+    args: &Args,
+    output_path: &Path,
+    depth_path: Option<&Path>,
+    alpha_path: Option<&Path>,
+    normal_path: Option<&Path>,
+    preview_path: Option<&Path>,
+    background_image: Option<&BackgroundImage>,
+) -> Result<Vec<meta::OutputMeta>> {
+    let render = |layout: ViewLayout, img_size: UVec2, suffix: Option<&str>| {
+        let with_suffix = |p: &Path| suffix.map_or(p.to_path_buf(), |s| path_with_suffix(p, s));
+        let output_path = with_suffix(output_path);
+        let depth_path = depth_path.map(with_suffix);
+        let alpha_path = alpha_path.map(with_suffix);
+        let normal_path = normal_path.map(with_suffix);
+        let preview_path = preview_path.map(with_suffix);
+        async move {
+            render_to_files(
+                splats,
+                &layout,
+                img_size,
+                args,
+                &output_path,
+                depth_path.as_deref(),
+                alpha_path.as_deref(),
+                normal_path.as_deref(),
+                preview_path.as_deref(),
+                background_image,
+            )
+            .await
+        }
+    };
+
+    if args.panorama {
+        let layout = ViewLayout::Panorama {
+            camera: camera.clone(),
+            face_size: args.cube_face_size(),
+        };
+        return Ok(vec![render(layout, img_size, None).await?]);
+    }
+
+    if args.cubemap {
+        let faces = cubemap::env_map_cameras(camera.position);
+        let face_size = UVec2::splat(args.cube_face_size());
+        return match args.cubemap_layout {
+            CubemapLayout::Cross => Ok(vec![
+                render(ViewLayout::CubeCross(faces), face_size, None).await?,
+            ]),
+            CubemapLayout::Separate => {
+                let mut outputs = Vec::with_capacity(6);
+                for (face, suffix) in faces.into_iter().zip(cubemap::FACE_SUFFIXES) {
+                    let layout = ViewLayout::Row {
+                        cameras: vec![face],
+                        mirrored: true,
+                    };
+                    outputs.push(render(layout, face_size, Some(&format!("_{suffix}"))).await?);
+                }
+                Ok(outputs)
+            }
+        };
+    }
+
+    if !args.stereo {
+        let layout = ViewLayout::Row {
+            cameras: vec![camera.clone()],
+            mirrored: false,
+        };
+        return Ok(vec![render(layout, img_size, None).await?]);
+    }
+
+    let eyes = camera.stereo_eyes(args.ipd, args.convergence_distance);
+    match args.stereo_layout {
+        StereoLayout::SideBySide => {
+            let layout = ViewLayout::Row {
+                cameras: eyes.to_vec(),
+                mirrored: false,
+            };
+            Ok(vec![render(layout, img_size, None).await?])
+        }
+        StereoLayout::Separate => {
+            let mut outputs = Vec::with_capacity(2);
+            for (eye, suffix) in eyes.into_iter().zip(["_L", "_R"]) {
+                let layout = ViewLayout::Row {
+                    cameras: vec![eye],
+                    mirrored: false,
+                };
+                outputs.push(render(layout, img_size, Some(suffix)).await?);
+            }
+            Ok(outputs)
+        }
+    }
+}
+
+/// Render each of `files` separately from `camera` into --out-dir, named after the files.
+/// Files that fail to load or render are reported and skipped, and fail the batch at the end.
+async fn render_batch(
+    args: &Args,
+    device: &WgpuDevice,
+    files: &[PathBuf],
+    camera: &Camera,
+    img_size: UVec2,
+    background_image: Option<&BackgroundImage>,
+) -> Result<()> {
+    let out_dir = args
+        .out_dir
+        .as_deref()
+        .context("Rendering a directory of PLY files needs --out-dir")?;
+    anyhow::ensure!(
+        args.depth.is_none()
+            && args.alpha_output.is_none()
+            && args.normal_output.is_none()
+            && args.preview_out.is_none(),
+        "Depth, alpha, normal and preview outputs aren't supported when rendering a directory"
+    );
+    anyhow::ensure!(
+        !args.watch && args.benchmark.is_none() && args.meta_out.is_none(),
+        "--watch, --benchmark and --meta-out aren't supported when rendering a directory"
+    );
+
+    let mut failed = 0;
+    for (i, file) in files.iter().enumerate() {
+        let file_args = Args {
+            input: vec![file.clone()],
+            ..args.clone()
+        };
+        let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+        let output = out_dir.join(format!("{stem}.{}", args.default_extension()));
+        let rendered = async {
+            let (splats, _) = load_splats(&file_args, device).await?;
+            render_camera(
+                &splats,
+                camera,
+                img_size,
+                args,
+                &output,
+                None,
+                None,
+                None,
+                None,
+                background_image,
+            )
+            .await
+        }
+        .await;
+        match rendered {
+            Ok(_) => status(
+                args,
+                format_args!("Rendered {}/{}: {}", i + 1, files.len(), file.display()),
+            ),
+            Err(err) => {
+                warn(format_args!("Failed to render {}: {err:#}", file.display()));
+                failed += 1;
+            }
+        }
+    }
+    anyhow::ensure!(
+        failed == 0,
+        "{failed} of the {} PLY files failed to render",
+        files.len()
+    );
+    Ok(())
+}
+
+/// What the render is composited over instead of the solid --background color.
+enum BackgroundImage {
+    /// An image stretched to the output resolution.
+    Stretched(image::DynamicImage),
+    /// A `[H, W, 3]` lat-long environment map, sampled along the ray of every pixel.
+    EnvMap(Tensor<MainBackend, 3>),
+}
+
+impl BackgroundImage {
+    /// The `[H, W, 3]` background behind a `view_size` view of `camera`.
+    fn view(
+        &self,
+        camera: &Camera,
+        view_size: UVec2,
+        device: &WgpuDevice,
+    ) -> Tensor<MainBackend, 3> {
+        let shape = [view_size.y as usize, view_size.x as usize, 3];
+        match self {
+            Self::Stretched(image) => {
+                let pixels = output::background_pixels(image, view_size.x, view_size.y);
+                Tensor::<MainBackend, 1>::from_floats(pixels.as_slice(), device).reshape(shape)
+            }
+            Self::EnvMap(env_map) => {
+                let dirs = env_map::ray_directions(camera, view_size, device);
+                env_map::sample_env_map(env_map.clone(), dirs)
+            }
+        }
+    }
+}
+
+/// How the views making up an output image are rendered and arranged.
+enum ViewLayout {
+    /// Views of the same size next to each other, eg. the eyes of a stereo pair. Mirrored
+    /// views are flipped horizontally, as cubemap faces expect.
+    Row {
+        cameras: Vec<Camera>,
+        mirrored: bool,
+    },
+    /// An equirectangular panorama resampled from the cubemap faces around the camera.
+    Panorama { camera: Camera, face_size: u32 },
+    /// Cubemap faces, from [`cubemap::env_map_cameras`], in a horizontal cross.
+    CubeCross([Camera; 6]),
+}
+
+impl ViewLayout {
+    /// Size of the whole image when each view is `view_size` large.
+    fn img_size(&self, view_size: UVec2) -> UVec2 {
+        match self {
+            Self::Row { cameras, .. } => uvec2(view_size.x * cameras.len() as u32, view_size.y),
+            Self::Panorama { .. } => view_size,
+            Self::CubeCross(_) => uvec2(view_size.x * 4, view_size.y * 3),
+        }
+    }
+}
+
+/// `path` with `suffix` appended to the file stem, eg. `out.png` -> `out_L.png`.
+fn path_with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let mut name = format!("{stem}{suffix}");
+    if let Some(ext) = path.extension() {
+        name.push('.');
+        name.push_str(&ext.to_string_lossy());
+    }
+    path.with_file_name(name)
+}
+
+/// Render the views of `layout`, each `img_size` large, and write the image (and optionally
+/// the depth map, alpha matte, normal map and checkerboard preview) to disk.
+async fn render_to_files(
+    splats: &Splats<MainBackend>,
+    layout: &ViewLayout,
+    img_size: UVec2,
+    args: &Args,
+    output_path: &Path,
+    depth_path: Option<&Path>,
+    alpha_path: Option<&Path>,
+    normal_path: Option<&Path>,
+    preview_path: Option<&Path>,
+    background_image: Option<&BackgroundImage>,
+) -> Result<meta::OutputMeta> {
+    // Background images are composited over the render with a black background.
+    let background = if background_image.is_some() {
+        Vec3::ZERO
+    } else {
+        Vec3::new(args.background[0], args.background[1], args.background[2])
+    };
+
+    let options = RenderOptions {
+        depth: depth_path.is_some(),
+        // Depth is resolved by dividing by alpha later, which also handles empty pixels.
+        // Keep those at zero so supersampled depth can be averaged.
+        depth_sentinel: 0.0,
+        premultiplied_alpha: args.premultiplied_alpha,
+        z_near: args.z_near,
+        antialias: args.antialias,
+        ..Default::default()
+    };
+    let normals = normal_path.map(|_| args.normal_space.render_mode());
+    let tile_stats = args.print_stats || args.meta_out.is_some();
+
+    let format = output::ImageFormat::resolve(output_path, args.output_format)?;
+    // Float formats keep linear colors linear.
+    let transform = color::OutputTransform {
+        color_space: args.color_space,
+        exposure: args.exposure,
+        tonemap: args.tonemap,
+        white_point: args.white_point,
+        encode: !args.linear_output
+            && (args.color_space == color::ColorSpace::Srgb || !format.is_float()),
+    };
+
+    let factor = args.supersample;
+    let render_size = img_size * factor;
+    let render = async |camera: &Camera| -> Result<RenderedView> {
+        let (Some(aperture), Some(focus)) = (args.dof_aperture, args.dof_focus) else {
+            return render_view_in_strips(
+                splats,
+                camera,
+                render_size,
+                background,
+                background_image,
+                transform,
+                options,
+                normals,
+                tile_stats,
+                args.max_render_pixels,
+            )
+            .await;
+        };
+        // The splats stay on the GPU, only the camera changes between the samples.
+        let cameras = dof::aperture_cameras(camera, aperture, focus, args.dof_samples);
+        let mut views = Vec::with_capacity(cameras.len());
+        for camera in &cameras {
+            let view = render_view_in_strips(
+                splats,
+                camera,
+                render_size,
+                background,
+                background_image,
+                transform,
+                options,
+                normals,
+                tile_stats,
+                args.max_render_pixels,
+            )
+            .await?;
+            views.push(view);
+        }
+        Ok(RenderedView::average(views))
+    };
+    let view = match layout {
+        ViewLayout::Row { cameras, mirrored } => {
+            let mut views = Vec::with_capacity(cameras.len());
+            for camera in cameras {
+                let mut view = render(camera).await?;
+                if *mirrored {
+                    view.flip_horizontal(render_size.x as usize);
+                }
+                views.push(view);
+            }
+            RenderedView::side_by_side(views, render_size.x as usize)
+        }
+        ViewLayout::Panorama { camera, face_size } => {
+            render_panorama(
+                splats,
+                camera,
+                face_size * factor,
+                render_size,
+                background,
+                background_image,
+                transform,
+                options,
+                normals,
+                tile_stats,
+                args.max_render_pixels,
+            )
+            .await?
+        }
+        ViewLayout::CubeCross(faces) => {
+            let mut views = Vec::with_capacity(6);
+            for camera in faces {
+                let mut view = render(camera).await?;
+                view.flip_horizontal(render_size.x as usize);
+                views.push(view);
+            }
+            RenderedView::cube_cross(views, render_size.x as usize)
+        }
+    };
+    let img_size = layout.img_size(img_size);
+    let render_size = layout.img_size(render_size);
+    for stats in &view.stats {
+        verbose(
+            args,
+            format_args!(
+                "Rendered {}x{} in {:.1} ms, read back in {:.1} ms. {} visible splats, {} \
+                 intersections, {:.1} MiB of splat and intersection buffers",
+                stats.img_size.x,
+                stats.img_size.y,
+                stats.render_ms,
+                stats.readback_ms,
+                stats.num_visible,
+                stats.num_intersections,
+                stats.buffer_bytes as f64 / (1024.0 * 1024.0)
+            ),
+        );
+    }
+    let mut output_meta = meta::OutputMeta {
+        path: output_path.to_path_buf(),
+        img_size,
+        num_visible: view.stats.iter().map(|s| s.num_visible).sum(),
+        num_intersections: view.stats.iter().map(|s| s.num_intersections).sum(),
+        tiles_used: view.stats.iter().map(|s| s.tiles_used).sum(),
+        max_splats_per_tile: view
+            .stats
+            .iter()
+            .map(|s| s.max_splats_per_tile)
+            .max()
+            .unwrap_or(0),
+        render_ms: view.stats.iter().map(|s| s.render_ms).sum(),
+        readback_ms: view.stats.iter().map(|s| s.readback_ms).sum(),
+        encode_ms: 0.0,
+        psnr: None,
+        ssim: None,
+    };
+    let data = output::downsample(view.rgba, render_size.x, render_size.y, 4, factor);
+    let depth = view
+        .depth
+        .map(|d| output::downsample(d, render_size.x, render_size.y, 1, factor));
+    let normals = view
+        .normals
+        .map(|n| output::downsample(n, render_size.x, render_size.y, 3, factor));
+    let (w, h) = (img_size.x as usize, img_size.y as usize);
+
+    let mut exr_depth = None;
+
+    if let Some(depth_path) = depth_path {
+        let depth = depth.as_deref().context("Renderer didn't output depth")?;
+        let alpha: Vec<f32> = data.chunks_exact(4).map(|c| c[3]).collect();
+        let depth = output::resolve_depth(depth, &alpha, args.depth_far, args.depth_normalize);
+        let png_scale = if args.depth_normalize {
+            u16::MAX as f32
+        } else {
+            args.depth_scale
+        };
+
+        if let Some(parent) = depth_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        output::save_depth(depth_path, w as u32, h as u32, &depth, png_scale)?;
+        status(
+            args,
+            format_args!("Saved depth to {}", depth_path.display()),
+        );
+
+        if format == output::ImageFormat::Exr {
+            exr_depth = Some(depth);
+        }
+    }
+
+    // The rasterizer writes the accumulated opacity to alpha before compositing the
+    // background, so it doesn't depend on the background color.
+    if let Some(alpha_path) = alpha_path {
+        let alpha: Vec<f32> = data.chunks_exact(4).map(|c| c[3]).collect();
+        if let Some(parent) = alpha_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        output::save_alpha(alpha_path, w as u32, h as u32, &alpha, args.bit_depth)?;
+        status(
+            args,
+            format_args!("Saved alpha to {}", alpha_path.display()),
+        );
+    }
+
+    if let Some(normal_path) = normal_path {
+        let normals = normals
+            .as_deref()
+            .context("Renderer didn't output normals")?;
+        if let Some(parent) = normal_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        output::save_normals(normal_path, w as u32, h as u32, normals, args.bit_depth)?;
+        status(
+            args,
+            format_args!("Saved normals to {}", normal_path.display()),
+        );
+    }
+
+    if let Some(reference_path) = &args.compare {
+        let reference = image::open(reference_path)
+            .with_context(|| {
+                format!(
+                    "Failed to open reference image {}",
+                    reference_path.display()
+                )
+            })?
+            .into_rgb32f();
+        anyhow::ensure!(
+            reference.dimensions() == (w as u32, h as u32),
+            "Reference image {} is {}x{}, but the render is {w}x{h}",
+            reference_path.display(),
+            reference.width(),
+            reference.height()
+        );
+        // The saved image is clamped to [0, 1] too.
+        let rgb: Vec<f32> = data
+            .chunks_exact(4)
+            .flat_map(|c| [c[0], c[1], c[2]])
+            .map(|v| v.clamp(0.0, 1.0))
+            .collect();
+        let psnr = metrics::psnr(&rgb, &reference);
+        let ssim = metrics::ssim(&rgb, &reference, w, h, 3);
+        status(
+            args,
+            format_args!(
+                "Compared to {}: PSNR {psnr:.2} dB, SSIM {ssim:.4}",
+                reference_path.display()
+            ),
+        );
+        output_meta.psnr = Some(psnr);
+        output_meta.ssim = Some(ssim);
+    }
+
+    // The checkerboard is made at the output resolution, scaling it would cause moiré.
+    if args.preview_checker {
+        let checker = output::checkerboard(w as u32, h as u32, args.checker_size);
+        let preview = output::composite_over(&data, &checker);
+        match preview_path {
+            Some(preview_path) => {
+                let preview_format = output::ImageFormat::from_path(preview_path)?;
+                if let Some(parent) = preview_path.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                output::save_image(
+                    preview_path,
+                    preview_format,
+                    w as u32,
+                    h as u32,
+                    preview,
+                    args.bit_depth,
+                    args.quality,
+                    None,
+                )?;
+                status(
+                    args,
+                    format_args!("Saved preview to {}", preview_path.display()),
+                );
+            }
+            None => data = preview,
+        }
+    }
+
+    let encode_start = Instant::now();
+    if args.output_to_stdout() {
+        let bytes = output::encode_image(
+            format,
+            w as u32,
+            h as u32,
+            data,
+            args.bit_depth,
+            args.quality,
+            exr_depth.as_deref(),
+        )?;
+        // Stdout is written as raw bytes on all platforms, there is no newline translation.
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(&bytes)?;
+        stdout.flush()?;
+        output_meta.encode_ms = encode_start.elapsed().as_secs_f64() * 1000.0;
+        verbose(
+            args,
+            format_args!("Encoded image in {:.1} ms", output_meta.encode_ms),
+        );
+        return Ok(output_meta);
+    }
+
+    if let Some(parent) = output_path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    output::save_image(
+        output_path,
+        format,
+        w as u32,
+        h as u32,
+        data,
+        args.bit_depth,
+        args.quality,
+        exr_depth.as_deref(),
+    )?;
+    output_meta.encode_ms = encode_start.elapsed().as_secs_f64() * 1000.0;
+    verbose(
+        args,
+        format_args!("Encoded and wrote image in {:.1} ms", output_meta.encode_ms),
+    );
+    status(
+        args,
+        format_args!("Saved image to {}", output_path.display()),
+    );
+
+    Ok(output_meta)
+}
+
+/// Print a progress message. These go to stderr when the image itself is written to stdout.
+#[allow(clippy::print_stderr)]
+fn status(args: &Args, message: std::fmt::Arguments<'_>) {
+    if args.output_to_stdout() {
+        eprintln!("{message}");
+    } else {
+        println!("{message}");
+    }
+}
+
+/// Print a message only with --verbose, see [`status`].
+fn verbose(args: &Args, message: std::fmt::Arguments<'_>) {
+    if args.verbose {
+        status(args, message);
+    }
+}
+
+#[allow(clippy::print_stderr)]
+fn warn(message: impl std::fmt::Display) {
+    eprintln!("Warning: {message}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse the arguments of a render of `in.ply` to `out.png` with `extra` options.
+    fn parse(extra: &[&str]) -> Result<Args, clap::Error> {
+        Args::try_parse_from(
+            ["brush-render", "in.ply", "-o", "out.png"]
+                .iter()
+                .chain(extra),
+        )
+    }
+
+    fn camera_from_args(extra: &[&str]) -> Camera {
+        compute_camera(&parse(extra).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn meta_camera_args_round_trip() {
+        let camera = Camera::new(
+            glam::vec3(-1.5, 0.25, 3.0),
+            Quat::from_rotation_y(0.3) * Quat::from_rotation_x(-0.2),
+            0.9,
+            0.7,
+            Vec2::new(0.45, 0.55),
+        );
+        let img_size = uvec2(640, 480);
+        let extra = meta::camera_args(&camera, img_size);
+        let args = parse(&extra.iter().map(String::as_str).collect::<Vec<_>>()).unwrap();
+        assert_eq!(args.img_size(), img_size);
+        let parsed = compute_camera(&args).unwrap();
+        assert_eq!(parsed.position, camera.position);
+        assert_eq!(parsed.rotation, camera.rotation);
+        assert_eq!(parsed.center_uv, camera.center_uv);
+        assert!((parsed.fov_x - camera.fov_x).abs() < 1e-12);
+        assert!((parsed.fov_y - camera.fov_y).abs() < 1e-12);
+    }
+
+    #[test]
+    fn benchmark_args() {
+        assert_eq!(parse(&["--benchmark", "20"]).unwrap().benchmark, Some(20));
+        assert_eq!(parse(&["--bench", "20"]).unwrap().benchmark, Some(20));
+        assert!(parse(&["--benchmark", "0"]).is_err());
+        assert!(parse(&["--benchmark-warmup", "5"]).is_err());
+
+        let args = Args::try_parse_from(["brush-render", "in.ply", "--bench", "50", "--no-output"])
+            .unwrap();
+        assert!(args.no_output && args.output.is_none());
+        assert_eq!(args.benchmark_warmup, 3);
+        assert!(Args::try_parse_from(["brush-render", "in.ply", "--no-output"]).is_err());
+        assert!(parse(&["--bench", "50", "--no-output"]).is_err());
+    }
+
+    #[test]
+    fn env_map_args() {
+        let args = parse(&["--env-map", "sky.exr"]).unwrap();
+        assert_eq!(args.env_map, Some(PathBuf::from("sky.exr")));
+        assert!(parse(&["--env-map", "sky.exr", "--background-image", "bg.png"]).is_err());
+        assert!(parse(&["--env-map", "sky.exr", "--background", "1", "1", "1"]).is_err());
+        assert!(parse(&["--env-map", "sky.exr", "--premultiplied-alpha"]).is_err());
+    }
+
+    #[test]
+    fn input_dir_args() {
+        let dir = std::env::temp_dir();
+        let dir_str = dir.to_str().unwrap();
+        let args = Args::try_parse_from(["brush-render", dir_str, "--out-dir", "renders"]).unwrap();
+        assert_eq!(args.input_dir(), Some(dir.as_path()));
+
+        let args = parse(&[]).unwrap();
+        assert_eq!(args.input_dir(), None);
+        let args =
+            Args::try_parse_from(["brush-render", dir_str, "b.ply", "-o", "out.png"]).unwrap();
+        assert_eq!(args.input_dir(), None);
+    }
+
+    #[test]
+    fn render_mode_args() {
+        assert_eq!(parse(&[]).unwrap().render_mode, None);
+        assert_eq!(
+            parse(&["--render-mode", "mip"]).unwrap().render_mode,
+            Some(SplatRenderMode::Mip)
+        );
+        assert_eq!(
+            parse(&["--render-mode", "normals-world-space"])
+                .unwrap()
+                .render_mode,
+            Some(SplatRenderMode::NormalsWorldSpace)
+        );
+        assert!(parse(&["--render-mode", "feature"]).is_err());
+    }
+
+    #[test]
+    fn watch_args() {
+        let args = parse(&["--watch"]).unwrap();
+        assert!(args.watch);
+        assert_eq!(args.watch_interval, 500);
+        assert!(parse(&["--watch-interval", "5"]).is_err());
+    }
+
+    #[test]
+    fn roi_args() {
+        let parse =
+            |extra: &[&str]| parse(&[&["--width", "640", "--height", "480"][..], extra].concat());
+        let args = parse(&["--roi", "100", "50", "200", "120"]).unwrap();
+        assert_eq!(args.roi, Some(vec![100, 50, 200, 120]));
+        assert!(parse(&["--roi", "100", "50", "200"]).is_err());
+        assert!(parse(&["--roi", "0", "0", "10", "10", "--panorama"]).is_err());
+
+        let camera = Camera::new(Vec3::ZERO, Quat::IDENTITY, 1.0, 0.8, Vec2::splat(0.5));
+        let (roi_camera, size) = args.roi_view(camera.clone(), args.img_size()).unwrap();
+        assert_eq!(size, uvec2(200, 120));
+        let center = camera.center(args.img_size()) - Vec2::new(100.0, 50.0);
+        assert!(roi_camera.center(size).abs_diff_eq(center, 1e-3));
+
+        let args = parse(&["--roi", "500", "0", "200", "120"]).unwrap();
+        assert!(args.roi_view(camera, args.img_size()).is_err());
+    }
+
+    #[test]
+    fn compare_args() {
+        let args = parse(&["--compare", "ref.png", "--min-psnr", "30"]).unwrap();
+        assert_eq!(args.compare, Some(PathBuf::from("ref.png")));
+        assert_eq!(args.min_psnr, Some(30.0));
+        assert!(parse(&["--min-psnr", "30"]).is_err());
+        assert!(parse(&["--compare", "ref.png", "--stereo"]).is_err());
+    }
+
+    #[test]
+    fn preview_checker_args() {
+        let args = parse(&["--preview-checker"]).unwrap();
+        assert!(args.preview_checker);
+        assert_eq!(args.checker_size, 16);
+        assert_eq!(args.preview_out, None);
+
+        let args = parse(&[
+            "--preview-checker",
+            "--checker-size",
+            "8",
+            "--preview-out",
+            "preview.png",
+        ])
+        .unwrap();
+        assert_eq!(args.checker_size, 8);
+        assert_eq!(args.preview_out, Some(PathBuf::from("preview.png")));
+
+        assert!(parse(&["--preview-out", "preview.png"]).is_err());
+        assert!(parse(&["--preview-checker", "--checker-size", "0"]).is_err());
+        assert!(parse(&["--preview-checker", "--background", "1", "1", "1"]).is_err());
+    }
+
+    #[test]
+    fn dof_args() {
+        let args = parse(&["--dof-aperture", "0.05", "--dof-focus", "3"]).unwrap();
+        assert_eq!(args.dof_aperture, Some(0.05));
+        assert_eq!(args.dof_focus, Some(3.0));
+        assert_eq!(args.dof_samples, 32);
+        assert!(parse(&["--dof-aperture", "0.05"]).is_err());
+        assert!(parse(&["--dof-samples", "8"]).is_err());
+        assert!(parse(&["--dof-aperture", "0.05", "--dof-focus", "3", "--panorama"]).is_err());
+
+        let average = RenderedView::average(vec![
+            RenderedView {
+                rgba: vec![0.0, 0.5, 1.0, 1.0],
+                depth: Some(vec![2.0]),
+                normals: None,
+                stats: vec![],
+            },
+            RenderedView {
+                rgba: vec![1.0, 0.5, 0.0, 1.0],
+                depth: Some(vec![4.0]),
+                normals: None,
+                stats: vec![],
+            },
+        ]);
+        assert_eq!(average.rgba, [0.5, 0.5, 0.5, 1.0]);
+        assert_eq!(average.depth, Some(vec![3.0]));
+    }
+
+    #[test]
+    fn color_args() {
+        let args = parse(&[]).unwrap();
+        assert_eq!(args.color_space, color::ColorSpace::Srgb);
+        assert_eq!(args.exposure, 0.0);
+        let args = parse(&["--color-space", "linear", "--exposure", "-1.5"]).unwrap();
+        assert_eq!(args.color_space, color::ColorSpace::Linear);
+        assert_eq!(args.exposure, -1.5);
+        assert_eq!(args.tonemap, color::Tonemap::None);
+        let args = parse(&["--tonemap", "reinhard", "--white-point", "4"]).unwrap();
+        assert_eq!(args.tonemap, color::Tonemap::Reinhard);
+        assert_eq!(args.white_point, 4.0);
+        let args = parse(&["--tonemap", "aces"]).unwrap();
+        assert_eq!(args.tonemap, color::Tonemap::Aces);
+        assert!(!args.linear_output);
+        assert!(parse(&["--linear-output"]).unwrap().linear_output);
+    }
+
+    #[test]
+    fn center_px_args() {
+        let camera = camera_from_args(&[
+            "--cx-px", "800", "--cy-px", "270", "--width", "1600", "--height", "900",
+        ]);
+        assert_eq!(camera.center_uv, Vec2::new(0.5, 0.3));
+        let camera = camera_from_args(&["--cx-px", "480", "--width", "1600"]);
+        assert_eq!(camera.center_uv, Vec2::new(0.3, 0.5));
+        assert!(parse(&["--cx-px", "800", "--center-x", "0.5"]).is_err());
+    }
+
+    #[test]
+    fn focal_35mm_args() {
+        let camera =
+            camera_from_args(&["--focal-35mm", "24", "--width", "1600", "--height", "900"]);
+        assert!((camera.fov_x.to_degrees() - 73.74).abs() < 0.01);
+        // The vertical fov follows from the aspect ratio.
+        let focal = fov_to_focal(camera.fov_x, 1600);
+        assert!((focal_to_fov(focal, 900) - camera.fov_y).abs() < 1e-12);
+        for [name, value] in [["--fov-x", "50"], ["--focal-x", "800"]] {
+            assert!(parse(&["--focal-35mm", "24", name, value]).is_err());
+        }
+    }
+
+    #[test]
+    fn normal_output_args() {
+        let args = parse(&["--normal-output", "normals.png"]).unwrap();
+        assert_eq!(args.normal_output, Some(PathBuf::from("normals.png")));
+        assert!(args.normal_space == NormalSpace::World);
+        let args = parse(&["--normal-output", "n.png", "--normal-space", "camera"]).unwrap();
+        assert!(args.normal_space == NormalSpace::Camera);
+        assert!(parse(&["--normal-space", "camera"]).is_err());
+    }
+
+    #[test]
+    fn parses_adapter() {
+        let args = parse(&["--adapter", "1"]).unwrap();
+        assert_eq!(
+            args.adapter,
+            Some(brush_process::AdapterPreference::Index(1))
+        );
+        let args = parse(&["--adapter", "nvidia"]).unwrap();
+        assert_eq!(
+            args.adapter,
+            Some(brush_process::AdapterPreference::Name("nvidia".to_owned()))
+        );
+        assert!(Args::try_parse_from(["brush-render", "--list-adapters"]).is_ok());
+    }
+
+    #[test]
+    fn info_args() {
+        let args = Args::try_parse_from(["brush-render", "in.ply", "--info"]).unwrap();
+        assert!(args.info && args.output.is_none());
+        assert!(
+            Args::try_parse_from(["brush-render", "in.ply", "--info", "--print-aabb"]).is_err()
+        );
+        assert!(Args::try_parse_from(["brush-render", "in.ply"]).is_err());
+    }
+
+    #[test]
+    fn euler_matches_quaternion() {
+        // 90° yaw around Y.
+        let half = std::f32::consts::FRAC_1_SQRT_2;
+        let quat_cam =
+            camera_from_args(&["--cam-rot", "0", &half.to_string(), "0", &half.to_string()]);
+        let euler_cam = camera_from_args(&["--cam-euler", "90", "0", "0", "--euler-order", "ypr"]);
+        assert!(quat_cam.rotation.angle_between(euler_cam.rotation) < 1e-4);
+        assert_eq!(quat_cam.position, euler_cam.position);
+        assert_eq!(quat_cam.fov_x, euler_cam.fov_x);
+
+        // Yaw is applied before pitch and roll.
+        let euler_cam =
+            camera_from_args(&["--cam-euler", "30", "20", "10", "--euler-order", "ypr"]);
+        let expected = Quat::from_rotation_y(30f32.to_radians())
+            * Quat::from_rotation_x(20f32.to_radians())
+            * Quat::from_rotation_z(10f32.to_radians());
+        assert!(euler_cam.rotation.angle_between(expected) < 1e-4);
+
+        // Roll pitch yaw in the ZYX convention by default.
+        let euler_cam = camera_from_args(&["--cam-euler", "10", "20", "30"]);
+        let expected = Quat::from_rotation_z(30f32.to_radians())
+            * Quat::from_rotation_y(20f32.to_radians())
+            * Quat::from_rotation_x(10f32.to_radians());
+        assert!(euler_cam.rotation.angle_between(expected) < 1e-4);
+    }
+
+    #[test]
+    fn rotation_args_conflict() {
+        let args = parse(&[
+            "--cam-rot",
+            "0",
+            "0",
+            "0",
+            "1",
+            "--cam-euler",
+            "0",
+            "0",
+            "0",
+        ]);
+        assert!(args.is_err());
+    }
+
+    #[test]
+    fn out_dir_frames() {
+        let args = Args::try_parse_from([
+            "brush-render",
+            "in.ply",
+            "--cameras",
+            "cams.json",
+            "--out-dir",
+            "renders",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.frame_output(12, 20).unwrap(),
+            Path::new("renders").join("frame_0012.png")
+        );
+
+        // Needs somewhere to write to, but only one of the two.
+        assert!(Args::try_parse_from(["brush-render", "in.ply"]).is_err());
+        assert!(parse(&["--out-dir", "renders"]).is_err());
+    }
+
+    #[test]
+    fn multiple_inputs() {
+        let args =
+            Args::try_parse_from(["brush-render", "scan.ply", "object.ply", "-o", "out.png"])
+                .unwrap();
+        assert_eq!(
+            args.input,
+            [PathBuf::from("scan.ply"), PathBuf::from("object.ply")]
+        );
+        assert!(Args::try_parse_from(["brush-render", "-o", "out.png"]).is_err());
+
+        let args = Args::try_parse_from([
+            "brush-render",
+            "--input",
+            "scan.ply",
+            "object.ply",
+            "-o",
+            "out.png",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.inputs().collect::<Vec<_>>(),
+            [Path::new("scan.ply"), Path::new("object.ply")]
+        );
+    }
+
+    #[test]
+    fn parses_input_transforms() {
+        let args = Args::try_parse_from([
+            "brush-render",
+            "scan.ply",
+            "object.ply",
+            "-o",
+            "out.png",
+            "--transform",
+            "0 0 0 0 0 0 1 1",
+            "--transform",
+            "-1 2 0.5 0 0 0 2 0.25",
+        ])
+        .unwrap();
+        assert_eq!(args.transform.len(), 2);
+        assert_eq!(
+            args.transform[1],
+            InputTransform {
+                translation: Vec3::new(-1.0, 2.0, 0.5),
+                rotation: Quat::IDENTITY,
+                scale: 0.25,
+            }
+        );
+
+        assert!("1 2 3".parse::<InputTransform>().is_err());
+        assert!("0 0 0 0 0 0 1 -1".parse::<InputTransform>().is_err());
+    }
+
+    #[test]
+    fn panorama_defaults() {
+        let args = Args::try_parse_from(["brush-render", "a.ply", "-o", "pano.png", "--panorama"])
+            .unwrap();
+        assert_eq!(args.img_size(), uvec2(4096, 2048));
+        assert_eq!(args.cube_face_size(), 1304);
+
+        assert!(
+            Args::try_parse_from([
+                "brush-render",
+                "a.ply",
+                "-o",
+                "pano.png",
+                "--panorama",
+                "--mode",
+                "equirectangular"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn cubemap_args() {
+        let args =
+            Args::try_parse_from(["brush-render", "a.ply", "-o", "env.exr", "--cubemap"]).unwrap();
+        assert_eq!(args.cube_face_size(), 1024);
+        assert!(args.cubemap_layout == CubemapLayout::Cross);
+        assert!(
+            Args::try_parse_from([
+                "brush-render",
+                "a.ply",
+                "-o",
+                "env.exr",
+                "--cubemap",
+                "--panorama"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn time_range_frames() {
+        assert_eq!(time_steps(0.0, 1.0, 5), [0.0, 0.25, 0.5, 0.75, 1.0]);
+        assert_eq!(time_steps(0.2, 0.8, 1), [0.2]);
+        assert_eq!(time_steps(1.0, 0.0, 2), [1.0, 0.0]);
+
+        let args = parse(&["--time-range", "0", "1", "--frames", "24"]).unwrap();
+        assert_eq!(args.time_range, Some(vec![0.0, 1.0]));
+        assert_eq!(args.frames, Some(24));
+        assert!(parse(&["--frames", "24"]).is_err());
+        assert!(parse(&["--time-range", "0", "1", "--frames", "0"]).is_err());
+        assert!(parse(&["--time-range", "0", "1", "--time", "0.5"]).is_err());
+        // Camera paths give the frames.
+        assert!(parse(&["--time-range", "0", "1", "--orbit", "12"]).is_ok());
+        assert!(parse(&["--time-range", "0", "1", "--orbit", "12", "--frames", "3"]).is_err());
+    }
+
+    #[test]
+    fn print_stats_args() {
+        let args = parse(&["--print-stats", "--stats-json"]).unwrap();
+        assert!(args.print_stats && args.stats_json);
+        assert!(parse(&["--stats-json"]).is_err());
+        assert!(parse(&["--print-stats", "--info"]).is_err());
+        assert!(parse(&["--print-stats", "--watch"]).is_err());
+    }
+
+    #[test]
+    fn auto_camera_args() {
+        let parse = |extra: &[&str]| parse(&[&["--auto-camera"][..], extra].concat());
+        assert!(parse(&[]).unwrap().auto_camera);
+        assert!(parse(&["--cam-pos", "1", "2", "3", "--fov-x", "60"]).is_ok());
+        assert!(parse(&["--look-at", "0", "0", "0"]).is_err());
+        assert!(parse(&["--orbit", "8"]).is_err());
+        assert!(parse(&["--fov-y", "40"]).is_err());
+    }
+
+    #[test]
+    fn ortho_args() {
+        let projection = |extra: &[&str]| compute_projection(&parse(extra).unwrap());
+        assert_eq!(
+            projection(&["--ortho", "--ortho-width", "8"]).unwrap(),
+            projection(&["--mode", "orthographic", "--ortho-width", "8"]).unwrap()
+        );
+        assert!(matches!(
+            projection(&["--ortho", "--ortho-width", "8"]).unwrap(),
+            ProjectionMode::Orthographic { width: 8.0, .. }
+        ));
+        assert!(projection(&["--ortho"]).is_err());
+        assert!(parse(&["--ortho", "--fisheye"]).is_err());
+        assert!(parse(&["--ortho", "--panorama"]).is_err());
+    }
+
+    #[test]
+    fn fisheye_args() {
+        let projection = |extra: &[&str]| compute_projection(&parse(extra).unwrap());
+        let fisheye = ProjectionMode::Fisheye {
+            max_fov_rad: 180f32.to_radians(),
+            model: FisheyeModel::Equidistant,
+        };
+        assert_eq!(projection(&["--fisheye"]).unwrap(), fisheye);
+        assert_eq!(projection(&["--mode", "fisheye"]).unwrap(), fisheye);
+        assert_eq!(
+            projection(&["--fisheye", "--max-fov", "220"]).unwrap(),
+            ProjectionMode::Fisheye {
+                max_fov_rad: 220f32.to_radians(),
+                model: FisheyeModel::Equidistant,
+            }
+        );
+        let equisolid = ProjectionMode::Fisheye {
+            max_fov_rad: 190f32.to_radians(),
+            model: FisheyeModel::Equisolid,
+        };
+        assert_eq!(
+            projection(&[
+                "--camera-model",
+                "fisheye",
+                "--max-fov",
+                "190",
+                "--fisheye-model",
+                "equisolid"
+            ])
+            .unwrap(),
+            equisolid
+        );
+        assert_eq!(
+            projection(&["--camera-model", "fisheye", "--fov-x", "190"]).unwrap(),
+            ProjectionMode::Fisheye {
+                max_fov_rad: 190f32.to_radians(),
+                model: FisheyeModel::Equidistant,
+            }
+        );
+        assert!(projection(&["--fisheye", "--max-fov", "400"]).is_err());
+        assert!(projection(&["--fisheye-model", "equisolid"]).is_err());
+        assert!(projection(&["--max-fov", "120"]).is_err());
+        assert!(parse(&["--fisheye", "--fov-x", "190", "--max-fov", "190"]).is_err());
+        assert!(parse(&["--fisheye", "--mode", "perspective"]).is_err());
+        assert!(parse(&["--fisheye", "--panorama"]).is_err());
+
+        // The meta camera arguments select the same fisheye.
+        for (projection, fov) in [(fisheye, 180f32), (equisolid, 190f32)] {
+            let camera = Camera::new(Vec3::ZERO, Quat::IDENTITY, 0.9, 0.7, Vec2::splat(0.5))
+                .with_projection(projection);
+            let extra = meta::camera_args(&camera, uvec2(512, 512));
+            let args = parse(&extra.iter().map(String::as_str).collect::<Vec<_>>()).unwrap();
+            let parsed = compute_projection(&args).unwrap();
+            let ProjectionMode::Fisheye { max_fov_rad, model } = parsed else {
+                panic!("Expected a fisheye camera, got {parsed:?}");
+            };
+            assert!((max_fov_rad - fov.to_radians()).abs() < 1e-5);
+            assert_eq!(
+                ProjectionMode::Fisheye {
+                    max_fov_rad: fov.to_radians(),
+                    model
+                },
+                projection
+            );
+        }
+    }
+
+    #[test]
+    fn stereo_output_paths() {
+        assert_eq!(
+            path_with_suffix(Path::new("renders/out.png"), "_L"),
+            PathBuf::from("renders/out_L.png")
+        );
+        assert_eq!(
+            path_with_suffix(Path::new("out"), "_R"),
+            PathBuf::from("out_R")
+        );
+
+        let side_by_side = RenderedView::side_by_side(
+            vec![
+                RenderedView {
+                    rgba: vec![1.0; 2 * 2 * 4],
+                    depth: None,
+                    normals: None,
+                    stats: vec![],
+                },
+                RenderedView {
+                    rgba: vec![2.0; 2 * 2 * 4],
+                    depth: None,
+                    normals: None,
+                    stats: vec![],
+                },
+            ],
+            2,
+        );
+        let row: Vec<f32> = [[1.0; 8], [2.0; 8]].concat();
+        assert_eq!(side_by_side.rgba, [row.clone(), row].concat());
+
+        assert!(
+            Args::try_parse_from(["brush-render", "a.ply", "-o", "out.png", "--ipd", "0.1"])
+                .is_err()
+        );
+    }
+}
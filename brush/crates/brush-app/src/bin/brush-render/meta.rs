@@ -0,0 +1,106 @@
+use brush_render::{
+    camera::{Camera, ProjectionMode},
+    gaussian_splats::SplatRenderMode,
+};
+use clap::ValueEnum;
+use glam::UVec2;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+
+/// Contents of the --meta-out JSON sidecar.
+#[derive(Serialize)]
+pub struct RenderMeta {
+    pub version: &'static str,
+    pub inputs: Vec<InputMeta>,
+    pub num_splats: u32,
+    pub render_mode: SplatRenderMode,
+    /// Whether the splats were rendered with the anti-aliasing filter, see --antialias.
+    pub antialiased: bool,
+    pub camera: Camera,
+    pub img_size: UVec2,
+    pub background: [f32; 3],
+    /// Camera arguments that reproduce `camera` when passed to brush-render.
+    pub camera_args: Vec<String>,
+    pub outputs: Vec<OutputMeta>,
+}
+
+/// An input file and how long it took to load.
+#[derive(Serialize)]
+pub struct InputMeta {
+    pub path: PathBuf,
+    /// Hex encoded SHA-256 of the file contents.
+    pub sha256: Option<String>,
+    pub num_splats: u32,
+    pub parse_ms: f64,
+    pub upload_ms: f64,
+}
+
+/// A written image with its render statistics and timings.
+#[derive(Serialize)]
+pub struct OutputMeta {
+    pub path: PathBuf,
+    pub img_size: UVec2,
+    pub num_visible: u32,
+    pub num_intersections: u32,
+    /// Tiles with at least one splat.
+    pub tiles_used: u32,
+    pub max_splats_per_tile: u32,
+    pub render_ms: f64,
+    pub readback_ms: f64,
+    pub encode_ms: f64,
+    /// Metrics against the --compare reference image.
+    pub psnr: Option<f64>,
+    pub ssim: Option<f64>,
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// brush-render arguments describing `camera` rendered at `img_size`.
+///
+/// Floats are printed with their shortest round-tripping representation, so parsing them
+/// gives the exact same values. The field of view is passed in degrees, which can be off by
+/// the last bit of the f64 when converted back.
+pub fn camera_args(camera: &Camera, img_size: UVec2) -> Vec<String> {
+    let mut args = vec![];
+    let pos = camera.position;
+    let rot = camera.rotation;
+    args.push("--cam-pos".to_owned());
+    args.extend([pos.x, pos.y, pos.z].map(|v| v.to_string()));
+    args.push("--cam-rot".to_owned());
+    args.extend([rot.x, rot.y, rot.z, rot.w].map(|v| v.to_string()));
+
+    // Single values use `--name=value`, so negative numbers aren't mistaken for flags.
+    let mut push = |name: &str, value: &dyn std::fmt::Display| {
+        args.push(format!("--{name}={value}"));
+    };
+    push("width", &img_size.x);
+    push("height", &img_size.y);
+    push("center-x", &camera.center_uv.x);
+    push("center-y", &camera.center_uv.y);
+    match camera.projection {
+        ProjectionMode::Perspective => {
+            push("fov-x", &camera.fov_x.to_degrees());
+            push("fov-y", &camera.fov_y.to_degrees());
+        }
+        ProjectionMode::Orthographic { width, height } => {
+            push("mode", &"orthographic");
+            push("ortho-width", &width);
+            push("ortho-height", &height);
+        }
+        ProjectionMode::Equirectangular => {
+            push("mode", &"equirectangular");
+        }
+        ProjectionMode::Fisheye { max_fov_rad, model } => {
+            push("mode", &"fisheye");
+            push("max-fov", &max_fov_rad.to_degrees());
+            push(
+                "fisheye-model",
+                &model.to_possible_value().expect("Not skipped").get_name(),
+            );
+        }
+    }
+    args
+}
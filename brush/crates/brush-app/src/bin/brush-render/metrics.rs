@@ -0,0 +1,170 @@
+//! Image quality metrics between a render and a reference image.
+
+/// Size of the Gaussian window used by [`ssim`].
+const SSIM_WINDOW: usize = 11;
+const SSIM_SIGMA: f64 = 1.5;
+
+/// Peak signal to noise ratio in dB between two images with values in [0, 1]. Identical
+/// images give infinity.
+pub fn psnr(a: &[f32], b: &[f32]) -> f64 {
+    assert_eq!(a.len(), b.len(), "Images must have the same size");
+    let squared_error: f64 = a
+        .iter()
+        .zip(b)
+        .map(|(&x, &y)| (f64::from(x) - f64::from(y)).powi(2))
+        .sum();
+    let mse = squared_error / a.len() as f64;
+    -10.0 * mse.log10()
+}
+
+/// Mean structural similarity between two images with `channels` interleaved channels and
+/// values in [0, 1].
+///
+/// Like the SSIM of brush-train this uses an 11x11 Gaussian window with a sigma of 1.5, and
+/// treats pixels outside the image as zero.
+pub fn ssim(a: &[f32], b: &[f32], width: usize, height: usize, channels: usize) -> f64 {
+    let len = width * height * channels;
+    assert!(
+        a.len() == len && b.len() == len,
+        "Images must be {width}x{height} with {channels} channels"
+    );
+    let window = gaussian_window();
+    let blur = |img: &[f64]| blur(img, width, height, &window);
+    let product =
+        |p: &[f64], q: &[f64]| -> Vec<f64> { p.iter().zip(q).map(|(p, q)| p * q).collect() };
+    let (c1, c2) = (0.01f64.powi(2), 0.03f64.powi(2));
+
+    let mut total = 0.0;
+    for c in 0..channels {
+        let channel = |img: &[f32]| -> Vec<f64> {
+            img.iter()
+                .skip(c)
+                .step_by(channels)
+                .map(|&v| f64::from(v))
+                .collect()
+        };
+        let (x, y) = (channel(a), channel(b));
+        let (mu_x, mu_y) = (blur(&x), blur(&y));
+        let xx = blur(&product(&x, &x));
+        let yy = blur(&product(&y, &y));
+        let xy = blur(&product(&x, &y));
+
+        for i in 0..width * height {
+            let (mx, my) = (mu_x[i], mu_y[i]);
+            let sigma_xx = xx[i] - mx * mx;
+            let sigma_yy = yy[i] - my * my;
+            let sigma_xy = xy[i] - mx * my;
+            total += ((2.0 * mx * my + c1) * (2.0 * sigma_xy + c2))
+                / ((mx * mx + my * my + c1) * (sigma_xx + sigma_yy + c2));
+        }
+    }
+    total / len as f64
+}
+
+fn gaussian_window() -> [f64; SSIM_WINDOW] {
+    let center = (SSIM_WINDOW / 2) as f64;
+    let window: [f64; SSIM_WINDOW] = std::array::from_fn(|i| {
+        (-(i as f64 - center).powi(2) / (2.0 * SSIM_SIGMA * SSIM_SIGMA)).exp()
+    });
+    let sum: f64 = window.iter().sum();
+    window.map(|w| w / sum)
+}
+
+/// Separable blur of a single channel image with `window`, with zeros outside the image.
+fn blur(img: &[f64], width: usize, height: usize, window: &[f64; SSIM_WINDOW]) -> Vec<f64> {
+    let radius = SSIM_WINDOW / 2;
+    // Weighted sum of the samples `at(i)` for i in [pos - radius, pos + radius] within `len`.
+    let convolve = |pos: usize, len: usize, at: &dyn Fn(usize) -> f64| -> f64 {
+        window
+            .iter()
+            .enumerate()
+            .filter_map(|(k, w)| {
+                let i = (pos + k).checked_sub(radius).filter(|&i| i < len)?;
+                Some(at(i) * w)
+            })
+            .sum()
+    };
+
+    let mut horizontal = vec![0.0; img.len()];
+    for y in 0..height {
+        let row = &img[y * width..(y + 1) * width];
+        for x in 0..width {
+            horizontal[y * width + x] = convolve(x, width, &|i| row[i]);
+        }
+    }
+    let mut out = vec![0.0; img.len()];
+    for y in 0..height {
+        for x in 0..width {
+            out[y * width + x] = convolve(y, height, &|i| horizontal[i * width + x]);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkerboard(size: usize, invert: bool) -> Vec<f32> {
+        (0..size * size)
+            .map(|i| {
+                let on = ((i % size) / 2 + (i / size) / 2) % 2 == 0;
+                if on != invert { 0.8 } else { 0.2 }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn identical_images() {
+        let img = checkerboard(16, false);
+        assert_eq!(psnr(&img, &img), f64::INFINITY);
+        assert!((ssim(&img, &img, 16, 16, 1) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn psnr_of_known_errors() {
+        let a = [0.5; 64];
+        let b = [0.6; 64];
+        assert!((psnr(&a, &b) - 20.0).abs() < 1e-4);
+        assert!(psnr(&[0.0; 64], &[1.0; 64]).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ssim_follows_structure() {
+        let size = 32;
+        let img = checkerboard(size, false);
+        // Deterministic noise in [-0.5, 0.5].
+        let noise: Vec<f32> = (0..size * size)
+            .map(|i| ((i * 7919) % 101) as f32 / 100.0 - 0.5)
+            .collect();
+        let noisy = |amount: f32| -> Vec<f32> {
+            img.iter()
+                .zip(&noise)
+                .map(|(v, n)| v + n * amount)
+                .collect()
+        };
+
+        let slight = ssim(&img, &noisy(0.05), size, size, 1);
+        let strong = ssim(&img, &noisy(0.3), size, size, 1);
+        assert!(slight < 1.0 && strong < slight, "{slight} {strong}");
+        // SSIM is symmetric.
+        let swapped = ssim(&noisy(0.3), &img, size, size, 1);
+        assert!((strong - swapped).abs() < 1e-12);
+        // Anti-correlated structure gives a negative SSIM.
+        let inverted = checkerboard(size, true);
+        assert!(ssim(&img, &inverted, size, size, 1) < 0.0);
+    }
+
+    #[test]
+    fn ssim_averages_channels() {
+        let size = 16;
+        let a = checkerboard(size, false);
+        let b = checkerboard(size, true);
+        let interleave = |c0: &[f32], c1: &[f32]| -> Vec<f32> {
+            c0.iter().zip(c1).flat_map(|(x, y)| [*x, *y]).collect()
+        };
+        let two = ssim(&interleave(&a, &a), &interleave(&a, &b), size, size, 2);
+        let expected = (1.0 + ssim(&a, &b, size, size, 1)) / 2.0;
+        assert!((two - expected).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,939 @@
+use anyhow::{Context, Result};
+use image::codecs::jpeg::JpegEncoder;
+use image::{ExtendedColorType, ImageBuffer, ImageEncoder, Luma, Rgb, Rgba, RgbaImage};
+use std::io::Cursor;
+use std::path::Path;
+
+/// File formats a depth map can be written as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthFormat {
+    /// 16-bit grayscale PNG.
+    Png16,
+    /// Raw little endian f32 values, row major.
+    RawF32,
+    /// NumPy array of f32 values with shape `[H, W]`.
+    Npy,
+    /// Grayscale Portable Float Map.
+    Pfm,
+}
+
+impl DepthFormat {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+        match ext.as_deref() {
+            Some("png") => Ok(Self::Png16),
+            Some("raw" | "f32") => Ok(Self::RawF32),
+            Some("npy") => Ok(Self::Npy),
+            Some("pfm") => Ok(Self::Pfm),
+            _ => Err(anyhow::anyhow!(
+                "Unsupported depth output {}, expected a .png, .raw, .f32, .npy or .pfm file",
+                path.display()
+            )),
+        }
+    }
+}
+
+/// Turn the alpha weighted depth from the renderer into a per-pixel depth map.
+///
+/// Depth is divided by the accumulated alpha, so partially covered pixels aren't
+/// pulled towards the camera. Pixels without coverage are set to `far`. When `normalize`
+/// is set, the min/max depth of the covered pixels is mapped to 0..1.
+pub fn resolve_depth(depth: &[f32], alpha: &[f32], far: f32, normalize: bool) -> Vec<f32> {
+    let covered = |a: f32| a > 0.0;
+
+    let mut resolved: Vec<f32> = depth
+        .iter()
+        .zip(alpha)
+        .map(|(&d, &a)| if covered(a) { d / a } else { far })
+        .collect();
+
+    if normalize {
+        let (min, max) = resolved
+            .iter()
+            .zip(alpha)
+            .filter(|&(_, &a)| covered(a))
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), (&d, _)| {
+                (min.min(d), max.max(d))
+            });
+        let range = (max - min).max(f32::EPSILON);
+        if min.is_finite() {
+            for d in &mut resolved {
+                *d = ((*d - min) / range).clamp(0.0, 1.0);
+            }
+        }
+    }
+    resolved
+}
+
+/// Write a depth map. For 16-bit PNGs values are multiplied by `png_scale` and clamped.
+pub fn save_depth(
+    path: &Path,
+    width: u32,
+    height: u32,
+    depth: &[f32],
+    png_scale: f32,
+) -> Result<()> {
+    match DepthFormat::from_path(path)? {
+        DepthFormat::Png16 => {
+            let pixels = depth
+                .iter()
+                .map(|d| (d * png_scale).round().clamp(0.0, u16::MAX as f32) as u16)
+                .collect();
+            let image = ImageBuffer::<Luma<u16>, Vec<u16>>::from_raw(width, height, pixels)
+                .context("Failed to build depth image buffer")?;
+            image.save(path)?;
+        }
+        DepthFormat::RawF32 => {
+            let bytes: Vec<u8> = depth.iter().flat_map(|d| d.to_le_bytes()).collect();
+            std::fs::write(path, bytes)?;
+        }
+        DepthFormat::Npy => {
+            std::fs::write(path, encode_npy(&[height as usize, width as usize], depth)?)?;
+        }
+        DepthFormat::Pfm => {
+            std::fs::write(path, encode_pfm(width, height, 1, depth)?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Encode f32 values as a NumPy `.npy` file (format version 1.0) with the given row major
+/// shape.
+pub fn encode_npy(shape: &[usize], data: &[f32]) -> Result<Vec<u8>> {
+    anyhow::ensure!(
+        shape.iter().product::<usize>() == data.len(),
+        "Array of {} values doesn't match the shape {shape:?}",
+        data.len()
+    );
+    let dims: String = shape.iter().map(|d| format!("{d}, ")).collect();
+    let mut header = format!(
+        "{{'descr': '<f4', 'fortran_order': False, 'shape': ({}), }}",
+        dims.trim_end()
+    );
+    // The magic, version and header length take 10 bytes, and the header is padded with
+    // spaces and a newline so the data starts 64 byte aligned.
+    let len = 10 + header.len() + 1;
+    header.extend(std::iter::repeat_n(' ', len.next_multiple_of(64) - len));
+    header.push('\n');
+
+    let mut bytes = Vec::with_capacity(10 + header.len() + data.len() * 4);
+    bytes.extend(b"\x93NUMPY\x01\x00");
+    bytes.extend((header.len() as u16).to_le_bytes());
+    bytes.extend(header.as_bytes());
+    bytes.extend(data.iter().flat_map(|v| v.to_le_bytes()));
+    Ok(bytes)
+}
+
+/// Encode row major f32 values with 1 (grayscale `Pf`) or 3 (color `PF`) channels as a
+/// little endian Portable Float Map. PFM stores the rows bottom to top, so they're flipped.
+pub fn encode_pfm(width: u32, height: u32, channels: usize, data: &[f32]) -> Result<Vec<u8>> {
+    let magic = match channels {
+        1 => "Pf",
+        3 => "PF",
+        _ => anyhow::bail!("PFM files have 1 or 3 channels, got {channels}"),
+    };
+    let row_len = width as usize * channels;
+    anyhow::ensure!(
+        data.len() == row_len * height as usize,
+        "{} values don't make a {width}x{height} image with {channels} channels",
+        data.len()
+    );
+    // A negative scale marks little endian data.
+    let mut bytes = format!("{magic}\n{width} {height}\n-1.0\n").into_bytes();
+    bytes.reserve(data.len() * 4);
+    for row in data.chunks_exact(row_len.max(1)).rev() {
+        bytes.extend(row.iter().flat_map(|v| v.to_le_bytes()));
+    }
+    Ok(bytes)
+}
+
+fn has_extension(path: &Path, ext: &str) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case(ext))
+}
+
+/// File formats the rendered image can be written as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Webp,
+    Bmp,
+    /// OpenEXR with full precision f32 channels.
+    Exr,
+    /// NumPy array of the f32 values, with shape `[H, W, 4]` for the image.
+    Npy,
+    /// Portable Float Map with the f32 RGB values. Alpha is dropped.
+    Pfm,
+}
+
+impl ImageFormat {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+        match ext.as_deref() {
+            Some("png") => Ok(Self::Png),
+            Some("jpg" | "jpeg") => Ok(Self::Jpeg),
+            Some("webp") => Ok(Self::Webp),
+            Some("bmp") => Ok(Self::Bmp),
+            Some("exr") => Ok(Self::Exr),
+            Some("npy") => Ok(Self::Npy),
+            Some("pfm") => Ok(Self::Pfm),
+            _ => Err(anyhow::anyhow!(
+                "Can't determine the image format of {}, use a .png, .jpg, .webp, .bmp, .exr, \
+                 .npy or .pfm file or pass --output-format",
+                path.display()
+            )),
+        }
+    }
+
+    /// Use the explicitly requested format, or otherwise pick one from the extension of `path`.
+    pub fn resolve(path: &Path, format: Option<Self>) -> Result<Self> {
+        format.map_or_else(|| Self::from_path(path), Ok)
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::Webp => "webp",
+            Self::Bmp => "bmp",
+            Self::Exr => "exr",
+            Self::Npy => "npy",
+            Self::Pfm => "pfm",
+        }
+    }
+
+    /// Whether the format stores the float values as they are, instead of quantizing them.
+    pub fn is_float(self) -> bool {
+        matches!(self, Self::Exr | Self::Npy | Self::Pfm)
+    }
+
+    /// Whether the format stores an alpha channel.
+    pub fn has_alpha(self) -> bool {
+        !matches!(self, Self::Jpeg | Self::Bmp | Self::Pfm)
+    }
+
+    fn image_format(self) -> image::ImageFormat {
+        match self {
+            Self::Png => image::ImageFormat::Png,
+            Self::Jpeg => image::ImageFormat::Jpeg,
+            Self::Webp => image::ImageFormat::WebP,
+            Self::Bmp => image::ImageFormat::Bmp,
+            Self::Exr => image::ImageFormat::OpenExr,
+            Self::Npy | Self::Pfm => {
+                unreachable!("NumPy arrays and PFM files aren't written by the image crate")
+            }
+        }
+    }
+}
+
+/// Bits per channel of quantized image outputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum BitDepth {
+    #[value(name = "8")]
+    Eight,
+    #[value(name = "16")]
+    Sixteen,
+}
+
+/// Check whether an image can be written in `format` with the given bit depth.
+pub fn check_image_format(format: ImageFormat, bit_depth: BitDepth) -> Result<()> {
+    if bit_depth == BitDepth::Sixteen && format != ImageFormat::Png && !format.is_float() {
+        anyhow::bail!(
+            "16-bit output is only supported for .png files, and .exr, .npy and .pfm files which \
+             always store f32 values, can't write a 16-bit .{} file",
+            format.extension()
+        );
+    }
+    Ok(())
+}
+
+/// Write the rendered RGBA image. EXR, NumPy and PFM files store the raw float values, other
+/// formats are clamped to 0..1 and quantized to `bit_depth` bits. `quality` (1-100) is used by JPEG.
+///
+/// Formats without alpha just drop it. The color channels are already composited onto the
+/// background, so that gives the image over the background.
+///
+/// `depth` is only used for EXR files, where it's stored as an extra `depth.Z` channel.
+pub fn save_image(
+    path: &Path,
+    format: ImageFormat,
+    width: u32,
+    height: u32,
+    rgba: Vec<f32>,
+    bit_depth: BitDepth,
+    quality: u8,
+    depth: Option<&[f32]>,
+) -> Result<()> {
+    let bytes = encode_image(format, width, height, rgba, bit_depth, quality, depth)?;
+    std::fs::write(path, bytes)?;
+    Ok(())
+}
+
+/// Encode the rendered RGBA image in memory, see [`save_image`].
+pub fn encode_image(
+    format: ImageFormat,
+    width: u32,
+    height: u32,
+    rgba: Vec<f32>,
+    bit_depth: BitDepth,
+    quality: u8,
+    depth: Option<&[f32]>,
+) -> Result<Vec<u8>> {
+    let mut bytes = vec![];
+    if format == ImageFormat::Exr {
+        encode_exr(&mut bytes, width, height, &rgba, depth)?;
+        return Ok(bytes);
+    }
+    if format == ImageFormat::Npy {
+        return encode_npy(&[height as usize, width as usize, 4], &rgba);
+    }
+    if format == ImageFormat::Pfm {
+        let rgb: Vec<f32> = rgba
+            .chunks_exact(4)
+            .flat_map(|c| [c[0], c[1], c[2]])
+            .collect();
+        return encode_pfm(width, height, 3, &rgb);
+    }
+
+    check_image_format(format, bit_depth)?;
+    let mut writer = Cursor::new(&mut bytes);
+
+    match bit_depth {
+        BitDepth::Eight => {
+            let rgba = rgba
+                .into_iter()
+                .map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8)
+                .collect();
+            let image = RgbaImage::from_raw(width, height, rgba)
+                .context("Failed to build output image buffer")?;
+            if format == ImageFormat::Jpeg {
+                let image = image::DynamicImage::ImageRgba8(image).into_rgb8();
+                JpegEncoder::new_with_quality(&mut writer, quality).write_image(
+                    &image,
+                    width,
+                    height,
+                    ExtendedColorType::Rgb8,
+                )?;
+            } else if !format.has_alpha() {
+                image::DynamicImage::ImageRgba8(image)
+                    .into_rgb8()
+                    .write_to(&mut writer, format.image_format())?;
+            } else {
+                image.write_to(&mut writer, format.image_format())?;
+            }
+        }
+        BitDepth::Sixteen => {
+            let rgba = rgba
+                .into_iter()
+                .map(|v| (v.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16)
+                .collect();
+            let image = ImageBuffer::<Rgba<u16>, Vec<u16>>::from_raw(width, height, rgba)
+                .context("Failed to build output image buffer")?;
+            image.write_to(&mut writer, format.image_format())?;
+        }
+    }
+    Ok(bytes)
+}
+
+/// Box filter an image with `channels` interleaved channels down by `factor` in both
+/// directions. Leftover rows and columns that don't fill a whole box are dropped.
+pub fn downsample(
+    data: Vec<f32>,
+    width: u32,
+    height: u32,
+    channels: usize,
+    factor: u32,
+) -> Vec<f32> {
+    if factor == 1 {
+        return data;
+    }
+    let (width, height, factor) = (width as usize, height as usize, factor as usize);
+    let (out_width, out_height) = (width / factor, height / factor);
+    let weight = 1.0 / (factor * factor) as f32;
+
+    let mut out = vec![0.0; out_width * out_height * channels];
+    for y in 0..out_height * factor {
+        for x in 0..out_width * factor {
+            let src = (y * width + x) * channels;
+            let dst = ((y / factor) * out_width + x / factor) * channels;
+            for c in 0..channels {
+                out[dst + c] += data[src + c] * weight;
+            }
+        }
+    }
+    out
+}
+
+/// Resize a background image to the output size, as f32 RGB in 0..1. Alpha is dropped.
+///
+/// Nb: The colors aren't linearized. Splat colors are fit to the (sRGB encoded) training
+/// images, so the render is in the same space as a regular image file.
+pub fn background_pixels(image: &image::DynamicImage, width: u32, height: u32) -> Vec<f32> {
+    image
+        .resize_exact(width, height, image::imageops::FilterType::Triangle)
+        .into_rgb32f()
+        .into_raw()
+}
+
+/// Composite a render over straight alpha `background` pixels. The render has to be made
+/// with a black background, so its colors are premultiplied by its alpha. The result uses
+/// straight alpha like the background.
+pub fn composite_over(rgba: &[f32], background: &[f32]) -> Vec<f32> {
+    rgba.chunks_exact(4)
+        .zip(background.chunks_exact(4))
+        .flat_map(|(fg, bg)| {
+            let bg_weight = bg[3] * (1.0 - fg[3]);
+            let alpha = fg[3] + bg_weight;
+            let color = |c: usize| {
+                if alpha > 0.0 {
+                    (fg[c] + bg[c] * bg_weight) / alpha
+                } else {
+                    0.0
+                }
+            };
+            [color(0), color(1), color(2), alpha]
+        })
+        .collect()
+}
+
+/// A straight alpha f32 RGBA checkerboard of `cell` pixel squares in a light and a dark
+/// gray, starting with a light square in the top left corner.
+pub fn checkerboard(width: u32, height: u32, cell: u32) -> Vec<f32> {
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x / cell + y / cell) % 2 == 0))
+        .flat_map(|light| {
+            let v = if light { 0.8 } else { 0.6 };
+            [v, v, v, 1.0]
+        })
+        .collect()
+}
+
+/// Write an alpha matte as a grayscale image, quantized to `bit_depth` bits.
+pub fn save_alpha(
+    path: &Path,
+    width: u32,
+    height: u32,
+    alpha: &[f32],
+    bit_depth: BitDepth,
+) -> Result<()> {
+    let format = ImageFormat::from_path(path)?;
+    if format == ImageFormat::Exr {
+        anyhow::bail!("Alpha mattes can't be written as EXR, the EXR render already has alpha");
+    }
+    if format == ImageFormat::Npy {
+        std::fs::write(path, encode_npy(&[height as usize, width as usize], alpha)?)?;
+        return Ok(());
+    }
+    if format == ImageFormat::Pfm {
+        std::fs::write(path, encode_pfm(width, height, 1, alpha)?)?;
+        return Ok(());
+    }
+    check_image_format(format, bit_depth)?;
+
+    match bit_depth {
+        BitDepth::Eight => {
+            let pixels = alpha
+                .iter()
+                .map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8)
+                .collect();
+            let image = ImageBuffer::<Luma<u8>, Vec<u8>>::from_raw(width, height, pixels)
+                .context("Failed to build alpha image buffer")?;
+            image.save_with_format(path, format.image_format())?;
+        }
+        BitDepth::Sixteen => {
+            let pixels = alpha
+                .iter()
+                .map(|v| (v.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16)
+                .collect();
+            let image = ImageBuffer::<Luma<u16>, Vec<u16>>::from_raw(width, height, pixels)
+                .context("Failed to build alpha image buffer")?;
+            image.save_with_format(path, format.image_format())?;
+        }
+    }
+    Ok(())
+}
+
+/// Map blended normals to 0..1 colors as `0.5 * n + 0.5`.
+///
+/// Blended normals are shorter than unit length where splats of different orientations
+/// overlap or the coverage is partial, so they're normalized first. Pixels without any
+/// normal map to 0.5 gray.
+pub fn encode_normals(normals: &[f32]) -> Vec<f32> {
+    normals
+        .chunks_exact(3)
+        .flat_map(|n| {
+            let n = glam::Vec3::from_slice(n).normalize_or_zero();
+            (n * 0.5 + 0.5).to_array()
+        })
+        .collect()
+}
+
+/// Write a normal map as an RGB image, quantized to `bit_depth` bits. See
+/// [`encode_normals`]. NumPy and PFM files store the blended normals as they are, NumPy
+/// files with shape `[H, W, 3]`.
+pub fn save_normals(
+    path: &Path,
+    width: u32,
+    height: u32,
+    normals: &[f32],
+    bit_depth: BitDepth,
+) -> Result<()> {
+    let format = ImageFormat::from_path(path)?;
+    if format == ImageFormat::Exr {
+        anyhow::bail!("Normal maps can't be written as EXR, use a PNG");
+    }
+    if format == ImageFormat::Npy {
+        std::fs::write(
+            path,
+            encode_npy(&[height as usize, width as usize, 3], normals)?,
+        )?;
+        return Ok(());
+    }
+    if format == ImageFormat::Pfm {
+        std::fs::write(path, encode_pfm(width, height, 3, normals)?)?;
+        return Ok(());
+    }
+    check_image_format(format, bit_depth)?;
+
+    let colors = encode_normals(normals);
+    match bit_depth {
+        BitDepth::Eight => {
+            let pixels = colors.iter().map(|v| (v * 255.0).round() as u8).collect();
+            let image = ImageBuffer::<Rgb<u8>, Vec<u8>>::from_raw(width, height, pixels)
+                .context("Failed to build normal image buffer")?;
+            image.save_with_format(path, format.image_format())?;
+        }
+        BitDepth::Sixteen => {
+            let pixels = colors
+                .iter()
+                .map(|v| (v * u16::MAX as f32).round() as u16)
+                .collect();
+            let image = ImageBuffer::<Rgb<u16>, Vec<u16>>::from_raw(width, height, pixels)
+                .context("Failed to build normal image buffer")?;
+            image.save_with_format(path, format.image_format())?;
+        }
+    }
+    Ok(())
+}
+
+/// Encode f32 RGBA (and optionally depth) as an OpenEXR image.
+///
+/// Pixels are read straight from the rendered buffer while the image is encoded, so no
+/// per-channel copy of the image is made.
+fn encode_exr(
+    bytes: &mut Vec<u8>,
+    width: u32,
+    height: u32,
+    rgba: &[f32],
+    depth: Option<&[f32]>,
+) -> Result<()> {
+    use exr::prelude::{Encoding, Image, Layer, LayerAttributes, SpecificChannels, WritableImage};
+
+    let (width, height) = (width as usize, height as usize);
+    anyhow::ensure!(
+        rgba.len() == width * height * 4,
+        "Image buffer doesn't match the output size"
+    );
+    let pixel = |pos: exr::math::Vec2<usize>| {
+        let i = (pos.y() * width + pos.x()) * 4;
+        (rgba[i], rgba[i + 1], rgba[i + 2], rgba[i + 3])
+    };
+
+    match depth {
+        None => {
+            let layer = Layer::new(
+                (width, height),
+                LayerAttributes::default(),
+                Encoding::SMALL_LOSSLESS,
+                SpecificChannels::rgba(pixel),
+            );
+            Image::from_layer(layer)
+                .write()
+                .to_buffered(Cursor::new(&mut *bytes))?;
+        }
+        Some(depth) => {
+            anyhow::ensure!(
+                depth.len() == width * height,
+                "Depth buffer doesn't match the output size"
+            );
+            let channels = SpecificChannels::build()
+                .with_channel::<f32>("R")
+                .with_channel::<f32>("G")
+                .with_channel::<f32>("B")
+                .with_channel::<f32>("A")
+                .with_channel::<f32>("depth.Z")
+                .with_pixel_fn(|pos: exr::math::Vec2<usize>| {
+                    let (r, g, b, a) = pixel(pos);
+                    (r, g, b, a, depth[pos.y() * width + pos.x()])
+                });
+            let layer = Layer::new(
+                (width, height),
+                LayerAttributes::default(),
+                Encoding::SMALL_LOSSLESS,
+                channels,
+            );
+            Image::from_layer(layer)
+                .write()
+                .to_buffered(Cursor::new(&mut *bytes))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn depth_format_from_extension() {
+        assert_eq!(
+            DepthFormat::from_path(Path::new("d.PNG")).unwrap(),
+            DepthFormat::Png16
+        );
+        assert_eq!(
+            DepthFormat::from_path(Path::new("d.f32")).unwrap(),
+            DepthFormat::RawF32
+        );
+        assert!(DepthFormat::from_path(Path::new("d.jpg")).is_err());
+    }
+
+    /// Parse the header of a `.npy` file, returning the header dict and the data.
+    fn parse_npy(bytes: &[u8]) -> (&str, Vec<f32>) {
+        assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+        let len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        assert_eq!((10 + len) % 64, 0);
+        let header = std::str::from_utf8(&bytes[10..10 + len]).unwrap();
+        assert!(header.ends_with('\n'));
+        let data = bytes[10 + len..]
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        (header.trim_end(), data)
+    }
+
+    #[test]
+    fn npy_round_trip() {
+        let rgba = vec![2.5, -0.25, 0.5, 0.75, 0.0, 1.0, 10.0, 1.0];
+        let bytes = encode_image(
+            ImageFormat::Npy,
+            1,
+            2,
+            rgba.clone(),
+            BitDepth::Eight,
+            90,
+            None,
+        )
+        .unwrap();
+        let (header, data) = parse_npy(&bytes);
+        assert_eq!(
+            header,
+            "{'descr': '<f4', 'fortran_order': False, 'shape': (2, 1, 4), }"
+        );
+        assert_eq!(data, rgba);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("depth.npy");
+        save_depth(&path, 3, 1, &[1.0, 2.0, 3.5], 1000.0).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        let (header, data) = parse_npy(&bytes);
+        assert!(header.contains("'shape': (1, 3)"));
+        assert_eq!(data, [1.0, 2.0, 3.5]);
+
+        assert!(encode_npy(&[2, 2], &[0.0; 3]).is_err());
+        assert_eq!(
+            ImageFormat::from_path(Path::new("out.NPY")).unwrap(),
+            ImageFormat::Npy
+        );
+    }
+
+    #[test]
+    fn normals_encoding() {
+        let normals = [0.0, 0.0, -0.5, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+        assert_eq!(
+            encode_normals(&normals),
+            [0.5, 0.5, 0.0, 0.5, 0.5, 0.5, 1.0, 0.5, 0.5]
+        );
+    }
+
+    #[test]
+    fn resolve_depth_far_and_normalize() {
+        let depth = [1.0, 1.5, 0.0, 4.0];
+        let alpha = [1.0, 0.5, 0.0, 1.0];
+        assert_eq!(
+            resolve_depth(&depth, &alpha, 100.0, false),
+            [1.0, 3.0, 100.0, 4.0]
+        );
+        assert_eq!(
+            resolve_depth(&depth, &alpha, 100.0, true),
+            [0.0, 2.0 / 3.0, 1.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn exr_keeps_float_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("render.exr");
+        let rgba = vec![2.5, -0.25, 0.5, 0.75, 0.0, 1.0, 10.0, 1.0];
+        save_image(
+            &path,
+            ImageFormat::Exr,
+            2,
+            1,
+            rgba.clone(),
+            BitDepth::Eight,
+            90,
+            None,
+        )
+        .unwrap();
+
+        let read = image::open(&path).unwrap().into_rgba32f();
+        assert_eq!(read.into_raw(), rgba);
+    }
+
+    #[test]
+    fn encodes_in_memory() {
+        let rgba = vec![1.0, 0.5, 0.0, 1.0, 0.0, 0.0, 1.0, 0.5];
+        let bytes = encode_image(ImageFormat::Png, 2, 1, rgba, BitDepth::Eight, 90, None).unwrap();
+        let read = image::load_from_memory_with_format(&bytes, image::ImageFormat::Png)
+            .unwrap()
+            .into_rgba8();
+        assert_eq!(read.into_raw(), [255, 128, 0, 255, 0, 0, 255, 128]);
+    }
+
+    #[test]
+    fn exr_depth_layer() {
+        use exr::prelude::{ReadChannels, ReadLayers};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("render.exr");
+        let rgba = vec![1.0, 0.5, 0.25, 1.0, 0.0, 0.0, 0.0, 0.0];
+        let depth = [3.5, 100.0];
+        save_image(
+            &path,
+            ImageFormat::Exr,
+            2,
+            1,
+            rgba,
+            BitDepth::Eight,
+            90,
+            Some(&depth),
+        )
+        .unwrap();
+
+        let image = exr::prelude::read()
+            .no_deep_data()
+            .largest_resolution_level()
+            .all_channels()
+            .all_layers()
+            .all_attributes()
+            .from_file(&path)
+            .unwrap();
+        let channels = &image.layer_data[0].channel_data.list;
+        let z = channels
+            .iter()
+            .find(|c| c.name.to_string() == "depth.Z")
+            .expect("Missing depth channel");
+        let values: Vec<f32> = z.sample_data.values_as_f32().collect();
+        assert_eq!(values, depth);
+    }
+
+    #[test]
+    fn sixteen_bit_png() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("render.png");
+        save_image(
+            &path,
+            ImageFormat::Png,
+            1,
+            1,
+            vec![0.0, 0.5, 1.0, 2.0],
+            BitDepth::Sixteen,
+            90,
+            None,
+        )
+        .unwrap();
+
+        let read = image::open(&path).unwrap().into_rgba16();
+        assert_eq!(read.into_raw(), [0, 32768, 65535, 65535]);
+
+        let err = check_image_format(ImageFormat::Jpeg, BitDepth::Sixteen).unwrap_err();
+        assert!(err.to_string().contains(".exr, .npy and .pfm"));
+        assert!(check_image_format(ImageFormat::Exr, BitDepth::Sixteen).is_ok());
+        assert!(check_image_format(ImageFormat::Jpeg, BitDepth::Eight).is_ok());
+    }
+
+    /// Parse a little endian PFM file into its channel count, size and top to bottom rows.
+    fn read_pfm(bytes: &[u8]) -> (usize, u32, u32, Vec<f32>) {
+        let mut lines = bytes.splitn(4, |&b| b == b'\n');
+        let mut line = || {
+            std::str::from_utf8(lines.next().unwrap())
+                .unwrap()
+                .to_owned()
+        };
+        let channels = match line().as_str() {
+            "Pf" => 1,
+            "PF" => 3,
+            magic => panic!("Unexpected PFM magic {magic}"),
+        };
+        let size = line();
+        let (width, height) = size.split_once(' ').unwrap();
+        let (width, height): (u32, u32) = (width.parse().unwrap(), height.parse().unwrap());
+        let scale: f32 = line().parse().unwrap();
+        assert!(scale < 0.0, "Expected little endian data");
+        let data: Vec<f32> = lines
+            .next()
+            .unwrap()
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        let rows: Vec<f32> = data
+            .chunks_exact(width as usize * channels)
+            .rev()
+            .flatten()
+            .copied()
+            .collect();
+        (channels, width, height, rows)
+    }
+
+    #[test]
+    fn pfm_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // A 2x2 image where every value is different, so a wrong row order shows.
+        let rgba: Vec<f32> = (0..16).map(|i| i as f32 * 0.5 - 2.0).collect();
+        let path = dir.path().join("render.pfm");
+        let format = ImageFormat::from_path(&path).unwrap();
+        assert_eq!(format, ImageFormat::Pfm);
+        save_image(&path, format, 2, 2, rgba.clone(), BitDepth::Eight, 90, None).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(bytes.starts_with(b"PF\n2 2\n-1.0\n"));
+        let (channels, width, height, rgb) = read_pfm(&bytes);
+        assert_eq!((channels, width, height), (3, 2, 2));
+        let expected: Vec<f32> = rgba
+            .chunks_exact(4)
+            .flat_map(|c| [c[0], c[1], c[2]])
+            .collect();
+        assert_eq!(rgb, expected);
+        // The bottom row comes first in the file.
+        assert_eq!(
+            f32::from_le_bytes(bytes[12..16].try_into().unwrap()),
+            rgba[8]
+        );
+
+        let depth = [1.0, 2.5, f32::INFINITY, 0.0, 7.25, -1.0];
+        let depth_path = dir.path().join("depth.pfm");
+        assert_eq!(
+            DepthFormat::from_path(&depth_path).unwrap(),
+            DepthFormat::Pfm
+        );
+        save_depth(&depth_path, 3, 2, &depth, 1000.0).unwrap();
+        let bytes = std::fs::read(&depth_path).unwrap();
+        assert!(bytes.starts_with(b"Pf\n3 2\n-1.0\n"));
+        assert_eq!(read_pfm(&bytes), (1, 3, 2, depth.to_vec()));
+
+        assert!(encode_pfm(1, 1, 4, &[0.0; 4]).is_err());
+        assert!(encode_pfm(2, 1, 1, &[0.0]).is_err());
+    }
+
+    #[test]
+    fn alpha_matte() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mask.png");
+        save_alpha(&path, 3, 1, &[0.0, 0.5, 1.5], BitDepth::Eight).unwrap();
+        let read = image::open(&path).unwrap();
+        assert_eq!(read.color(), image::ColorType::L8);
+        assert_eq!(read.into_luma8().into_raw(), [0, 128, 255]);
+
+        save_alpha(&path, 3, 1, &[0.0, 0.5, 1.0], BitDepth::Sixteen).unwrap();
+        let read = image::open(&path).unwrap();
+        assert_eq!(read.into_luma16().into_raw(), [0, 32768, 65535]);
+
+        let exr = dir.path().join("mask.exr");
+        assert!(save_alpha(&exr, 1, 1, &[1.0], BitDepth::Eight).is_err());
+    }
+
+    #[test]
+    fn box_downsample() {
+        // A 4x2 image with 2 channels, downsampled to 2x1.
+        let data = vec![
+            0.0, 1.0, 1.0, 1.0, 4.0, 0.0, 4.0, 0.0, //
+            2.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 2.0,
+        ];
+        assert_eq!(downsample(data.clone(), 4, 2, 2, 1), data);
+        assert_eq!(downsample(data, 4, 2, 2, 2), [1.0, 1.0, 2.0, 0.5]);
+    }
+
+    #[test]
+    fn composites_over_background() {
+        // Half covered red splat over an opaque blue background, an empty pixel over a half
+        // transparent green background, and an empty pixel over nothing.
+        let render = [0.5, 0.0, 0.0, 0.5, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+        let background = [0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 0.0, 0.5, 1.0, 1.0, 1.0, 0.0];
+        let out = composite_over(&render, &background);
+        let expected = [0.5, 0.0, 0.5, 1.0, 0.0, 1.0, 0.0, 0.5, 0.0, 0.0, 0.0, 0.0];
+        for (a, b) in out.iter().zip(expected) {
+            assert!((a - b).abs() < 1e-6, "{out:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn checkerboard_cells() {
+        let checker = checkerboard(5, 3, 2);
+        let gray: Vec<f32> = checker.chunks_exact(4).map(|c| c[0]).collect();
+        assert_eq!(
+            gray,
+            [
+                0.8, 0.8, 0.6, 0.6, 0.8, //
+                0.8, 0.8, 0.6, 0.6, 0.8, //
+                0.6, 0.6, 0.8, 0.8, 0.6,
+            ]
+        );
+        assert!(checker.chunks_exact(4).all(|c| c[3] == 1.0));
+
+        // Empty pixels show the checkerboard, covered ones the render.
+        let rgba = [0.0, 0.0, 0.0, 0.0, 0.5, 0.25, 0.0, 0.5];
+        let preview = composite_over(&rgba, &checkerboard(2, 1, 1));
+        let expected = [0.8, 0.8, 0.8, 1.0, 0.8, 0.55, 0.3, 1.0];
+        assert!(
+            preview
+                .iter()
+                .zip(expected)
+                .all(|(a, b)| (a - b).abs() < 1e-6)
+        );
+    }
+
+    #[test]
+    fn image_format_from_extension() {
+        let path = Path::new("render.JPG");
+        assert_eq!(ImageFormat::from_path(path).unwrap(), ImageFormat::Jpeg);
+        assert_eq!(
+            ImageFormat::resolve(path, Some(ImageFormat::Exr)).unwrap(),
+            ImageFormat::Exr
+        );
+        assert!(ImageFormat::from_path(Path::new("render")).is_err());
+        assert_eq!(
+            ImageFormat::from_path(Path::new("render.bmp")).unwrap(),
+            ImageFormat::Bmp
+        );
+        assert!(ImageFormat::from_path(Path::new("render.tiff")).is_err());
+    }
+
+    #[test]
+    fn drops_alpha_without_alpha_channel() {
+        let rgba = vec![1.0, 0.5, 0.0, 0.25, 0.0, 0.0, 1.0, 0.0];
+        for format in [ImageFormat::Bmp, ImageFormat::Jpeg] {
+            let bytes =
+                encode_image(format, 2, 1, rgba.clone(), BitDepth::Eight, 95, None).unwrap();
+            let read = image::load_from_memory_with_format(&bytes, format.image_format()).unwrap();
+            assert!(!read.color().has_alpha());
+        }
+    }
+}
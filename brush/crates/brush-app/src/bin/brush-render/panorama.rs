@@ -0,0 +1,101 @@
+use brush_render::camera::CubeFace;
+use glam::{Vec2, vec3};
+use std::f32::consts::{PI, TAU};
+
+/// Resample cubemap faces into an equirectangular panorama of `width` x `height` pixels.
+///
+/// The faces are square images of `face_size` pixels with `channels` interleaved channels, in
+/// [`CubeFace::ALL`] order as rendered by `Camera::cube_faces`. The panorama uses the same
+/// layout as an equirectangular camera: the center of the image looks forward, longitude
+/// increases to the right and the top row looks straight up.
+///
+/// With `ray_distance` the samples are planar depths of the face cameras, and get converted
+/// to the distance along the ray.
+pub fn equirect_from_cube(
+    faces: [&[f32]; 6],
+    face_size: u32,
+    channels: usize,
+    width: u32,
+    height: u32,
+    ray_distance: bool,
+) -> Vec<f32> {
+    let mut out = Vec::with_capacity(width as usize * height as usize * channels);
+    for y in 0..height {
+        let lat = ((y as f32 + 0.5) / height as f32 - 0.5) * PI;
+        for x in 0..width {
+            let lon = ((x as f32 + 0.5) / width as f32 - 0.5) * TAU;
+            let dir = vec3(lat.cos() * lon.sin(), lat.sin(), lat.cos() * lon.cos());
+
+            let face = CubeFace::from_dir(dir);
+            let local = face.local_rotation().inverse() * dir;
+            let plane = Vec2::new(local.x, local.y) / local.z;
+            // 90° fov, so the plane coordinates span [-1, 1] over the face.
+            let px = (plane * 0.5 + 0.5) * face_size as f32;
+            let scale = if ray_distance {
+                plane.extend(1.0).length()
+            } else {
+                1.0
+            };
+
+            let face_data = faces[face as usize];
+            for c in 0..channels {
+                out.push(sample_bilinear(face_data, face_size, channels, px, c) * scale);
+            }
+        }
+    }
+    out
+}
+
+/// Bilinearly sample channel `c` at `pos` in pixel units, clamping at the image border.
+fn sample_bilinear(data: &[f32], size: u32, channels: usize, pos: Vec2, c: usize) -> f32 {
+    let max = (size - 1) as f32;
+    let p = (pos - 0.5).clamp(Vec2::ZERO, Vec2::splat(max));
+    let (x0, y0) = (p.x.floor() as usize, p.y.floor() as usize);
+    let (x1, y1) = (
+        (x0 + 1).min(size as usize - 1),
+        (y0 + 1).min(size as usize - 1),
+    );
+    let (tx, ty) = (p.x - x0 as f32, p.y - y0 as f32);
+    let at = |x: usize, y: usize| data[(y * size as usize + x) * channels + c];
+    let top = at(x0, y0) * (1.0 - tx) + at(x1, y0) * tx;
+    let bottom = at(x0, y1) * (1.0 - tx) + at(x1, y1) * tx;
+    top * (1.0 - ty) + bottom * ty
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_matching_faces() {
+        // Fill every face with its own index.
+        let faces: Vec<Vec<f32>> = (0..6).map(|i| vec![i as f32; 4 * 4]).collect();
+        let faces: [&[f32]; 6] = std::array::from_fn(|i| faces[i].as_slice());
+        let (w, h) = (16, 8);
+        let pano = equirect_from_cube(faces, 4, 1, w, h, false);
+        let at = |x: u32, y: u32| pano[(y * w + x) as usize];
+
+        assert_eq!(at(w / 2, h / 2), CubeFace::PosZ as usize as f32);
+        assert_eq!(at(w * 3 / 4, h / 2), CubeFace::PosX as usize as f32);
+        assert_eq!(at(w / 4, h / 2), CubeFace::NegX as usize as f32);
+        assert_eq!(at(0, h / 2), CubeFace::NegZ as usize as f32);
+        // +Y points down in camera space.
+        assert_eq!(at(w / 2, 0), CubeFace::NegY as usize as f32);
+        assert_eq!(at(w / 2, h - 1), CubeFace::PosY as usize as f32);
+    }
+
+    #[test]
+    fn converts_planar_depth_to_distance() {
+        let faces = [[1.0f32; 4]; 6];
+        let faces: [&[f32]; 6] = std::array::from_fn(|i| faces[i].as_slice());
+        let pano = equirect_from_cube(faces, 2, 1, 64, 32, true);
+        // Straight ahead the planar depth is the distance, away from the face center the
+        // distance is larger.
+        assert!((pano[16 * 64 + 32] - 1.0).abs() < 0.05);
+        assert!(
+            pano.iter()
+                .all(|&d| (1.0..=3f32.sqrt() + 1e-4).contains(&d))
+        );
+        assert!(pano.iter().any(|&d| d > 1.5));
+    }
+}
@@ -0,0 +1,148 @@
+use crate::meta::{InputMeta, OutputMeta};
+use serde::Serialize;
+use std::fmt;
+
+/// Summary of a render printed by --print-stats.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct RenderSummary {
+    /// Splats read from the inputs.
+    pub num_loaded: u32,
+    /// Splats left after cropping and --min-opacity.
+    pub num_rendered: u32,
+    /// Splats that survived the frustum and opacity culling of the renderer, summed over the
+    /// views of the output.
+    pub num_visible: u32,
+    pub num_intersections: u32,
+    /// Tiles with at least one splat, summed over the views of the output.
+    pub tiles_used: u32,
+    pub max_splats_per_tile: u32,
+    /// Bytes of GPU memory reserved by the allocator. Reserved memory is kept for reuse and
+    /// not given back, so this is the peak of the run.
+    pub gpu_bytes_reserved: u64,
+    /// Bytes of GPU memory in use after the render.
+    pub gpu_bytes_in_use: u64,
+    pub timings: PhaseTimings,
+}
+
+/// Wall clock time of each stage, in milliseconds.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct PhaseTimings {
+    pub parse_ms: f64,
+    pub upload_ms: f64,
+    pub render_ms: f64,
+    pub readback_ms: f64,
+    pub encode_ms: f64,
+    /// From the start of brush-render, including setting up the GPU.
+    pub total_ms: f64,
+}
+
+impl RenderSummary {
+    pub fn new(
+        inputs: &[InputMeta],
+        num_rendered: u32,
+        outputs: &[OutputMeta],
+        memory: &burn_cubecl::cubecl::MemoryUsage,
+        total_ms: f64,
+    ) -> Self {
+        Self {
+            num_loaded: inputs.iter().map(|i| i.num_splats).sum(),
+            num_rendered,
+            num_visible: outputs.iter().map(|o| o.num_visible).sum(),
+            num_intersections: outputs.iter().map(|o| o.num_intersections).sum(),
+            tiles_used: outputs.iter().map(|o| o.tiles_used).sum(),
+            max_splats_per_tile: outputs
+                .iter()
+                .map(|o| o.max_splats_per_tile)
+                .max()
+                .unwrap_or(0),
+            gpu_bytes_reserved: memory.bytes_reserved,
+            gpu_bytes_in_use: memory.bytes_in_use,
+            timings: PhaseTimings {
+                parse_ms: inputs.iter().map(|i| i.parse_ms).sum(),
+                upload_ms: inputs.iter().map(|i| i.upload_ms).sum(),
+                render_ms: outputs.iter().map(|o| o.render_ms).sum(),
+                readback_ms: outputs.iter().map(|o| o.readback_ms).sum(),
+                encode_ms: outputs.iter().map(|o| o.encode_ms).sum(),
+                total_ms,
+            },
+        }
+    }
+}
+
+/// One `key=value` line per statistic, like the --benchmark output.
+impl fmt::Display for RenderSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "splats_loaded={}", self.num_loaded)?;
+        writeln!(f, "splats_rendered={}", self.num_rendered)?;
+        writeln!(f, "splats_visible={}", self.num_visible)?;
+        writeln!(f, "intersections={}", self.num_intersections)?;
+        writeln!(f, "tiles_used={}", self.tiles_used)?;
+        writeln!(f, "max_splats_per_tile={}", self.max_splats_per_tile)?;
+        writeln!(f, "gpu_bytes_reserved={}", self.gpu_bytes_reserved)?;
+        writeln!(f, "gpu_bytes_in_use={}", self.gpu_bytes_in_use)?;
+        let t = &self.timings;
+        writeln!(f, "parse_ms={:.3}", t.parse_ms)?;
+        writeln!(f, "upload_ms={:.3}", t.upload_ms)?;
+        writeln!(f, "render_ms={:.3}", t.render_ms)?;
+        writeln!(f, "readback_ms={:.3}", t.readback_ms)?;
+        writeln!(f, "encode_ms={:.3}", t.encode_ms)?;
+        write!(f, "total_ms={:.3}", t.total_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::uvec2;
+
+    fn output(num_visible: u32, tiles_used: u32, max_splats_per_tile: u32) -> OutputMeta {
+        OutputMeta {
+            path: "out.png".into(),
+            img_size: uvec2(64, 32),
+            num_visible,
+            num_intersections: num_visible * 2,
+            tiles_used,
+            max_splats_per_tile,
+            render_ms: 2.0,
+            readback_ms: 1.0,
+            encode_ms: 3.0,
+            psnr: None,
+            ssim: None,
+        }
+    }
+
+    #[test]
+    fn summarises_outputs() {
+        let inputs = [InputMeta {
+            path: "in.ply".into(),
+            sha256: None,
+            num_splats: 100,
+            parse_ms: 4.0,
+            upload_ms: 0.5,
+        }];
+        let memory = burn_cubecl::cubecl::MemoryUsage {
+            number_allocs: 3,
+            bytes_in_use: 1024,
+            bytes_padding: 0,
+            bytes_reserved: 4096,
+        };
+        // Eg. the two eyes of a stereo render.
+        let outputs = [output(40, 6, 12), output(30, 5, 20)];
+        let summary = RenderSummary::new(&inputs, 80, &outputs, &memory, 50.0);
+        assert_eq!(summary.num_loaded, 100);
+        assert_eq!(summary.num_visible, 70);
+        assert_eq!(summary.num_intersections, 140);
+        assert_eq!(summary.tiles_used, 11);
+        assert_eq!(summary.max_splats_per_tile, 20);
+        assert_eq!(summary.timings.render_ms, 4.0);
+
+        let text = summary.to_string();
+        assert!(text.starts_with("splats_loaded=100\nsplats_rendered=80\n"));
+        assert!(text.ends_with("total_ms=50.000"));
+
+        let json: serde_json::Value = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["max_splats_per_tile"], 20);
+        assert_eq!(json["gpu_bytes_reserved"], 4096);
+        assert_eq!(json["timings"]["parse_ms"], 4.0);
+    }
+}
@@ -0,0 +1,200 @@
+use anyhow::{Context, Result};
+use brush_render::camera::{Camera, focal_to_fov, fov_to_focal};
+use glam::UVec2;
+use serde::Deserialize;
+
+/// Camera intrinsics, which can be set for the whole file or per frame.
+#[derive(Deserialize)]
+struct Intrinsics {
+    camera_angle_x: Option<f64>,
+    camera_angle_y: Option<f64>,
+    fl_x: Option<f64>,
+    fl_y: Option<f64>,
+    cx: Option<f64>,
+    cy: Option<f64>,
+    w: Option<f64>,
+    h: Option<f64>,
+    k1: Option<f64>,
+    k2: Option<f64>,
+    k3: Option<f64>,
+    k4: Option<f64>,
+    p1: Option<f64>,
+    p2: Option<f64>,
+}
+
+impl Intrinsics {
+    fn has_distortion(&self) -> bool {
+        [self.k1, self.k2, self.k3, self.k4, self.p1, self.p2]
+            .iter()
+            .any(|k| k.is_some_and(|k| k != 0.0))
+    }
+}
+
+#[derive(Deserialize)]
+struct Frame {
+    #[serde(flatten)]
+    intrinsics: Intrinsics,
+    /// OpenGL style camera to world matrix, row major.
+    transform_matrix: [[f32; 4]; 4],
+    #[serde(default)]
+    file_path: String,
+}
+
+#[derive(Deserialize)]
+struct TransformsFile {
+    #[serde(flatten)]
+    intrinsics: Intrinsics,
+    frames: Vec<Frame>,
+}
+
+/// A camera of a nerfstudio / NeRF synthetic `transforms.json` file.
+pub struct TransformsView {
+    pub file_path: String,
+    pub camera: Camera,
+    pub img_size: UVec2,
+}
+
+/// Parse all frames of a `transforms.json`. Lens distortion isn't supported, frames with
+/// distortion coefficients are rendered undistorted and reported in the returned warnings.
+pub fn parse_transforms(json: &str) -> Result<(Vec<TransformsView>, Vec<String>)> {
+    let file: TransformsFile =
+        serde_json::from_str(json).context("Failed to parse transforms file")?;
+    let scene = &file.intrinsics;
+
+    let mut warnings = vec![];
+    let views = file
+        .frames
+        .iter()
+        .enumerate()
+        .map(|(i, frame)| {
+            let cam = &frame.intrinsics;
+            let name = if frame.file_path.is_empty() {
+                format!("frame {i}")
+            } else {
+                frame.file_path.clone()
+            };
+
+            if cam.has_distortion() || scene.has_distortion() {
+                warnings.push(format!(
+                    "'{name}' has lens distortion which is ignored, the render won't match \
+                     the original image exactly"
+                ));
+            }
+
+            let w = cam.w.or(scene.w).context("Missing image width (w)")? as u32;
+            let h = cam.h.or(scene.h).context("Missing image height (h)")? as u32;
+
+            let fov_x = cam
+                .camera_angle_x
+                .or(cam.fl_x.map(|fx| focal_to_fov(fx, w)))
+                .or(scene.camera_angle_x)
+                .or(scene.fl_x.map(|fx| focal_to_fov(fx, w)));
+            let fov_y = cam
+                .camera_angle_y
+                .or(cam.fl_y.map(|fy| focal_to_fov(fy, h)))
+                .or(scene.camera_angle_y)
+                .or(scene.fl_y.map(|fy| focal_to_fov(fy, h)));
+            let (fov_x, fov_y) = match (fov_x, fov_y) {
+                (None, None) => anyhow::bail!("'{name}' has no focal length or field of view"),
+                (None, Some(fov_y)) => (focal_to_fov(fov_to_focal(fov_y, h), w), fov_y),
+                (Some(fov_x), None) => (fov_x, focal_to_fov(fov_to_focal(fov_x, w), h)),
+                (Some(fov_x), Some(fov_y)) => (fov_x, fov_y),
+            };
+
+            let cx = cam.cx.or(scene.cx).map_or(0.5, |cx| cx / w as f64);
+            let cy = cam.cy.or(scene.cy).map_or(0.5, |cy| cy / h as f64);
+
+            // Flip the Y and Z axes from OpenGL (Y up, looking down -Z) to Brush's convention.
+            let mut transform = glam::Mat4::from_cols_array_2d(&frame.transform_matrix).transpose();
+            transform.y_axis *= -1.0;
+            transform.z_axis *= -1.0;
+            let (_, rotation, position) = transform.to_scale_rotation_translation();
+
+            let camera = Camera::new(
+                position,
+                rotation,
+                fov_x,
+                fov_y,
+                glam::vec2(cx as f32, cy as f32),
+            );
+            anyhow::ensure!(camera.is_valid(), "'{name}' has an invalid camera");
+
+            Ok(TransformsView {
+                file_path: name,
+                camera,
+                img_size: UVec2::new(w, h),
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok((views, warnings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::{Vec3, uvec2, vec2, vec3};
+
+    const TRANSFORMS: &str = r#"{
+        "fl_x": 500, "fl_y": 520, "cx": 320, "cy": 200, "w": 640, "h": 480,
+        "frames": [
+            {
+                "file_path": "images/a.png",
+                "transform_matrix": [
+                    [1, 0, 0, 1],
+                    [0, 1, 0, 2],
+                    [0, 0, 1, 3],
+                    [0, 0, 0, 1]
+                ]
+            },
+            {
+                "file_path": "images/b.png",
+                "k1": 0.1,
+                "transform_matrix": [
+                    [1, 0, 0, 0],
+                    [0, 1, 0, 0],
+                    [0, 0, 1, 0],
+                    [0, 0, 0, 1]
+                ]
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn parses_frames() {
+        let (views, warnings) = parse_transforms(TRANSFORMS).unwrap();
+        assert_eq!(views.len(), 2);
+
+        let view = &views[0];
+        assert_eq!(view.file_path, "images/a.png");
+        assert_eq!(view.img_size, uvec2(640, 480));
+        assert!(view.camera.position.abs_diff_eq(vec3(1.0, 2.0, 3.0), 1e-6));
+        assert!(
+            view.camera
+                .center_uv
+                .abs_diff_eq(vec2(0.5, 200.0 / 480.0), 1e-6)
+        );
+        assert!(
+            view.camera
+                .focal(view.img_size)
+                .abs_diff_eq(vec2(500.0, 520.0), 1e-3)
+        );
+
+        // An identity OpenGL camera looks down -Z with +Y up.
+        let rot = view.camera.rotation;
+        assert!((rot * Vec3::Z).abs_diff_eq(-Vec3::Z, 1e-6));
+        assert!((rot * Vec3::Y).abs_diff_eq(-Vec3::Y, 1e-6));
+
+        // Only the distorted frame warns.
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("images/b.png"));
+    }
+
+    #[test]
+    fn needs_image_size() {
+        let json = r#"{"fl_x": 500, "frames": [{"transform_matrix": [
+            [1, 0, 0, 0], [0, 1, 0, 0], [0, 0, 1, 0], [0, 0, 0, 1]
+        ]}]}"#;
+        assert!(parse_transforms(json).is_err());
+    }
+}
@@ -0,0 +1,124 @@
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+/// Modification time and size of each watched file, `None` while a file doesn't exist.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Snapshot(Vec<Option<(SystemTime, u64)>>);
+
+impl Snapshot {
+    pub fn of(paths: &[PathBuf]) -> Self {
+        Self(
+            paths
+                .iter()
+                .map(|path| {
+                    let meta = std::fs::metadata(path).ok()?;
+                    Some((meta.modified().ok()?, meta.len()))
+                })
+                .collect(),
+        )
+    }
+
+    fn complete(&self) -> bool {
+        self.0.iter().all(Option::is_some)
+    }
+}
+
+/// Notices changes of the watched files by polling their [`Snapshot`].
+///
+/// Writers like a training run can take a while to write a file, so a change is only
+/// reported once the files stayed the same for a whole poll, and all of them exist.
+pub struct ChangeDetector {
+    seen: Snapshot,
+    pending: Option<Snapshot>,
+}
+
+impl ChangeDetector {
+    pub fn new(seen: Snapshot) -> Self {
+        Self {
+            seen,
+            pending: None,
+        }
+    }
+
+    /// Feed the latest poll. Returns whether the files changed since the last reported change
+    /// and then settled.
+    pub fn update(&mut self, current: Snapshot) -> bool {
+        if current == self.seen {
+            self.pending = None;
+            return false;
+        }
+        if self.pending.as_ref() == Some(&current) && current.complete() {
+            self.seen = current;
+            self.pending = None;
+            return true;
+        }
+        self.pending = Some(current);
+        false
+    }
+
+    /// Report the current files as changed again once they settle, eg. when they couldn't
+    /// be loaded as they were still being written.
+    pub fn retry(&mut self) {
+        self.seen = Snapshot::default();
+    }
+
+    /// Poll `paths` every `interval` until they changed and settled.
+    pub async fn wait(&mut self, paths: &[PathBuf], interval: Duration) {
+        loop {
+            tokio::time::sleep(interval).await;
+            if self.update(Snapshot::of(paths)) {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(files: &[Option<u64>]) -> Snapshot {
+        Snapshot(
+            files
+                .iter()
+                .map(|len| len.map(|len| (SystemTime::UNIX_EPOCH, len)))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn debounces_changes() {
+        let mut detector = ChangeDetector::new(snapshot(&[Some(10)]));
+        assert!(!detector.update(snapshot(&[Some(10)])));
+
+        // Still being written.
+        assert!(!detector.update(snapshot(&[Some(20)])));
+        assert!(!detector.update(snapshot(&[Some(30)])));
+        // Settled.
+        assert!(detector.update(snapshot(&[Some(30)])));
+        assert!(!detector.update(snapshot(&[Some(30)])));
+
+        // Replaced by removing and writing the file again.
+        assert!(!detector.update(snapshot(&[None])));
+        assert!(!detector.update(snapshot(&[None])));
+        assert!(!detector.update(snapshot(&[Some(40)])));
+        assert!(detector.update(snapshot(&[Some(40)])));
+
+        detector.retry();
+        assert!(!detector.update(snapshot(&[Some(40)])));
+        assert!(detector.update(snapshot(&[Some(40)])));
+    }
+
+    #[test]
+    fn snapshots_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("splats.ply");
+        let paths = [path.clone()];
+        assert_eq!(Snapshot::of(&paths), snapshot(&[None]));
+
+        std::fs::write(&path, [0u8; 12]).unwrap();
+        let written = Snapshot::of(&paths);
+        assert!(written.complete());
+        assert_eq!(written.0[0].unwrap().1, 12);
+    }
+}
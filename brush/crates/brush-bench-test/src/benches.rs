@@ -1,6 +1,6 @@
 use brush_dataset::scene::SceneBatch;
 use brush_render::{
-    AlphaMode, MainBackend,
+    AlphaMode, MainBackend, RenderOptions,
     camera::Camera,
     gaussian_splats::{SplatRenderMode, Splats},
     render_splats,
@@ -194,11 +194,112 @@ mod forward_rendering {
     }
 }
 
+/// Throughput of the tile size on a large scene. The tile size is fixed at build time, so
+/// `tests::tile_size_bench` rebuilds with each width in `BRUSH_TILE_SIZE` and runs this once
+/// per width:
+///
+/// ```text
+/// cargo test -p brush-bench-test --release tile_size_bench -- --ignored
+/// ```
+#[divan::bench_group(max_time = 4)]
+mod tile_size {
+    use brush_render::shaders::helpers::TILE_WIDTH;
+
+    use super::{
+        AutodiffModule, Backend, Camera, ITERS_PER_SYNC, MainBackend, Quat, Vec3, WgpuDevice,
+        gen_splats, render_splats,
+    };
+
+    pub(super) const SPLAT_COUNT: usize = 30_000_000;
+
+    pub(super) const TILE_WIDTHS: [u32; 3] = [8, 16, 32];
+
+    // Only the compiled in width can run, the arg labels the results with it.
+    #[divan::bench(args = [TILE_WIDTH])]
+    fn render_30m_splats(bencher: divan::Bencher, tile_width: u32) {
+        let device = WgpuDevice::default();
+        let splats = gen_splats(&device, SPLAT_COUNT).valid();
+        let camera = Camera::new(
+            Vec3::new(0.0, 0.0, 5.0),
+            Quat::IDENTITY,
+            50.0,
+            50.0,
+            glam::vec2(0.5, 0.5),
+        );
+        log::info!("Benchmarking {tile_width}x{tile_width} tiles");
+
+        bencher
+            .counter(divan::counter::ItemsCount::new(SPLAT_COUNT))
+            .bench_local(move || {
+                for _ in 0..ITERS_PER_SYNC {
+                    let _ =
+                        render_splats(&splats, &camera, glam::uvec2(1920, 1080), Vec3::ZERO, None);
+                }
+                MainBackend::sync(&device).expect("Failed to sync");
+            });
+    }
+}
+
+/// Sorting 30M keys, about the number of tile intersections of a large scene. Compares the
+/// GPU radix sort to burn's generic argsort.
+#[divan::bench_group(max_time = 4)]
+mod sorting {
+    use brush_render::MainBackendBase;
+    use brush_sort::radix_argsort;
+    use burn::tensor::{Int, Tensor};
+
+    use super::{Backend, Rng, SEED, SeedableRng, WgpuDevice};
+
+    const KEY_COUNT: usize = 30_000_000;
+
+    /// Random positive depths as their bit patterns, which sort the same as the floats.
+    fn gen_keys(device: &WgpuDevice) -> Tensor<MainBackendBase, 1, Int> {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(SEED);
+        let keys: Vec<i32> = (0..KEY_COUNT)
+            .map(|_| rng.random_range(0.01f32..1000.0).to_bits() as i32)
+            .collect();
+        Tensor::from_ints(keys.as_slice(), device)
+    }
+
+    #[divan::bench]
+    fn radix_argsort_30m(bencher: divan::Bencher) {
+        let device = WgpuDevice::default();
+        let keys = gen_keys(&device);
+        let values = Tensor::<MainBackendBase, 1, Int>::arange(0..KEY_COUNT as i64, &device);
+        let count = Tensor::<MainBackendBase, 1, Int>::from_ints([KEY_COUNT as i32], &device);
+
+        bencher
+            .counter(divan::counter::ItemsCount::new(KEY_COUNT))
+            .bench_local(move || {
+                let _ = radix_argsort(
+                    keys.clone().into_primitive(),
+                    values.clone().into_primitive(),
+                    &count.clone().into_primitive(),
+                    32,
+                );
+                MainBackendBase::sync(&device).expect("Failed to sync");
+            });
+    }
+
+    #[divan::bench]
+    fn burn_argsort_30m(bencher: divan::Bencher) {
+        let device = WgpuDevice::default();
+        let keys = gen_keys(&device);
+
+        bencher
+            .counter(divan::counter::ItemsCount::new(KEY_COUNT))
+            .bench_local(move || {
+                let _ = keys.clone().argsort(0);
+                MainBackendBase::sync(&device).expect("Failed to sync");
+            });
+    }
+}
+
 #[divan::bench_group(max_time = 2)]
 mod backward_rendering {
     use super::{
-        Backend, Camera, DiffBackend, ITERS_PER_SYNC, MainBackend, Quat, RESOLUTIONS, Tensor,
-        TensorPrimitive, Vec3, WgpuDevice, gen_splats, render_splats_diff,
+        Backend, Camera, DiffBackend, ITERS_PER_SYNC, MainBackend, Quat, RESOLUTIONS,
+        RenderOptions, Tensor, TensorPrimitive, Vec3, WgpuDevice, gen_splats, render_splats_diff,
     };
 
     #[divan::bench(args = [1_000_000, 2_000_000, 5_000_000])]
@@ -215,8 +316,13 @@ mod backward_rendering {
 
         bencher.bench_local(move || {
             for _ in 0..ITERS_PER_SYNC {
-                let diff_out =
-                    render_splats_diff(&splats, &camera, glam::uvec2(1920, 1080), Vec3::ZERO);
+                let diff_out = render_splats_diff(
+                    &splats,
+                    &camera,
+                    glam::uvec2(1920, 1080),
+                    Vec3::ZERO,
+                    RenderOptions::default(),
+                );
                 let img: Tensor<DiffBackend, 3> =
                     Tensor::from_primitive(TensorPrimitive::Float(diff_out.img));
                 let _ = img.mean().backward();
@@ -238,8 +344,13 @@ mod backward_rendering {
         );
         bencher.bench_local(move || {
             for _ in 0..ITERS_PER_SYNC {
-                let diff_out =
-                    render_splats_diff(&splats, &camera, glam::uvec2(width, height), Vec3::ZERO);
+                let diff_out = render_splats_diff(
+                    &splats,
+                    &camera,
+                    glam::uvec2(width, height),
+                    Vec3::ZERO,
+                    RenderOptions::default(),
+                );
                 let img: Tensor<DiffBackend, 3> =
                     Tensor::from_primitive(TensorPrimitive::Float(diff_out.img));
                 let _ = img.mean().backward();
@@ -291,7 +402,13 @@ mod tests {
 
     #[allow(unused)]
     fn regex_except(arg: &str) -> String {
-        let all = ["forward_rendering", "backward_rendering", "training"];
+        let all = [
+            "forward_rendering",
+            "tile_size",
+            "sorting",
+            "backward_rendering",
+            "training",
+        ];
         let to_skip: Vec<_> = all
             .iter()
             .filter(|&&s| s != arg)
@@ -307,6 +424,72 @@ mod tests {
             .test_benches();
     }
 
+    // Needs a few GB of memory for the 30M splats, run it manually. Without BRUSH_TILE_SIZE
+    // this rebuilds and reruns itself for each tile width, in a separate target dir per width
+    // as the outer cargo holds the lock on the default one.
+    #[test]
+    #[ignore]
+    fn tile_size_bench() {
+        if std::env::var_os("BRUSH_TILE_SIZE").is_some() {
+            Divan::default()
+                .skip_regex(regex_except("tile_size"))
+                .run_benches();
+            return;
+        }
+
+        let target_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../target");
+        for width in super::tile_size::TILE_WIDTHS {
+            let status = std::process::Command::new(env!("CARGO"))
+                .args(["test", "-p", "brush-bench-test", "--release", "--lib"])
+                .arg("benches::tests::tile_size_bench")
+                .args(["--", "--ignored", "--exact", "--nocapture"])
+                .env("BRUSH_TILE_SIZE", width.to_string())
+                .env("CARGO_TARGET_DIR", target_dir.join(format!("tile{width}")))
+                .status()
+                .expect("Failed to run cargo");
+            assert!(status.success(), "Tile size {width} bench failed");
+        }
+    }
+
+    // Sorting 30M keys with burn's argsort is slow, run it manually.
+    #[test]
+    #[ignore]
+    fn sorting_bench() {
+        Divan::default()
+            .skip_regex(regex_except("sorting"))
+            .run_benches();
+    }
+
+    // Needs a few GB of memory for the 30M splats, run it manually.
+    #[test]
+    #[ignore]
+    fn f16_storage_memory() {
+        use brush_render::gaussian_splats::SplatPrecision;
+        use burn::module::AutodiffModule;
+
+        let device = burn::backend::wgpu::WgpuDevice::default();
+        let splats = super::gen_splats(&device, super::tile_size::SPLAT_COUNT).valid();
+        let f32_bytes = splats.size_bytes();
+        let splats = splats.with_precision(SplatPrecision::F16);
+        let f16_bytes = splats.size_bytes();
+        let ratio = f16_bytes as f64 / f32_bytes as f64;
+        log::info!(
+            "{} splats take {:.0}% of the f32 memory as {:?}",
+            super::tile_size::SPLAT_COUNT,
+            100.0 * ratio,
+            splats.precision()
+        );
+        if splats.precision() == SplatPrecision::F16 {
+            // Only the opacities stay f32.
+            assert!(
+                (0.5..0.6).contains(&ratio),
+                "f16 splats take {ratio} of the memory"
+            );
+        } else {
+            assert_eq!(f16_bytes, f32_bytes);
+        }
+    }
+
     #[test]
     fn test_bwd_bench() {
         Divan::default()
@@ -1,7 +1,7 @@
 use std::{fs::File, io::Read};
 
 use brush_render::{
-    MainBackend,
+    MainBackend, RenderOptions,
     camera::{Camera, focal_to_fov, fov_to_focal},
     gaussian_splats::Splats,
 };
@@ -131,6 +131,7 @@ async fn test_reference() -> Result<()> {
             &cam,
             glam::uvec2(w as u32, h as u32),
             Vec3::ZERO,
+            RenderOptions::default(),
         );
 
         let (out, aux) = (
@@ -201,6 +201,7 @@ async fn load_dataset_inner(
             log_scales: None,
             sh_coeffs: Some(colors),
             raw_opacities: None,
+            motion: None,
         };
 
         Some(SplatMessage {
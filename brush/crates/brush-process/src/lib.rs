@@ -42,6 +42,87 @@ pub async fn burn_init_setup() -> WgpuDevice {
     WgpuDevice::DefaultDevice
 }
 
+/// Which GPU adapter to run on, see [`burn_init_setup_with`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum AdapterPreference {
+    /// Let wgpu pick, like [`burn_init_setup`].
+    #[default]
+    Default,
+    /// Index into the adapters of [`list_adapters`].
+    Index(usize),
+    /// The first adapter whose name contains this, ignoring case.
+    Name(String),
+}
+
+impl std::str::FromStr for AdapterPreference {
+    type Err = std::convert::Infallible;
+
+    /// Parses a number as an index, and anything else as a name.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(s.parse()
+            .map_or_else(|_| Self::Name(s.to_owned()), Self::Index))
+    }
+}
+
+/// The adapters available to the graphics API burn runs on, in the order used by
+/// [`AdapterPreference::Index`].
+#[cfg(not(target_family = "wasm"))]
+pub fn list_adapters() -> Vec<Adapter> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+    instance.enumerate_adapters(AutoGraphicsApi::backend().into())
+}
+
+/// One line description of an adapter, with its index in [`list_adapters`].
+pub fn describe_adapter(index: usize, adapter: &Adapter) -> String {
+    let info = adapter.get_info();
+    format!(
+        "{index}: {} ({:?}, {:?})",
+        info.name, info.backend, info.device_type
+    )
+}
+
+/// Like [`burn_init_setup`], but runs on the adapter picked by `preference`. Errors with the
+/// available adapters when none matches.
+#[cfg(not(target_family = "wasm"))]
+pub async fn burn_init_setup_with(preference: &AdapterPreference) -> Result<WgpuDevice, Error> {
+    let mut adapters = list_adapters();
+    let index = match preference {
+        AdapterPreference::Default => return Ok(burn_init_setup().await),
+        AdapterPreference::Index(index) => (*index < adapters.len()).then_some(*index),
+        AdapterPreference::Name(name) => {
+            let name = name.to_lowercase();
+            adapters
+                .iter()
+                .position(|a| a.get_info().name.to_lowercase().contains(&name))
+        }
+    };
+    let Some(index) = index else {
+        let candidates: Vec<_> = adapters
+            .iter()
+            .enumerate()
+            .map(|(i, a)| format!("  {}", describe_adapter(i, a)))
+            .collect();
+        anyhow::bail!(
+            "No GPU adapter matches {preference:?}. Available adapters:\n{}",
+            candidates.join("\n")
+        );
+    };
+
+    let adapter = adapters.swap_remove(index);
+    let (device, queue) = adapter
+        .request_device(&wgpu::DeviceDescriptor {
+            label: Some("brush"),
+            required_features: adapter
+                .features()
+                .difference(wgpu::Features::MAPPABLE_PRIMARY_BUFFERS),
+            required_limits: adapter.limits(),
+            memory_hints: wgpu::MemoryHints::MemoryUsage,
+            trace: wgpu::Trace::Off,
+        })
+        .await?;
+    Ok(burn_init_device(adapter, device, queue))
+}
+
 pub fn burn_init_device(adapter: Adapter, device: Device, queue: Queue) -> WgpuDevice {
     let setup = burn_wgpu::WgpuSetup {
         instance: wgpu::Instance::new(&wgpu::InstanceDescriptor::default()), // unused... need to fix this in Burn.
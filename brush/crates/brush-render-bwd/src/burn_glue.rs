@@ -1,9 +1,9 @@
 use brush_render::{
-    MainBackendBase, SplatForward,
-    camera::Camera,
+    MainBackendBase, RenderOptions, SplatForward,
+    camera::{Camera, ProjectionMode},
     gaussian_splats::{SplatRenderMode, Splats},
     render_aux::RenderAux,
-    sh::{sh_coeffs_for_degree, sh_degree_from_coeffs},
+    sh::{sh_coeffs_for_degree, sh_degree_from_coeffs, truncate_sh_coeffs},
 };
 use burn::{
     backend::{
@@ -40,6 +40,9 @@ pub trait SplatForwardDiff<B: Backend> {
     ///
     /// This projects the gaussians, sorts them, and rasterizes them to a buffer, in a
     /// differentiable way.
+    ///
+    /// Only SH bands up to `sh_degree_active` contribute to the color, the coefficients of
+    /// higher bands receive zero gradients.
     #[allow(clippy::too_many_arguments)]
     fn render_splats(
         camera: &Camera,
@@ -51,6 +54,8 @@ pub trait SplatForwardDiff<B: Backend> {
         raw_opacity: FloatTensor<B>,
         render_mode: SplatRenderMode,
         background: Vec3,
+        sh_degree_active: u8,
+        options: RenderOptions,
     ) -> SplatOutputDiff<B>;
 }
 
@@ -77,7 +82,9 @@ pub struct GaussianBackwardState<B: Backend> {
     pub(crate) compact_gid_from_isect: IntTensor<B>,
     pub(crate) global_from_compact_gid: IntTensor<B>,
     pub(crate) tile_offsets: IntTensor<B>,
-    pub(crate) render_mode: SplatRenderMode,
+    /// Whether the forward pass used the anti-aliasing filter, see
+    /// [`RenderOptions::antialiased`].
+    pub(crate) antialiased: bool,
     pub(crate) sh_degree: u32,
 }
 
@@ -162,7 +169,29 @@ impl<B: Backend + SplatBackwardOps<B> + SplatForward<B>, C: CheckpointStrategy>
         raw_opacity: FloatTensor<Self>,
         render_mode: SplatRenderMode,
         background: Vec3,
+        sh_degree_active: u8,
+        options: RenderOptions,
     ) -> SplatOutputDiff<Self> {
+        assert_eq!(
+            camera.projection,
+            ProjectionMode::Perspective,
+            "Gradients are only implemented for perspective cameras"
+        );
+        assert!(
+            !matches!(
+                render_mode,
+                SplatRenderMode::NormalsWorldSpace
+                    | SplatRenderMode::NormalsCameraSpace
+                    | SplatRenderMode::AlphaOnly
+                    | SplatRenderMode::WorldPosition
+                    | SplatRenderMode::Feature { .. }
+            ),
+            "Gradients are only implemented for color renders"
+        );
+
+        // Slice off the inactive SH bands before tracking, so their gradients are zero.
+        let sh_coeffs = truncate_sh_coeffs::<Self>(sh_coeffs, sh_degree_active);
+
         // Get backend tensors & dequantize if needed. Could try and support quantized inputs
         // in the future.
         let device =
@@ -194,6 +223,7 @@ impl<B: Backend + SplatBackwardOps<B> + SplatForward<B>, C: CheckpointStrategy>
             render_mode,
             background,
             true,
+            options,
         );
 
         let wrapped_aux = RenderAux::<Self> {
@@ -204,7 +234,12 @@ impl<B: Backend + SplatBackwardOps<B> + SplatForward<B>, C: CheckpointStrategy>
             global_from_compact_gid: aux.global_from_compact_gid.clone(),
             uniforms_buffer: aux.uniforms_buffer.clone(),
             visible: <Self as AutodiffBackend>::from_inner(aux.visible),
+            depth: aux.depth.map(<Self as AutodiffBackend>::from_inner),
+            tile_hit_counts: aux.tile_hit_counts,
+            tile_coverage: aux.tile_coverage,
+            screen_radii: aux.screen_radii.map(<Self as AutodiffBackend>::from_inner),
             img_size: aux.img_size,
+            antialiased: aux.antialiased,
         };
 
         match prep_nodes {
@@ -224,7 +259,7 @@ impl<B: Backend + SplatBackwardOps<B> + SplatForward<B>, C: CheckpointStrategy>
                     uniforms_buffer: aux.uniforms_buffer,
                     tile_offsets: aux.tile_offsets,
                     compact_gid_from_isect: aux.compact_gid_from_isect,
-                    render_mode,
+                    antialiased: options.antialiased(render_mode),
                     global_from_compact_gid: aux.global_from_compact_gid,
                 };
 
@@ -257,7 +292,7 @@ impl SplatBackwardOps<Self> for Fusion<MainBackendBase> {
         #[derive(Debug)]
         struct CustomOp {
             desc: CustomOpIr,
-            render_mode: SplatRenderMode,
+            antialiased: bool,
             sh_degree: u32,
         }
 
@@ -298,7 +333,7 @@ impl SplatBackwardOps<Self> for Fusion<MainBackendBase> {
                     global_from_compact_gid: h
                         .get_int_tensor::<MainBackendBase>(global_from_compact_gid),
                     sh_degree: self.sh_degree,
-                    render_mode: self.render_mode,
+                    antialiased: self.antialiased,
                 };
 
                 let grads =
@@ -388,7 +423,7 @@ impl SplatBackwardOps<Self> for Fusion<MainBackendBase> {
                     // state,
                     desc,
                     sh_degree: state.sh_degree,
-                    render_mode: state.render_mode,
+                    antialiased: state.antialiased,
                 },
             )
             .outputs();
@@ -413,12 +448,13 @@ impl SplatBackwardOps<Self> for Fusion<MainBackendBase> {
     }
 }
 
-/// Render splats on a differentiable backend.
+/// Render splats on a differentiable backend, using all stored SH degrees.
 pub fn render_splats<B>(
     splats: &Splats<B>,
     camera: &Camera,
     img_size: glam::UVec2,
     background: Vec3,
+    options: RenderOptions,
 ) -> SplatOutputDiff<B>
 where
     B: Backend + SplatForwardDiff<B>,
@@ -434,7 +470,9 @@ where
         splats.raw_opacities.val().into_primitive().tensor(),
         splats.render_mode,
         background,
+        u8::MAX,
+        options,
     );
-    result.aux.validate_values();
+    result.aux.unwrap_values();
     result
 }
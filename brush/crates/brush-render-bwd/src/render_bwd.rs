@@ -1,5 +1,4 @@
 use brush_kernel::{CubeCount, calc_cube_count_1d};
-use brush_render::gaussian_splats::SplatRenderMode;
 use brush_wgsl::wgsl_kernel;
 
 use brush_render::MainBackendBase;
@@ -107,7 +106,7 @@ impl SplatBackwardOps<Self> for MainBackendBase {
             .contains(TypeUsage::AtomicAdd);
 
         let webgpu = cfg!(target_family = "wasm");
-        let mip_splat = matches!(state.render_mode, SplatRenderMode::Mip);
+        let mip_splat = state.antialiased;
 
         // Use checked execution, as the atomic loops are potentially unbounded.
         tracing::trace_span!("RasterizeBackwards").in_scope(|| {
@@ -54,7 +54,8 @@
 #endif
 
 const THREAD_COUNT: u32 = 64u;
-const PIXELS_PER_THREAD: u32 = 4u;
+// Each workgroup covers one tile.
+const PIXELS_PER_THREAD: u32 = helpers::TILE_SIZE / THREAD_COUNT;
 var<workgroup> local_batch: array<helpers::ProjectedSplat, THREAD_COUNT>;
 var<workgroup> load_gid: array<u32, THREAD_COUNT>;
 
@@ -78,7 +79,7 @@ fn main(
     var rgb_pixel_finals = array<vec4f, PIXELS_PER_THREAD>();
 
     for (var i = 0u; i < PIXELS_PER_THREAD; i++) {
-        // Process 4 consecutive pixels in the original linear order
+        // Process consecutive pixels in the original linear order
         let thread_id = global_id.x * PIXELS_PER_THREAD + i;
         pix_locs[i] = helpers::map_1d_to_2d(thread_id, uniforms.tile_bounds.x);
         let pix_id = pix_locs[i].x + pix_locs[i].y * uniforms.img_size.x;
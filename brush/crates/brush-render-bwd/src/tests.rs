@@ -1,6 +1,6 @@
 use crate::burn_glue::SplatForwardDiff;
 use assert_approx_eq::assert_approx_eq;
-use brush_render::{camera::Camera, gaussian_splats::SplatRenderMode};
+use brush_render::{RenderOptions, camera::Camera, gaussian_splats::SplatRenderMode};
 use burn::{
     backend::Autodiff,
     tensor::{Distribution, Tensor, TensorPrimitive},
@@ -45,8 +45,10 @@ fn diffs_at_all() {
         raw_opacity.into_primitive().tensor(),
         SplatRenderMode::Default,
         Vec3::ZERO,
+        u8::MAX,
+        RenderOptions::default(),
     );
-    result.aux.validate_values();
+    assert_eq!(result.aux.validate_values(), Ok(()));
 
     let output: Tensor<TestBackend, 3> = Tensor::from_primitive(TensorPrimitive::Float(result.img));
     let rgb = output.clone().slice([0..32, 0..32, 0..3]);
@@ -114,6 +116,77 @@ fn diffs_many_splats() {
         raw_opacity.into_primitive().tensor(),
         SplatRenderMode::Default,
         Vec3::ZERO,
+        u8::MAX,
+        RenderOptions::default(),
     );
-    result.aux.validate_values();
+    assert_eq!(result.aux.validate_values(), Ok(()));
+}
+
+#[test]
+fn antialias_option_matches_mip_gradients() {
+    let cam = Camera::new(
+        glam::vec3(0.0, 0.0, -5.0),
+        glam::Quat::IDENTITY,
+        0.5,
+        0.5,
+        glam::vec2(0.5, 0.5),
+    );
+    let img_size = glam::uvec2(32, 32);
+    let device = WgpuDevice::DefaultDevice;
+    let num_points = 64;
+    let means = Tensor::<TestBackend, 2>::random(
+        [num_points, 3],
+        Distribution::Uniform(-1.0, 1.0),
+        &device,
+    );
+    // Thin splats, where the filter changes the footprint the most.
+    let log_scales = Tensor::<TestBackend, 2>::random(
+        [num_points, 3],
+        Distribution::Uniform(-6.0, -4.0),
+        &device,
+    );
+    let quats = Tensor::<TestBackend, 1>::from_floats(glam::Quat::IDENTITY.to_array(), &device)
+        .unsqueeze_dim(0)
+        .repeat_dim(0, num_points);
+    let sh_coeffs =
+        Tensor::<TestBackend, 3>::random([num_points, 1, 3], Distribution::Default, &device);
+    let raw_opacity = Tensor::<TestBackend, 1>::ones([num_points], &device);
+
+    let mean_grads = |render_mode: SplatRenderMode, antialias: bool| {
+        let means = means.clone().detach().require_grad();
+        let result = <TestBackend as SplatForwardDiff<TestBackend>>::render_splats(
+            &cam,
+            img_size,
+            means.clone().into_primitive().tensor(),
+            log_scales.clone().into_primitive().tensor(),
+            quats.clone().into_primitive().tensor(),
+            sh_coeffs.clone().into_primitive().tensor(),
+            raw_opacity.clone().into_primitive().tensor(),
+            render_mode,
+            Vec3::ZERO,
+            u8::MAX,
+            RenderOptions {
+                antialias,
+                ..Default::default()
+            },
+        );
+        let output: Tensor<TestBackend, 3> =
+            Tensor::from_primitive(TensorPrimitive::Float(result.img));
+        let grads = output.mean().backward();
+        means
+            .grad(&grads)
+            .expect("Means need a gradient")
+            .into_data()
+            .to_vec::<f32>()
+            .expect("Wrong type")
+    };
+
+    // The option applies the same filter as mip splats, in the backward pass as well.
+    let mip = mean_grads(SplatRenderMode::Mip, false);
+    let antialiased = mean_grads(SplatRenderMode::Default, true);
+    let aliased = mean_grads(SplatRenderMode::Default, false);
+    for (a, b) in mip.iter().zip(&antialiased) {
+        assert_approx_eq!(*a, *b, 1e-5);
+    }
+    assert_ne!(mip, aliased);
 }
@@ -1,3 +1,7 @@
+use burn::{Tensor, prelude::Backend};
+
+use crate::camera::{Camera, focal_to_fov, fov_to_focal};
+
 #[derive(Clone, Copy)]
 pub struct BoundingBox {
     pub center: glam::Vec3,
@@ -26,3 +30,75 @@ impl BoundingBox {
         extents[1] * 2.0
     }
 }
+
+/// Axis aligned bounding box `(min, max)` containing every splat up to `sigma` standard
+/// deviations. The rotation of the splats is ignored, so each splat is bounded by a sphere
+/// with the radius of its largest axis. Computed on the GPU, only the final box is read
+/// back. Empty splat sets give a zero sized box at the origin.
+pub async fn splat_aabb<B: Backend>(
+    means: &Tensor<B, 2>,
+    log_scales: &Tensor<B, 2>,
+    sigma: f32,
+) -> (glam::Vec3, glam::Vec3) {
+    if means.dims()[0] == 0 {
+        return (glam::Vec3::ZERO, glam::Vec3::ZERO);
+    }
+
+    let radius = log_scales.clone().exp().max_dim(1) * sigma;
+    let min = (means.clone() - radius.clone()).min_dim(0);
+    let max = (means.clone() + radius).max_dim(0);
+    let bounds: Vec<f32> = Tensor::cat(vec![min, max], 0)
+        .into_data_async()
+        .await
+        .expect("Failed to read splat bounds")
+        .convert::<f32>()
+        .into_vec()
+        .expect("Failed to read splat bounds");
+    (
+        glam::Vec3::from_slice(&bounds[0..3]),
+        glam::Vec3::from_slice(&bounds[3..6]),
+    )
+}
+
+/// Perspective camera looking along +Z at the bounding sphere of the splat `means`, backed
+/// off along -Z so the whole sphere fits in an `img_size` image with horizontal field of
+/// view `fov_x`.
+///
+/// The sphere is centered on the box around the means. The narrower of both fields of view
+/// limits the distance, which is `r / sin(fov / 2)` rather than `r / tan(fov / 2)`, as the
+/// sphere bulges towards the camera and would otherwise touch the edges of the frame.
+pub async fn auto_camera_for_splats<B: Backend>(
+    means: &Tensor<B, 2>,
+    fov_x: f64,
+    img_size: glam::UVec2,
+) -> Camera {
+    let fov_y = focal_to_fov(fov_to_focal(fov_x, img_size.x), img_size.y);
+    let (center, radius) = if means.dims()[0] == 0 {
+        (glam::Vec3::ZERO, 0.0)
+    } else {
+        let center = (means.clone().min_dim(0) + means.clone().max_dim(0)) / 2.0;
+        let radius = (means.clone() - center.clone())
+            .powi_scalar(2)
+            .sum_dim(1)
+            .sqrt()
+            .max_dim(0);
+        let sphere: Vec<f32> = Tensor::cat(vec![center.reshape([3]), radius.reshape([1])], 0)
+            .into_data_async()
+            .await
+            .expect("Failed to read splat bounds")
+            .convert::<f32>()
+            .into_vec()
+            .expect("Failed to read splat bounds");
+        (glam::Vec3::from_slice(&sphere[0..3]), sphere[3])
+    };
+
+    let half_fov = fov_x.min(fov_y) / 2.0;
+    let distance = radius / half_fov.sin() as f32;
+    Camera::new(
+        center - glam::Vec3::Z * distance,
+        glam::Quat::IDENTITY,
+        fov_x,
+        fov_y,
+        glam::vec2(0.5, 0.5),
+    )
+}
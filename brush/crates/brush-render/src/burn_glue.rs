@@ -1,22 +1,266 @@
-use burn::tensor::{DType, Shape, ops::FloatTensor};
+use brush_kernel::create_tensor;
+use burn::tensor::{
+    DType, FloatDType, IntDType, Shape,
+    ops::{FloatTensor, FloatTensorOps, IntTensorOps},
+};
 use burn_cubecl::{BoolElement, fusion::FusionCubeRuntime};
 use burn_fusion::{
     Fusion, FusionHandle,
     stream::{Operation, OperationStreams},
 };
 use burn_ir::{CustomOpIr, HandleContainer, OperationIr, OperationOutput, TensorIr};
-use burn_wgpu::WgpuRuntime;
+use burn_wgpu::{WgpuDevice, WgpuRuntime};
 use glam::Vec3;
+use tokio_util::sync::CancellationToken;
 
 use crate::{
-    MainBackendBase, SplatForward,
+    MainBackendBase, RenderCancelled, RenderOptions, SplatForward,
     camera::Camera,
+    check_cancelled,
     gaussian_splats::SplatRenderMode,
     render::{calc_tile_bounds, max_intersections},
-    render_aux::RenderAux,
+    render_aux::{RenderAux, tile_coverage},
     shaders,
 };
 
+/// Number of outputs of one rendered view: the image and the aux buffers.
+const VIEW_OUTPUTS: usize = 10;
+
+/// Register the image and aux of a view rendered on the inner backend as `outputs`, in the
+/// order of [`view_outputs`].
+fn register_view<BT: BoolElement>(
+    h: &mut HandleContainer<FusionHandle<FusionCubeRuntime<WgpuRuntime, BT>>>,
+    outputs: &[TensorIr],
+    img: FloatTensor<MainBackendBase>,
+    aux: RenderAux<MainBackendBase>,
+) {
+    let [
+        // Img
+        out_img,
+        // Aux
+        projected_splats,
+        uniforms_buffer,
+        num_intersections,
+        tile_offsets,
+        compact_gid_from_isect,
+        global_from_compact_gid,
+        visible,
+        depth,
+        tile_hit_counts,
+    ] = outputs
+    else {
+        panic!("Expected {VIEW_OUTPUTS} outputs per view");
+    };
+
+    // Unrequested outputs still need a (dummy) tensor registered.
+    let depth_tensor = aux
+        .depth
+        .unwrap_or_else(|| create_tensor([1], &img.device, DType::F32));
+    let hit_counts_tensor = aux
+        .tile_hit_counts
+        .unwrap_or_else(|| create_tensor([1], &img.device, DType::U32));
+
+    // Register output.
+    h.register_float_tensor::<MainBackendBase>(&out_img.id, img);
+    h.register_float_tensor::<MainBackendBase>(&projected_splats.id, aux.projected_splats);
+    h.register_int_tensor::<MainBackendBase>(&uniforms_buffer.id, aux.uniforms_buffer);
+    h.register_int_tensor::<MainBackendBase>(&num_intersections.id, aux.num_intersections);
+    h.register_int_tensor::<MainBackendBase>(&tile_offsets.id, aux.tile_offsets);
+    h.register_int_tensor::<MainBackendBase>(
+        &compact_gid_from_isect.id,
+        aux.compact_gid_from_isect,
+    );
+    h.register_int_tensor::<MainBackendBase>(
+        &global_from_compact_gid.id,
+        aux.global_from_compact_gid,
+    );
+
+    h.register_float_tensor::<MainBackendBase>(&visible.id, aux.visible);
+    h.register_float_tensor::<MainBackendBase>(&depth.id, depth_tensor);
+    h.register_int_tensor::<MainBackendBase>(&tile_hit_counts.id, hit_counts_tensor);
+}
+
+/// Register zeroed `outputs` for a view whose render was cancelled, so the operations queued
+/// after it still find tensors of the right shape.
+fn register_cancelled_view<BT: BoolElement>(
+    h: &mut HandleContainer<FusionHandle<FusionCubeRuntime<WgpuRuntime, BT>>>,
+    outputs: &[TensorIr],
+    device: &WgpuDevice,
+) {
+    for output in outputs {
+        if output.dtype == DType::U32 {
+            let zeros = MainBackendBase::int_zeros(output.shape.clone(), device, IntDType::U32);
+            h.register_int_tensor::<MainBackendBase>(&output.id, zeros);
+        } else {
+            let zeros = MainBackendBase::float_zeros(output.shape.clone(), device, FloatDType::F32);
+            h.register_float_tensor::<MainBackendBase>(&output.id, zeros);
+        }
+    }
+}
+
+/// The uninitialized outputs of one view rendered from `means`.
+fn view_outputs(
+    means: &FloatTensor<Fusion<MainBackendBase>>,
+    img_size: glam::UVec2,
+    render_mode: SplatRenderMode,
+    bwd_info: bool,
+    options: RenderOptions,
+) -> [TensorIr; VIEW_OUTPUTS] {
+    let client = means.client.clone();
+
+    let num_points = means.shape[0];
+
+    let proj_size = size_of::<shaders::helpers::ProjectedSplat>() / 4;
+    let uniforms_size = size_of::<shaders::helpers::RenderUniforms>() / 4;
+    let tile_bounds = calc_tile_bounds(img_size);
+    let max_intersects = max_intersections(img_size, num_points as u32);
+
+    // Normals and positions are always rendered as three f32 channels, features as one f32
+    // channel per feature, and alpha only renders as f32 alpha, see the forward pass.
+    let signed_color = matches!(
+        render_mode,
+        SplatRenderMode::NormalsWorldSpace
+            | SplatRenderMode::NormalsCameraSpace
+            | SplatRenderMode::WorldPosition
+            | SplatRenderMode::Feature { .. }
+    );
+    let alpha_only = render_mode == SplatRenderMode::AlphaOnly;
+    let float_output = bwd_info || signed_color || alpha_only;
+
+    // If render_u32_buffer is true, we render a packed buffer of u32 values, otherwise
+    // render RGBA f32 values.
+    let channels = if let SplatRenderMode::Feature { dim } = render_mode {
+        dim
+    } else if signed_color {
+        3
+    } else if alpha_only {
+        1
+    } else if bwd_info {
+        4
+    } else {
+        1
+    };
+
+    let out_img = TensorIr::uninit(
+        client.create_empty_handle(),
+        Shape::new([img_size.y as usize, img_size.x as usize, channels]),
+        if float_output { DType::F32 } else { DType::U32 },
+    );
+
+    let visible_shape = if float_output {
+        Shape::new([num_points])
+    } else {
+        Shape::new([1])
+    };
+
+    let projected_splats = TensorIr::uninit(
+        client.create_empty_handle(),
+        Shape::new([num_points, proj_size]),
+        DType::F32,
+    );
+    let uniforms_buffer = TensorIr::uninit(
+        client.create_empty_handle(),
+        Shape::new([uniforms_size]),
+        DType::U32,
+    );
+    let num_intersections =
+        TensorIr::uninit(client.create_empty_handle(), Shape::new([1]), DType::U32);
+    let tile_offsets = TensorIr::uninit(
+        client.create_empty_handle(),
+        Shape::new([tile_bounds.y as usize, tile_bounds.x as usize, 2]),
+        DType::U32,
+    );
+    let compact_gid_from_isect = TensorIr::uninit(
+        client.create_empty_handle(),
+        Shape::new([max_intersects as usize]),
+        DType::U32,
+    );
+    let global_from_compact_gid = TensorIr::uninit(
+        client.create_empty_handle(),
+        Shape::new([num_points]),
+        DType::U32,
+    );
+    let visible = TensorIr::uninit(client.create_empty_handle(), visible_shape, DType::F32);
+
+    let depth_shape = if options.depth {
+        Shape::new([img_size.y as usize, img_size.x as usize, 1])
+    } else {
+        Shape::new([1])
+    };
+    let depth = TensorIr::uninit(client.create_empty_handle(), depth_shape, DType::F32);
+
+    let hit_counts_shape = if options.record_hit_counts {
+        Shape::new([img_size.y as usize, img_size.x as usize])
+    } else {
+        Shape::new([1])
+    };
+    let tile_hit_counts =
+        TensorIr::uninit(client.create_empty_handle(), hit_counts_shape, DType::U32);
+
+    [
+        out_img,
+        projected_splats,
+        uniforms_buffer,
+        num_intersections,
+        tile_offsets,
+        compact_gid_from_isect,
+        global_from_compact_gid,
+        visible,
+        depth,
+        tile_hit_counts,
+    ]
+}
+
+/// Assemble the image and aux of a view from the registered outputs of [`view_outputs`].
+fn view_from_outputs(
+    outputs: [FloatTensor<Fusion<MainBackendBase>>; VIEW_OUTPUTS],
+    img_size: glam::UVec2,
+    render_mode: SplatRenderMode,
+    options: RenderOptions,
+) -> (
+    FloatTensor<Fusion<MainBackendBase>>,
+    RenderAux<Fusion<MainBackendBase>>,
+) {
+    let [
+        // Img
+        out_img,
+        // Aux
+        projected_splats,
+        uniforms_buffer,
+        num_intersections,
+        tile_offsets,
+        compact_gid_from_isect,
+        global_from_compact_gid,
+        visible,
+        depth,
+        tile_hit_counts,
+    ] = outputs;
+
+    let coverage = options
+        .record_tile_coverage
+        .then(|| tile_coverage::<Fusion<MainBackendBase>>(tile_offsets.clone()));
+
+    let mut aux = RenderAux::<Fusion<MainBackendBase>> {
+        projected_splats,
+        uniforms_buffer,
+        num_intersections,
+        tile_offsets,
+        compact_gid_from_isect,
+        global_from_compact_gid,
+        visible,
+        depth: options.depth.then_some(depth),
+        tile_hit_counts: options.record_hit_counts.then_some(tile_hit_counts),
+        tile_coverage: coverage,
+        screen_radii: None,
+        img_size,
+        antialiased: options.antialiased(render_mode),
+    };
+    if options.record_screen_radii {
+        aux.screen_radii = Some(aux.calc_screen_radii().into_primitive().tensor());
+    }
+    (out_img, aux)
+}
+
 impl SplatForward<Self> for Fusion<MainBackendBase> {
     fn render_splats(
         cam: &Camera,
@@ -29,7 +273,40 @@ impl SplatForward<Self> for Fusion<MainBackendBase> {
         render_mode: SplatRenderMode,
         background: Vec3,
         bwd_info: bool,
+        options: RenderOptions,
     ) -> (FloatTensor<Self>, RenderAux<Self>) {
+        // A token that's never cancelled runs all stages.
+        Self::render_splats_cancellable(
+            cam,
+            img_size,
+            means,
+            log_scales,
+            quats,
+            sh_coeffs,
+            opacity,
+            render_mode,
+            background,
+            bwd_info,
+            options,
+            &CancellationToken::new(),
+        )
+        .expect("Render can't be cancelled")
+    }
+
+    fn render_splats_cancellable(
+        cam: &Camera,
+        img_size: glam::UVec2,
+        means: FloatTensor<Self>,
+        log_scales: FloatTensor<Self>,
+        quats: FloatTensor<Self>,
+        sh_coeffs: FloatTensor<Self>,
+        opacity: FloatTensor<Self>,
+        render_mode: SplatRenderMode,
+        background: Vec3,
+        bwd_info: bool,
+        options: RenderOptions,
+        token: &CancellationToken,
+    ) -> Result<(FloatTensor<Self>, RenderAux<Self>), RenderCancelled> {
         #[derive(Debug)]
         struct CustomOp {
             cam: Camera,
@@ -37,6 +314,8 @@ impl SplatForward<Self> for Fusion<MainBackendBase> {
             render_mode: SplatRenderMode,
             bwd_info: bool,
             background: Vec3,
+            options: RenderOptions,
+            token: CancellationToken,
             desc: CustomOpIr,
         }
 
@@ -45,26 +324,18 @@ impl SplatForward<Self> for Fusion<MainBackendBase> {
                 &self,
                 h: &mut HandleContainer<FusionHandle<FusionCubeRuntime<WgpuRuntime, BT>>>,
             ) {
-                let (inputs, outputs) = self.desc.as_fixed();
+                let (inputs, outputs): (_, &[_; VIEW_OUTPUTS]) = self.desc.as_fixed();
 
                 let [means, log_scales, quats, sh_coeffs, opacity] = inputs;
-                let [
-                    // Img
-                    out_img,
-                    // Aux
-                    projected_splats,
-                    uniforms_buffer,
-                    num_intersections,
-                    tile_offsets,
-                    compact_gid_from_isect,
-                    global_from_compact_gid,
-                    visible,
-                ] = outputs;
-
-                let (img, aux) = MainBackendBase::render_splats(
+
+                let means = h.get_float_tensor::<MainBackendBase>(means);
+                let device = means.device.clone();
+                // The op only runs once the stream is flushed, so the token is checked
+                // between the stages here rather than when the op is registered.
+                let view = MainBackendBase::render_splats_cancellable(
                     &self.cam,
                     self.img_size,
-                    h.get_float_tensor::<MainBackendBase>(means),
+                    means,
                     h.get_float_tensor::<MainBackendBase>(log_scales),
                     h.get_float_tensor::<MainBackendBase>(quats),
                     h.get_float_tensor::<MainBackendBase>(sh_coeffs),
@@ -72,141 +343,136 @@ impl SplatForward<Self> for Fusion<MainBackendBase> {
                     self.render_mode,
                     self.background,
                     self.bwd_info,
+                    self.options,
+                    &self.token,
                 );
-
-                // Register output.
-                h.register_float_tensor::<MainBackendBase>(&out_img.id, img);
-                h.register_float_tensor::<MainBackendBase>(
-                    &projected_splats.id,
-                    aux.projected_splats,
-                );
-                h.register_int_tensor::<MainBackendBase>(&uniforms_buffer.id, aux.uniforms_buffer);
-                h.register_int_tensor::<MainBackendBase>(
-                    &num_intersections.id,
-                    aux.num_intersections,
-                );
-                h.register_int_tensor::<MainBackendBase>(&tile_offsets.id, aux.tile_offsets);
-                h.register_int_tensor::<MainBackendBase>(
-                    &compact_gid_from_isect.id,
-                    aux.compact_gid_from_isect,
-                );
-                h.register_int_tensor::<MainBackendBase>(
-                    &global_from_compact_gid.id,
-                    aux.global_from_compact_gid,
-                );
-
-                h.register_float_tensor::<MainBackendBase>(&visible.id, aux.visible);
+                match view {
+                    Ok((img, aux)) => register_view(h, outputs, img, aux),
+                    // The caller sees the cancelled token as well, and drops the outputs.
+                    Err(RenderCancelled) => register_cancelled_view(h, outputs, &device),
+                }
             }
         }
 
+        check_cancelled(token)?;
+
         let client = means.client.clone();
+        let outputs = view_outputs(&means, img_size, render_mode, bwd_info, options);
+
+        let input_tensors = [means, log_scales, quats, sh_coeffs, opacity];
+        let stream = OperationStreams::with_inputs(&input_tensors);
+        let desc = CustomOpIr::new(
+            "render_splats",
+            &input_tensors.map(|t| t.into_ir()),
+            &outputs,
+        );
+        let op = CustomOp {
+            cam: cam.clone(),
+            img_size,
+            bwd_info,
+            background,
+            options,
+            render_mode,
+            token: token.clone(),
+            desc: desc.clone(),
+        };
 
-        let num_points = means.shape[0];
+        let outputs = client
+            .register(stream, OperationIr::Custom(desc), op)
+            .outputs();
+        Ok(view_from_outputs(outputs, img_size, render_mode, options))
+    }
 
-        let proj_size = size_of::<shaders::helpers::ProjectedSplat>() / 4;
-        let uniforms_size = size_of::<shaders::helpers::RenderUniforms>() / 4;
-        let tile_bounds = calc_tile_bounds(img_size);
-        let max_intersects = max_intersections(img_size, num_points as u32);
+    fn render_splats_stereo(
+        left: &Camera,
+        right: &Camera,
+        img_size: glam::UVec2,
+        means: FloatTensor<Self>,
+        log_scales: FloatTensor<Self>,
+        quats: FloatTensor<Self>,
+        sh_coeffs: FloatTensor<Self>,
+        opacity: FloatTensor<Self>,
+        render_mode: SplatRenderMode,
+        background: Vec3,
+        bwd_info: bool,
+        options: RenderOptions,
+    ) -> [(FloatTensor<Self>, RenderAux<Self>); 2] {
+        #[derive(Debug)]
+        struct CustomOp {
+            left: Camera,
+            right: Camera,
+            img_size: glam::UVec2,
+            render_mode: SplatRenderMode,
+            bwd_info: bool,
+            background: Vec3,
+            options: RenderOptions,
+            desc: CustomOpIr,
+        }
 
-        // If render_u32_buffer is true, we render a packed buffer of u32 values, otherwise
-        // render RGBA f32 values.
-        let channels = if bwd_info { 4 } else { 1 };
+        impl<BT: BoolElement> Operation<FusionCubeRuntime<WgpuRuntime, BT>> for CustomOp {
+            fn execute(
+                &self,
+                h: &mut HandleContainer<FusionHandle<FusionCubeRuntime<WgpuRuntime, BT>>>,
+            ) {
+                let (inputs, outputs): (_, &[_; 2 * VIEW_OUTPUTS]) = self.desc.as_fixed();
 
-        let out_img = TensorIr::uninit(
-            client.create_empty_handle(),
-            Shape::new([img_size.y as usize, img_size.x as usize, channels]),
-            if bwd_info { DType::F32 } else { DType::U32 },
-        );
+                let [means, log_scales, quats, sh_coeffs, opacity] = inputs;
 
-        let visible_shape = if bwd_info {
-            Shape::new([num_points])
-        } else {
-            Shape::new([1])
-        };
+                let [left, right] = MainBackendBase::render_splats_stereo(
+                    &self.left,
+                    &self.right,
+                    self.img_size,
+                    h.get_float_tensor::<MainBackendBase>(means),
+                    h.get_float_tensor::<MainBackendBase>(log_scales),
+                    h.get_float_tensor::<MainBackendBase>(quats),
+                    h.get_float_tensor::<MainBackendBase>(sh_coeffs),
+                    h.get_float_tensor::<MainBackendBase>(opacity),
+                    self.render_mode,
+                    self.background,
+                    self.bwd_info,
+                    self.options,
+                );
+                let (left_outputs, right_outputs) = outputs.split_at(VIEW_OUTPUTS);
+                register_view(h, left_outputs, left.0, left.1);
+                register_view(h, right_outputs, right.0, right.1);
+            }
+        }
 
-        let projected_splats = TensorIr::uninit(
-            client.create_empty_handle(),
-            Shape::new([num_points, proj_size]),
-            DType::F32,
-        );
-        let uniforms_buffer = TensorIr::uninit(
-            client.create_empty_handle(),
-            Shape::new([uniforms_size]),
-            DType::U32,
-        );
-        let num_intersections =
-            TensorIr::uninit(client.create_empty_handle(), Shape::new([1]), DType::U32);
-        let tile_offsets = TensorIr::uninit(
-            client.create_empty_handle(),
-            Shape::new([tile_bounds.y as usize, tile_bounds.x as usize, 2]),
-            DType::U32,
-        );
-        let compact_gid_from_isect = TensorIr::uninit(
-            client.create_empty_handle(),
-            Shape::new([max_intersects as usize]),
-            DType::U32,
-        );
-        let global_from_compact_gid = TensorIr::uninit(
-            client.create_empty_handle(),
-            Shape::new([num_points]),
-            DType::U32,
-        );
-        let visible = TensorIr::uninit(client.create_empty_handle(), visible_shape, DType::F32);
+        let client = means.client.clone();
+        let mut outputs = view_outputs(&means, img_size, render_mode, bwd_info, options).to_vec();
+        outputs.extend(view_outputs(
+            &means,
+            img_size,
+            render_mode,
+            bwd_info,
+            options,
+        ));
 
         let input_tensors = [means, log_scales, quats, sh_coeffs, opacity];
         let stream = OperationStreams::with_inputs(&input_tensors);
         let desc = CustomOpIr::new(
-            "render_splats",
+            "render_splats_stereo",
             &input_tensors.map(|t| t.into_ir()),
-            &[
-                out_img,
-                projected_splats,
-                uniforms_buffer,
-                num_intersections,
-                tile_offsets,
-                compact_gid_from_isect,
-                global_from_compact_gid,
-                visible,
-            ],
+            &outputs,
         );
         let op = CustomOp {
-            cam: cam.clone(),
+            left: left.clone(),
+            right: right.clone(),
             img_size,
             bwd_info,
             background,
+            options,
             render_mode,
             desc: desc.clone(),
         };
 
-        let outputs = client
+        let outputs: [_; 2 * VIEW_OUTPUTS] = client
             .register(stream, OperationIr::Custom(desc), op)
             .outputs();
-
-        let [
-            // Img
-            out_img,
-            // Aux
-            projected_splats,
-            uniforms_buffer,
-            num_intersections,
-            tile_offsets,
-            compact_gid_from_isect,
-            global_from_compact_gid,
-            visible,
-        ] = outputs;
-
-        (
-            out_img,
-            RenderAux::<Self> {
-                projected_splats,
-                uniforms_buffer,
-                num_intersections,
-                tile_offsets,
-                compact_gid_from_isect,
-                global_from_compact_gid,
-                visible,
-                img_size,
-            },
-        )
+        let mut outputs = outputs.into_iter();
+        [(); 2].map(|()| {
+            let view = std::array::from_fn(|_| outputs.next().expect("Outputs of both eyes"));
+            view_from_outputs(view, img_size, render_mode, options)
+        })
     }
 }
@@ -1,12 +1,139 @@
 use glam::Affine3A;
+use thiserror::Error;
 
-#[derive(Debug, Default, Clone, PartialEq)]
+/// How camera space is projected onto the image plane.
+#[derive(Debug, Default, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case", tag = "type")]
+pub enum ProjectionMode {
+    /// Pinhole projection using the field of view of the camera.
+    #[default]
+    Perspective,
+    /// Parallel projection where the image covers `width` x `height` world units. The field of
+    /// view of the camera is ignored.
+    Orthographic { width: f32, height: f32 },
+    /// Full 360° x 180° latitude/longitude panorama around the camera position. The field of
+    /// view is ignored. Splats crossing the ±180° seam are cut off at the image border.
+    Equirectangular,
+    /// Fisheye, where the distance of a pixel from the principal point grows with the angle
+    /// of its ray from the optical axis as given by `model`. The circle covering a cone of
+    /// `max_fov_rad` fits the shorter side of the image, and can be wider than 180° to see
+    /// behind the camera. Pixels outside the circle are transparent. The field of view of
+    /// the camera is ignored.
+    Fisheye {
+        max_fov_rad: f32,
+        #[serde(default)]
+        model: FisheyeModel,
+    },
+}
+
+/// How the distance of a pixel from the principal point of a fisheye relates to the angle θ
+/// of its ray from the optical axis.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    clap::ValueEnum,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum FisheyeModel {
+    /// `r = f * θ`, the angles are spread evenly over the image.
+    #[default]
+    Equidistant,
+    /// `r = 2 f * sin(θ / 2)`, which keeps the area of the image proportional to the solid
+    /// angle it covers, like many real fisheye lenses.
+    Equisolid,
+}
+
+impl FisheyeModel {
+    /// Distance from the principal point in focal lengths of a ray `theta` radians from the
+    /// optical axis.
+    pub fn radius(self, theta: f32) -> f32 {
+        match self {
+            Self::Equidistant => theta,
+            Self::Equisolid => 2.0 * (theta / 2.0).sin(),
+        }
+    }
+
+    /// The inverse of [`Self::radius`].
+    pub fn angle(self, radius: f32) -> f32 {
+        match self {
+            Self::Equidistant => radius,
+            Self::Equisolid => 2.0 * (radius / 2.0).clamp(-1.0, 1.0).asin(),
+        }
+    }
+}
+
+/// A face of a cubemap, named after the camera space axis it looks along. Camera space has +X
+/// pointing right, +Y pointing down and +Z forward.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubeFace {
+    PosX,
+    NegX,
+    PosY,
+    NegY,
+    PosZ,
+    NegZ,
+}
+
+impl CubeFace {
+    pub const ALL: [Self; 6] = [
+        Self::PosX,
+        Self::NegX,
+        Self::PosY,
+        Self::NegY,
+        Self::PosZ,
+        Self::NegZ,
+    ];
+
+    /// Rotation from the face camera to the camera space of the cube.
+    pub fn local_rotation(self) -> glam::Quat {
+        use std::f32::consts::{FRAC_PI_2, PI};
+        match self {
+            Self::PosX => glam::Quat::from_rotation_y(FRAC_PI_2),
+            Self::NegX => glam::Quat::from_rotation_y(-FRAC_PI_2),
+            Self::PosY => glam::Quat::from_rotation_x(-FRAC_PI_2),
+            Self::NegY => glam::Quat::from_rotation_x(FRAC_PI_2),
+            Self::PosZ => glam::Quat::IDENTITY,
+            Self::NegZ => glam::Quat::from_rotation_y(PI),
+        }
+    }
+
+    /// The face a camera space direction points into.
+    pub fn from_dir(dir: glam::Vec3) -> Self {
+        let abs = dir.abs();
+        if abs.x >= abs.y && abs.x >= abs.z {
+            if dir.x >= 0.0 { Self::PosX } else { Self::NegX }
+        } else if abs.y >= abs.z {
+            if dir.y >= 0.0 { Self::PosY } else { Self::NegY }
+        } else if dir.z >= 0.0 {
+            Self::PosZ
+        } else {
+            Self::NegZ
+        }
+    }
+}
+
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum LookAtError {
+    #[error("Camera position and look-at target are the same point.")]
+    TargetAtPosition,
+    #[error("View direction is parallel to the up vector.")]
+    ParallelUp,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Camera {
     pub fov_x: f64,
     pub fov_y: f64,
     pub center_uv: glam::Vec2,
     pub position: glam::Vec3,
     pub rotation: glam::Quat,
+    pub projection: ProjectionMode,
 }
 
 impl Camera {
@@ -23,7 +150,142 @@ impl Camera {
             center_uv,
             position,
             rotation,
+            projection: ProjectionMode::Perspective,
+        }
+    }
+
+    /// Perspective camera from pixel space intrinsics, as output by calibration tools: the
+    /// focal length `focal` and principal point `center` in pixels of an `img_size` image.
+    pub fn from_intrinsics(
+        position: glam::Vec3,
+        rotation: glam::Quat,
+        focal: glam::DVec2,
+        center: glam::Vec2,
+        img_size: glam::UVec2,
+    ) -> Self {
+        Self::new(
+            position,
+            rotation,
+            focal_to_fov(focal.x, img_size.x),
+            focal_to_fov(focal.y, img_size.y),
+            center / img_size.as_vec2(),
+        )
+    }
+
+    /// Create a camera at `position` looking towards `target`, with `up` pointing up in the
+    /// image.
+    pub fn look_at(
+        position: glam::Vec3,
+        target: glam::Vec3,
+        up: glam::Vec3,
+        fov_x: f64,
+        fov_y: f64,
+        center_uv: glam::Vec2,
+    ) -> Result<Self, LookAtError> {
+        let rotation = look_at_rotation(position, target, up)?;
+        Ok(Self::new(position, rotation, fov_x, fov_y, center_uv))
+    }
+
+    /// Camera on a sphere of `radius` around `center`, looking at the center with +Y up.
+    ///
+    /// At an azimuth and elevation of 0 the camera sits on the -Z side of the center, looking
+    /// down +Z. Increasing the azimuth moves it around the Y axis towards +X, and a positive
+    /// elevation raises it towards +Y. At the poles the image up direction is +Z instead.
+    pub fn orbit(
+        center: glam::Vec3,
+        radius: f32,
+        azimuth_rad: f32,
+        elevation_rad: f32,
+        fov_x: f64,
+        fov_y: f64,
+    ) -> Self {
+        let dir = glam::vec3(
+            elevation_rad.cos() * azimuth_rad.sin(),
+            elevation_rad.sin(),
+            -elevation_rad.cos() * azimuth_rad.cos(),
+        );
+        let position = center + dir * radius;
+        let rotation = look_at_rotation(position, center, glam::Vec3::Y)
+            .or_else(|_| look_at_rotation(position, center, glam::Vec3::Z))
+            .unwrap_or(glam::Quat::IDENTITY);
+        Self::new(position, rotation, fov_x, fov_y, glam::Vec2::splat(0.5))
+    }
+
+    /// Left and right eye cameras for stereo rendering, offset by `ipd / 2` to either side
+    /// along the local X axis. The eyes look parallel by default, or toe in to converge at
+    /// `convergence_distance` in front of the camera.
+    pub fn stereo_eyes(&self, ipd: f32, convergence_distance: Option<f32>) -> [Self; 2] {
+        let half_ipd = ipd / 2.0;
+        let toe_in = convergence_distance.map_or(0.0, |d| half_ipd.atan2(d));
+        [(-1.0, toe_in), (1.0, -toe_in)].map(|(side, yaw)| Self {
+            position: self.position + self.rotation * glam::vec3(side * half_ipd, 0.0, 0.0),
+            rotation: self.rotation * glam::Quat::from_rotation_y(yaw),
+            ..self.clone()
+        })
+    }
+
+    /// A camera whose view covers the views of both `left` and `right`, so the eyes of a
+    /// stereo pair can share one depth sort. Returns the camera and how much further away
+    /// splats are from it than from the eyes.
+    ///
+    /// The eyes have to be perspective cameras with the same intrinsics, looking the same
+    /// way and offset along their X axis, like the parallel [`Self::stereo_eyes`]. The camera
+    /// then sits behind the midpoint of the eyes, far enough back for its frustum to contain
+    /// both of theirs. It looks the same way, so its depths only differ by a constant and
+    /// sort the splats the same as the depths of either eye.
+    pub(crate) fn stereo_sort_camera(left: &Self, right: &Self) -> Option<(Self, f32)> {
+        let parallel = left.projection == ProjectionMode::Perspective
+            && right.projection == ProjectionMode::Perspective
+            && left.fov_x == right.fov_x
+            && left.fov_y == right.fov_y
+            && left.center_uv == right.center_uv
+            && left.rotation.angle_between(right.rotation) < 1e-5;
+        if !parallel {
+            return None;
+        }
+        let baseline = left.world_to_local().transform_point3(right.position);
+        if baseline.y.abs() > 1e-4 * baseline.x.abs() + 1e-6
+            || baseline.z.abs() > 1e-4 * baseline.x.abs() + 1e-6
+        {
+            return None;
         }
+        // Tangent of the angle between the view direction and the left and right edges of
+        // the image.
+        let tan_width = 2.0 * (left.fov_x as f32 * 0.5).tan();
+        let edge_tan = (left.center_uv.x * tan_width).min((1.0 - left.center_uv.x) * tan_width);
+        if edge_tan <= 0.0 {
+            return None;
+        }
+        let pull_back = 0.5 * baseline.x.abs() / edge_tan;
+        let midpoint = (left.position + right.position) * 0.5;
+        let position = midpoint - left.rotation * glam::Vec3::Z * pull_back;
+        Some((
+            Self {
+                position,
+                ..left.clone()
+            },
+            pull_back,
+        ))
+    }
+
+    /// Six perspective cameras with a 90° field of view at the camera position, one looking
+    /// along each local axis of this camera in [`CubeFace::ALL`] order. Together they cover
+    /// every direction, with the edges of neighbouring faces lining up exactly.
+    pub fn cube_faces(&self) -> [Self; 6] {
+        CubeFace::ALL.map(|face| {
+            Self::new(
+                self.position,
+                self.rotation * face.local_rotation(),
+                std::f64::consts::FRAC_PI_2,
+                std::f64::consts::FRAC_PI_2,
+                glam::Vec2::splat(0.5),
+            )
+        })
+    }
+
+    pub fn with_projection(mut self, projection: ProjectionMode) -> Self {
+        self.projection = projection;
+        self
     }
 
     /// Check if the camera has valid (non-nan/inf) settings.
@@ -33,13 +295,36 @@ impl Camera {
             && self.center_uv.is_finite()
             && self.position.is_finite()
             && self.rotation.is_finite()
+            && match self.projection {
+                ProjectionMode::Perspective | ProjectionMode::Equirectangular => true,
+                ProjectionMode::Orthographic { width, height } => {
+                    width.is_finite() && height.is_finite() && width > 0.0 && height > 0.0
+                }
+                ProjectionMode::Fisheye { max_fov_rad, .. } => {
+                    max_fov_rad > 0.0 && max_fov_rad <= std::f32::consts::TAU
+                }
+            }
     }
 
+    /// Focal length in pixels. For orthographic cameras this is the number of pixels
+    /// per world unit instead, for equirectangular and equidistant fisheye cameras the pixels
+    /// per radian, and for other fisheyes the `f` of their [`FisheyeModel`].
     pub fn focal(&self, img_size: glam::UVec2) -> glam::Vec2 {
-        glam::vec2(
-            fov_to_focal(self.fov_x, img_size.x) as f32,
-            fov_to_focal(self.fov_y, img_size.y) as f32,
-        )
+        match self.projection {
+            ProjectionMode::Perspective => glam::vec2(
+                fov_to_focal(self.fov_x, img_size.x) as f32,
+                fov_to_focal(self.fov_y, img_size.y) as f32,
+            ),
+            ProjectionMode::Orthographic { width, height } => {
+                img_size.as_vec2() / glam::vec2(width, height)
+            }
+            ProjectionMode::Equirectangular => {
+                img_size.as_vec2() / glam::vec2(std::f32::consts::TAU, std::f32::consts::PI)
+            }
+            ProjectionMode::Fisheye { max_fov_rad, model } => glam::Vec2::splat(
+                img_size.min_element() as f32 / (2.0 * model.radius(max_fov_rad / 2.0)),
+            ),
+        }
     }
 
     pub fn center(&self, img_size: glam::UVec2) -> glam::Vec2 {
@@ -49,6 +334,35 @@ impl Camera {
         )
     }
 
+    /// Camera rendering the `size` pixel region at `offset` of the `img_size` image of this
+    /// camera, eg. to render a large image in parts. The focal length and principal point
+    /// stay the same in pixels. Returns `None` for equirectangular and fisheye cameras, which
+    /// can't be split up.
+    pub fn sub_view(
+        &self,
+        img_size: glam::UVec2,
+        offset: glam::UVec2,
+        size: glam::UVec2,
+    ) -> Option<Self> {
+        let focal = self.focal(img_size).as_dvec2();
+        let center = self.center(img_size) - offset.as_vec2();
+        let projection = match self.projection {
+            ProjectionMode::Perspective => ProjectionMode::Perspective,
+            ProjectionMode::Orthographic { width, height } => ProjectionMode::Orthographic {
+                width: width * size.x as f32 / img_size.x as f32,
+                height: height * size.y as f32 / img_size.y as f32,
+            },
+            ProjectionMode::Equirectangular | ProjectionMode::Fisheye { .. } => return None,
+        };
+        Some(Self {
+            fov_x: focal_to_fov(focal.x, size.x),
+            fov_y: focal_to_fov(focal.y, size.y),
+            center_uv: center / size.as_vec2(),
+            projection,
+            ..self.clone()
+        })
+    }
+
     pub fn local_to_world(&self) -> Affine3A {
         Affine3A::from_rotation_translation(self.rotation, self.position)
     }
@@ -57,12 +371,303 @@ impl Camera {
         self.local_to_world().inverse()
     }
 }
+
+/// Rotation of a camera at `position` looking towards `target`.
+///
+/// Cameras look down their local +Z axis, with +X pointing right and +Y pointing down in
+/// the image, so the rotation maps those axes onto the world space view frame.
+pub fn look_at_rotation(
+    position: glam::Vec3,
+    target: glam::Vec3,
+    up: glam::Vec3,
+) -> Result<glam::Quat, LookAtError> {
+    let forward = (target - position)
+        .try_normalize()
+        .ok_or(LookAtError::TargetAtPosition)?;
+    let right = forward
+        .cross(up)
+        .try_normalize()
+        .ok_or(LookAtError::ParallelUp)?;
+    let down = forward.cross(right);
+    Ok(glam::Quat::from_mat3(&glam::Mat3::from_cols(
+        right, down, forward,
+    )))
+}
+
 // Converts field of view to focal length
-pub fn fov_to_focal(fov_rad: f64, pixels: u32) -> f64 {
-    0.5 * (pixels as f64) / (fov_rad * 0.5).tan()
+pub fn fov_to_focal<T: Into<f64>>(fov_rad: f64, pixels: T) -> f64 {
+    0.5 * pixels.into() / (fov_rad * 0.5).tan()
 }
 
 // Converts focal length to field of view
-pub fn focal_to_fov(focal: f64, pixels: u32) -> f64 {
-    2.0 * f64::atan((pixels as f64) / (2.0 * focal))
+pub fn focal_to_fov<T: Into<f64>>(focal: f64, pixels: T) -> f64 {
+    2.0 * f64::atan(pixels.into() / (2.0 * focal))
+}
+
+/// Width of a full frame (35mm film) sensor in millimeters.
+pub const SENSOR_WIDTH_35MM: f64 = 36.0;
+
+/// Converts a 35mm equivalent focal length in millimeters to the horizontal field of view.
+pub fn fov_from_focal_35mm(focal_mm: f64) -> f64 {
+    focal_to_fov(focal_mm, SENSOR_WIDTH_35MM)
+}
+
+/// Converts a horizontal field of view to a 35mm equivalent focal length in millimeters.
+pub fn focal_35mm_from_fov(fov_rad: f64) -> f64 {
+    fov_to_focal(fov_rad, SENSOR_WIDTH_35MM)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::{Vec2, Vec3};
+
+    #[test]
+    fn from_intrinsics_round_trips() {
+        let img_size = glam::uvec2(1600, 900);
+        let cam = Camera::from_intrinsics(
+            Vec3::ZERO,
+            glam::Quat::IDENTITY,
+            glam::dvec2(1200.0, 1180.0),
+            Vec2::new(812.5, 441.0),
+            img_size,
+        );
+        assert!(
+            cam.focal(img_size)
+                .abs_diff_eq(Vec2::new(1200.0, 1180.0), 1e-3)
+        );
+        assert!(
+            cam.center(img_size)
+                .abs_diff_eq(Vec2::new(812.5, 441.0), 1e-3)
+        );
+        assert_eq!(cam.projection, ProjectionMode::Perspective);
+    }
+
+    #[test]
+    fn focal_35mm_conversions() {
+        assert!((fov_from_focal_35mm(24.0).to_degrees() - 73.74).abs() < 0.01);
+        assert!((fov_from_focal_35mm(35.0).to_degrees() - 54.43).abs() < 0.01);
+        assert!((fov_from_focal_35mm(50.0).to_degrees() - 39.60).abs() < 0.01);
+        assert!((focal_35mm_from_fov(fov_from_focal_35mm(85.0)) - 85.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn stereo_sort_camera_covers_both_eyes() {
+        let cam = Camera::new(
+            Vec3::new(1.0, 2.0, 0.0),
+            glam::Quat::from_rotation_y(0.5),
+            1.2,
+            0.9,
+            Vec2::new(0.4, 0.5),
+        );
+        let [left, right] = cam.stereo_eyes(0.064, None);
+        let (sort_cam, offset) = Camera::stereo_sort_camera(&left, &right).unwrap();
+        assert!(offset > 0.0);
+        let img_size = glam::uvec2(200, 100);
+        let in_view = |camera: &Camera, point: Vec3| {
+            let local = camera.world_to_local().transform_point3(point);
+            let pixel =
+                camera.focal(img_size) * local.truncate() / local.z + camera.center(img_size);
+            local.z > 0.0 && pixel.cmpge(Vec2::ZERO).all() && pixel.cmple(img_size.as_vec2()).all()
+        };
+        for eye in [&left, &right] {
+            for corner in [Vec2::ZERO, Vec2::X, Vec2::Y, Vec2::ONE] {
+                // Points on the edges of the eye frustum at a few depths.
+                let uv = (corner - eye.center_uv) * 0.999;
+                let tan = Vec2::new(
+                    2.0 * (eye.fov_x as f32 * 0.5).tan(),
+                    2.0 * (eye.fov_y as f32 * 0.5).tan(),
+                );
+                for depth in [0.05, 1.0, 30.0] {
+                    let local = (uv * tan * depth).extend(depth);
+                    let point = eye.local_to_world().transform_point3(local);
+                    assert!(in_view(&sort_cam, point));
+                    let eye_depth = eye.world_to_local().transform_point3(point).z;
+                    let sort_depth = sort_cam.world_to_local().transform_point3(point).z;
+                    assert!((sort_depth - eye_depth - offset).abs() < 1e-4);
+                }
+            }
+        }
+
+        // Toed in eyes don't share a depth order.
+        let [left, right] = cam.stereo_eyes(0.064, Some(2.0));
+        assert!(Camera::stereo_sort_camera(&left, &right).is_none());
+    }
+
+    #[test]
+    fn stereo_eyes_offset_and_converge() {
+        let cam = Camera::new(
+            Vec3::new(1.0, 0.0, 0.0),
+            glam::Quat::from_rotation_y(0.5),
+            0.5,
+            0.5,
+            Vec2::splat(0.5),
+        );
+        let [left, right] = cam.stereo_eyes(0.064, None);
+        // Offset along the local X axis, looking the same way.
+        let local_left = cam.world_to_local().transform_point3(left.position);
+        let local_right = cam.world_to_local().transform_point3(right.position);
+        assert!(local_left.abs_diff_eq(Vec3::new(-0.032, 0.0, 0.0), 1e-6));
+        assert!(local_right.abs_diff_eq(Vec3::new(0.032, 0.0, 0.0), 1e-6));
+        assert_eq!(left.rotation, cam.rotation);
+
+        // Toed in eyes both look at the convergence point.
+        let target = cam
+            .local_to_world()
+            .transform_point3(Vec3::new(0.0, 0.0, 2.0));
+        for eye in cam.stereo_eyes(0.064, Some(2.0)) {
+            let local = eye.world_to_local().transform_point3(target);
+            assert!(local.x.abs() < 1e-5 && local.y.abs() < 1e-5 && local.z > 0.0);
+        }
+    }
+
+    #[test]
+    fn focal_fov_accept_any_dimension() {
+        let focal = fov_to_focal(1.0, 640_u32);
+        assert_eq!(focal, fov_to_focal(1.0, 640_u16));
+        assert_eq!(focal, fov_to_focal(1.0, 640.0_f64));
+        assert!((focal_to_fov(focal, 640.0_f32) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn cube_faces_look_along_axes() {
+        let cam = Camera::new(
+            glam::vec3(1.0, 2.0, 3.0),
+            glam::Quat::from_rotation_z(0.3),
+            1.0,
+            1.0,
+            glam::Vec2::splat(0.5),
+        );
+        for (face, face_cam) in CubeFace::ALL.into_iter().zip(cam.cube_faces()) {
+            let forward = cam.rotation.inverse() * (face_cam.rotation * glam::Vec3::Z);
+            assert_eq!(CubeFace::from_dir(forward), face);
+            assert!(forward.abs().max_element() > 0.9999);
+            assert_eq!(face_cam.position, cam.position);
+            assert_eq!(face_cam.fov_x, std::f64::consts::FRAC_PI_2);
+        }
+    }
+
+    #[test]
+    fn serde_round_trips() {
+        let cam = Camera::new(
+            glam::vec3(1.5, -2.0, 0.1),
+            glam::Quat::from_rotation_y(0.3),
+            0.9,
+            0.7,
+            glam::vec2(0.4, 0.6),
+        )
+        .with_projection(ProjectionMode::Orthographic {
+            width: 2.0,
+            height: 1.5,
+        });
+        let json = serde_json::to_string(&cam).unwrap();
+        assert_eq!(serde_json::from_str::<Camera>(&json).unwrap(), cam);
+    }
+
+    #[test]
+    fn look_at_points_at_target() {
+        let position = Vec3::new(1.0, 2.0, 3.0);
+        let target = Vec3::new(-2.0, 0.5, 7.0);
+        let cam = Camera::look_at(position, target, Vec3::Y, 0.5, 0.5, Vec2::splat(0.5)).unwrap();
+
+        // The target ends up straight ahead in camera space.
+        let local = cam.world_to_local().transform_point3(target);
+        assert!(local.x.abs() < 1e-5 && local.y.abs() < 1e-5);
+        assert!((local.z - position.distance(target)).abs() < 1e-5);
+
+        // World up points up in the image, which is -Y in camera space.
+        let up_local = cam.world_to_local().transform_vector3(Vec3::Y);
+        assert!(up_local.y < 0.0);
+        assert!(up_local.x.abs() < 1e-5);
+    }
+
+    #[test]
+    fn look_at_degenerate() {
+        let position = Vec3::new(1.0, 2.0, 3.0);
+        assert_eq!(
+            look_at_rotation(position, position, Vec3::Y),
+            Err(LookAtError::TargetAtPosition)
+        );
+        assert_eq!(
+            look_at_rotation(position, position + Vec3::Y * 2.0, Vec3::Y),
+            Err(LookAtError::ParallelUp)
+        );
+    }
+
+    #[test]
+    fn sub_view_keeps_pixel_intrinsics() {
+        let img_size = glam::uvec2(640, 480);
+        let offset = glam::uvec2(320, 120);
+        let size = glam::uvec2(160, 240);
+        let cam = Camera::new(
+            Vec3::ZERO,
+            glam::Quat::IDENTITY,
+            0.8,
+            0.6,
+            Vec2::new(0.4, 0.5),
+        );
+
+        for projection in [
+            ProjectionMode::Perspective,
+            ProjectionMode::Orthographic {
+                width: 4.0,
+                height: 3.0,
+            },
+        ] {
+            let cam = cam.clone().with_projection(projection);
+            let sub = cam.sub_view(img_size, offset, size).unwrap();
+            assert!(sub.focal(size).abs_diff_eq(cam.focal(img_size), 1e-3));
+            assert!((sub.center(size) + offset.as_vec2()).abs_diff_eq(cam.center(img_size), 1e-3));
+        }
+
+        let panorama = cam.with_projection(ProjectionMode::Equirectangular);
+        assert!(panorama.sub_view(img_size, offset, size).is_none());
+    }
+
+    #[test]
+    fn orbit_looks_at_center() {
+        let center = Vec3::new(1.0, -2.0, 0.5);
+        let front = Camera::orbit(center, 3.0, 0.0, 0.0, 0.5, 0.5);
+        assert!(front.position.abs_diff_eq(center - Vec3::Z * 3.0, 1e-5));
+        assert!((front.rotation * Vec3::Z).abs_diff_eq(Vec3::Z, 1e-5));
+
+        for (azimuth, elevation) in [(1.0, 0.3), (-2.5, -0.7), (0.0, std::f32::consts::FRAC_PI_2)] {
+            let cam = Camera::orbit(center, 3.0, azimuth, elevation, 0.5, 0.5);
+            assert!((cam.position.distance(center) - 3.0).abs() < 1e-4);
+            assert!((cam.position.y - center.y - 3.0 * elevation.sin()).abs() < 1e-4);
+            let forward = cam.rotation * Vec3::Z;
+            assert!(forward.dot((center - cam.position).normalize()) > 0.9999);
+        }
+
+        // Quarter turn ends up on the +X side.
+        let side = Camera::orbit(center, 3.0, std::f32::consts::FRAC_PI_2, 0.0, 0.5, 0.5);
+        assert!(side.position.abs_diff_eq(center + Vec3::X * 3.0, 1e-5));
+    }
+
+    #[test]
+    fn fisheye_models() {
+        for model in [FisheyeModel::Equidistant, FisheyeModel::Equisolid] {
+            for theta in [0.0, 0.5, 1.6, 3.0] {
+                assert!((model.angle(model.radius(theta)) - theta).abs() < 1e-5);
+            }
+            // The image circle of a 190° lens fits the shorter side.
+            let max_fov_rad = 190f32.to_radians();
+            let cam = Camera::new(Vec3::ZERO, glam::Quat::IDENTITY, 1.0, 1.0, Vec2::splat(0.5))
+                .with_projection(ProjectionMode::Fisheye { max_fov_rad, model });
+            let focal = cam.focal(glam::uvec2(400, 300));
+            assert!((focal.x * model.radius(max_fov_rad / 2.0) - 150.0).abs() < 1e-3);
+        }
+        assert!(FisheyeModel::Equisolid.radius(1.0) < FisheyeModel::Equidistant.radius(1.0));
+
+        // Cameras saved before the model existed are equidistant.
+        let projection: ProjectionMode =
+            serde_json::from_str(r#"{"type": "fisheye", "max_fov_rad": 3.0}"#).unwrap();
+        assert_eq!(
+            projection,
+            ProjectionMode::Fisheye {
+                max_fov_rad: 3.0,
+                model: FisheyeModel::Equidistant,
+            }
+        );
+    }
 }
@@ -0,0 +1,27 @@
+use burn::{Tensor, prelude::Backend};
+
+use crate::tonemap::map_color;
+
+/// Encode the linear colors of an `[H, W, C]` image with the piecewise sRGB transfer
+/// function of IEC 61966-2-1, in one element-wise pass.
+///
+/// Negative values are clamped to 0, values above 1 follow the power curve. The fourth
+/// channel of an RGBA image is alpha and left alone.
+pub fn linear_to_srgb<B: Backend>(img: Tensor<B, 3>) -> Tensor<B, 3> {
+    map_color(img, |color| {
+        let color = color.clamp_min(0.0);
+        let linear = color.clone() * 12.92;
+        let curve = color.clone().powf_scalar(1.0 / 2.4) * 1.055 - 0.055;
+        curve.mask_where(color.lower_equal_elem(0.003_130_8), linear)
+    })
+}
+
+/// The inverse of [`linear_to_srgb`], decoding sRGB colors to linear light.
+pub fn srgb_to_linear<B: Backend>(img: Tensor<B, 3>) -> Tensor<B, 3> {
+    map_color(img, |color| {
+        let color = color.clamp_min(0.0);
+        let linear = color.clone() / 12.92;
+        let curve = ((color.clone() + 0.055) / 1.055).powf_scalar(2.4);
+        curve.mask_where(color.lower_equal_elem(0.040_45), linear)
+    })
+}
@@ -0,0 +1,57 @@
+use burn::{
+    prelude::Backend,
+    tensor::{FloatDType, IndexingUpdateOp, Int, Tensor},
+};
+use glam::UVec3;
+
+/// Count the splat centers `means` in each voxel of a `grid_size` grid spanning their
+/// bounding box, as a `[gx, gy, gz]` int tensor, eg. to visualize the density of a scene
+/// or find holes in its coverage.
+///
+/// Splats on the far faces of the box count towards the last voxels. The splats are
+/// binned with a single scatter add, which runs as one compute pass with atomics on the GPU
+/// backends, and nothing is read back, the counts stay on the device.
+///
+/// # Panics
+///
+/// When a dimension of `grid_size` is zero.
+pub fn density_voxelise<B: Backend>(means: Tensor<B, 2>, grid_size: UVec3) -> Tensor<B, 3, Int> {
+    assert!(grid_size.min_element() > 0, "Grid size must be positive");
+    let device = means.device();
+    let [num_splats, _] = means.dims();
+    let shape = grid_size.to_array().map(|d| d as usize);
+    let counts = Tensor::<B, 1, Int>::zeros([shape.iter().product::<usize>()], &device);
+    if num_splats == 0 {
+        return counts.reshape(shape);
+    }
+
+    let means = means.cast(FloatDType::F32);
+    let box_min = means.clone().min_dim(0);
+    let box_max = means.clone().max_dim(0);
+    // Flat axes put every splat in the first voxel.
+    let extent = (box_max - box_min.clone()).clamp_min(1e-12);
+    let grid = Tensor::<B, 1>::from_floats(grid_size.as_vec3().to_array(), &device)
+        .reshape([1, 3])
+        .expand([num_splats, 3]);
+    // Splats on the far faces of the box land one past the end, count them in the last voxel.
+    let cells = ((means - box_min) / extent * grid.clone())
+        .floor()
+        .clamp_min(0.0)
+        .min_pair(grid - 1.0)
+        .int();
+
+    // Voxels are indexed as [x][y][z].
+    let strides = [grid_size.y * grid_size.z, grid_size.z, 1].map(|s| s as i32);
+    let strides = Tensor::<B, 1, Int>::from_ints(strides, &device)
+        .reshape([1, 3])
+        .expand([num_splats, 3]);
+    let voxels = (cells * strides).sum_dim(1).reshape([num_splats]);
+    counts
+        .scatter(
+            0,
+            voxels,
+            Tensor::ones([num_splats], &device),
+            IndexingUpdateOp::Add,
+        )
+        .reshape(shape)
+}
@@ -0,0 +1,166 @@
+use std::ops::Range;
+
+use brush_kernel::create_uniform_buffer;
+use brush_sort::radix_argsort;
+use burn::tensor::{
+    FloatDType, Int, IntDType, Tensor, TensorPrimitive,
+    ops::{FloatTensorOps, IntTensorOps},
+};
+use burn_cubecl::cubecl::Runtime;
+use burn_wgpu::{WgpuDevice, WgpuRuntime};
+use glam::Vec3;
+
+use crate::{
+    MainBackendBase, RenderOptions,
+    camera::Camera,
+    gaussian_splats::{SplatRenderMode, Splats},
+    render::{
+        DepthSorted, Projected, RenderInputs, num_visible_of, project_splats, rasterize_sorted,
+        render_uniforms,
+    },
+    render_aux::RenderAux,
+    shaders::helpers::RenderUniforms,
+};
+
+/// Render splats with the per-splat projection pass spread over several devices.
+///
+/// Each `(device, range)` pair copies that slice of the splats to `device` and runs the
+/// projection pass there, which culls the splats `camera` can't see and computes the depth of
+/// the rest. The visible ids and depths of all slices are gathered on `primary`, merged into
+/// one front to back order with a single radix sort, and rasterized there. With ranges that
+/// don't overlap and cover all splats the image matches [`crate::render_splats`] on one
+/// device.
+///
+/// Splats outside of every range aren't rendered. The whole scene is copied to `primary` as
+/// well, as the rasterizer looks up the visible splats there.
+///
+/// # Panics
+///
+/// When a range is out of bounds, or for feature renders, which rasterize in several passes.
+pub async fn render_splats_distributed(
+    splats: &Splats<MainBackendBase>,
+    parts: &[(WgpuDevice, Range<usize>)],
+    primary: &WgpuDevice,
+    camera: &Camera,
+    img_size: glam::UVec2,
+    background: Vec3,
+) -> (Tensor<MainBackendBase, 3>, RenderAux<MainBackendBase>) {
+    assert!(
+        img_size[0] > 0 && img_size[1] > 0,
+        "Can't render images with 0 size."
+    );
+    let render_mode = splats.render_mode;
+    assert!(
+        !matches!(render_mode, SplatRenderMode::Feature { .. }),
+        "Feature renders can't be distributed"
+    );
+    splats.validate_values();
+
+    let options = RenderOptions::default();
+    let total_splats = splats.num_splats() as usize;
+    let inputs = RenderInputs::new(
+        splats.means.val().into_primitive().tensor(),
+        splats.log_scales.val().into_primitive().tensor(),
+        splats.rotations.val().into_primitive().tensor(),
+        splats.sh_coeffs.val().into_primitive().tensor(),
+        splats.raw_opacities.val().into_primitive().tensor(),
+        render_mode,
+        options,
+    );
+
+    let mut ids = vec![];
+    let mut depths = vec![];
+    let mut num_visible = 0;
+    for (device, range) in parts {
+        assert!(
+            range.end <= total_splats,
+            "Splat range {range:?} is out of bounds"
+        );
+        if range.is_empty() {
+            continue;
+        }
+        let part = inputs.slice_to_device(range.clone(), device);
+        let uniforms = render_uniforms(camera, img_size, &part, render_mode, background, options);
+        let Projected {
+            global_from_presort_gid,
+            depths: part_depths,
+            num_visible: part_visible,
+            ..
+        } = project_splats(camera, &part, render_mode, uniforms, options);
+
+        // Only the first part_visible entries are valid, so the count has to be read back to
+        // know what to gather.
+        let part_visible = Tensor::<MainBackendBase, 1, Int>::from_primitive(part_visible)
+            .into_data_async()
+            .await
+            .expect("Failed to read back the visible count")
+            .iter::<u32>()
+            .next()
+            .expect("Visible count has one element") as usize;
+        if part_visible == 0 {
+            continue;
+        }
+        let visible = [(0..part_visible).into()];
+        let part_ids = MainBackendBase::int_slice(global_from_presort_gid, &visible);
+        let part_ids = Tensor::<MainBackendBase, 1, Int>::from_primitive(
+            MainBackendBase::int_to_device(part_ids, primary),
+        ) + range.start as u32;
+        ids.push(part_ids.into_primitive());
+        depths.push(MainBackendBase::float_to_device(
+            MainBackendBase::float_slice(part_depths, &visible),
+            primary,
+        ));
+        num_visible += part_visible;
+    }
+
+    // Pad the merged lists to the size of a single device render, the entries past
+    // num_visible are ignored like theirs.
+    let padding = total_splats - num_visible;
+    if padding > 0 {
+        ids.push(MainBackendBase::int_zeros(
+            [padding].into(),
+            primary,
+            IntDType::U32,
+        ));
+        depths.push(MainBackendBase::float_zeros(
+            [padding].into(),
+            primary,
+            FloatDType::F32,
+        ));
+    }
+
+    let inputs = inputs.slice_to_device(0..total_splats, primary);
+    let uniforms = RenderUniforms {
+        num_visible: num_visible as u32,
+        ..render_uniforms(camera, img_size, &inputs, render_mode, background, options)
+    };
+    let uniforms_buffer = create_uniform_buffer(uniforms, primary, &WgpuRuntime::client(primary));
+    let num_visible = num_visible_of(&uniforms_buffer);
+
+    // Each slice is culled independently, so sorting the gathered depths gives the same
+    // front to back order as projecting all splats at once.
+    let (depth_from_compact_gid, global_from_compact_gid) = radix_argsort(
+        MainBackendBase::float_cat(depths, 0),
+        MainBackendBase::int_cat(ids, 0),
+        &num_visible,
+        32,
+    );
+
+    let sorted = DepthSorted {
+        uniforms_buffer,
+        global_from_compact_gid,
+        depth_from_compact_gid,
+        num_visible,
+    };
+    let (img, aux) = rasterize_sorted(
+        camera,
+        img_size,
+        &inputs,
+        sorted,
+        render_mode,
+        false,
+        options,
+    );
+    aux.unwrap_values();
+    (Tensor::from_primitive(TensorPrimitive::Float(img)), aux)
+}
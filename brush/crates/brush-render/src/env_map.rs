@@ -0,0 +1,115 @@
+use std::f32::consts::{PI, TAU};
+
+use burn::{
+    Tensor,
+    prelude::Backend,
+    tensor::{Int, s},
+};
+use glam::Mat3;
+
+use crate::camera::{Camera, FisheyeModel, ProjectionMode};
+
+/// Normalized world space direction of the ray through the center of every pixel of an
+/// `img_size` render of `camera`, as an `[H, W, 3]` tensor.
+///
+/// Orthographic cameras have the same direction for every pixel, and equirectangular and
+/// fisheye cameras use the angles of the pixel like the rasterizer.
+pub fn ray_directions<B: Backend>(
+    camera: &Camera,
+    img_size: glam::UVec2,
+    device: &B::Device,
+) -> Tensor<B, 3> {
+    let (w, h) = (img_size.x as usize, img_size.y as usize);
+    let focal = camera.focal(img_size);
+    let center = camera.center(img_size);
+
+    // Image plane coordinates of the pixel centers.
+    let x = (Tensor::<B, 1, Int>::arange(0..w as i64, device).float() + 0.5 - center.x) / focal.x;
+    let y = (Tensor::<B, 1, Int>::arange(0..h as i64, device).float() + 0.5 - center.y) / focal.y;
+    let x = x.reshape([1, w]).expand([h, w]);
+    let y = y.reshape([h, 1]).expand([h, w]);
+
+    let local = match camera.projection {
+        ProjectionMode::Perspective => vec![x, y, Tensor::ones([h, w], device)],
+        ProjectionMode::Orthographic { .. } => vec![
+            Tensor::zeros([h, w], device),
+            Tensor::zeros([h, w], device),
+            Tensor::ones([h, w], device),
+        ],
+        ProjectionMode::Equirectangular => {
+            let (lon, lat) = (x, y);
+            vec![
+                lat.clone().cos() * lon.clone().sin(),
+                lat.clone().sin(),
+                lat.cos() * lon.cos(),
+            ]
+        }
+        ProjectionMode::Fisheye { model, .. } => {
+            // The distance from the principal point gives the angle from the optical axis.
+            let radius = (x.clone().powi_scalar(2) + y.clone().powi_scalar(2)).sqrt();
+            let theta = match model {
+                FisheyeModel::Equidistant => radius.clone(),
+                FisheyeModel::Equisolid => {
+                    // 2 asin(r / 2), written with atan2.
+                    let half = (radius.clone() / 2.0).clamp(0.0, 1.0);
+                    let cos = (half.clone().powi_scalar(2).neg() + 1.0).sqrt();
+                    half.atan2(cos) * 2.0
+                }
+            };
+            let scale = theta.clone().sin() / radius.clamp_min(1e-8);
+            vec![x * scale.clone(), y * scale, theta.cos()]
+        }
+    };
+    let local = Tensor::stack::<3>(local, 2).reshape([h * w, 3]);
+
+    // The pixels are row vectors, so multiply by the transposed camera to world rotation.
+    let rotation = Mat3::from_quat(camera.rotation);
+    let rotation = Tensor::<B, 2>::from_floats(rotation.to_cols_array_2d(), device);
+    let dirs = local.matmul(rotation);
+    let len = dirs.clone().powi_scalar(2).sum_dim(1).sqrt();
+    (dirs / len).reshape([h, w, 3])
+}
+
+/// Bilinearly sample the `[H, W, C]` lat-long environment map `env_map` along the `[H, W, 3]`
+/// world space directions `dirs`, giving one `C` channel sample per direction.
+///
+/// The map uses the layout of an equirectangular render with an identity rotation, so a
+/// panorama rendered by brush can be used directly: the center of the map looks along +Z,
+/// longitude increases to the right and the top row looks along -Y. Samples wrap around
+/// horizontally and clamp at the poles.
+pub fn sample_env_map<B: Backend>(env_map: Tensor<B, 3>, dirs: Tensor<B, 3>) -> Tensor<B, 3> {
+    let [map_h, map_w, channels] = env_map.dims();
+    let [h, w, _] = dirs.dims();
+    let dirs = dirs.reshape([h * w, 3]);
+    let dx = dirs.clone().slice(s![.., 0..1]);
+    let dy = dirs.clone().slice(s![.., 1..2]);
+    let dz = dirs.slice(s![.., 2..3]);
+
+    let horizontal = (dx.clone().powi_scalar(2) + dz.clone().powi_scalar(2)).sqrt();
+    let lon = dx.atan2(dz);
+    let lat = dy.atan2(horizontal);
+
+    // Continuous pixel coordinates, with pixel centers at whole numbers.
+    let px = (lon / TAU + 0.5) * map_w as f32 - 0.5;
+    let py = (lat / PI + 0.5) * map_h as f32 - 0.5;
+    let (x0, y0) = (px.clone().floor(), py.clone().floor());
+    let (tx, ty) = (px - x0.clone(), py - y0.clone());
+
+    // Longitude wraps around, latitude clamps.
+    let x0 = (x0 + map_w as f32).remainder_scalar(map_w as f32);
+    let x1 = (x0.clone() + 1.0).remainder_scalar(map_w as f32);
+    let max_y = (map_h - 1) as f32;
+    let y1 = (y0.clone() + 1.0).clamp(0.0, max_y);
+    let y0 = y0.clamp(0.0, max_y);
+
+    let texels = env_map.reshape([map_h * map_w, channels]);
+    let at = |x: Tensor<B, 2>, y: Tensor<B, 2>| {
+        let index = (y * map_w as f32 + x).int().reshape([h * w]);
+        texels.clone().select(0, index)
+    };
+    let top =
+        at(x0.clone(), y0.clone()) * (tx.clone().neg() + 1.0) + at(x1.clone(), y0) * tx.clone();
+    let bottom = at(x0, y1.clone()) * (tx.clone().neg() + 1.0) + at(x1, y1) * tx;
+    let color = top * (ty.clone().neg() + 1.0) + bottom * ty;
+    color.reshape([h, w, channels])
+}
@@ -2,17 +2,20 @@ use burn::{
     Tensor,
     module::{Module, Param, ParamId},
     prelude::Backend,
-    tensor::{TensorData, TensorPrimitive, activation::sigmoid, s},
+    tensor::{Bool, DType, FloatDType, Int, TensorData, TensorPrimitive, activation::sigmoid, s},
 };
 use clap::ValueEnum;
-use glam::Vec3;
+use glam::{Mat3, Quat, Vec3};
+use thiserror::Error;
+use tokio_util::sync::CancellationToken;
 use tracing::trace_span;
 
 use crate::{
-    SplatForward,
+    RenderCancelled, RenderOptions, SplatForward,
     camera::Camera,
+    env_map,
     render_aux::RenderAux,
-    sh::{sh_coeffs_for_degree, sh_degree_from_coeffs},
+    sh::{sh_coeffs_for_degree, sh_degree_from_coeffs, sh_rotation_matrix},
 };
 
 #[derive(
@@ -22,6 +25,204 @@ use crate::{
 pub enum SplatRenderMode {
     Default,
     Mip,
+    /// Render the alpha weighted world space normal of the splats instead of their color.
+    /// The normal of a splat is the shortest axis of its ellipsoid, flipped to face the
+    /// camera. The image is `[H, W, 3]` with values in `[-1, 1]` and ignores the background.
+    NormalsWorldSpace,
+    /// Like [`Self::NormalsWorldSpace`], with the normals in the camera frame (+X right, +Y
+    /// down, +Z forward). Surfaces facing the camera have a normal towards -Z.
+    NormalsCameraSpace,
+    /// Render only the accumulated alpha as an `[H, W, 1]` f32 image, which matches the alpha
+    /// channel of a color render. Skips evaluating the spherical harmonics, so it's cheaper.
+    AlphaOnly,
+    /// Render the world space position of every pixel as an `[H, W, 3]` f32 image, eg. for
+    /// distance maps or to compare geometry between frames. The position is the point on the
+    /// pixel ray at the expected depth, which is also the depth output of these renders.
+    /// Pixels no splat contributed to are zero, and the background is ignored.
+    WorldPosition,
+    /// Alpha blend the `dim` dimensional [`Splats::features`] instead of the color, eg. for
+    /// semantic feature fields. The image is `[H, W, dim]`, with the features premultiplied
+    /// by alpha and no background. The spherical harmonics aren't evaluated.
+    #[value(skip)]
+    Feature {
+        dim: usize,
+    },
+}
+
+impl std::fmt::Display for SplatRenderMode {
+    /// The kebab case name also used by the CLI and serde, with the dimension appended for
+    /// features, eg. `normals-world-space` or `feature-8`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::Mip => write!(f, "mip"),
+            Self::NormalsWorldSpace => write!(f, "normals-world-space"),
+            Self::NormalsCameraSpace => write!(f, "normals-camera-space"),
+            Self::AlphaOnly => write!(f, "alpha-only"),
+            Self::WorldPosition => write!(f, "world-position"),
+            Self::Feature { dim } => write!(f, "feature-{dim}"),
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("Unknown render mode '{0}'")]
+pub struct ParseRenderModeError(String);
+
+impl std::str::FromStr for SplatRenderMode {
+    type Err = ParseRenderModeError;
+
+    /// Parse the names written by [`Display`](std::fmt::Display), ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_ascii_lowercase();
+        let mode = match name.as_str() {
+            "default" => Self::Default,
+            "mip" => Self::Mip,
+            "normals-world-space" => Self::NormalsWorldSpace,
+            "normals-camera-space" => Self::NormalsCameraSpace,
+            "alpha-only" => Self::AlphaOnly,
+            "world-position" => Self::WorldPosition,
+            _ => {
+                let dim = name
+                    .strip_prefix("feature-")
+                    .and_then(|dim| dim.parse().ok())
+                    .ok_or_else(|| ParseRenderModeError(s.to_owned()))?;
+                Self::Feature { dim }
+            }
+        };
+        Ok(mode)
+    }
+}
+
+/// How the means, log scales, rotations and SH coefficients of [`Splats`] are stored on the
+/// GPU. The opacities, features and motion are always f32.
+///
+/// F16 splats take a bit over half the memory of f32 splats, and get upcast to f32 when the
+/// shaders load them. They can only be rendered, training needs f32.
+#[derive(
+    Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum SplatPrecision {
+    #[default]
+    F32,
+    F16,
+}
+
+impl SplatPrecision {
+    pub fn dtype(self) -> FloatDType {
+        match self {
+            Self::F32 => FloatDType::F32,
+            Self::F16 => FloatDType::F16,
+        }
+    }
+
+    /// The precision to store splats in for rendering on `device`: f16 when the device
+    /// supports it (the `SHADER_F16` feature on wgpu), otherwise f32 with a warning.
+    pub fn preferred<B: Backend>(device: &B::Device) -> Self {
+        if B::supports_dtype(device, DType::F16) {
+            Self::F16
+        } else {
+            log::warn!("Device doesn't support f16, storing splats as f32");
+            Self::F32
+        }
+    }
+}
+
+/// Largest feature dimension supported by [`SplatRenderMode::Feature`].
+pub const MAX_FEATURE_DIM: usize = 16;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum FeatureError {
+    #[error("Features have {0} dimensions, expected between 1 and {MAX_FEATURE_DIM}")]
+    InvalidDim(usize),
+    #[error("Got features for {actual} splats, expected {expected}")]
+    WrongCount { expected: usize, actual: usize },
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ConcatError {
+    #[error("No splats to concatenate")]
+    Empty,
+    #[error("Can't concatenate splats with different render modes ({0:?} and {1:?})")]
+    MismatchedRenderModes(SplatRenderMode, SplatRenderMode),
+}
+
+/// Minimum, median and maximum of a per splat value, see [`SplatStatistics`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ValueSummary {
+    pub min: f32,
+    pub median: f32,
+    pub max: f32,
+}
+
+impl ValueSummary {
+    fn from_values(mut values: Vec<f32>) -> Self {
+        if values.is_empty() {
+            return Self::default();
+        }
+        values.sort_unstable_by(f32::total_cmp);
+        Self {
+            min: values[0],
+            median: values[values.len() / 2],
+            max: values[values.len() - 1],
+        }
+    }
+}
+
+impl std::fmt::Display for ValueSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "min {:.4}, median {:.4}, max {:.4}",
+            self.min, self.median, self.max
+        )
+    }
+}
+
+/// Device buffers of a forward render that grow with the number of splats: the projected
+/// splats (9 floats), their depths, sort keys and values, tile counts and compact ids.
+const RENDER_BYTES_PER_SPLAT: usize = (9 + 5) * 4;
+
+/// Summary of a set of splats, see [`Splats::statistics`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SplatStatistics {
+    pub num_splats: u32,
+    pub sh_degree: u32,
+    pub render_mode: SplatRenderMode,
+    /// Bounds of the splat means, ignoring their extent.
+    pub means_min: Vec3,
+    pub means_max: Vec3,
+    /// Opacities after the sigmoid.
+    pub opacity: ValueSummary,
+    /// Largest axis of each splat, after the exponential.
+    pub scale: ValueSummary,
+    /// Size of the splat attributes on the device, see [`Splats::size_bytes`].
+    pub size_bytes: usize,
+    /// Estimated device memory needed to render the splats: the attributes plus the per
+    /// splat render buffers. Buffers scaling with the image size or the number of tile
+    /// intersections depend on the view and aren't included.
+    pub render_bytes: usize,
+}
+
+impl std::fmt::Display for SplatStatistics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mib = |bytes: usize| bytes as f64 / (1024.0 * 1024.0);
+        let (min, max) = (self.means_min, self.means_max);
+        writeln!(f, "Splats: {}", self.num_splats)?;
+        writeln!(f, "SH degree: {}", self.sh_degree)?;
+        writeln!(f, "Render mode: {:?}", self.render_mode)?;
+        writeln!(f, "Means min: [{}, {}, {}]", min.x, min.y, min.z)?;
+        writeln!(f, "Means max: [{}, {}, {}]", max.x, max.y, max.z)?;
+        writeln!(f, "Opacity: {}", self.opacity)?;
+        writeln!(f, "Scale: {}", self.scale)?;
+        writeln!(f, "Splat memory: {:.1} MiB", mib(self.size_bytes))?;
+        writeln!(
+            f,
+            "Estimated render memory: {:.1} MiB",
+            mib(self.render_bytes)
+        )
+    }
 }
 
 #[derive(Module, Debug)]
@@ -31,6 +232,11 @@ pub struct Splats<B: Backend> {
     pub log_scales: Param<Tensor<B, 2>>,
     pub sh_coeffs: Param<Tensor<B, 3>>,
     pub raw_opacities: Param<Tensor<B, 1>>,
+    /// Optional `[N, D]` per splat feature vectors, rendered by [`SplatRenderMode::Feature`].
+    pub features: Option<Param<Tensor<B, 2>>>,
+    /// Optional `[N, 5]` motion of dynamic (4D) splats, see [`Splats::at_time`]. Each row is
+    /// the time center, log time scale and xyz velocity of a splat.
+    pub motion: Option<Param<Tensor<B, 2>>>,
     pub render_mode: SplatRenderMode,
 }
 
@@ -117,10 +323,96 @@ impl<B: Backend> Splats<B> {
             rotations: Param::initialized(ParamId::new(), rotation.detach().require_grad()),
             raw_opacities: Param::initialized(ParamId::new(), raw_opacity.detach().require_grad()),
             log_scales: Param::initialized(ParamId::new(), log_scales.detach().require_grad()),
+            features: None,
+            motion: None,
             render_mode: mode,
         }
     }
 
+    /// Attach `[N, D]` feature vectors to the splats and render them instead of the color,
+    /// with [`SplatRenderMode::Feature`].
+    pub fn with_features(mut self, features: Tensor<B, 2>) -> Result<Self, FeatureError> {
+        let [n, dim] = features.dims();
+        if !(1..=MAX_FEATURE_DIM).contains(&dim) {
+            return Err(FeatureError::InvalidDim(dim));
+        }
+        if n != self.num_splats() as usize {
+            return Err(FeatureError::WrongCount {
+                expected: self.num_splats() as usize,
+                actual: n,
+            });
+        }
+        self.features = Some(Param::initialized(
+            ParamId::new(),
+            features.detach().require_grad(),
+        ));
+        self.render_mode = SplatRenderMode::Feature { dim };
+        Ok(self)
+    }
+
+    /// Attach `[N, 5]` motion to the splats, making them dynamic, see [`Self::at_time`].
+    ///
+    /// # Panics
+    ///
+    /// When `motion` doesn't have a row of 5 values for each splat.
+    pub fn with_motion(mut self, motion: Tensor<B, 2>) -> Self {
+        assert_eq!(
+            motion.dims(),
+            [self.num_splats() as usize, 5],
+            "Motion must have 5 values per splat"
+        );
+        self.motion = Some(Param::initialized(
+            ParamId::new(),
+            motion.detach().require_grad(),
+        ));
+        self
+    }
+
+    /// Evaluate dynamic splats at time `t`, giving static splats that render the scene at
+    /// that moment. Splats without [`Self::motion`] are returned unchanged.
+    ///
+    /// Times are normalized to `[0, 1]` over the capture, and `t` is clamped to that range.
+    /// Each splat moves linearly with its velocity, passing its mean at its time center.
+    /// Its opacity fades with a gaussian of the time since the center, with a standard
+    /// deviation of the exponent of its log time scale. Everything stays on the device.
+    pub fn at_time(&self, t: f32) -> Self {
+        let mut splats = self.clone();
+        let Some(motion) = splats.motion.take() else {
+            return splats;
+        };
+        let t = t.clamp(0.0, 1.0);
+        let motion = motion.val();
+        let dt = motion.clone().slice(s![.., 0..1]).neg() + t;
+        let t_scales = motion.clone().slice(s![.., 1..2]).exp();
+        let velocities = motion.slice(s![.., 2..5]);
+
+        splats.means = splats.means.map(|m| {
+            let dtype = m.dtype();
+            (m.cast(FloatDType::F32) + velocities * dt.clone())
+                .cast(dtype)
+                .detach()
+                .require_grad()
+        });
+        // Scale the opacity after the sigmoid, and go back to raw logits.
+        let weights = (dt / t_scales).powi_scalar(2).mul_scalar(-0.5).exp();
+        let opacities = (self.opacities() * weights.squeeze_dim(1)).clamp(1e-12, 1.0 - 1e-7);
+        splats.raw_opacities = splats.raw_opacities.map(|_| {
+            (opacities.clone() / (opacities.clone().neg() + 1.0))
+                .log()
+                .detach()
+                .require_grad()
+        });
+        splats
+    }
+
+    /// Drop the SH bands above `max_degree`. Splats with a lower degree are left unchanged.
+    pub fn with_max_sh_degree(self, max_degree: u32) -> Self {
+        if self.sh_degree() <= max_degree {
+            return self;
+        }
+        self.with_sh_degree(max_degree)
+    }
+
     pub fn opacities(&self) -> Tensor<B, 1> {
         sigmoid(self.raw_opacities.val())
     }
@@ -151,6 +443,342 @@ impl<B: Backend> Splats<B> {
         self.means.device()
     }
 
+    pub fn precision(&self) -> SplatPrecision {
+        if self.means.val().dtype() == DType::F16 {
+            SplatPrecision::F16
+        } else {
+            SplatPrecision::F32
+        }
+    }
+
+    /// Store the attributes in `precision`, see [`SplatPrecision`].
+    ///
+    /// F16 needs a device with f16 support (the `SHADER_F16` feature on wgpu). Other devices
+    /// keep f32 and log a warning.
+    pub fn with_precision(mut self, precision: SplatPrecision) -> Self {
+        let precision =
+            if precision == SplatPrecision::F16 && !B::supports_dtype(&self.device(), DType::F16) {
+                log::warn!("Device doesn't support f16, storing splats as f32");
+                SplatPrecision::F32
+            } else {
+                precision
+            };
+        let dtype = precision.dtype();
+        self.means = self.means.map(|x| x.cast(dtype));
+        self.rotations = self.rotations.map(|x| x.cast(dtype));
+        self.log_scales = self.log_scales.map(|x| x.cast(dtype));
+        self.sh_coeffs = self.sh_coeffs.map(|x| x.cast(dtype));
+        self
+    }
+
+    /// Size of the splat attributes on the device in bytes.
+    pub fn size_bytes(&self) -> usize {
+        fn bytes<B: Backend, const D: usize>(param: &Param<Tensor<B, D>>) -> usize {
+            let tensor = param.val();
+            tensor.shape().num_elements() * tensor.dtype().size()
+        }
+        bytes(&self.means)
+            + bytes(&self.rotations)
+            + bytes(&self.log_scales)
+            + bytes(&self.sh_coeffs)
+            + bytes(&self.raw_opacities)
+            + self.features.as_ref().map_or(0, bytes)
+            + self.motion.as_ref().map_or(0, bytes)
+    }
+
+    /// Read the splats back from the device and summarize them. The bounds are zero and the
+    /// summaries default when there are no splats.
+    pub async fn statistics(&self) -> SplatStatistics {
+        async fn read<B: Backend, const D: usize>(tensor: Tensor<B, D>) -> Vec<f32> {
+            tensor
+                .into_data_async()
+                .await
+                .expect("Failed to read splats")
+                .convert::<f32>()
+                .into_vec()
+                .expect("Failed to read splats")
+        }
+        let means = read(self.means.val()).await;
+        let opacities = read(self.opacities()).await;
+        let scales = read(self.log_scales.val().max_dim(1).exp()).await;
+
+        let (means_min, means_max) = if means.is_empty() {
+            (Vec3::ZERO, Vec3::ZERO)
+        } else {
+            means
+                .chunks_exact(3)
+                .map(Vec3::from_slice)
+                .fold((Vec3::INFINITY, Vec3::NEG_INFINITY), |(min, max), p| {
+                    (min.min(p), max.max(p))
+                })
+        };
+        let size_bytes = self.size_bytes();
+        SplatStatistics {
+            num_splats: self.num_splats(),
+            sh_degree: self.sh_degree(),
+            render_mode: self.render_mode,
+            means_min,
+            means_max,
+            opacity: ValueSummary::from_values(opacities),
+            scale: ValueSummary::from_values(scales),
+            size_bytes,
+            render_bytes: size_bytes + self.num_splats() as usize * RENDER_BYTES_PER_SPLAT,
+        }
+    }
+
+    /// Only keep the splats where `keep` is true.
+    pub async fn retain(self, keep: Tensor<B, 1, Bool>) -> Self {
+        assert_eq!(
+            keep.dims()[0] as u32,
+            self.num_splats(),
+            "Mask must have same number of elements as splats"
+        );
+        let indices = keep.argwhere_async().await.squeeze_dim(1);
+        if indices.dims()[0] as u32 == self.num_splats() {
+            return self;
+        }
+        self.select(indices)
+    }
+
+    /// Only keep the splats at `indices`, in that order.
+    fn select(mut self, indices: Tensor<B, 1, Int>) -> Self {
+        self.means = self.means.map(|x| x.select(0, indices.clone()));
+        self.rotations = self.rotations.map(|x| x.select(0, indices.clone()));
+        self.log_scales = self.log_scales.map(|x| x.select(0, indices.clone()));
+        self.sh_coeffs = self.sh_coeffs.map(|x| x.select(0, indices.clone()));
+        self.features = self
+            .features
+            .map(|f| f.map(|x| x.select(0, indices.clone())));
+        self.motion = self.motion.map(|m| m.map(|x| x.select(0, indices.clone())));
+        self.raw_opacities = self.raw_opacities.map(|x| x.select(0, indices));
+        self
+    }
+
+    /// Remove all splats whose mean lies outside of the axis aligned box from `min` to `max`.
+    /// Splats exactly on the boundary are kept.
+    pub async fn crop_aabb(self, min: Vec3, max: Vec3) -> Self {
+        let means = self.means.val();
+        let inside = (0..3)
+            .map(|axis| {
+                let coord = means.clone().slice(s![.., axis]).squeeze_dim::<1>(1);
+                coord
+                    .clone()
+                    .greater_equal_elem(min[axis])
+                    .bool_and(coord.lower_equal_elem(max[axis]))
+            })
+            .reduce(Tensor::bool_and)
+            .expect("Always 3 axes");
+        self.retain(inside).await
+    }
+
+    /// Concatenate several sets of splats into one. Sets with a lower SH degree are padded
+    /// with zero coefficients up to the highest degree. All sets need the same render mode.
+    pub fn concat(splats: &[Self]) -> Result<Self, ConcatError> {
+        let first = splats.first().ok_or(ConcatError::Empty)?;
+        if let Some(other) = splats.iter().find(|s| s.render_mode != first.render_mode) {
+            return Err(ConcatError::MismatchedRenderModes(
+                first.render_mode,
+                other.render_mode,
+            ));
+        }
+        let sh_degree = splats.iter().map(Self::sh_degree).max().unwrap_or(0);
+        let splats: Vec<_> = splats
+            .iter()
+            .map(|s| s.clone().with_sh_degree(sh_degree))
+            .collect();
+
+        let mut merged = Self::from_tensor_data(
+            Tensor::cat(splats.iter().map(|s| s.means.val()).collect(), 0),
+            Tensor::cat(splats.iter().map(|s| s.rotations.val()).collect(), 0),
+            Tensor::cat(splats.iter().map(|s| s.log_scales.val()).collect(), 0),
+            Tensor::cat(splats.iter().map(|s| s.sh_coeffs.val()).collect(), 0),
+            Tensor::cat(splats.iter().map(|s| s.raw_opacities.val()).collect(), 0),
+            first.render_mode,
+        );
+        // Features are only kept when every set has them. Feature renders always do, and
+        // their dimensions match as the render modes are the same.
+        if let Some(features) = splats
+            .iter()
+            .map(|s| s.features.as_ref().map(|f| f.val()))
+            .collect::<Option<Vec<_>>>()
+            && features
+                .iter()
+                .all(|f| f.dims()[1] == features[0].dims()[1])
+        {
+            merged.features = Some(Param::initialized(
+                ParamId::new(),
+                Tensor::cat(features, 0).detach().require_grad(),
+            ));
+        }
+        // Static sets stand still and are always visible when merged with dynamic ones: a
+        // huge time scale keeps their opacity.
+        if splats.iter().any(|s| s.motion.is_some()) {
+            let motion = splats
+                .iter()
+                .map(|s| match &s.motion {
+                    Some(motion) => motion.val(),
+                    None => {
+                        let still = [0.5, 1e6f32.ln(), 0.0, 0.0, 0.0];
+                        Tensor::<B, 1>::from_floats(still, &s.device())
+                            .reshape([1, 5])
+                            .repeat_dim(0, s.num_splats() as usize)
+                    }
+                })
+                .collect();
+            merged.motion = Some(Param::initialized(
+                ParamId::new(),
+                Tensor::cat(motion, 0).detach().require_grad(),
+            ));
+        }
+        Ok(merged)
+    }
+
+    /// Merge two sets of splats into one, see [`Self::concat`]. Both sets need the same
+    /// render mode. Cloning splats is cheap, the tensors share their data until modified, so
+    /// `a.clone()` can be merged while `a` stays in use.
+    pub fn merge(a: Self, b: Self) -> Result<Self, ConcatError> {
+        Self::concat(&[a, b])
+    }
+
+    /// Move the splats by the similarity transform `p' = scale * rotation * p + translation`.
+    /// The splat rotations and scales follow along, and the SH coefficients are rotated so
+    /// view dependent colors keep facing the same way relative to the splats.
+    ///
+    /// # Panics
+    ///
+    /// When `scale` isn't positive.
+    pub fn transform(mut self, translation: Vec3, rotation: Quat, scale: f32) -> Self {
+        assert!(scale > 0.0, "Scale must be positive");
+        let device = self.device();
+        let rotation = rotation.normalize();
+
+        // Points are stored as rows, so multiply by the transposed rotation matrix.
+        let rot = Mat3::from_quat(rotation);
+        let rot_t = Tensor::<B, 2>::from_floats(
+            [
+                rot.x_axis.to_array(),
+                rot.y_axis.to_array(),
+                rot.z_axis.to_array(),
+            ],
+            &device,
+        );
+        let translation = Tensor::<B, 1>::from_floats(translation.to_array(), &device);
+        self.means = self.means.map(|m| {
+            (m.matmul(rot_t.clone()) * scale + translation.unsqueeze())
+                .detach()
+                .require_grad()
+        });
+        self.log_scales = self
+            .log_scales
+            .map(|s| (s + scale.ln()).detach().require_grad());
+
+        // Left multiply the w, x, y, z quaternions by the rotation.
+        let Quat { x, y, z, w } = rotation;
+        let quat_mul_t = Tensor::<B, 2>::from_floats(
+            [[w, x, y, z], [-x, w, z, -y], [-y, -z, w, x], [-z, y, -x, w]],
+            &device,
+        );
+        self.rotations = self
+            .rotations
+            .map(|r| r.matmul(quat_mul_t).detach().require_grad());
+        // Velocities rotate and scale like the means, without the translation.
+        self.motion = self.motion.map(|m| {
+            m.map(|m| {
+                let time = m.clone().slice(s![.., 0..2]);
+                let velocities = m.slice(s![.., 2..5]).matmul(rot_t.clone()) * scale;
+                Tensor::cat(vec![time, velocities], 1)
+                    .detach()
+                    .require_grad()
+            })
+        });
+
+        let [n, num_coeffs, _] = self.sh_coeffs.dims();
+        if num_coeffs > 1 {
+            let sh_rot = sh_rotation_matrix(self.sh_degree(), rotation);
+            let sh_rot = Tensor::<B, 2>::from_data(
+                TensorData::new(sh_rot.concat(), [num_coeffs, num_coeffs]),
+                &device,
+            );
+            self.sh_coeffs = self.sh_coeffs.map(|c| {
+                // Rotate the coefficients of each color channel as rows.
+                let rows = c.swap_dims(1, 2).reshape([n * 3, num_coeffs]);
+                rows.matmul(sh_rot)
+                    .reshape([n, 3, num_coeffs])
+                    .swap_dims(1, 2)
+                    .detach()
+                    .require_grad()
+            });
+        }
+        self
+    }
+
+    /// Normalize the scene by moving the mean of the splat centers to the origin, and scaling
+    /// it so the 90th percentile of the distances to the center becomes one. The percentile
+    /// ignores a few far away outliers, unlike the bounding box.
+    ///
+    /// Also returns the applied `translation` and `scale`, with `p' = (p + translation) *
+    /// scale`, to map positions back. Empty or single point scenes aren't scaled.
+    pub async fn center_and_scale(self) -> (Self, Vec3, f32) {
+        let means: Vec<Vec3> = self
+            .means
+            .val()
+            .into_data_async()
+            .await
+            .expect("Failed to read means")
+            .convert::<f32>()
+            .into_vec::<f32>()
+            .expect("Failed to read means")
+            .chunks_exact(3)
+            .map(Vec3::from_slice)
+            .collect();
+        if means.is_empty() {
+            return (self, Vec3::ZERO, 1.0);
+        }
+        let center = means.iter().sum::<Vec3>() / means.len() as f32;
+        let mut distances: Vec<f32> = means.iter().map(|p| p.distance(center)).collect();
+        distances.sort_by(f32::total_cmp);
+        // Nearest rank percentile.
+        let rank = (0.9 * distances.len() as f32).ceil() as usize;
+        let radius = distances[rank.clamp(1, distances.len()) - 1];
+        let scale = if radius > 0.0 { 1.0 / radius } else { 1.0 };
+
+        let translation = -center;
+        let splats = self.transform(translation * scale, Quat::IDENTITY, scale);
+        (splats, translation, scale)
+    }
+
+    /// Remove all splats with an opacity (after the sigmoid) below `threshold`.
+    pub async fn prune_by_opacity(self, threshold: f32) -> Self {
+        let keep = self.opacities().greater_equal_elem(threshold);
+        self.retain(keep).await
+    }
+
+    /// Remove all splats with an opacity (after the sigmoid) of at most `min_opacity`.
+    /// Unlike [`Self::prune_by_opacity`] the splats to keep are picked on the CPU, from the
+    /// opacities read back from the device. Also returns the number of removed splats.
+    pub async fn filter_by_opacity(self, min_opacity: f32) -> (Self, u32) {
+        let raw_opacities: Vec<f32> = self
+            .raw_opacities
+            .val()
+            .into_data_async()
+            .await
+            .expect("Failed to read opacities")
+            .into_vec()
+            .expect("Failed to read opacities");
+        let keep: Vec<i32> = raw_opacities
+            .iter()
+            .enumerate()
+            .filter(|&(_, &raw)| 1.0 / (1.0 + (-raw).exp()) > min_opacity)
+            .map(|(i, _)| i as i32)
+            .collect();
+        let removed = (raw_opacities.len() - keep.len()) as u32;
+        if removed == 0 {
+            return (self, 0);
+        }
+        let indices = Tensor::from_ints(keep.as_slice(), &self.device());
+        (self.select(indices), removed)
+    }
+
     pub fn validate_values(&self) {
         #[cfg(any(test, feature = "debug-validation"))]
         {
@@ -226,6 +854,29 @@ impl<B: Backend> Splats<B> {
                 n_sh, num_splats as usize,
                 "Inconsistent number of splats in SH coeffs"
             );
+            if let Some(features) = &self.features {
+                validate_tensor_val(&features.val(), "features", None, None);
+                assert_eq!(
+                    features.dims()[0],
+                    num_splats as usize,
+                    "Inconsistent number of splats in features"
+                );
+            }
+            if let Some(motion) = &self.motion {
+                validate_tensor_val(&motion.val(), "motion", None, None);
+                assert_eq!(
+                    motion.dims(),
+                    [num_splats as usize, 5],
+                    "Inconsistent number of splats in motion"
+                );
+            }
+            if let SplatRenderMode::Feature { dim } = self.render_mode {
+                let features = self
+                    .features
+                    .as_ref()
+                    .expect("Feature render mode needs features");
+                assert_eq!(features.dims()[1], dim, "Feature dimension mismatch");
+            }
         }
     }
 }
@@ -250,9 +901,52 @@ pub fn render_splats<B: Backend + SplatForward<B>>(
         scales = scales + scale.ln();
     };
 
-    let (img, aux) = B::render_splats(
+    let (img, aux) = forward(
+        splats,
         camera,
         img_size,
+        scales,
+        background,
+        false,
+        RenderOptions::default(),
+    );
+
+    aux.unwrap_values();
+
+    (img, aux)
+}
+
+/// Render a stereo pair like [`render_splats`] for the `left` and `right` eye.
+///
+/// Eyes looking the same way, like the parallel eyes of [`Camera::stereo_eyes`], share one
+/// depth sort, which saves most of the cost of the second eye's projection and sorting.
+/// Other pairs, eg. toed in eyes, are rendered separately with a warning. Feature renders
+/// always render the eyes separately.
+pub fn render_splats_stereo<B: Backend + SplatForward<B>>(
+    splats: &Splats<B>,
+    left: &Camera,
+    right: &Camera,
+    img_size: glam::UVec2,
+    background: Vec3,
+    splat_scale: Option<f32>,
+) -> ((Tensor<B, 3>, RenderAux<B>), (Tensor<B, 3>, RenderAux<B>)) {
+    if matches!(splats.render_mode, SplatRenderMode::Feature { .. }) {
+        return (
+            render_splats(splats, left, img_size, background, splat_scale),
+            render_splats(splats, right, img_size, background, splat_scale),
+        );
+    }
+    splats.validate_values();
+
+    let mut scales = splats.log_scales.val();
+    if let Some(scale) = splat_scale {
+        scales = scales + scale.ln();
+    };
+
+    let [left, right] = B::render_splats_stereo(
+        left,
+        right,
+        img_size,
         splats.means.val().into_primitive().tensor(),
         scales.into_primitive().tensor(),
         splats.rotations.val().into_primitive().tensor(),
@@ -261,10 +955,309 @@ pub fn render_splats<B: Backend + SplatForward<B>>(
         splats.render_mode,
         background,
         false,
+        RenderOptions::default(),
+    )
+    .map(|(img, aux)| {
+        aux.unwrap_values();
+        (Tensor::from_primitive(TensorPrimitive::Float(img)), aux)
+    });
+    (left, right)
+}
+
+/// Run the forward pass for `splats` with `log_scales` in place of their own scales. Feature
+/// renders pass their features in place of the SH coefficients, padded to `[N, ceil(D / 3), 3]`,
+/// and render an `[H, W, D]` image.
+fn forward<B: Backend + SplatForward<B>>(
+    splats: &Splats<B>,
+    camera: &Camera,
+    img_size: glam::UVec2,
+    log_scales: Tensor<B, 2>,
+    background: Vec3,
+    bwd_info: bool,
+    options: RenderOptions,
+) -> (Tensor<B, 3>, RenderAux<B>) {
+    forward_cancellable(
+        splats,
+        camera,
+        img_size,
+        log_scales,
+        background,
+        bwd_info,
+        options,
+        &CancellationToken::new(),
+    )
+    .expect("Render can't be cancelled")
+}
+
+fn forward_cancellable<B: Backend + SplatForward<B>>(
+    splats: &Splats<B>,
+    camera: &Camera,
+    img_size: glam::UVec2,
+    log_scales: Tensor<B, 2>,
+    background: Vec3,
+    bwd_info: bool,
+    options: RenderOptions,
+    token: &CancellationToken,
+) -> Result<(Tensor<B, 3>, RenderAux<B>), RenderCancelled> {
+    let coeffs = match splats.render_mode {
+        SplatRenderMode::Feature { dim } => {
+            let features = splats
+                .features
+                .as_ref()
+                .expect("Feature render mode needs features")
+                .val();
+            let [n, _] = features.dims();
+            let device = features.device();
+            // Pad the features to a multiple of three channels.
+            let padded = dim.div_ceil(3) * 3;
+            Tensor::cat(vec![features, Tensor::zeros([n, padded - dim], &device)], 1).reshape([
+                n,
+                padded / 3,
+                3,
+            ])
+        }
+        _ => splats.sh_coeffs.val(),
+    };
+    let (img, aux) = B::render_splats_cancellable(
+        camera,
+        img_size,
+        splats.means.val().into_primitive().tensor(),
+        log_scales.into_primitive().tensor(),
+        splats.rotations.val().into_primitive().tensor(),
+        coeffs.into_primitive().tensor(),
+        splats.raw_opacities.val().into_primitive().tensor(),
+        splats.render_mode,
+        background,
+        bwd_info,
+        options,
+        token,
+    )?;
+    Ok((Tensor::from_primitive(TensorPrimitive::Float(img)), aux))
+}
+
+/// Like [`render_splats`], but stops with [`RenderCancelled`] once `token` is cancelled, eg.
+/// from another task when the render isn't needed anymore.
+///
+/// The token is checked between the stages of the render, see
+/// [`SplatForward::render_splats_cancellable`]. This also waits for the device to finish the
+/// render, and drops the result when the token was cancelled in the meantime.
+pub async fn render_splats_cancellable<B: Backend + SplatForward<B>>(
+    splats: &Splats<B>,
+    camera: &Camera,
+    img_size: glam::UVec2,
+    background: Vec3,
+    splat_scale: Option<f32>,
+    token: CancellationToken,
+) -> Result<(Tensor<B, 3>, RenderAux<B>), RenderCancelled> {
+    splats.validate_values();
+
+    let mut scales = splats.log_scales.val();
+    if let Some(scale) = splat_scale {
+        scales = scales + scale.ln();
+    }
+
+    let (img, aux) = forward_cancellable(
+        splats,
+        camera,
+        img_size,
+        scales,
+        background,
+        false,
+        RenderOptions::default(),
+        &token,
+    )?;
+    // Reading back the visible count waits for the whole render.
+    let _ = aux.num_visible().into_scalar_async().await;
+    if token.is_cancelled() {
+        return Err(RenderCancelled);
+    }
+
+    aux.unwrap_values();
+
+    Ok((img, aux))
+}
+
+/// The background image passed to [`render_splats_over_image`] doesn't match the output size.
+#[derive(Debug, Error)]
+#[error("Background image has shape {actual:?}, expected {expected:?}")]
+pub struct BackgroundSizeError {
+    pub expected: [usize; 3],
+    pub actual: [usize; 3],
+}
+
+/// Render splats over an `[H, W, 3]` background image instead of a solid color. Returns a
+/// float `[H, W, 4]` image where the color is composited over the background as
+/// `fg.rgb * fg.a + bg.rgb * (1 - fg.a)`, and alpha is the accumulated splat opacity like
+/// for a solid background.
+///
+/// NB: This doesn't work on a differentiable backend.
+///
+/// # Panics
+///
+/// Normal and alpha only renders have no color to composite.
+pub fn render_splats_over_image<B: Backend + SplatForward<B>>(
+    splats: &Splats<B>,
+    camera: &Camera,
+    img_size: glam::UVec2,
+    background_image: Tensor<B, 3>,
+    splat_scale: Option<f32>,
+) -> Result<(Tensor<B, 3>, RenderAux<B>), BackgroundSizeError> {
+    let expected = [img_size.y as usize, img_size.x as usize, 3];
+    if background_image.dims() != expected {
+        return Err(BackgroundSizeError {
+            expected,
+            actual: background_image.dims(),
+        });
+    }
+    Ok(render_over_background(
+        splats,
+        camera,
+        img_size,
+        background_image,
+        splat_scale,
+    ))
+}
+
+/// Render splats over a background sampled from the `[H, W, 3]` lat-long environment map
+/// `env_map` along the ray of each pixel, see [`env_map::sample_env_map`] for its layout.
+/// Returns a float `[H, W, 4]` image like [`render_splats_over_image`].
+///
+/// NB: This doesn't work on a differentiable backend.
+///
+/// # Panics
+///
+/// Normal and alpha only renders have no color to composite, and the environment map needs
+/// 3 channels.
+pub fn render_splats_over_env_map<B: Backend + SplatForward<B>>(
+    splats: &Splats<B>,
+    camera: &Camera,
+    img_size: glam::UVec2,
+    env_map: Tensor<B, 3>,
+    splat_scale: Option<f32>,
+) -> (Tensor<B, 3>, RenderAux<B>) {
+    assert_eq!(env_map.dims()[2], 3, "Environment map must be RGB");
+    let dirs = env_map::ray_directions(camera, img_size, &splats.device());
+    let background = env_map::sample_env_map(env_map, dirs);
+    render_over_background(splats, camera, img_size, background, splat_scale)
+}
+
+fn render_over_background<B: Backend + SplatForward<B>>(
+    splats: &Splats<B>,
+    camera: &Camera,
+    img_size: glam::UVec2,
+    background: Tensor<B, 3>,
+    splat_scale: Option<f32>,
+) -> (Tensor<B, 3>, RenderAux<B>) {
+    assert!(
+        !matches!(
+            splats.render_mode,
+            SplatRenderMode::NormalsWorldSpace
+                | SplatRenderMode::NormalsCameraSpace
+                | SplatRenderMode::AlphaOnly
+                | SplatRenderMode::WorldPosition
+                | SplatRenderMode::Feature { .. }
+        ),
+        "Normal, alpha, position and feature renders can't be composited over a background"
+    );
+    splats.validate_values();
+
+    let mut scales = splats.log_scales.val();
+    if let Some(scale) = splat_scale {
+        scales = scales + scale.ln();
+    }
+
+    // The rasterizer accumulates premultiplied colors, so leaving out the background gives
+    // fg.rgb * fg.a directly.
+    let (img, aux) = B::render_splats(
+        camera,
+        img_size,
+        splats.means.val().into_primitive().tensor(),
+        scales.into_primitive().tensor(),
+        splats.rotations.val().into_primitive().tensor(),
+        splats.sh_coeffs.val().into_primitive().tensor(),
+        splats.raw_opacities.val().into_primitive().tensor(),
+        splats.render_mode,
+        Vec3::ZERO,
+        true,
+        RenderOptions {
+            premultiplied_alpha: true,
+            ..Default::default()
+        },
     );
-    let img = Tensor::from_primitive(TensorPrimitive::Float(img));
+    let img = Tensor::<B, 3>::from_primitive(TensorPrimitive::Float(img));
 
-    aux.validate_values();
+    aux.unwrap_values();
 
-    (img, aux)
+    (composite_over_background(img, background), aux)
+}
+
+/// Composite an `[H, W, 4]` render with premultiplied colors, eg. one made with a black
+/// background, over an `[H, W, 3]` background as `fg.rgb * fg.a + bg.rgb * (1 - fg.a)`.
+/// Alpha stays the accumulated splat opacity, like for a solid background.
+pub fn composite_over_background<B: Backend>(
+    img: Tensor<B, 3>,
+    background: Tensor<B, 3>,
+) -> Tensor<B, 3> {
+    let color = img.clone().slice(s![.., .., 0..3]);
+    let alpha = img.slice(s![.., .., 3..4]);
+    let color = color + background * (alpha.clone().neg() + 1.0);
+    Tensor::cat(vec![color, alpha], 2)
+}
+
+/// Render splats in tiles of at most `tile_size` pixels, and stitch them into one float
+/// `[H, W, C]` image, like the output of [`SplatForward::render_splats`] with `bwd_info`.
+/// This keeps the intermediate buffers of very large renders small.
+///
+/// Each tile is rendered with [`Camera::sub_view`], so the result is the same as a single
+/// render of the whole image.
+///
+/// # Panics
+///
+/// Equirectangular and fisheye cameras can't be rendered in tiles.
+pub fn render_splats_tiled<B: Backend + SplatForward<B>>(
+    splats: &Splats<B>,
+    camera: &Camera,
+    img_size: glam::UVec2,
+    tile_size: glam::UVec2,
+    background: Vec3,
+    splat_scale: Option<f32>,
+) -> Tensor<B, 3> {
+    assert!(
+        tile_size.x > 0 && tile_size.y > 0,
+        "Tile size must be at least one pixel"
+    );
+    splats.validate_values();
+
+    let mut scales = splats.log_scales.val();
+    if let Some(scale) = splat_scale {
+        scales = scales + scale.ln();
+    }
+
+    let rows = (0..img_size.y)
+        .step_by(tile_size.y as usize)
+        .map(|y| {
+            let tiles = (0..img_size.x)
+                .step_by(tile_size.x as usize)
+                .map(|x| {
+                    let offset = glam::uvec2(x, y);
+                    let size = tile_size.min(img_size - offset);
+                    let tile_camera = camera
+                        .sub_view(img_size, offset, size)
+                        .expect("Equirectangular and fisheye cameras can't be rendered in tiles");
+                    let (img, _) = forward(
+                        splats,
+                        &tile_camera,
+                        size,
+                        scales.clone(),
+                        background,
+                        true,
+                        RenderOptions::default(),
+                    );
+                    img
+                })
+                .collect();
+            Tensor::cat(tiles, 1)
+        })
+        .collect();
+    Tensor::cat(rows, 0)
 }
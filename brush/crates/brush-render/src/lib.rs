@@ -9,9 +9,15 @@ use camera::Camera;
 use clap::ValueEnum;
 use glam::Vec3;
 use render_aux::RenderAux;
+use thiserror::Error;
+use tokio_util::sync::CancellationToken;
 
 use crate::gaussian_splats::SplatRenderMode;
-pub use crate::gaussian_splats::render_splats;
+pub use crate::gaussian_splats::{
+    composite_over_background, render_splats, render_splats_cancellable,
+    render_splats_over_env_map, render_splats_over_image, render_splats_stereo,
+    render_splats_tiled,
+};
 
 mod burn_glue;
 mod dim_check;
@@ -25,14 +31,117 @@ mod tests;
 
 pub mod bounding_box;
 pub mod camera;
+pub mod color;
+pub mod density;
+#[cfg(feature = "distributed-render")]
+pub mod distributed;
+pub mod env_map;
 pub mod gaussian_splats;
 mod get_tile_offset;
 pub mod render;
+pub mod tonemap;
 pub mod validation;
 
 pub type MainBackendBase = CubeBackend<WgpuRuntime, f32, i32, u32>;
 pub type MainBackend = Fusion<MainBackendBase>;
 
+/// Extra settings for [`SplatForward::render_splats`] beyond the camera and splat data.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderOptions {
+    /// Also rasterize an `[H, W, 1]` buffer with the depth of each pixel, see
+    /// [`RenderAux::depth`].
+    pub depth: bool,
+    /// What the depth buffer holds, see [`DepthMode`]. Defaults to the alpha weighted depth.
+    pub depth_mode: DepthMode,
+    /// Depth written for pixels no splat contributed to. Defaults to `f32::INFINITY`.
+    pub depth_sentinel: f32,
+    /// Also record an `[H, W]` buffer with the number of splats evaluated for each pixel,
+    /// see [`RenderAux::tile_hit_counts`].
+    pub record_hit_counts: bool,
+    /// Highest SH degree used for the view dependent color, clamped to the degree stored in
+    /// the coefficients. Degree 0 only uses the DC component. Defaults to all stored degrees.
+    pub sh_degree_active: u8,
+    /// Splats with `sigmoid(raw_opacity)` below this are culled before projecting, and
+    /// don't take up any tile intersections. They aren't counted in
+    /// [`RenderAux::num_visible`]. Defaults to 0, which keeps all splats.
+    pub min_opacity: f32,
+    /// Output premultiplied alpha, where the color channels are the splat colors weighted by
+    /// the accumulated alpha. The background is ignored. By default the background is
+    /// composited into the color channels.
+    pub premultiplied_alpha: bool,
+    /// Also return a `[num_tiles_y, num_tiles_x]` buffer with the number of splats assigned
+    /// to each tile, see [`RenderAux::tile_coverage`].
+    pub record_tile_coverage: bool,
+    /// Also return an `[N]` buffer with the screen space radius of each splat in pixels, see
+    /// [`RenderAux::screen_radii`].
+    pub record_screen_radii: bool,
+    /// Distance of the near plane. Splats whose center is closer to the camera are culled
+    /// before projecting, as their projected footprint blows up. Splats straddling the
+    /// plane aren't clipped, they're kept or culled as a whole depending on their center.
+    /// For equirectangular and fisheye cameras this is the distance from the camera instead
+    /// of the depth. Defaults to 0.01.
+    pub z_near: f32,
+    /// Anti-alias the splats with the 2D filter of Mip-Splatting, which keeps thin and
+    /// distant splats from flickering, instead of the dilation of classic 3DGS. Splats are
+    /// always anti-aliased in [`SplatRenderMode::Mip`]. Defaults to false.
+    pub antialias: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            depth: false,
+            depth_mode: DepthMode::Expected,
+            depth_sentinel: f32::INFINITY,
+            record_hit_counts: false,
+            sh_degree_active: u8::MAX,
+            min_opacity: 0.0,
+            premultiplied_alpha: false,
+            record_tile_coverage: false,
+            record_screen_radii: false,
+            z_near: 0.01,
+            antialias: false,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// Whether splats rendered in `render_mode` with these options get the anti-aliasing
+    /// filter, see [`Self::antialias`].
+    pub fn antialiased(&self, render_mode: SplatRenderMode) -> bool {
+        self.antialias || render_mode == SplatRenderMode::Mip
+    }
+}
+
+/// How the depths of the splats along a ray are combined into the depth of the pixel.
+#[derive(
+    Default, ValueEnum, Clone, Copy, Eq, PartialEq, Debug, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum DepthMode {
+    /// The alpha weighted depth Σ αᵢ Tᵢ zᵢ. Divide by the alpha of the pixel to get the
+    /// expected depth, which blends the depths across object boundaries.
+    #[default]
+    Expected,
+    /// The depth of the splat whose contribution pushes the accumulated alpha past 0.5,
+    /// where the transmittance crosses 0.5. Stays on one surface at object boundaries, eg. for
+    /// mesh extraction. Pixels whose alpha stays below 0.5 get the sentinel.
+    Median,
+}
+
+/// A render was stopped through its [`CancellationToken`].
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+#[error("Render was cancelled")]
+pub struct RenderCancelled;
+
+fn check_cancelled(token: &CancellationToken) -> Result<(), RenderCancelled> {
+    if token.is_cancelled() {
+        Err(RenderCancelled)
+    } else {
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct RenderStats {
     pub num_visible: u32,
@@ -54,6 +163,8 @@ pub trait SplatForward<B: Backend> {
     /// The [`xy_grad_dummy`] variable is only used to carry screenspace xy gradients.
     /// This function can optionally render a "u32" buffer, which is a packed RGBA (8 bits per channel)
     /// buffer. This is useful when the results need to be displayed immediately.
+    ///
+    /// Any extra outputs requested in `options` are returned in the [`RenderAux`].
     fn render_splats(
         camera: &Camera,
         img_size: glam::UVec2,
@@ -65,7 +176,82 @@ pub trait SplatForward<B: Backend> {
         render_mode: SplatRenderMode,
         background: Vec3,
         bwd_info: bool,
+        options: RenderOptions,
     ) -> (FloatTensor<B>, RenderAux<B>);
+
+    /// Render the `left` and `right` eye of a stereo pair, like [`Self::render_splats`] for
+    /// each eye.
+    ///
+    /// Backends can share the depth sort between the eyes when they look the same way,
+    /// like the parallel eyes of [`Camera::stereo_eyes`]. By default each eye is rendered
+    /// separately.
+    fn render_splats_stereo(
+        left: &Camera,
+        right: &Camera,
+        img_size: glam::UVec2,
+        means: FloatTensor<B>,
+        log_scales: FloatTensor<B>,
+        quats: FloatTensor<B>,
+        sh_coeffs: FloatTensor<B>,
+        raw_opacities: FloatTensor<B>,
+        render_mode: SplatRenderMode,
+        background: Vec3,
+        bwd_info: bool,
+        options: RenderOptions,
+    ) -> [(FloatTensor<B>, RenderAux<B>); 2] {
+        [left, right].map(|eye| {
+            Self::render_splats(
+                eye,
+                img_size,
+                means.clone(),
+                log_scales.clone(),
+                quats.clone(),
+                sh_coeffs.clone(),
+                raw_opacities.clone(),
+                render_mode,
+                background,
+                bwd_info,
+                options,
+            )
+        })
+    }
+
+    /// Like [`Self::render_splats`], but stops with [`RenderCancelled`] once `token` is
+    /// cancelled, dropping the buffers of the stages that already ran.
+    ///
+    /// Backends check the token between the stages of the pipeline (projection and depth
+    /// sort, rasterization). By default it's only checked before and after the render.
+    fn render_splats_cancellable(
+        camera: &Camera,
+        img_size: glam::UVec2,
+        means: FloatTensor<B>,
+        log_scales: FloatTensor<B>,
+        quats: FloatTensor<B>,
+        sh_coeffs: FloatTensor<B>,
+        raw_opacities: FloatTensor<B>,
+        render_mode: SplatRenderMode,
+        background: Vec3,
+        bwd_info: bool,
+        options: RenderOptions,
+        token: &CancellationToken,
+    ) -> Result<(FloatTensor<B>, RenderAux<B>), RenderCancelled> {
+        check_cancelled(token)?;
+        let output = Self::render_splats(
+            camera,
+            img_size,
+            means,
+            log_scales,
+            quats,
+            sh_coeffs,
+            raw_opacities,
+            render_mode,
+            background,
+            bwd_info,
+            options,
+        );
+        check_cancelled(token)?;
+        Ok(output)
+    }
 }
 
 #[derive(
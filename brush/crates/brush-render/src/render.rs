@@ -1,11 +1,14 @@
 use crate::{
-    INTERSECTS_UPPER_BOUND, MainBackendBase, SplatForward,
-    camera::Camera,
+    DepthMode, INTERSECTS_UPPER_BOUND, MainBackendBase, RenderCancelled, RenderOptions,
+    SplatForward,
+    camera::{Camera, FisheyeModel, ProjectionMode},
+    check_cancelled,
     dim_check::DimCheck,
+    env_map::ray_directions,
     gaussian_splats::SplatRenderMode,
     get_tile_offset::{CHECKS_PER_ITER, get_tile_offsets},
-    render_aux::RenderAux,
-    sh::sh_degree_from_coeffs,
+    render_aux::{RenderAux, tile_coverage},
+    sh::{sh_degree_from_coeffs, truncate_sh_coeffs},
     shaders::{self, MapGaussiansToIntersect, ProjectSplats, ProjectVisible, Rasterize},
 };
 use brush_kernel::create_dispatch_buffer_1d;
@@ -14,10 +17,14 @@ use brush_kernel::create_uniform_buffer;
 use brush_kernel::{CubeCount, calc_cube_count_1d};
 use brush_prefix_sum::prefix_sum;
 use brush_sort::radix_argsort;
-use burn::tensor::{DType, IntDType, ops::FloatTensor};
 use burn::tensor::{
-    FloatDType,
+    DType, ElementConversion, Int, IntDType,
+    ops::{FloatTensor, IntTensor},
+};
+use burn::tensor::{
+    FloatDType, Tensor, TensorPrimitive,
     ops::{FloatTensorOps, IntTensorOps},
+    s,
 };
 use burn_cubecl::cubecl::server::Bindings;
 
@@ -26,6 +33,7 @@ use burn_wgpu::CubeDim;
 use burn_wgpu::WgpuRuntime;
 use glam::{Vec3, uvec2};
 use std::mem::offset_of;
+use tokio_util::sync::CancellationToken;
 
 pub(crate) fn calc_tile_bounds(img_size: glam::UVec2) -> glam::UVec2 {
     uvec2(
@@ -51,36 +59,61 @@ pub fn max_intersections(img_size: glam::UVec2, num_splats: u32) -> u32 {
     max_possible.min(INTERSECTS_UPPER_BOUND)
 }
 
-// Implement forward functions for the inner wgpu backend.
-impl SplatForward<Self> for MainBackendBase {
-    fn render_splats(
-        camera: &Camera,
-        img_size: glam::UVec2,
-        means: FloatTensor<Self>,
-        log_scales: FloatTensor<Self>,
-        quats: FloatTensor<Self>,
-        sh_coeffs: FloatTensor<Self>,
-        raw_opacities: FloatTensor<Self>,
-        render_mode: SplatRenderMode,
-        background: Vec3,
-        bwd_info: bool,
-    ) -> (FloatTensor<Self>, RenderAux<Self>) {
-        assert!(
-            img_size[0] > 0 && img_size[1] > 0,
-            "Can't render images with 0 size."
-        );
+/// The splat attributes of a render, contiguous and in their storage precision.
+pub(crate) struct RenderInputs {
+    means: FloatTensor<MainBackendBase>,
+    log_scales: FloatTensor<MainBackendBase>,
+    quats: FloatTensor<MainBackendBase>,
+    sh_coeffs: FloatTensor<MainBackendBase>,
+    raw_opacities: FloatTensor<MainBackendBase>,
+    /// Whether the attributes are stored as f16, see [`crate::gaussian_splats::SplatPrecision`].
+    f16_storage: bool,
+}
 
+impl RenderInputs {
+    pub(crate) fn new(
+        means: FloatTensor<MainBackendBase>,
+        log_scales: FloatTensor<MainBackendBase>,
+        quats: FloatTensor<MainBackendBase>,
+        sh_coeffs: FloatTensor<MainBackendBase>,
+        raw_opacities: FloatTensor<MainBackendBase>,
+        render_mode: SplatRenderMode,
+        options: RenderOptions,
+    ) -> Self {
         // Tensor params might not be contiguous, convert them to contiguous tensors.
         let means = into_contiguous(means);
         let log_scales = into_contiguous(log_scales);
         let quats = into_contiguous(quats);
-        let sh_coeffs = into_contiguous(sh_coeffs);
+        // The features of a feature render aren't SH bands, so they're never truncated.
+        let sh_coeffs = if matches!(render_mode, SplatRenderMode::Feature { .. }) {
+            into_contiguous(sh_coeffs)
+        } else {
+            into_contiguous(truncate_sh_coeffs::<MainBackendBase>(
+                sh_coeffs,
+                options.sh_degree_active,
+            ))
+        };
         let raw_opacities = into_contiguous(raw_opacities);
 
-        let device = &means.device.clone();
-        let client = means.client.clone();
-
-        let _span = tracing::trace_span!("render_forward").entered();
+        // Attributes stored as f16 (see SplatPrecision) are read by the projection kernels as
+        // packed pairs of halves. The other attributes, eg. the features of a feature pass,
+        // are converted to match the means.
+        let f16_storage = means.dtype == DType::F16;
+        let storage_dtype = if f16_storage {
+            FloatDType::F16
+        } else {
+            FloatDType::F32
+        };
+        let as_storage = |tensor: FloatTensor<MainBackendBase>| {
+            if tensor.dtype == means.dtype {
+                tensor
+            } else {
+                into_contiguous(MainBackendBase::float_cast(tensor, storage_dtype))
+            }
+        };
+        let log_scales = as_storage(log_scales);
+        let quats = as_storage(quats);
+        let sh_coeffs = as_storage(sh_coeffs);
 
         // Check whether input dimensions are valid.
         DimCheck::new()
@@ -90,306 +123,947 @@ impl SplatForward<Self> for MainBackendBase {
             .check_dims("sh_coeffs", &sh_coeffs, &["D".into(), "C".into(), 3.into()])
             .check_dims("raw_opacities", &raw_opacities, &["D".into()]);
 
-        // Divide screen into tiles.
-        let tile_bounds = calc_tile_bounds(img_size);
-
-        // A note on some confusing naming that'll be used throughout this function:
-        // Gaussians are stored in various states of buffers, eg. at the start they're all in one big buffer,
-        // then we sparsely store some results, then sort gaussian based on depths, etc.
-        // Overall this means there's lots of indices flying all over the place, and it's hard to keep track
-        // what is indexing what. So, for some sanity, try to match a few "gaussian ids" (gid) variable names.
-        // - Global Gaussian ID - global_gid
-        // - Compacted Gaussian ID - compact_gid
-        // - Per tile intersection depth sorted ID - tiled_gid
-        // - Sorted by tile per tile intersection depth sorted ID - sorted_tiled_gid
-        // Then, various buffers map between these, which are named x_from_y_gid, eg.
-        //  global_from_compact_gid.
-
-        // Tile rendering setup.
-        let sh_degree = sh_degree_from_coeffs(sh_coeffs.shape.dims[1] as u32);
-        let total_splats = means.shape.dims[0];
-        let max_intersects = max_intersections(img_size, total_splats as u32);
-
-        let uniforms = shaders::helpers::RenderUniforms {
-            viewmat: glam::Mat4::from(camera.world_to_local()).to_cols_array_2d(),
-            camera_position: [camera.position.x, camera.position.y, camera.position.z, 0.0],
-            focal: camera.focal(img_size).into(),
-            pixel_center: camera.center(img_size).into(),
-            img_size: img_size.into(),
-            tile_bounds: tile_bounds.into(),
-            sh_degree,
-            total_splats: total_splats as u32,
-            max_intersects,
-            background: [background.x, background.y, background.z, 1.0],
-            // Nb: Bit of a hack as these aren't _really_ uniforms but are written to by the shaders.
-            num_visible: 0,
+        // Features are passed in as SH coefficients, three channels per coefficient.
+        if let SplatRenderMode::Feature { dim } = render_mode {
+            assert_eq!(
+                sh_coeffs.shape.dims[1],
+                dim.div_ceil(3),
+                "Feature renders take their channels padded to [N, ceil(dim / 3), 3]"
+            );
+        }
+
+        Self {
+            means,
+            log_scales,
+            quats,
+            sh_coeffs,
+            raw_opacities,
+            f16_storage,
+        }
+    }
+
+    fn total_splats(&self) -> usize {
+        self.means.shape.dims[0]
+    }
+
+    /// Copy the splats in `range` to `device`, eg. to project them there.
+    #[cfg(feature = "distributed-render")]
+    pub(crate) fn slice_to_device(
+        &self,
+        range: std::ops::Range<usize>,
+        device: &burn_wgpu::WgpuDevice,
+    ) -> Self {
+        // Selecting the rows copies them, where a slice could be a view at an offset into
+        // the full buffer, which the kernels can't bind.
+        fn select<const D: usize>(
+            tensor: &FloatTensor<MainBackendBase>,
+            indices: &Tensor<MainBackendBase, 1, Int>,
+            device: &burn_wgpu::WgpuDevice,
+        ) -> FloatTensor<MainBackendBase> {
+            Tensor::<MainBackendBase, D>::from_primitive(TensorPrimitive::Float(tensor.clone()))
+                .select(0, indices.clone())
+                .to_device(device)
+                .into_primitive()
+                .tensor()
+        }
+
+        let indices = Tensor::<MainBackendBase, 1, Int>::arange(
+            range.start as i64..range.end as i64,
+            &self.means.device,
+        );
+        Self {
+            means: select::<2>(&self.means, &indices, device),
+            log_scales: select::<2>(&self.log_scales, &indices, device),
+            quats: select::<2>(&self.quats, &indices, device),
+            sh_coeffs: select::<3>(&self.sh_coeffs, &indices, device),
+            raw_opacities: select::<1>(&self.raw_opacities, &indices, device),
+            f16_storage: self.f16_storage,
+        }
+    }
+
+    /// Split the inputs of a feature render into one set per three feature channels, each
+    /// passed to the rasterizer as a degree 0 SH coefficient.
+    fn feature_chunks(&self) -> Vec<Self> {
+        let [n, chunks, _] = self.sh_coeffs.shape.dims[..] else {
+            unreachable!("SH coefficients are checked to be 3D");
         };
+        (0..chunks)
+            .map(|c| Self {
+                means: self.means.clone(),
+                log_scales: self.log_scales.clone(),
+                quats: self.quats.clone(),
+                sh_coeffs: into_contiguous(MainBackendBase::float_slice(
+                    self.sh_coeffs.clone(),
+                    &[(0..n).into(), (c..c + 1).into(), (0..3).into()],
+                )),
+                raw_opacities: self.raw_opacities.clone(),
+                f16_storage: self.f16_storage,
+            })
+            .collect()
+    }
+}
 
-        // Nb: This contains both static metadata and some dynamic data so can't pass this as metadata to execute. In the future
-        // should separate the two.
-        let uniforms_buffer = create_uniform_buffer(uniforms, device, &client);
+/// Normals, positions and features are signed, so they can't be packed into 8 bit colors and
+/// always use the f32 output.
+fn signed_color(render_mode: SplatRenderMode) -> bool {
+    matches!(
+        render_mode,
+        SplatRenderMode::NormalsWorldSpace
+            | SplatRenderMode::NormalsCameraSpace
+            | SplatRenderMode::WorldPosition
+            | SplatRenderMode::Feature { .. }
+    )
+}
 
-        let client = &means.client.clone();
+pub(crate) fn render_uniforms(
+    camera: &Camera,
+    img_size: glam::UVec2,
+    inputs: &RenderInputs,
+    render_mode: SplatRenderMode,
+    background: Vec3,
+    options: RenderOptions,
+) -> shaders::helpers::RenderUniforms {
+    // The accumulated color is already premultiplied, so leaving out the background
+    // gives premultiplied output.
+    let background = if signed_color(render_mode) || options.premultiplied_alpha {
+        Vec3::ZERO
+    } else {
+        background
+    };
+    let total_splats = inputs.total_splats() as u32;
 
-        let mip_splat = matches!(render_mode, SplatRenderMode::Mip);
+    shaders::helpers::RenderUniforms {
+        viewmat: glam::Mat4::from(camera.world_to_local()).to_cols_array_2d(),
+        camera_position: [camera.position.x, camera.position.y, camera.position.z, 0.0],
+        focal: camera.focal(img_size).into(),
+        pixel_center: camera.center(img_size).into(),
+        img_size: img_size.into(),
+        tile_bounds: calc_tile_bounds(img_size).into(),
+        sh_degree: sh_degree_from_coeffs(inputs.sh_coeffs.shape.dims[1] as u32),
+        total_splats,
+        max_intersects: max_intersections(img_size, total_splats),
+        background: [background.x, background.y, background.z, 1.0],
+        min_opacity: options.min_opacity,
+        z_near: options.z_near,
+        max_view_angle: match camera.projection {
+            ProjectionMode::Fisheye { max_fov_rad, .. } => max_fov_rad / 2.0,
+            _ => f32::MAX,
+        },
+        max_view_radius: match camera.projection {
+            ProjectionMode::Fisheye { max_fov_rad, model } => model.radius(max_fov_rad / 2.0),
+            _ => f32::MAX,
+        },
+        // Nb: Bit of a hack as these aren't _really_ uniforms but are written to by the shaders.
+        num_visible: 0,
+    }
+}
 
-        let (global_from_compact_gid, num_visible) = {
-            let global_from_presort_gid =
-                Self::int_zeros([total_splats].into(), device, IntDType::U32);
-            let depths = create_tensor([total_splats], device, DType::F32);
+/// The splats visible to a camera, sorted front to back. See [`depth_sort`].
+pub(crate) struct DepthSorted {
+    /// Uniforms of the camera, with the number of visible splats filled in.
+    pub(crate) uniforms_buffer: IntTensor<MainBackendBase>,
+    pub(crate) global_from_compact_gid: IntTensor<MainBackendBase>,
+    pub(crate) depth_from_compact_gid: FloatTensor<MainBackendBase>,
+    pub(crate) num_visible: IntTensor<MainBackendBase>,
+}
 
-            tracing::trace_span!("ProjectSplats").in_scope(||
-            // SAFETY: Kernel checked to have no OOB, bounded loops.
-            unsafe {
-            client.launch_unchecked(
-                ProjectSplats::task(mip_splat),
-                calc_cube_count_1d(total_splats as u32, ProjectSplats::WORKGROUP_SIZE[0]),
-                Bindings::new().with_buffers(
-                vec![
-                    uniforms_buffer.handle.clone().binding(),
-                    means.handle.clone().binding(),
-                    quats.handle.clone().binding(),
-                    log_scales.handle.clone().binding(),
-                    raw_opacities.handle.clone().binding(),
-                    global_from_presort_gid.handle.clone().binding(),
-                    depths.handle.clone().binding(),
-                ]),
-            ).expect("Failed to render splats");
+/// The splats visible to a camera in the order the projection pass found them. Only the first
+/// `num_visible` ids and depths are valid. See [`project_splats`].
+pub(crate) struct Projected {
+    /// Uniforms of the camera, with the number of visible splats filled in.
+    pub(crate) uniforms_buffer: IntTensor<MainBackendBase>,
+    pub(crate) global_from_presort_gid: IntTensor<MainBackendBase>,
+    pub(crate) depths: FloatTensor<MainBackendBase>,
+    pub(crate) num_visible: IntTensor<MainBackendBase>,
+}
+
+/// Cull the splats the camera of `uniforms` can't see, and compact the ids and depths of the
+/// rest.
+pub(crate) fn project_splats(
+    camera: &Camera,
+    inputs: &RenderInputs,
+    render_mode: SplatRenderMode,
+    uniforms: shaders::helpers::RenderUniforms,
+    options: RenderOptions,
+) -> Projected {
+    let device = &inputs.means.device.clone();
+    let client = &inputs.means.client.clone();
+    let total_splats = inputs.total_splats();
+
+    // Nb: This contains both static metadata and some dynamic data so can't pass this as metadata to execute. In the future
+    // should separate the two.
+    let uniforms_buffer = create_uniform_buffer(uniforms, device, client);
+
+    let mip_splat = options.antialiased(render_mode);
+    let orthographic = matches!(camera.projection, ProjectionMode::Orthographic { .. });
+    let equirectangular = matches!(camera.projection, ProjectionMode::Equirectangular);
+    let fisheye = matches!(camera.projection, ProjectionMode::Fisheye { .. });
+    let equisolid = matches!(
+        camera.projection,
+        ProjectionMode::Fisheye {
+            model: FisheyeModel::Equisolid,
+            ..
+        }
+    );
+
+    let global_from_presort_gid =
+        MainBackendBase::int_zeros([total_splats].into(), device, IntDType::U32);
+    let depths = create_tensor([total_splats], device, DType::F32);
+
+    tracing::trace_span!("ProjectSplats").in_scope(||
+        // SAFETY: Kernel checked to have no OOB, bounded loops.
+        unsafe {
+        client.launch_unchecked(
+            ProjectSplats::task(
+                mip_splat,
+                orthographic,
+                equirectangular,
+                fisheye,
+                equisolid,
+                inputs.f16_storage,
+            ),
+            calc_cube_count_1d(total_splats as u32, ProjectSplats::WORKGROUP_SIZE[0]),
+            Bindings::new().with_buffers(
+            vec![
+                uniforms_buffer.handle.clone().binding(),
+                inputs.means.handle.clone().binding(),
+                inputs.quats.handle.clone().binding(),
+                inputs.log_scales.handle.clone().binding(),
+                inputs.raw_opacities.handle.clone().binding(),
+                global_from_presort_gid.handle.clone().binding(),
+                depths.handle.clone().binding(),
+            ]),
+        ).expect("Failed to render splats");
+    });
+
+    Projected {
+        num_visible: num_visible_of(&uniforms_buffer),
+        uniforms_buffer,
+        global_from_presort_gid,
+        depths,
+    }
+}
+
+/// Get just the number of visible splats from the uniforms buffer.
+pub(crate) fn num_visible_of(
+    uniforms_buffer: &IntTensor<MainBackendBase>,
+) -> IntTensor<MainBackendBase> {
+    MainBackendBase::int_slice(
+        uniforms_buffer.clone(),
+        &[(NUM_VISIBLE_OFFSET..NUM_VISIBLE_OFFSET + 1).into()],
+    )
+}
+
+/// Cull the splats the camera of `uniforms` can't see, and sort the rest by their depth.
+fn depth_sort(
+    camera: &Camera,
+    inputs: &RenderInputs,
+    render_mode: SplatRenderMode,
+    uniforms: shaders::helpers::RenderUniforms,
+    options: RenderOptions,
+) -> DepthSorted {
+    let Projected {
+        uniforms_buffer,
+        global_from_presort_gid,
+        depths,
+        num_visible,
+    } = project_splats(camera, inputs, render_mode, uniforms, options);
+
+    let (depth_from_compact_gid, global_from_compact_gid) = tracing::trace_span!("DepthSort")
+        .in_scope(|| {
+            // Interpret the depth as a u32. This is fine for a radix sort, as long as the depth > 0.0,
+            // which we know to be the case given how we cull splats.
+            radix_argsort(depths, global_from_presort_gid, &num_visible, 32)
         });
 
-            // Get just the number of visible splats from the uniforms buffer.
-            let num_vis_field_offset =
-                offset_of!(shaders::helpers::RenderUniforms, num_visible) / 4;
-            let num_visible = Self::int_slice(
-                uniforms_buffer.clone(),
-                &[(num_vis_field_offset..num_vis_field_offset + 1).into()],
-            );
+    DepthSorted {
+        uniforms_buffer,
+        global_from_compact_gid,
+        depth_from_compact_gid,
+        num_visible,
+    }
+}
 
-            let (_, global_from_compact_gid) = tracing::trace_span!("DepthSort").in_scope(|| {
-                // Interpret the depth as a u32. This is fine for a radix sort, as long as the depth > 0.0,
-                // which we know to be the case given how we cull splats.
-                radix_argsort(depths, global_from_presort_gid, &num_visible, 32)
-            });
+/// Offset of the number of visible splats in the uniforms buffer, in words.
+const NUM_VISIBLE_OFFSET: usize = offset_of!(shaders::helpers::RenderUniforms, num_visible) / 4;
 
-            (global_from_compact_gid, num_visible)
-        };
+/// Project the depth sorted splats for `camera`, bin them into tiles and rasterize them.
+pub(crate) fn rasterize_sorted(
+    camera: &Camera,
+    img_size: glam::UVec2,
+    inputs: &RenderInputs,
+    sorted: DepthSorted,
+    render_mode: SplatRenderMode,
+    bwd_info: bool,
+    options: RenderOptions,
+) -> (FloatTensor<MainBackendBase>, RenderAux<MainBackendBase>) {
+    // A note on some confusing naming that'll be used throughout this function:
+    // Gaussians are stored in various states of buffers, eg. at the start they're all in one big buffer,
+    // then we sparsely store some results, then sort gaussian based on depths, etc.
+    // Overall this means there's lots of indices flying all over the place, and it's hard to keep track
+    // what is indexing what. So, for some sanity, try to match a few "gaussian ids" (gid) variable names.
+    // - Global Gaussian ID - global_gid
+    // - Compacted Gaussian ID - compact_gid
+    // - Per tile intersection depth sorted ID - tiled_gid
+    // - Sorted by tile per tile intersection depth sorted ID - sorted_tiled_gid
+    // Then, various buffers map between these, which are named x_from_y_gid, eg.
+    //  global_from_compact_gid.
+    let DepthSorted {
+        uniforms_buffer,
+        global_from_compact_gid,
+        depth_from_compact_gid,
+        num_visible,
+    } = sorted;
+
+    let device = &inputs.means.device.clone();
+    let client = &inputs.means.client.clone();
+
+    let normals = matches!(
+        render_mode,
+        SplatRenderMode::NormalsWorldSpace | SplatRenderMode::NormalsCameraSpace
+    );
+    // Alpha is sliced out of the f32 output as well.
+    let alpha_only = render_mode == SplatRenderMode::AlphaOnly;
+    let world_position = render_mode == SplatRenderMode::WorldPosition;
+    let features = matches!(render_mode, SplatRenderMode::Feature { .. });
+    let signed_color = signed_color(render_mode);
+    let bwd_info = bwd_info || signed_color || alpha_only;
+
+    let tile_bounds = calc_tile_bounds(img_size);
+    let total_splats = inputs.total_splats();
+    let max_intersects = max_intersections(img_size, total_splats as u32);
+
+    let mip_splat = options.antialiased(render_mode);
+    let orthographic = matches!(camera.projection, ProjectionMode::Orthographic { .. });
+    let equirectangular = matches!(camera.projection, ProjectionMode::Equirectangular);
+    let fisheye = matches!(camera.projection, ProjectionMode::Fisheye { .. });
+    let equisolid = matches!(
+        camera.projection,
+        ProjectionMode::Fisheye {
+            model: FisheyeModel::Equisolid,
+            ..
+        }
+    );
+    let f16_storage = inputs.f16_storage;
+
+    // Create a buffer of 'projected' splats, that is,
+    // project XY, projected conic, and converted color.
+    let proj_size = size_of::<shaders::helpers::ProjectedSplat>() / size_of::<f32>();
+    let projected_splats = create_tensor([total_splats, proj_size], device, DType::F32);
+
+    tracing::trace_span!("ProjectVisible").in_scope(|| {
+        // Create a buffer to determine how many threads to dispatch for all visible splats.
+        let num_vis_wg =
+            create_dispatch_buffer_1d(num_visible.clone(), ProjectVisible::WORKGROUP_SIZE[0]);
+        // SAFETY: Kernel checked to have no OOB, bounded loops.
+        unsafe {
+            client
+                .launch_unchecked(
+                    ProjectVisible::task(
+                        mip_splat,
+                        orthographic,
+                        equirectangular,
+                        fisheye,
+                        equisolid,
+                        normals,
+                        alpha_only,
+                        world_position,
+                        features,
+                        f16_storage,
+                    ),
+                    CubeCount::Dynamic(num_vis_wg.handle.binding()),
+                    Bindings::new().with_buffers(vec![
+                        uniforms_buffer.clone().handle.binding(),
+                        inputs.means.handle.clone().binding(),
+                        inputs.log_scales.handle.clone().binding(),
+                        inputs.quats.handle.clone().binding(),
+                        inputs.sh_coeffs.handle.clone().binding(),
+                        inputs.raw_opacities.handle.clone().binding(),
+                        global_from_compact_gid.handle.clone().binding(),
+                        projected_splats.handle.clone().binding(),
+                    ]),
+                )
+                .expect("Failed to render splats");
+        }
+    });
+
+    // Each intersection maps to a gaussian.
+    let (tile_offsets, compact_gid_from_isect, num_intersections) = {
+        let num_tiles = tile_bounds.x * tile_bounds.y;
 
-        // Create a buffer of 'projected' splats, that is,
-        // project XY, projected conic, and converted color.
-        let proj_size = size_of::<shaders::helpers::ProjectedSplat>() / size_of::<f32>();
-        let projected_splats = create_tensor([total_splats, proj_size], device, DType::F32);
+        let splat_intersect_counts =
+            MainBackendBase::int_zeros([total_splats + 1].into(), device, IntDType::U32);
 
-        tracing::trace_span!("ProjectVisible").in_scope(|| {
-            // Create a buffer to determine how many threads to dispatch for all visible splats.
-            let num_vis_wg =
-                create_dispatch_buffer_1d(num_visible.clone(), ProjectVisible::WORKGROUP_SIZE[0]);
+        let num_vis_map_wg =
+            create_dispatch_buffer_1d(num_visible, MapGaussiansToIntersect::WORKGROUP_SIZE[0]);
+
+        // First do a prepass to compute the tile counts, then fill in intersection counts.
+        tracing::trace_span!("MapGaussiansToIntersectPrepass").in_scope(|| {
             // SAFETY: Kernel checked to have no OOB, bounded loops.
             unsafe {
                 client
                     .launch_unchecked(
-                        ProjectVisible::task(mip_splat),
-                        CubeCount::Dynamic(num_vis_wg.handle.binding()),
+                        MapGaussiansToIntersect::task(true),
+                        CubeCount::Dynamic(num_vis_map_wg.handle.clone().binding()),
                         Bindings::new().with_buffers(vec![
-                            uniforms_buffer.clone().handle.binding(),
-                            means.handle.binding(),
-                            log_scales.handle.binding(),
-                            quats.handle.binding(),
-                            sh_coeffs.handle.binding(),
-                            raw_opacities.handle.binding(),
-                            global_from_compact_gid.handle.clone().binding(),
+                            uniforms_buffer.handle.clone().binding(),
                             projected_splats.handle.clone().binding(),
+                            splat_intersect_counts.handle.clone().binding(),
                         ]),
                     )
                     .expect("Failed to render splats");
             }
         });
 
-        // Each intersection maps to a gaussian.
-        let (tile_offsets, compact_gid_from_isect, num_intersections) = {
-            let num_tiles = tile_bounds.x * tile_bounds.y;
-
-            let splat_intersect_counts =
-                Self::int_zeros([total_splats + 1].into(), device, IntDType::U32);
-
-            let num_vis_map_wg =
-                create_dispatch_buffer_1d(num_visible, MapGaussiansToIntersect::WORKGROUP_SIZE[0]);
-
-            // First do a prepass to compute the tile counts, then fill in intersection counts.
-            tracing::trace_span!("MapGaussiansToIntersectPrepass").in_scope(|| {
-                // SAFETY: Kernel checked to have no OOB, bounded loops.
-                unsafe {
-                    client
-                        .launch_unchecked(
-                            MapGaussiansToIntersect::task(true),
-                            CubeCount::Dynamic(num_vis_map_wg.handle.clone().binding()),
-                            Bindings::new().with_buffers(vec![
-                                uniforms_buffer.handle.clone().binding(),
-                                projected_splats.handle.clone().binding(),
-                                splat_intersect_counts.handle.clone().binding(),
-                            ]),
-                        )
-                        .expect("Failed to render splats");
-                }
-            });
+        // TODO: Only need to do this up to num_visible gaussians really.
+        let cum_tiles_hit = tracing::trace_span!("PrefixSumGaussHits")
+            .in_scope(|| prefix_sum(splat_intersect_counts));
 
-            // TODO: Only need to do this up to num_visible gaussians really.
-            let cum_tiles_hit = tracing::trace_span!("PrefixSumGaussHits")
-                .in_scope(|| prefix_sum(splat_intersect_counts));
-
-            let tile_id_from_isect = create_tensor([max_intersects as usize], device, DType::U32);
-            let compact_gid_from_isect =
-                create_tensor([max_intersects as usize], device, DType::U32);
-
-            // Zero this out, as the kernel _might_ not run at all if no gaussians are visible.
-            let num_intersections = Self::int_zeros([1].into(), device, IntDType::U32);
-
-            tracing::trace_span!("MapGaussiansToIntersect").in_scope(|| {
-                // SAFETY: Kernel checked to have no OOB, bounded loops.
-                unsafe {
-                    client
-                        .launch_unchecked(
-                            MapGaussiansToIntersect::task(false),
-                            CubeCount::Dynamic(num_vis_map_wg.handle.clone().binding()),
-                            Bindings::new().with_buffers(vec![
-                                uniforms_buffer.handle.clone().binding(),
-                                projected_splats.handle.clone().binding(),
-                                cum_tiles_hit.handle.binding(),
-                                tile_id_from_isect.handle.clone().binding(),
-                                compact_gid_from_isect.handle.clone().binding(),
-                                num_intersections.handle.clone().binding(),
-                            ]),
-                        )
-                        .expect("Failed to render splats");
-                }
-            });
+        let tile_id_from_isect = create_tensor([max_intersects as usize], device, DType::U32);
+        let compact_gid_from_isect = create_tensor([max_intersects as usize], device, DType::U32);
 
-            // We're sorting by tile ID, but we know beforehand what the maximum value
-            // can be. We don't need to sort all the leading 0 bits!
-            let bits = u32::BITS - num_tiles.leading_zeros();
-
-            let (tile_id_from_isect, compact_gid_from_isect) = tracing::trace_span!("Tile sort")
-                .in_scope(|| {
-                    radix_argsort(
-                        tile_id_from_isect,
-                        compact_gid_from_isect,
-                        &num_intersections,
-                        bits,
-                    )
-                });
-
-            let cube_dim = CubeDim::new_1d(256);
-            let num_vis_map_wg =
-                create_dispatch_buffer_1d(num_intersections.clone(), 256 * CHECKS_PER_ITER);
-            let cube_count = CubeCount::Dynamic(num_vis_map_wg.handle.binding());
-
-            // Tiles without splats will be written as having a range of [0, 0].
-            let tile_offsets = Self::int_zeros(
-                [tile_bounds.y as usize, tile_bounds.x as usize, 2].into(),
-                device,
-                IntDType::U32,
-            );
+        // Zero this out, as the kernel _might_ not run at all if no gaussians are visible.
+        let num_intersections = MainBackendBase::int_zeros([1].into(), device, IntDType::U32);
 
-            // SAFETY: Safe kernel.
+        tracing::trace_span!("MapGaussiansToIntersect").in_scope(|| {
+            // SAFETY: Kernel checked to have no OOB, bounded loops.
             unsafe {
-                get_tile_offsets::launch_unchecked::<WgpuRuntime>(
-                    client,
-                    cube_count,
-                    cube_dim,
-                    tile_id_from_isect.as_tensor_arg(1),
-                    tile_offsets.as_tensor_arg(1),
-                    num_intersections.as_tensor_arg(1),
-                )
-                .expect("Failed to render splats");
+                client
+                    .launch_unchecked(
+                        MapGaussiansToIntersect::task(false),
+                        CubeCount::Dynamic(num_vis_map_wg.handle.clone().binding()),
+                        Bindings::new().with_buffers(vec![
+                            uniforms_buffer.handle.clone().binding(),
+                            projected_splats.handle.clone().binding(),
+                            cum_tiles_hit.handle.binding(),
+                            tile_id_from_isect.handle.clone().binding(),
+                            compact_gid_from_isect.handle.clone().binding(),
+                            num_intersections.handle.clone().binding(),
+                        ]),
+                    )
+                    .expect("Failed to render splats");
             }
+        });
 
-            (tile_offsets, compact_gid_from_isect, num_intersections)
-        };
+        // We're sorting by tile ID, but we know beforehand what the maximum value
+        // can be. We don't need to sort all the leading 0 bits!
+        let bits = u32::BITS - num_tiles.leading_zeros();
 
-        let _span = tracing::trace_span!("Rasterize").entered();
+        let (tile_id_from_isect, compact_gid_from_isect) = tracing::trace_span!("Tile sort")
+            .in_scope(|| {
+                radix_argsort(
+                    tile_id_from_isect,
+                    compact_gid_from_isect,
+                    &num_intersections,
+                    bits,
+                )
+            });
 
-        let out_dim = if bwd_info {
-            4
-        } else {
-            // Channels are packed into 4 bytes, aka one float.
-            1
-        };
+        let cube_dim = CubeDim::new_1d(256);
+        let num_vis_map_wg =
+            create_dispatch_buffer_1d(num_intersections.clone(), 256 * CHECKS_PER_ITER);
+        let cube_count = CubeCount::Dynamic(num_vis_map_wg.handle.binding());
 
-        let out_img = create_tensor(
-            [img_size.y as usize, img_size.x as usize, out_dim],
+        // Tiles without splats will be written as having a range of [0, 0].
+        let tile_offsets = MainBackendBase::int_zeros(
+            [tile_bounds.y as usize, tile_bounds.x as usize, 2].into(),
             device,
-            DType::F32,
+            IntDType::U32,
         );
 
-        let mut bindings = Bindings::new().with_buffers(vec![
-            uniforms_buffer.handle.clone().binding(),
-            compact_gid_from_isect.handle.clone().binding(),
-            tile_offsets.handle.clone().binding(),
-            projected_splats.handle.clone().binding(),
-            out_img.handle.clone().binding(),
+        // SAFETY: Safe kernel.
+        unsafe {
+            get_tile_offsets::launch_unchecked::<WgpuRuntime>(
+                client,
+                cube_count,
+                cube_dim,
+                tile_id_from_isect.as_tensor_arg(1),
+                tile_offsets.as_tensor_arg(1),
+                num_intersections.as_tensor_arg(1),
+            )
+            .expect("Failed to render splats");
+        }
+
+        (tile_offsets, compact_gid_from_isect, num_intersections)
+    };
+
+    let _span = tracing::trace_span!("Rasterize").entered();
+
+    let out_dim = if bwd_info {
+        4
+    } else {
+        // Channels are packed into 4 bytes, aka one float.
+        1
+    };
+
+    let out_img = create_tensor(
+        [img_size.y as usize, img_size.x as usize, out_dim],
+        device,
+        DType::F32,
+    );
+
+    let mut bindings = Bindings::new().with_buffers(vec![
+        uniforms_buffer.handle.clone().binding(),
+        compact_gid_from_isect.handle.clone().binding(),
+        tile_offsets.handle.clone().binding(),
+        projected_splats.handle.clone().binding(),
+        out_img.handle.clone().binding(),
+    ]);
+
+    let visible = if bwd_info {
+        let visible = MainBackendBase::float_zeros([total_splats].into(), device, FloatDType::F32);
+        // Add the buffer to the bindings
+        bindings = bindings.with_buffers(vec![
+            global_from_compact_gid.handle.clone().binding(),
+            visible.handle.clone().binding(),
         ]);
+        visible
+    } else {
+        create_tensor([1], device, DType::F32)
+    };
 
-        let visible = if bwd_info {
-            let visible = Self::float_zeros([total_splats].into(), device, FloatDType::F32);
-            // Add the buffer to the bindings
-            bindings = bindings.with_buffers(vec![
-                global_from_compact_gid.handle.clone().binding(),
-                visible.handle.clone().binding(),
-            ]);
-            visible
-        } else {
-            create_tensor([1], device, DType::F32)
-        };
+    // World positions are placed at the expected depth, so they always rasterize it.
+    let raster_depth = options.depth || world_position;
+    let depth = if raster_depth {
+        let depth = create_tensor(
+            [img_size.y as usize, img_size.x as usize, 1],
+            device,
+            DType::F32,
+        );
+        // The sorted keys are the depth of each compacted splat.
+        bindings = bindings.with_buffers(vec![
+            depth_from_compact_gid.handle.clone().binding(),
+            depth.handle.clone().binding(),
+        ]);
+        Some(depth)
+    } else {
+        None
+    };
 
-        // Compile the kernel, including/excluding info for backwards pass.
-        // see the BWD_INFO define in the rasterize shader.
-        let raster_task = Rasterize::task(bwd_info, cfg!(target_family = "wasm"));
+    let tile_hit_counts = if options.record_hit_counts {
+        let hit_counts = create_tensor(
+            [img_size.y as usize, img_size.x as usize],
+            device,
+            DType::U32,
+        );
+        bindings = bindings.with_buffers(vec![hit_counts.handle.clone().binding()]);
+        Some(hit_counts)
+    } else {
+        None
+    };
 
-        // SAFETY: Kernel checked to have no OOB, bounded loops.
-        unsafe {
-            client
-                .launch_unchecked(
-                    raster_task,
-                    CubeCount::Static(tile_bounds.x * tile_bounds.y, 1, 1),
-                    bindings,
-                )
-                .expect("Failed to render splats");
+    // Compile the kernel, including/excluding info for backwards pass.
+    // see the BWD_INFO define in the rasterize shader.
+    let raster_task = Rasterize::task(
+        bwd_info,
+        cfg!(target_family = "wasm"),
+        raster_depth,
+        options.depth_mode == DepthMode::Median && !world_position,
+        options.record_hit_counts,
+        signed_color,
+    );
+
+    // SAFETY: Kernel checked to have no OOB, bounded loops.
+    unsafe {
+        client
+            .launch_unchecked(
+                raster_task,
+                CubeCount::Static(tile_bounds.x * tile_bounds.y, 1, 1),
+                bindings,
+            )
+            .expect("Failed to render splats");
+    }
+
+    // Divide the blended depth by alpha to get the expected depth, and place the position on
+    // the pixel ray at that depth. Empty pixels stay zero.
+    let out_img = if let (true, Some(depth)) = (world_position, &depth) {
+        let img: Tensor<MainBackendBase, 3> =
+            Tensor::from_primitive(TensorPrimitive::Float(out_img));
+        let depth: Tensor<MainBackendBase, 3> =
+            Tensor::from_primitive(TensorPrimitive::Float(depth.clone()));
+        let alpha = img.slice(s![.., .., 3..4]);
+        let depth = depth / alpha.clone().clamp_min(f32::MIN_POSITIVE);
+        let position =
+            points_on_rays(camera, img_size, depth) * alpha.clone().greater_elem(0.0).float();
+        Tensor::cat(vec![position, alpha], 2)
+            .into_primitive()
+            .tensor()
+    } else {
+        out_img
+    };
+
+    // Drop the alpha channel of the normals, positions and features, or keep only alpha.
+    let out_img = if signed_color || alpha_only {
+        let img: Tensor<MainBackendBase, 3> =
+            Tensor::from_primitive(TensorPrimitive::Float(out_img));
+        let channels = if signed_color { 0..3 } else { 3..4 };
+        into_contiguous(img.slice(s![.., .., channels]).into_primitive().tensor())
+    } else {
+        out_img
+    };
+
+    // Rotating the blended world space normals gives the blended camera space normals,
+    // as blending is linear.
+    let out_img = if render_mode == SplatRenderMode::NormalsCameraSpace {
+        let img: Tensor<MainBackendBase, 3> =
+            Tensor::from_primitive(TensorPrimitive::Float(out_img));
+        let [h, w, _] = img.dims();
+        // The pixels are row vectors, so multiply by the transposed world to camera
+        // rotation. The columns of the rotation are the rows of its transpose.
+        let world_to_camera = glam::Mat3::from_quat(camera.rotation.inverse());
+        let rotation =
+            Tensor::<MainBackendBase, 2>::from_floats(world_to_camera.to_cols_array_2d(), device);
+        let rotated = img.reshape([h * w, 3]).matmul(rotation).reshape([h, w, 3]);
+        into_contiguous(rotated.into_primitive().tensor())
+    } else {
+        out_img
+    };
+
+    // Every visible splat is in front of the near plane, so only pixels nothing
+    // contributed to (or whose alpha never reached 0.5 for the median) end up with a depth
+    // of exactly zero.
+    let depth = depth.filter(|_| options.depth).map(|depth| {
+        let depth: Tensor<MainBackendBase, 3> =
+            Tensor::from_primitive(TensorPrimitive::Float(depth));
+        depth
+            .clone()
+            .mask_fill(depth.equal_elem(0.0), options.depth_sentinel)
+            .into_primitive()
+            .tensor()
+    });
+
+    // Sanity check the buffers.
+    assert!(
+        uniforms_buffer.is_contiguous(),
+        "Uniforms must be contiguous"
+    );
+    assert!(
+        tile_offsets.is_contiguous(),
+        "Tile offsets must be contiguous"
+    );
+    assert!(
+        global_from_compact_gid.is_contiguous(),
+        "Global from compact gid must be contiguous"
+    );
+    assert!(visible.is_contiguous(), "Visible must be contiguous");
+    assert!(
+        projected_splats.is_contiguous(),
+        "Projected splats must be contiguous"
+    );
+    assert!(
+        num_intersections.is_contiguous(),
+        "Num intersections must be contiguous"
+    );
+
+    let coverage = options
+        .record_tile_coverage
+        .then(|| tile_coverage::<MainBackendBase>(tile_offsets.clone()));
+
+    let mut aux = RenderAux {
+        uniforms_buffer,
+        tile_offsets,
+        num_intersections,
+        projected_splats,
+        compact_gid_from_isect,
+        global_from_compact_gid,
+        visible,
+        depth,
+        tile_hit_counts,
+        tile_coverage: coverage,
+        screen_radii: None,
+        img_size,
+        antialiased: mip_splat,
+    };
+    if options.record_screen_radii {
+        aux.screen_radii = Some(aux.calc_screen_radii().into_primitive().tensor());
+    }
+    (out_img, aux)
+}
+
+/// World space points at `depth` along the ray through the center of every pixel, as an
+/// `[H, W, 3]` tensor. Like the rasterized depth, `depth` is the distance from the camera for
+/// equirectangular and fisheye cameras, and the depth along the optical axis otherwise.
+fn points_on_rays(
+    camera: &Camera,
+    img_size: glam::UVec2,
+    depth: Tensor<MainBackendBase, 3>,
+) -> Tensor<MainBackendBase, 3> {
+    let device = depth.device();
+    let dirs = ray_directions::<MainBackendBase>(camera, img_size, &device);
+    let vec3 = |v: Vec3| {
+        Tensor::<MainBackendBase, 1>::from_floats(v.to_array(), &device).reshape([1, 1, 3])
+    };
+    let origin = vec3(camera.position);
+
+    match camera.projection {
+        ProjectionMode::Perspective => {
+            // Scale the unit rays to a depth of one along the optical axis.
+            let forward = vec3(camera.rotation * Vec3::Z);
+            let axis_depth = (dirs.clone() * forward).sum_dim(2);
+            origin + dirs / axis_depth * depth
         }
+        ProjectionMode::Orthographic { .. } => {
+            // The rays are parallel, starting from the pixel on the plane of the camera.
+            let (w, h) = (img_size.x as usize, img_size.y as usize);
+            let focal = camera.focal(img_size);
+            let center = camera.center(img_size);
+            let x = (Tensor::<MainBackendBase, 1, Int>::arange(0..w as i64, &device).float() + 0.5
+                - center.x)
+                / focal.x;
+            let y = (Tensor::<MainBackendBase, 1, Int>::arange(0..h as i64, &device).float() + 0.5
+                - center.y)
+                / focal.y;
+            let offsets = Tensor::stack::<3>(
+                vec![
+                    x.reshape([1, w]).expand([h, w]),
+                    y.reshape([h, 1]).expand([h, w]),
+                    Tensor::zeros([h, w], &device),
+                ],
+                2,
+            )
+            .reshape([h * w, 3]);
+            // The pixels are row vectors, so multiply by the transposed camera to world rotation.
+            let rotation = glam::Mat3::from_quat(camera.rotation);
+            let rotation =
+                Tensor::<MainBackendBase, 2>::from_floats(rotation.to_cols_array_2d(), &device);
+            let offsets = offsets.matmul(rotation).reshape([h, w, 3]);
+            origin + offsets + dirs * depth
+        }
+        ProjectionMode::Equirectangular | ProjectionMode::Fisheye { .. } => origin + dirs * depth,
+    }
+}
 
-        // Sanity check the buffers.
-        assert!(
-            uniforms_buffer.is_contiguous(),
-            "Uniforms must be contiguous"
+/// Render a feature render with a single depth sort. The rasterizer blends three channels at
+/// a time, so the features are rasterized in passes of three channels that share the sorted
+/// splats, and stitched into one `[H, W, dim]` image.
+fn render_features(
+    camera: &Camera,
+    img_size: glam::UVec2,
+    inputs: &RenderInputs,
+    dim: usize,
+    bwd_info: bool,
+    options: RenderOptions,
+    token: &CancellationToken,
+) -> Result<(FloatTensor<MainBackendBase>, RenderAux<MainBackendBase>), RenderCancelled> {
+    let render_mode = SplatRenderMode::Feature { dim };
+    let chunks = inputs.feature_chunks();
+    // Features ignore the background, see render_uniforms.
+    let uniforms = render_uniforms(
+        camera,
+        img_size,
+        &chunks[0],
+        render_mode,
+        Vec3::ZERO,
+        options,
+    );
+    let sorted = depth_sort(camera, &chunks[0], render_mode, uniforms, options);
+
+    let mut imgs = Vec::with_capacity(chunks.len());
+    let mut aux = None;
+    for chunk in &chunks {
+        check_cancelled(token)?;
+        // The passes only read the uniforms, so they can share them as well.
+        let chunk_sorted = DepthSorted {
+            uniforms_buffer: sorted.uniforms_buffer.clone(),
+            global_from_compact_gid: sorted.global_from_compact_gid.clone(),
+            depth_from_compact_gid: sorted.depth_from_compact_gid.clone(),
+            num_visible: sorted.num_visible.clone(),
+        };
+        let (img, chunk_aux) = rasterize_sorted(
+            camera,
+            img_size,
+            chunk,
+            chunk_sorted,
+            render_mode,
+            bwd_info,
+            options,
         );
+        imgs.push(Tensor::<MainBackendBase, 3>::from_primitive(
+            TensorPrimitive::Float(img),
+        ));
+        aux = Some(chunk_aux);
+    }
+    let img = Tensor::cat(imgs, 2).slice(s![.., .., 0..dim]);
+    // All passes project the same sorted splats, so any of their aux data will do.
+    let aux = aux.expect("Feature renders have at least one channel");
+    Ok((into_contiguous(img.into_primitive().tensor()), aux))
+}
+
+// Implement forward functions for the inner wgpu backend.
+impl SplatForward<Self> for MainBackendBase {
+    fn render_splats(
+        camera: &Camera,
+        img_size: glam::UVec2,
+        means: FloatTensor<Self>,
+        log_scales: FloatTensor<Self>,
+        quats: FloatTensor<Self>,
+        sh_coeffs: FloatTensor<Self>,
+        raw_opacities: FloatTensor<Self>,
+        render_mode: SplatRenderMode,
+        background: Vec3,
+        bwd_info: bool,
+        options: RenderOptions,
+    ) -> (FloatTensor<Self>, RenderAux<Self>) {
+        // A token that's never cancelled runs all stages.
+        Self::render_splats_cancellable(
+            camera,
+            img_size,
+            means,
+            log_scales,
+            quats,
+            sh_coeffs,
+            raw_opacities,
+            render_mode,
+            background,
+            bwd_info,
+            options,
+            &CancellationToken::new(),
+        )
+        .expect("Render can't be cancelled")
+    }
+
+    fn render_splats_cancellable(
+        camera: &Camera,
+        img_size: glam::UVec2,
+        means: FloatTensor<Self>,
+        log_scales: FloatTensor<Self>,
+        quats: FloatTensor<Self>,
+        sh_coeffs: FloatTensor<Self>,
+        raw_opacities: FloatTensor<Self>,
+        render_mode: SplatRenderMode,
+        background: Vec3,
+        bwd_info: bool,
+        options: RenderOptions,
+        token: &CancellationToken,
+    ) -> Result<(FloatTensor<Self>, RenderAux<Self>), RenderCancelled> {
         assert!(
-            tile_offsets.is_contiguous(),
-            "Tile offsets must be contiguous"
+            img_size[0] > 0 && img_size[1] > 0,
+            "Can't render images with 0 size."
         );
-        assert!(
-            global_from_compact_gid.is_contiguous(),
-            "Global from compact gid must be contiguous"
+        check_cancelled(token)?;
+        let inputs = RenderInputs::new(
+            means,
+            log_scales,
+            quats,
+            sh_coeffs,
+            raw_opacities,
+            render_mode,
+            options,
         );
-        assert!(visible.is_contiguous(), "Visible must be contiguous");
-        assert!(
-            projected_splats.is_contiguous(),
-            "Projected splats must be contiguous"
+        let _span = tracing::trace_span!("render_forward").entered();
+        if let SplatRenderMode::Feature { dim } = render_mode {
+            let output = render_features(camera, img_size, &inputs, dim, bwd_info, options, token)?;
+            check_cancelled(token)?;
+            return Ok(output);
+        }
+        let uniforms = render_uniforms(camera, img_size, &inputs, render_mode, background, options);
+        let sorted = depth_sort(camera, &inputs, render_mode, uniforms, options);
+        check_cancelled(token)?;
+        let output = rasterize_sorted(
+            camera,
+            img_size,
+            &inputs,
+            sorted,
+            render_mode,
+            bwd_info,
+            options,
         );
+        check_cancelled(token)?;
+        Ok(output)
+    }
+
+    fn render_splats_stereo(
+        left: &Camera,
+        right: &Camera,
+        img_size: glam::UVec2,
+        means: FloatTensor<Self>,
+        log_scales: FloatTensor<Self>,
+        quats: FloatTensor<Self>,
+        sh_coeffs: FloatTensor<Self>,
+        raw_opacities: FloatTensor<Self>,
+        render_mode: SplatRenderMode,
+        background: Vec3,
+        bwd_info: bool,
+        options: RenderOptions,
+    ) -> [(FloatTensor<Self>, RenderAux<Self>); 2] {
         assert!(
-            num_intersections.is_contiguous(),
-            "Num intersections must be contiguous"
+            img_size[0] > 0 && img_size[1] > 0,
+            "Can't render images with 0 size."
         );
+        let Some((sort_camera, depth_offset)) = Camera::stereo_sort_camera(left, right) else {
+            log::warn!("Stereo eyes aren't parallel, rendering them without a shared depth sort");
+            return [left, right].map(|eye| {
+                Self::render_splats(
+                    eye,
+                    img_size,
+                    means.clone(),
+                    log_scales.clone(),
+                    quats.clone(),
+                    sh_coeffs.clone(),
+                    raw_opacities.clone(),
+                    render_mode,
+                    background,
+                    bwd_info,
+                    options,
+                )
+            });
+        };
 
-        (
-            out_img,
-            RenderAux {
+        let inputs = RenderInputs::new(
+            means,
+            log_scales,
+            quats,
+            sh_coeffs,
+            raw_opacities,
+            render_mode,
+            options,
+        );
+        let device = &inputs.means.device.clone();
+        let client = &inputs.means.client.clone();
+        let _span = tracing::trace_span!("render_forward_stereo").entered();
+
+        // The sort camera is further away than the eyes, so move its near plane back to cull
+        // the same splats.
+        let sort_options = RenderOptions {
+            z_near: options.z_near + depth_offset,
+            ..options
+        };
+        let uniforms = render_uniforms(
+            &sort_camera,
+            img_size,
+            &inputs,
+            render_mode,
+            background,
+            sort_options,
+        );
+        let sorted = depth_sort(&sort_camera, &inputs, render_mode, uniforms, sort_options);
+        let depth_from_compact_gid =
+            Self::float_sub_scalar(sorted.depth_from_compact_gid, depth_offset.elem());
+
+        [left, right].map(|eye| {
+            // The uniforms of the eye, with the number of splats visible to either eye.
+            let uniforms =
+                render_uniforms(eye, img_size, &inputs, render_mode, background, options);
+            let uniforms_buffer = Self::int_slice_assign(
+                create_uniform_buffer(uniforms, device, client),
+                &[(NUM_VISIBLE_OFFSET..NUM_VISIBLE_OFFSET + 1).into()],
+                sorted.num_visible.clone(),
+            );
+            let eye_sorted = DepthSorted {
                 uniforms_buffer,
-                tile_offsets,
-                num_intersections,
-                projected_splats,
-                compact_gid_from_isect,
-                global_from_compact_gid,
-                visible,
+                global_from_compact_gid: sorted.global_from_compact_gid.clone(),
+                depth_from_compact_gid: depth_from_compact_gid.clone(),
+                num_visible: sorted.num_visible.clone(),
+            };
+            rasterize_sorted(
+                eye,
                 img_size,
-            },
-        )
+                &inputs,
+                eye_sorted,
+                render_mode,
+                bwd_info,
+                options,
+            )
+        })
     }
 }
@@ -4,14 +4,59 @@ use burn::{
     Tensor,
     prelude::Backend,
     tensor::{
-        Int,
+        IndexingUpdateOp, Int, TensorPrimitive,
         ops::{FloatTensor, IntTensor},
         s,
     },
 };
 
+use thiserror::Error;
+
 use crate::shaders::{self, helpers::TILE_WIDTH};
 
+/// Invalid values found when validating a render, see [`RenderAux::validate_values`] for the
+/// aux buffers and [`crate::validation::validate_output`] for the output image.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum SplatRenderError {
+    #[error("NaN in {buffer} at index {index}")]
+    NanInBuffer { buffer: &'static str, index: usize },
+    #[error("Infinite value in {buffer} at index {index}")]
+    InfInBuffer { buffer: &'static str, index: usize },
+    #[error("NaN in channel {channel} of the render output at pixel {pixel:?}")]
+    NanInOutput { channel: usize, pixel: [usize; 2] },
+    #[error("Infinite value in channel {channel} of the render output at pixel {pixel:?}")]
+    InfInOutput { channel: usize, pixel: [usize; 2] },
+    #[error("Too many intersections, estimated too low of a number. {count} / {estimate}")]
+    IntersectionsOverEstimate { count: u32, estimate: u32 },
+    #[error("Too many intersections, Brush currently can't handle this. {count} > {limit}")]
+    IntersectionsOverLimit { count: u32, limit: u32 },
+    #[error("More visible gaussians than gaussians. {num_visible} > {num_points}")]
+    TooManyVisible { num_visible: u32, num_points: u32 },
+    #[error("Tile offsets exceed bounds. Value: {offset}, num_intersections: {num_intersections}")]
+    TileOffsetOutOfBounds { offset: u32, num_intersections: u32 },
+    #[error("Invalid elements in tile offsets. Start {start} ending at {end}")]
+    InvalidTileRange { start: u32, end: u32 },
+    #[error("One tile has more hits than total visible splats. {hits} > {num_visible}")]
+    TileHitsOverVisible { hits: u32, num_visible: u32 },
+    #[error("Invalid gaussian ID in intersection buffer. {id} out of {num_visible} at {index}")]
+    InvalidCompactId {
+        index: usize,
+        id: u32,
+        num_visible: u32,
+    },
+    #[error("Invalid gaussian ID in global_from_compact_gid buffer. {id} out of {num_points}")]
+    InvalidGlobalId { id: u32, num_points: u32 },
+}
+
+/// Number of intersections per tile from the `[ty, tx, 2]` start/end tile offsets.
+pub(crate) fn tile_coverage<B: Backend>(tile_offsets: IntTensor<B>) -> IntTensor<B> {
+    let tile_offsets: Tensor<B, 3, Int> = Tensor::from_primitive(tile_offsets);
+    let [ty, tx, _] = tile_offsets.dims();
+    let end = tile_offsets.clone().slice(s![.., .., 1]);
+    let start = tile_offsets.slice(s![.., .., 0]);
+    (end - start).reshape([ty, tx]).into_primitive()
+}
+
 #[derive(Debug, Clone)]
 pub struct RenderAux<B: Backend> {
     /// The packed projected splat information, see `ProjectedSplat` in helpers.wgsl
@@ -22,39 +67,135 @@ pub struct RenderAux<B: Backend> {
     pub compact_gid_from_isect: IntTensor<B>,
     pub global_from_compact_gid: IntTensor<B>,
     pub visible: FloatTensor<B>,
+    /// `[H, W, 1]` camera space depth, accumulated in the same pass and front to back order
+    /// as the color. By default alpha weighted, Σ αᵢ Tᵢ zᵢ, see
+    /// [`crate::RenderOptions::depth_mode`]. Pixels without any coverage are set to
+    /// [`crate::RenderOptions::depth_sentinel`]. Only present when requested with
+    /// [`crate::RenderOptions::depth`].
+    pub depth: Option<FloatTensor<B>>,
+    /// `[H, W]` u32 count of the splats assigned to each pixel's tile that the pixel evaluated
+    /// before saturating. Useful to spot tile workload imbalance. Only present when requested
+    /// with [`crate::RenderOptions::record_hit_counts`].
+    pub tile_hit_counts: Option<IntTensor<B>>,
+    /// `[num_tiles_y, num_tiles_x]` u32 count of the splats assigned to each tile before
+    /// rasterization. Upsample by the tile width to line it up with the image. Only present
+    /// when requested with [`crate::RenderOptions::record_tile_coverage`].
+    pub tile_coverage: Option<IntTensor<B>>,
+    /// `[N]` screen space radius of each splat in pixels, see [`Self::calc_screen_radii`].
+    /// Only present when requested with [`crate::RenderOptions::record_screen_radii`].
+    pub screen_radii: Option<FloatTensor<B>>,
     pub img_size: glam::UVec2,
+    /// Whether the splats were rendered with the anti-aliasing filter, see
+    /// [`crate::RenderOptions::antialias`].
+    pub antialiased: bool,
 }
 
 impl<B: Backend> RenderAux<B> {
     pub fn calc_tile_depth(&self) -> Tensor<B, 2, Int> {
-        let tile_offsets: Tensor<B, 3, Int> = Tensor::from_primitive(self.tile_offsets.clone());
-        let max = tile_offsets.clone().slice(s![.., .., 1]);
-        let min = tile_offsets.slice(s![.., .., 0]);
         let [w, h] = self.img_size.into();
         let [ty, tx] = [h.div_ceil(TILE_WIDTH), w.div_ceil(TILE_WIDTH)];
-        (max - min).reshape([ty as usize, tx as usize])
+        Tensor::<B, 2, Int>::from_primitive(tile_coverage::<B>(self.tile_offsets.clone()))
+            .reshape([ty as usize, tx as usize])
+    }
+
+    pub fn tile_coverage(&self) -> Option<Tensor<B, 2, Int>> {
+        self.tile_coverage.clone().map(Tensor::from_primitive)
+    }
+
+    /// `[N]` radius in pixels of the 3 sigma ellipse of each projected splat along its major
+    /// axis, indexed like the input splats. Culled splats have a radius of 0.
+    ///
+    /// Computed from the projected conics on the device, without reading anything back.
+    pub fn calc_screen_radii(&self) -> Tensor<B, 1> {
+        let projected: Tensor<B, 2> =
+            Tensor::from_primitive(TensorPrimitive::Float(self.projected_splats.clone()));
+        let [num_splats, _] = projected.dims();
+        let device = projected.device();
+        let conic = |i: usize| projected.clone().slice(s![.., i]).squeeze_dim::<1>(1);
+        let (a, b, c) = (conic(2), conic(3), conic(4));
+
+        // The largest variance of the 2D covariance is the inverse of the smallest
+        // eigenvalue of the conic.
+        let half_trace = (a.clone() + c.clone()) * 0.5;
+        let half_diff = (a - c) * 0.5;
+        let min_eigen = half_trace - (half_diff.powi_scalar(2) + b.powi_scalar(2)).sqrt();
+        let radii = min_eigen.clamp_min(1e-12).sqrt().recip() * 3.0;
+
+        // Only the first num_visible projected splats are valid, the rest of the buffers
+        // can hold anything.
+        let culled = Tensor::<B, 1, Int>::arange(0..num_splats as i64, &device)
+            .greater_equal(self.num_visible().expand([num_splats]));
+        let global_gids: Tensor<B, 1, Int> =
+            Tensor::from_primitive(self.global_from_compact_gid.clone());
+        let global_gids = global_gids.mask_fill(culled.clone(), 0);
+        let radii = radii.mask_fill(culled, 0.0);
+        Tensor::zeros([num_splats], &device).scatter(0, global_gids, radii, IndexingUpdateOp::Add)
+    }
+
+    pub fn screen_radii(&self) -> Option<Tensor<B, 1>> {
+        self.screen_radii
+            .clone()
+            .map(|r| Tensor::from_primitive(TensorPrimitive::Float(r)))
     }
 
     pub fn num_intersections(&self) -> Tensor<B, 1, Int> {
         Tensor::from_primitive(self.num_intersections.clone())
     }
 
+    pub fn depth(&self) -> Option<Tensor<B, 3>> {
+        self.depth
+            .clone()
+            .map(|d| Tensor::from_primitive(TensorPrimitive::Float(d)))
+    }
+
+    pub fn tile_hit_counts(&self) -> Option<Tensor<B, 2, Int>> {
+        self.tile_hit_counts.clone().map(Tensor::from_primitive)
+    }
+
+    /// Number of splats that survived the opacity threshold and frustum culling, and were
+    /// projected for rasterization.
     pub fn num_visible(&self) -> Tensor<B, 1, Int> {
         let num_vis_field_offset = offset_of!(shaders::helpers::RenderUniforms, num_visible) / 4;
         Tensor::from_primitive(self.uniforms_buffer.clone()).slice(s![num_vis_field_offset])
     }
 
-    pub fn validate_values(&self) {
+    /// Number of splats dropped by culling before tile binning, ie. splats which were below
+    /// [`crate::RenderOptions::min_opacity`], behind the camera, degenerate, or whose
+    /// projected extent falls outside the image. The culling happens in the projection pass,
+    /// which compacts the survivors into [`Self::global_from_compact_gid`], so this is derived
+    /// as the total splat count minus [`Self::num_visible`] rather than counted separately.
+    /// Kept on the GPU like [`Self::num_visible`] so reading it doesn't force a sync.
+    pub fn num_culled(&self) -> Tensor<B, 1, Int> {
+        let total_field_offset = offset_of!(shaders::helpers::RenderUniforms, total_splats) / 4;
+        let total: Tensor<B, 1, Int> =
+            Tensor::from_primitive(self.uniforms_buffer.clone()).slice(s![total_field_offset]);
+        total - self.num_visible()
+    }
+
+    /// Check the aux buffers for out of bounds ids and offsets, and for NaN or infinite
+    /// values in the projected splats. Only checks anything in tests or with the
+    /// `debug-validation` feature, as it reads back all buffers.
+    pub fn validate_values(&self) -> Result<(), SplatRenderError> {
         #[cfg(any(test, feature = "debug-validation"))]
         {
-            use burn::tensor::{ElementConversion, TensorPrimitive};
+            use burn::tensor::ElementConversion;
 
             use crate::{
-                INTERSECTS_UPPER_BOUND, render::max_intersections, validation::validate_tensor_val,
+                INTERSECTS_UPPER_BOUND,
+                render::max_intersections,
+                validation::{validate_finite, validate_not_nan},
             };
 
+            macro_rules! ensure {
+                ($cond:expr, $err:expr) => {
+                    if !$cond {
+                        return Err($err);
+                    }
+                };
+            }
+
             if std::env::args().any(|a| a == "--bench") {
-                return;
+                return Ok(());
             }
 
             let num_intersects: Tensor<B, 1, Int> = self.num_intersections();
@@ -62,41 +203,53 @@ impl<B: Backend> RenderAux<B> {
                 Tensor::from_primitive(self.compact_gid_from_isect.clone());
             let num_visible: Tensor<B, 1, Int> = self.num_visible();
 
-            let num_intersections = num_intersects.into_scalar().elem::<i32>();
+            let num_intersections = num_intersects.into_scalar().elem::<i32>() as u32;
             let num_points = compact_gid_from_isect.dims()[0] as u32;
             let num_visible = num_visible.into_scalar().elem::<i32>() as u32;
             let img_size = self.img_size;
 
             let max_intersects = max_intersections(img_size, num_points);
 
-            assert!(
-                num_intersections < max_intersects as i32,
-                "Too many intersections, estimated too low of a number. {num_intersections} / {max_intersects}"
+            ensure!(
+                num_intersections < max_intersects,
+                SplatRenderError::IntersectionsOverEstimate {
+                    count: num_intersections,
+                    estimate: max_intersects,
+                }
             );
 
-            assert!(
-                num_intersections < INTERSECTS_UPPER_BOUND as i32,
-                "Too many intersections, Brush currently can't handle this. {num_intersections} > {INTERSECTS_UPPER_BOUND}"
+            ensure!(
+                num_intersections < INTERSECTS_UPPER_BOUND,
+                SplatRenderError::IntersectionsOverLimit {
+                    count: num_intersections,
+                    limit: INTERSECTS_UPPER_BOUND,
+                }
             );
 
-            assert!(
+            ensure!(
                 num_visible <= num_points,
-                "Something went wrong when calculating the number of visible gaussians. {num_visible} > {num_points}"
+                SplatRenderError::TooManyVisible {
+                    num_visible,
+                    num_points,
+                }
             );
 
             // Projected splats is only valid up to num_visible and undefined for other values.
             if num_visible > 0 {
-                use crate::validation::validate_tensor_val;
-
                 let projected_splats: Tensor<B, 2> =
                     Tensor::from_primitive(TensorPrimitive::Float(self.projected_splats.clone()));
                 let projected_splats = projected_splats.slice(s![0..num_visible]);
-                validate_tensor_val(&projected_splats, "projected_splats", None, None);
+                validate_finite(&projected_splats, "projected_splats")?;
             }
 
             let visible: Tensor<B, 2> =
                 Tensor::from_primitive(TensorPrimitive::Float(self.visible.clone()));
-            validate_tensor_val(&visible, "visible", None, None);
+            validate_finite(&visible, "visible")?;
+
+            // The sentinel of uncovered pixels can be infinite, so only NaNs are invalid.
+            if let Some(depth) = self.depth() {
+                validate_not_nan(&depth, "depth")?;
+            }
 
             let tile_offsets: Tensor<B, 3, Int> = Tensor::from_primitive(self.tile_offsets.clone());
 
@@ -104,29 +257,31 @@ impl<B: Backend> RenderAux<B> {
                 .into_data()
                 .into_vec::<u32>()
                 .expect("Failed to fetch tile offsets");
-            for &offsets in &tile_offsets {
-                assert!(
-                    offsets as i32 <= num_intersections,
-                    "Tile offsets exceed bounds. Value: {offsets}, num_intersections: {num_intersections}"
+            for &offset in &tile_offsets {
+                ensure!(
+                    offset <= num_intersections,
+                    SplatRenderError::TileOffsetOutOfBounds {
+                        offset,
+                        num_intersections,
+                    }
                 );
             }
 
             if num_intersections > 0 {
                 for i in 0..(tile_offsets.len() - 1) / 2 {
                     // Check pairs of start/end points.
-                    let start = tile_offsets[i * 2] as i32;
-                    let end = tile_offsets[i * 2 + 1] as i32;
-                    assert!(
-                        start < num_intersections && end <= num_intersections,
-                        "Invalid elements in tile offsets. Start {start} ending at {end}"
-                    );
-                    assert!(
-                        end >= start,
-                        "Invalid elements in tile offsets. Start {start} ending at {end}"
+                    let start = tile_offsets[i * 2];
+                    let end = tile_offsets[i * 2 + 1];
+                    ensure!(
+                        start < num_intersections && end <= num_intersections && end >= start,
+                        SplatRenderError::InvalidTileRange { start, end }
                     );
-                    assert!(
-                        end - start <= num_visible as i32,
-                        "One tile has more hits than total visible splats. Start {start} ending at {end}"
+                    ensure!(
+                        end - start <= num_visible,
+                        SplatRenderError::TileHitsOverVisible {
+                            hits: end - start,
+                            num_visible,
+                        }
                     );
                 }
             }
@@ -138,15 +293,15 @@ impl<B: Backend> RenderAux<B> {
                     .into_vec::<u32>()
                     .expect("Failed to fetch compact_gid_from_isect");
 
-                for (i, &compact_gid) in compact_gid_from_isect.iter().enumerate() {
-                    assert!(
-                compact_gid < num_visible,
-                "Invalid gaussian ID in intersection buffer. {compact_gid} out of {num_visible}. At {i} out of {num_intersections} intersections. \n
-
-                {compact_gid_from_isect:?}
-
-                \n\n\n"
-            );
+                for (index, &id) in compact_gid_from_isect.iter().enumerate() {
+                    ensure!(
+                        id < num_visible,
+                        SplatRenderError::InvalidCompactId {
+                            index,
+                            id,
+                            num_visible,
+                        }
+                    );
                 }
             }
 
@@ -158,12 +313,20 @@ impl<B: Backend> RenderAux<B> {
                 .into_vec::<u32>()
                 .expect("Failed to fetch global_from_compact_gid")[0..num_visible as usize];
 
-            for &global_gid in global_from_compact_gid {
-                assert!(
-                    global_gid < num_points,
-                    "Invalid gaussian ID in global_from_compact_gid buffer. {global_gid} out of {num_points}"
+            for &id in global_from_compact_gid {
+                ensure!(
+                    id < num_points,
+                    SplatRenderError::InvalidGlobalId { id, num_points }
                 );
             }
         }
+        Ok(())
+    }
+
+    /// Like [`Self::validate_values`], but panics on invalid values.
+    pub fn unwrap_values(&self) {
+        if let Err(err) = self.validate_values() {
+            panic!("{err}");
+        }
     }
 }
@@ -1,5 +1,7 @@
 use crate::shaders;
 
+use burn::prelude::Backend;
+use burn::tensor::{Tensor, TensorPrimitive, ops::FloatTensor, s};
 use glam::Vec3;
 const SH_C0: f32 = shaders::SH_C0;
 
@@ -18,6 +20,21 @@ pub fn sh_degree_from_coeffs(coeffs_per_channel: u32) -> u32 {
     }
 }
 
+/// Narrow `[N, coeffs, 3]` SH coefficients to the first `(degree + 1)²` coefficients, so
+/// the bands above `degree` are treated as zero. Degrees above the stored degree are clamped.
+pub fn truncate_sh_coeffs<B: Backend>(sh_coeffs: FloatTensor<B>, degree: u8) -> FloatTensor<B> {
+    let sh_coeffs = Tensor::<B, 3>::from_primitive(TensorPrimitive::Float(sh_coeffs));
+    let stored_degree = sh_degree_from_coeffs(sh_coeffs.dims()[1] as u32);
+    if u32::from(degree) >= stored_degree {
+        return sh_coeffs.into_primitive().tensor();
+    }
+    let num_coeffs = sh_coeffs_for_degree(u32::from(degree)) as usize;
+    sh_coeffs
+        .slice(s![.., 0..num_coeffs])
+        .into_primitive()
+        .tensor()
+}
+
 pub fn channel_to_sh(rgb: f32) -> f32 {
     (rgb - 0.5) / SH_C0
 }
@@ -29,3 +46,177 @@ pub fn rgb_to_sh(rgb: Vec3) -> Vec3 {
         channel_to_sh(rgb.z),
     )
 }
+
+/// Real SH basis up to `degree` at unit direction `dir`, with the same ordering and signs
+/// as `sh_coeffs_to_color` in the projection shader.
+fn sh_basis(degree: u32, dir: glam::DVec3) -> Vec<f64> {
+    let (x, y, z) = (dir.x, dir.y, dir.z);
+    let mut basis = vec![f64::from(SH_C0)];
+    if degree == 0 {
+        return basis;
+    }
+    let tmp0a = 0.48860251190292;
+    basis.extend([-tmp0a * y, tmp0a * z, -tmp0a * x]);
+    if degree == 1 {
+        return basis;
+    }
+
+    let z2 = z * z;
+    let tmp0b = -1.092548430592079 * z;
+    let tmp1a = 0.5462742152960395;
+    let c1 = x * x - y * y;
+    let s1 = 2.0 * x * y;
+    let sh6 = 0.9461746957575601 * z2 - 0.3153915652525201;
+    basis.extend([tmp1a * s1, tmp0b * y, sh6, tmp0b * x, tmp1a * c1]);
+    if degree == 2 {
+        return basis;
+    }
+
+    let tmp0c = -2.285228997322329 * z2 + 0.4570457994644658;
+    let tmp1b = 1.445305721320277 * z;
+    let tmp2a = -0.5900435899266435;
+    let c2 = x * c1 - y * s1;
+    let s2 = x * s1 + y * c1;
+    let sh12 = z * (1.865881662950577 * z2 - 1.119528997770346);
+    basis.extend([
+        tmp2a * s2,
+        tmp1b * s1,
+        tmp0c * y,
+        sh12,
+        tmp0c * x,
+        tmp1b * c1,
+        tmp2a * c2,
+    ]);
+    if degree == 3 {
+        return basis;
+    }
+
+    let tmp0d = z * (-4.683325804901025 * z2 + 2.007139630671868);
+    let tmp1c = 3.31161143515146 * z2 - 0.47308734787878;
+    let tmp2b = -1.770130769779931 * z;
+    let tmp3a = 0.6258357354491763;
+    let c3 = x * c2 - y * s2;
+    let s3 = x * s2 + y * c2;
+    let sh20 = 1.984313483298443 * z * sh12 - 1.006230589874905 * sh6;
+    basis.extend([
+        tmp3a * s3,
+        tmp2b * s2,
+        tmp1c * s1,
+        tmp0d * y,
+        sh20,
+        tmp0d * x,
+        tmp1c * c1,
+        tmp2b * c2,
+        tmp3a * c3,
+    ]);
+    basis
+}
+
+/// Solve `a x = b` for a small dense system with Gauss-Jordan elimination.
+fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<Vec<f64>>) -> Vec<Vec<f64>> {
+    let n = a.len();
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+            .expect("Non empty system");
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        for row in 0..n {
+            if row == col {
+                continue;
+            }
+            let f = a[row][col] / a[col][col];
+            for k in 0..n {
+                a[row][k] -= f * a[col][k];
+            }
+            for k in 0..b[row].len() {
+                b[row][k] -= f * b[col][k];
+            }
+        }
+    }
+    b.into_iter()
+        .enumerate()
+        .map(|(i, row)| row.into_iter().map(|v| v / a[i][i]).collect())
+        .collect()
+}
+
+/// `[coeffs, coeffs]` matrix `m` which rotates SH coefficients stored as rows, so that
+/// `coeffs · m` gives the same colors for directions rotated by `rotation` as `coeffs`
+/// did for the original directions. Each band only mixes with itself.
+pub fn sh_rotation_matrix(degree: u32, rotation: glam::Quat) -> Vec<Vec<f32>> {
+    let num_coeffs = sh_coeffs_for_degree(degree) as usize;
+    let inv_rotation = rotation.as_dquat().inverse();
+
+    // Fit the matrix from enough well spread directions, the relation holds exactly.
+    let num_dirs = 4 * num_coeffs;
+    let golden_angle = std::f64::consts::PI * (3.0 - 5.0f64.sqrt());
+    let dirs = (0..num_dirs).map(|i| {
+        let y = 1.0 - 2.0 * (i as f64 + 0.5) / num_dirs as f64;
+        let r = (1.0 - y * y).sqrt();
+        let theta = golden_angle * i as f64;
+        glam::dvec3(r * theta.cos(), y, r * theta.sin())
+    });
+
+    // Find m where basis(R⁻¹ d) = basis(d) · m for each band, from the normal equations.
+    let mut ata = vec![vec![0.0; num_coeffs]; num_coeffs];
+    let mut atb = vec![vec![0.0; num_coeffs]; num_coeffs];
+    for dir in dirs {
+        let a = sh_basis(degree, dir);
+        let b = sh_basis(degree, inv_rotation * dir);
+        for i in 0..num_coeffs {
+            for j in 0..num_coeffs {
+                ata[i][j] += a[i] * a[j];
+                atb[i][j] += a[i] * b[j];
+            }
+        }
+    }
+    let m = solve(ata, atb);
+
+    // Colors are coeffs · basis(R⁻¹ d) = coeffs · mᵀ · basis(d), so rotate the rows by mᵀ.
+    // Entries between different bands are zero up to rounding.
+    let band = |k: usize| (k as f64).sqrt() as usize;
+    (0..num_coeffs)
+        .map(|i| {
+            (0..num_coeffs)
+                .map(|j| {
+                    if band(i) == band(j) {
+                        m[j][i] as f32
+                    } else {
+                        0.0
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotated_coeffs_follow_rotated_directions() {
+        let rotation = glam::Quat::from_euler(glam::EulerRot::YXZ, 0.7, -1.1, 0.4);
+        for degree in 0..=4 {
+            let num_coeffs = sh_coeffs_for_degree(degree) as usize;
+            let coeffs: Vec<f64> = (0..num_coeffs).map(|i| (i as f64 * 0.37).sin()).collect();
+            let m = sh_rotation_matrix(degree, rotation);
+            let rotated: Vec<f64> = (0..num_coeffs)
+                .map(|j| (0..num_coeffs).map(|i| coeffs[i] * m[i][j] as f64).sum())
+                .collect();
+
+            for dir in [glam::dvec3(0.3, -0.5, 0.8), glam::dvec3(-0.9, 0.1, 0.2)] {
+                let dir = dir.normalize();
+                let eval = |c: &[f64], d| -> f64 {
+                    c.iter().zip(sh_basis(degree, d)).map(|(c, b)| c * b).sum()
+                };
+                let expected = eval(&coeffs, dir);
+                let actual = eval(&rotated, rotation.as_dquat() * dir);
+                assert!(
+                    (expected - actual).abs() < 1e-5,
+                    "Degree {degree}: {expected} != {actual}"
+                );
+            }
+        }
+    }
+}
@@ -5,11 +5,25 @@ use brush_wgsl::wgsl_kernel;
 #[wgsl_kernel(source = "src/shaders/project_forward.wgsl")]
 pub struct ProjectSplats {
     mip_splatting: bool,
+    orthographic: bool,
+    equirectangular: bool,
+    fisheye: bool,
+    equisolid: bool,
+    f16_storage: bool,
 }
 
 #[wgsl_kernel(source = "src/shaders/project_visible.wgsl")]
 pub struct ProjectVisible {
     mip_splatting: bool,
+    orthographic: bool,
+    equirectangular: bool,
+    fisheye: bool,
+    equisolid: bool,
+    normals: bool,
+    alpha_only: bool,
+    world_position: bool,
+    features: bool,
+    f16_storage: bool,
 }
 
 #[wgsl_kernel(source = "src/shaders/map_gaussian_to_intersects.wgsl")]
@@ -21,6 +35,10 @@ pub struct MapGaussiansToIntersect {
 pub struct Rasterize {
     pub bwd_info: bool,
     pub webgpu: bool,
+    pub depth: bool,
+    pub median_depth: bool,
+    pub hit_counts: bool,
+    pub signed_color: bool,
 }
 
 // Re-export helper types and constants from the kernel modules that use them
@@ -1,4 +1,5 @@
-const TILE_WIDTH: u32 = 16u;
+// Set by brush-wgsl from the tile features or BRUSH_TILE_SIZE.
+const TILE_WIDTH: u32 = #{TILE_WIDTH}u;
 const TILE_SIZE: u32 = TILE_WIDTH * TILE_WIDTH;
 
 // Compute linear workgroup ID from 2D dispatch
@@ -54,6 +55,9 @@ struct RenderUniforms {
     // Position of camera (xyz + pad)
     camera_position: vec4f,
 
+    // Nb: Alpha is ignored atm.
+    background: vec4f,
+
     // Degree of sh coefficients used.
     sh_degree: u32,
 
@@ -70,8 +74,16 @@ struct RenderUniforms {
     total_splats: u32,
     max_intersects: u32,
 
-    // Nb: Alpha is ignored atm.
-    background: vec4f,
+    // Splats with a lower opacity are culled before projecting.
+    min_opacity: f32,
+    // Splats closer to the camera than this are culled before projecting.
+    z_near: f32,
+    // Largest angle from the optical axis the camera sees, in radians. Only fisheye
+    // cameras set this.
+    max_view_angle: f32,
+    // Distance of the edge of the fisheye image circle from the principal point, in focal
+    // lengths. Pixels further out stay transparent.
+    max_view_radius: f32,
 }
 
 struct ProjectedSplat {
@@ -168,7 +180,99 @@ fn calc_cov3d(scale: vec3f, quat: vec4f) -> mat3x3f {
     return M * transpose(M);
 }
 
+fn project_mean(mean_c: vec3f, focal: vec2f, pixel_center: vec2f) -> vec2f {
+#ifdef ORTHOGRAPHIC
+    return focal * mean_c.xy + pixel_center;
+#else
+#ifdef EQUIRECTANGULAR
+    // Longitude & latitude of the direction, the focal is pixels per radian.
+    let lon = atan2(mean_c.x, mean_c.z);
+    let lat = atan2(mean_c.y, length(mean_c.xz));
+    return focal * vec2f(lon, lat) + pixel_center;
+#else
+#ifdef FISHEYE
+    // The distance from the principal point is a function of the angle from the optical
+    // axis, see fisheye_radius.
+    return focal * mean_c.xy * fisheye_scale(mean_c) + pixel_center;
+#else
+    return focal * mean_c.xy * (1.0 / mean_c.z) + pixel_center;
+#endif
+#endif
+#endif
+}
+
+// Image radius in focal lengths of a ray theta radians from the optical axis, and its
+// derivative.
+fn fisheye_radius(theta: f32) -> vec2f {
+#ifdef EQUISOLID
+    return vec2f(2.0 * sin(0.5 * theta), cos(0.5 * theta));
+#else
+    // Equidistant, the focal is pixels per radian.
+    return vec2f(theta, 1.0);
+#endif
+}
+
+// Image radius divided by the distance from the optical axis, which maps the camera space
+// xy to the image plane of a fisheye camera.
+fn fisheye_scale(mean_c: vec3f) -> f32 {
+    let rho = max(length(mean_c.xy), 1e-4f);
+    return fisheye_radius(atan2(rho, mean_c.z)).x / rho;
+}
+
+// Distance used to sort splats and to cull splats too close to the camera.
+fn view_depth(mean_c: vec3f) -> f32 {
+#ifdef EQUIRECTANGULAR
+    return length(mean_c);
+#else
+#ifdef FISHEYE
+    // Wide fisheyes see behind the camera, so use the distance like panoramas.
+    return length(mean_c);
+#else
+    return mean_c.z;
+#endif
+#endif
+}
+
 fn calc_cam_J(mean_c: vec3f, focal: vec2f, img_size: vec2u, pixel_center: vec2f) -> mat3x2f {
+#ifdef ORTHOGRAPHIC
+    // Orthographic projection is linear, depth doesn't affect the footprint.
+    return mat3x2f(
+        vec2f(focal.x, 0.0),
+        vec2f(0.0, focal.y),
+        vec2f(0.0, 0.0)
+    );
+#else
+#ifdef EQUIRECTANGULAR
+    // Jacobian of the longitude/latitude mapping at the mean. Clamp the distance to the
+    // vertical axis to keep things finite at the poles.
+    let rho2 = max(dot(mean_c.xz, mean_c.xz), 1e-8f);
+    let rho = sqrt(rho2);
+    let r2 = rho2 + mean_c.y * mean_c.y;
+    let dlat_dxz = -mean_c.y / (r2 * rho);
+    return mat3x2f(
+        vec2f(focal.x * mean_c.z / rho2, focal.y * mean_c.x * dlat_dxz),
+        vec2f(0.0, focal.y * rho / r2),
+        vec2f(-focal.x * mean_c.x / rho2, focal.y * mean_c.z * dlat_dxz)
+    );
+#else
+#ifdef FISHEYE
+    // Jacobian of the mapping k * xy, with k = s(θ) / ρ the image radius of the angle from
+    // the optical axis divided by the distance to it. Clamp the distance to keep things
+    // finite on the axis.
+    let rho2 = max(dot(mean_c.xy, mean_c.xy), 1e-8f);
+    let r2 = rho2 + mean_c.z * mean_c.z;
+    let s = fisheye_radius(atan2(sqrt(rho2), mean_c.z));
+    let k = s.x / sqrt(rho2);
+    // dk/dx = x * g and dk/dy = y * g, with dθ/dρ = z / r².
+    let g = (s.y * mean_c.z / r2 - k) / rho2;
+    let dxy = mean_c.x * mean_c.y * g;
+    // dθ/dz = -ρ / r².
+    return mat3x2f(
+        focal * vec2f(k + mean_c.x * mean_c.x * g, dxy),
+        focal * vec2f(dxy, k + mean_c.y * mean_c.y * g),
+        -focal * mean_c.xy * s.y / r2
+    );
+#else
     let lims_pos = (1.15f * vec2f(img_size.xy) - pixel_center) / focal;
     let lims_neg = (-0.15f * vec2f(img_size.xy) - pixel_center) / focal;
     let rz = 1.0 / mean_c.z;
@@ -184,6 +288,9 @@ fn calc_cam_J(mean_c: vec3f, focal: vec2f, img_size: vec2u, pixel_center: vec2f)
     );
 
     return J;
+#endif
+#endif
+#endif
 }
 
 fn calc_cov2d(cov3d: mat3x3f, mean_c: vec3f, focal: vec2f, img_size: vec2u, pixel_center: vec2f, viewmat: mat4x4f) -> mat2x2f {
@@ -297,6 +404,17 @@ fn ceil_div(a: u32, b: u32) -> u32 {
     return (a + b - 1) / b;
 }
 
+// Unpack the vec3 at element `first` of an f16 buffer read as u32 words, from the word holding
+// `first` and the word after it.
+fn unpack_half3(w0: u32, w1: u32, first: u32) -> vec3f {
+    let a = unpack2x16float(w0);
+    let b = unpack2x16float(w1);
+    if first % 2u == 1u {
+        return vec3f(a.y, b);
+    }
+    return vec3f(a, b.x);
+}
+
 fn as_vec(packed: PackedVec3) -> vec3f {
     return vec3f(packed.x, packed.y, packed.z);
 }
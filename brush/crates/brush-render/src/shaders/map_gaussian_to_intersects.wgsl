@@ -38,6 +38,12 @@ fn main(
     let conic = vec3f(projected.conic_x, projected.conic_y, projected.conic_z);
     let opac = projected.color_a;
 
+    // Splats sorted for another camera (see the stereo render) can be too faint for this
+    // one, which would give a NaN extent. Their intersection count stays zero.
+    if opac < 1.0 / 255.0 {
+        return;
+    }
+
     let power_threshold = log(opac * 255.0);
     let cov2d = helpers::inverse(mat2x2f(conic.x, conic.y, conic.y, conic.z));
     let extent = helpers::compute_bbox_extent(cov2d, power_threshold);
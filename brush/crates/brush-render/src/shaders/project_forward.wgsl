@@ -5,9 +5,39 @@
 // Unfiroms contains the splat count which we're writing to.
 @group(0) @binding(0) var<storage, read_write> uniforms: helpers::RenderUniforms;
 
-@group(0) @binding(1) var<storage, read> means: array<helpers::PackedVec3>;
-@group(0) @binding(2) var<storage, read> quats: array<vec4f>;
-@group(0) @binding(3) var<storage, read> log_scales: array<helpers::PackedVec3>;
+#ifdef F16_STORAGE
+    // f16 attributes, two per word.
+    @group(0) @binding(1) var<storage, read> means: array<u32>;
+    @group(0) @binding(2) var<storage, read> quats: array<u32>;
+    @group(0) @binding(3) var<storage, read> log_scales: array<u32>;
+
+    fn load_mean(gid: u32) -> vec3f {
+        let first = gid * 3u;
+        return helpers::unpack_half3(means[first / 2u], means[first / 2u + 1u], first);
+    }
+    fn load_log_scale(gid: u32) -> vec3f {
+        let first = gid * 3u;
+        return helpers::unpack_half3(log_scales[first / 2u], log_scales[first / 2u + 1u], first);
+    }
+    fn load_quat(gid: u32) -> vec4f {
+        return vec4f(unpack2x16float(quats[gid * 2u]), unpack2x16float(quats[gid * 2u + 1u]));
+    }
+#else
+    @group(0) @binding(1) var<storage, read> means: array<helpers::PackedVec3>;
+    @group(0) @binding(2) var<storage, read> quats: array<vec4f>;
+    @group(0) @binding(3) var<storage, read> log_scales: array<helpers::PackedVec3>;
+
+    fn load_mean(gid: u32) -> vec3f {
+        return helpers::as_vec(means[gid]);
+    }
+    fn load_log_scale(gid: u32) -> vec3f {
+        return helpers::as_vec(log_scales[gid]);
+    }
+    fn load_quat(gid: u32) -> vec4f {
+        return quats[gid];
+    }
+#endif
+
 @group(0) @binding(4) var<storage, read> raw_opacities: array<f32>;
 
 @group(0) @binding(5) var<storage, read_write> global_from_compact_gid: array<u32>;
@@ -28,8 +58,14 @@ fn main(
         return;
     }
 
+    // Cull nearly transparent splats before doing any other work. Phrase as negative
+    // so NaN opacities pass through like they did before.
+    if helpers::sigmoid(raw_opacities[global_gid]) < uniforms.min_opacity {
+        return;
+    }
+
     // Project world space to camera space.
-    let mean = helpers::as_vec(means[global_gid]);
+    let mean = load_mean(global_gid);
 
     let img_size = uniforms.img_size;
     let viewmat = uniforms.viewmat;
@@ -37,12 +73,23 @@ fn main(
     let mean_c = R * mean + viewmat[3].xyz;
 
     // Check if this splat is 'valid' (aka visible). Phrase as positive to bail on NaN.
-    if mean_c.z < 0.01 || mean_c.z > 1e10 {
+    // Splats straddling the near plane are kept or culled as a whole by their center, so
+    // nothing is projected from behind the plane.
+    let depth = helpers::view_depth(mean_c);
+    if depth < uniforms.z_near || depth > 1e10 {
+        return;
+    }
+
+#ifdef FISHEYE
+    // Splats centered outside the view cone would only show up at the border, and their
+    // projection degenerates behind a full 360° fisheye.
+    if atan2(length(mean_c.xy), mean_c.z) > uniforms.max_view_angle {
         return;
     }
+#endif
 
-    let scale = exp(helpers::as_vec(log_scales[global_gid]));
-    var quat = quats[global_gid];
+    let scale = exp(load_log_scale(global_gid));
+    var quat = load_quat(global_gid);
 
     // Skip any invalid rotations. This will mean overtime
     // these gaussians just die off while optimizing. For the viewer, the importer
@@ -61,7 +108,7 @@ fn main(
     opac *= helpers::compensate_cov2d(&cov2d);
 
     // compute the projected mean
-    let mean2d = uniforms.focal * mean_c.xy * (1.0 / mean_c.z) + uniforms.pixel_center;
+    let mean2d = helpers::project_mean(mean_c, uniforms.focal, uniforms.pixel_center);
 
     if opac < 1.0 / 255.0 {
         return;
@@ -72,6 +119,8 @@ fn main(
         return;
     }
 
+    // Cull splats whose projected extent misses the image rectangle, so only the compacted
+    // survivors below reach tile binning.
     if mean2d.x + extent.x <= 0 || mean2d.x - extent.x >= f32(uniforms.img_size.x) ||
        mean2d.y + extent.y <= 0 || mean2d.y - extent.y >= f32(uniforms.img_size.y) {
         return;
@@ -79,5 +128,5 @@ fn main(
     // Now write all the data to the buffers.
     let write_id = atomicAdd(&uniforms.num_visible, 1u);
     global_from_compact_gid[write_id] = global_gid;
-    depths[write_id] = mean_c.z;
+    depths[write_id] = depth;
 }
@@ -7,10 +7,48 @@ struct IsectInfo {
 
 @group(0) @binding(0) var<storage, read> uniforms: helpers::RenderUniforms;
 
-@group(0) @binding(1) var<storage, read> means: array<helpers::PackedVec3>;
-@group(0) @binding(2) var<storage, read> log_scales: array<helpers::PackedVec3>;
-@group(0) @binding(3) var<storage, read> quats: array<vec4f>;
-@group(0) @binding(4) var<storage, read> coeffs: array<helpers::PackedVec3>;
+#ifdef F16_STORAGE
+    // f16 attributes, two per word.
+    @group(0) @binding(1) var<storage, read> means: array<u32>;
+    @group(0) @binding(2) var<storage, read> log_scales: array<u32>;
+    @group(0) @binding(3) var<storage, read> quats: array<u32>;
+    @group(0) @binding(4) var<storage, read> coeffs: array<u32>;
+
+    fn load_mean(gid: u32) -> vec3f {
+        let first = gid * 3u;
+        return helpers::unpack_half3(means[first / 2u], means[first / 2u + 1u], first);
+    }
+    fn load_log_scale(gid: u32) -> vec3f {
+        let first = gid * 3u;
+        return helpers::unpack_half3(log_scales[first / 2u], log_scales[first / 2u + 1u], first);
+    }
+    fn load_quat(gid: u32) -> vec4f {
+        return vec4f(unpack2x16float(quats[gid * 2u]), unpack2x16float(quats[gid * 2u + 1u]));
+    }
+    fn load_coeff(id: u32) -> vec3f {
+        let first = id * 3u;
+        return helpers::unpack_half3(coeffs[first / 2u], coeffs[first / 2u + 1u], first);
+    }
+#else
+    @group(0) @binding(1) var<storage, read> means: array<helpers::PackedVec3>;
+    @group(0) @binding(2) var<storage, read> log_scales: array<helpers::PackedVec3>;
+    @group(0) @binding(3) var<storage, read> quats: array<vec4f>;
+    @group(0) @binding(4) var<storage, read> coeffs: array<helpers::PackedVec3>;
+
+    fn load_mean(gid: u32) -> vec3f {
+        return helpers::as_vec(means[gid]);
+    }
+    fn load_log_scale(gid: u32) -> vec3f {
+        return helpers::as_vec(log_scales[gid]);
+    }
+    fn load_quat(gid: u32) -> vec4f {
+        return quats[gid];
+    }
+    fn load_coeff(id: u32) -> vec3f {
+        return helpers::as_vec(coeffs[id]);
+    }
+#endif
+
 @group(0) @binding(5) var<storage, read> raw_opacities: array<f32>;
 @group(0) @binding(6) var<storage, read> global_from_compact_gid: array<u32>;
 @group(0) @binding(7) var<storage, read_write> projected: array<helpers::ProjectedSplat>;
@@ -155,7 +193,7 @@ fn num_sh_coeffs(degree: u32) -> u32 {
 }
 
 fn read_coeffs(base_id: ptr<function, u32>) -> vec3f {
-    let ret = helpers::as_vec(coeffs[*base_id]);
+    let ret = load_coeff(*base_id);
     *base_id += 1u;
     return ret;
 }
@@ -178,11 +216,11 @@ fn main(
     let global_gid = global_from_compact_gid[compact_gid];
 
     // Project world space to camera space.
-    let mean = helpers::as_vec(means[global_gid]);
-    let scale = exp(helpers::as_vec(log_scales[global_gid]));
+    let mean = load_mean(global_gid);
+    let scale = exp(load_log_scale(global_gid));
 
     // Safe to normalize, splats with length(quat) == 0 are invisible.
-    let quat = normalize(quats[global_gid]);
+    let quat = normalize(load_quat(global_gid));
     var opac = helpers::sigmoid(raw_opacities[global_gid]);
 
     let viewmat = uniforms.viewmat;
@@ -196,9 +234,9 @@ fn main(
     let conic = helpers::inverse(cov2d);
 
     // compute the projected mean
-    let rz = 1.0 / mean_c.z;
-    let mean2d = uniforms.focal * mean_c.xy * rz + uniforms.pixel_center;
+    let mean2d = helpers::project_mean(mean_c, uniforms.focal, uniforms.pixel_center);
 
+#ifndef ALPHA_ONLY
     let sh_degree = uniforms.sh_degree;
     let num_coeffs = num_sh_coeffs(sh_degree);
     var base_id = u32(global_gid) * num_coeffs;
@@ -241,10 +279,54 @@ fn main(
             }
         }
     }
+#endif
 
     // Write projected splat information.
+#ifdef ORTHOGRAPHIC
+    // All rays are parallel to the camera forward axis.
+    let viewdir = normalize(vec3f(R[0].z, R[1].z, R[2].z));
+#else
     let viewdir = normalize(mean - uniforms.camera_position.xyz);
+#endif
+#ifdef NORMALS
+    // The normal is the shortest axis of the ellipsoid, facing towards the camera.
+    let rot = helpers::quat_to_mat(quat);
+    var color = vec3f(0.0);
+    // Degenerate splats, with a (near) zero or non-finite scale, have no normal. The exponent
+    // bits are checked directly as NaN comparisons aren't reliable in shaders.
+    let finite = all((bitcast<vec3u>(scale) & vec3u(0x7f800000u)) != vec3u(0x7f800000u));
+    if finite && max(scale.x, max(scale.y, scale.z)) > 1e-12 {
+        var axis = 0u;
+        if scale.y < scale[axis] {
+            axis = 1u;
+        }
+        if scale.z < scale[axis] {
+            axis = 2u;
+        }
+        color = rot[axis];
+        if dot(color, viewdir) > 0.0 {
+            color = -color;
+        }
+    }
+#else
+#ifdef ALPHA_ONLY
+    // Only the opacity is rasterized, so skip reading and evaluating the SH entirely.
+    var color = vec3f(0.0);
+#else
+#ifdef WORLD_POSITION
+    // Only the alpha and depth are rasterized, the position is placed on the pixel ray at the
+    // blended depth after rasterizing.
+    var color = vec3f(0.0);
+#else
+#ifdef FEATURES
+    // The coefficients hold three raw feature channels, blended as is.
+    var color = sh.b0_c0;
+#else
     var color = sh_coeffs_to_color(sh_degree, viewdir, sh) + vec3f(0.5);
+#endif
+#endif
+#endif
+#endif
 
     projected[compact_gid] = helpers::create_projected_splat(
         mean2d,
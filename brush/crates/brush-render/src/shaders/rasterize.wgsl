@@ -13,6 +13,32 @@
     @group(0) @binding(4) var<storage, read_write> out_img: array<u32>;
 #endif
 
+#ifdef DEPTH
+    #ifdef BWD_INFO
+        @group(0) @binding(7) var<storage, read> depths: array<f32>;
+        @group(0) @binding(8) var<storage, read_write> out_depth: array<f32>;
+    #else
+        @group(0) @binding(5) var<storage, read> depths: array<f32>;
+        @group(0) @binding(6) var<storage, read_write> out_depth: array<f32>;
+    #endif
+#endif
+
+#ifdef HIT_COUNTS
+    #ifdef BWD_INFO
+        #ifdef DEPTH
+            @group(0) @binding(9) var<storage, read_write> out_hit_counts: array<u32>;
+        #else
+            @group(0) @binding(7) var<storage, read_write> out_hit_counts: array<u32>;
+        #endif
+    #else
+        #ifdef DEPTH
+            @group(0) @binding(7) var<storage, read_write> out_hit_counts: array<u32>;
+        #else
+            @group(0) @binding(5) var<storage, read_write> out_hit_counts: array<u32>;
+        #endif
+    #endif
+#endif
+
 var<workgroup> range_uniform: vec2u;
 
 var<workgroup> local_batch: array<helpers::ProjectedSplat, helpers::TILE_SIZE>;
@@ -21,6 +47,10 @@ var<workgroup> local_batch: array<helpers::ProjectedSplat, helpers::TILE_SIZE>;
     var<workgroup> load_gid: array<u32, helpers::TILE_SIZE>;
 #endif
 
+#ifdef DEPTH
+    var<workgroup> load_depth: array<f32, helpers::TILE_SIZE>;
+#endif
+
 // kernel function for rasterizing each tile
 // each thread treats a single pixel
 // each thread group uses the same gaussian data in a tile
@@ -51,7 +81,20 @@ fn main(
     // current visibility left to render
     var T = 1.0;
     var pix_out = vec3f(0.0);
-    var done = !inside;
+    // Pixels outside the image circle of a fisheye camera stay transparent.
+    let view_radius = length((pixel_coord - uniforms.pixel_center) / uniforms.focal);
+    var done = !inside || view_radius > uniforms.max_view_radius;
+
+#ifdef DEPTH
+    // Alpha weighted depth, accumulated in the same order as the color. For the median
+    // depth, the depth of the splat that pushes the accumulated alpha past 0.5.
+    var depth_out = 0.0f;
+#endif
+
+#ifdef HIT_COUNTS
+    // Number of splats from the tile this pixel evaluated.
+    var hit_count = 0u;
+#endif
 
     // each thread loads one gaussian at a time before rasterizing its
     // designated pixel
@@ -68,12 +111,22 @@ fn main(
             #ifdef BWD_INFO
                 load_gid[local_idx] = global_from_compact_gid[compact_gid];
             #endif
+            #ifdef DEPTH
+                load_depth[local_idx] = depths[compact_gid];
+            #endif
         }
         workgroupBarrier();
 
         for (var t = 0u; !done && t < remaining; t++) {
             let proj = local_batch[t];
 
+            #ifdef HIT_COUNTS
+                // Saturate instead of wrapping around.
+                if hit_count < 0xffffffffu {
+                    hit_count += 1u;
+                }
+            #endif
+
             let xy = vec2f(proj.xy_x, proj.xy_y);
             let conic = vec3f(proj.conic_x, proj.conic_y, proj.conic_z);
             let color = vec4f(proj.color_r, proj.color_g, proj.color_b, proj.color_a);
@@ -96,7 +149,21 @@ fn main(
                 #endif
 
                 let vis = alpha * T;
-                pix_out += max(color.rgb, vec3f(0.0)) * vis;
+                #ifdef SIGNED_COLOR
+                    // Normals and features are signed.
+                    pix_out += color.rgb * vis;
+                #else
+                    pix_out += max(color.rgb, vec3f(0.0)) * vis;
+                #endif
+                #ifdef DEPTH
+                    #ifdef MEDIAN_DEPTH
+                        if T > 0.5f && next_T <= 0.5f {
+                            depth_out = load_depth[t];
+                        }
+                    #else
+                        depth_out += load_depth[t] * vis;
+                    #endif
+                #endif
                 T = next_T;
             }
         }
@@ -114,5 +181,13 @@ fn main(
             let packed: u32 = colors_u.x | (colors_u.y << 8u) | (colors_u.z << 16u) | (colors_u.w << 24u);
             out_img[pix_id] = packed;
         #endif
+
+        #ifdef DEPTH
+            out_depth[pix_id] = depth_out;
+        #endif
+
+        #ifdef HIT_COUNTS
+            out_hit_counts[pix_id] = hit_count;
+        #endif
     }
 }
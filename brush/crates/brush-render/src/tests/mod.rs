@@ -1,9 +1,113 @@
-use crate::{MainBackend, SplatForward, camera::Camera, gaussian_splats::SplatRenderMode};
+use crate::{
+    DepthMode, MainBackend, RenderOptions, SplatForward,
+    bounding_box::{auto_camera_for_splats, splat_aabb},
+    camera::{Camera, FisheyeModel, ProjectionMode},
+    color::{linear_to_srgb, srgb_to_linear},
+    density::density_voxelise,
+    env_map::{ray_directions, sample_env_map},
+    gaussian_splats::{
+        ConcatError, FeatureError, MAX_FEATURE_DIM, SplatPrecision, SplatRenderMode, Splats,
+        render_splats, render_splats_over_env_map, render_splats_over_image, render_splats_tiled,
+    },
+    render_aux::{RenderAux, SplatRenderError},
+    shaders::helpers::TILE_WIDTH,
+    tonemap::{apply_aces_tonemapping, apply_reinhard_tonemapping},
+    validation::validate_output,
+};
 use assert_approx_eq::assert_approx_eq;
-use burn::tensor::{Distribution, Tensor, TensorPrimitive};
+use burn::tensor::{Distribution, ElementConversion, Int, Tensor, TensorPrimitive, s};
 use burn_wgpu::WgpuDevice;
 use glam::Vec3;
 
+/// Camera at the origin looking down +Z, used by most tests.
+fn test_camera() -> Camera {
+    Camera::new(
+        Vec3::ZERO,
+        glam::Quat::IDENTITY,
+        0.5,
+        0.5,
+        glam::vec2(0.5, 0.5),
+    )
+}
+
+/// Deterministic pseudo random value in `[0, 1)` for element `i` of attribute `k`.
+fn hash(i: usize, k: usize) -> f32 {
+    ((i * 7919 + k * 104_729) % 1000) as f32 / 1000.0
+}
+
+/// Unrotated white splats at `means`, all with the same log scale and raw opacity.
+fn white_splats(means: &[[f32; 3]], log_scale: f32, raw_opacity: f32) -> Splats<MainBackend> {
+    let num_points = means.len();
+    Splats::from_raw(
+        means.concat(),
+        [1.0, 0.0, 0.0, 0.0].repeat(num_points),
+        vec![log_scale; num_points * 3],
+        vec![1.0; num_points * 3],
+        vec![raw_opacity; num_points],
+        SplatRenderMode::Default,
+        &WgpuDevice::DefaultDevice,
+    )
+}
+
+/// A deterministic spread of `num_points` splats with centers in the box `min..min + size`,
+/// and varying scales, colors and opacities.
+fn scattered_splats(num_points: usize, min: Vec3, size: Vec3) -> Splats<MainBackend> {
+    Splats::from_raw(
+        (0..num_points)
+            .flat_map(|i| (min + size * Vec3::new(hash(i, 0), hash(i, 1), hash(i, 2))).to_array())
+            .collect(),
+        [1.0, 0.0, 0.0, 0.0].repeat(num_points),
+        (0..num_points * 3).map(|i| -2.5 + hash(i, 3)).collect(),
+        (0..num_points * 3).map(|i| hash(i, 4) - 0.5).collect(),
+        (0..num_points).map(|i| hash(i, 5) * 4.0 - 1.0).collect(),
+        SplatRenderMode::Default,
+        &WgpuDevice::DefaultDevice,
+    )
+}
+
+/// Render `splats` with the forward op directly, as the trainer does, giving the `f32` image
+/// and the aux without validating them.
+fn render_test_splats(
+    cam: &Camera,
+    img_size: glam::UVec2,
+    splats: &Splats<MainBackend>,
+    background: Vec3,
+    options: RenderOptions,
+) -> (Tensor<MainBackend, 3>, RenderAux<MainBackend>) {
+    let (img, aux) = <MainBackend as SplatForward<MainBackend>>::render_splats(
+        cam,
+        img_size,
+        splats.means.val().into_primitive().tensor(),
+        splats.log_scales.val().into_primitive().tensor(),
+        splats.rotations.val().into_primitive().tensor(),
+        splats.sh_coeffs.val().into_primitive().tensor(),
+        splats.raw_opacities.val().into_primitive().tensor(),
+        splats.render_mode,
+        background,
+        true,
+        options,
+    );
+    (Tensor::from_primitive(TensorPrimitive::Float(img)), aux)
+}
+
+#[test]
+fn validate_output_reports_first_invalid_pixel() {
+    let device = WgpuDevice::DefaultDevice;
+    let mut values = vec![0.0f32; 4 * 3 * 4];
+    // Pixel (x 2, y 1), channel 3.
+    values[(3 + 2) * 4 + 3] = f32::NAN;
+    values[(2 * 3 + 1) * 4] = f32::INFINITY;
+    let output =
+        Tensor::<MainBackend, 1>::from_floats(values.as_slice(), &device).reshape([4, 3, 4]);
+    assert_eq!(
+        validate_output(&output),
+        Err(SplatRenderError::NanInOutput {
+            channel: 3,
+            pixel: [2, 1],
+        })
+    );
+}
+
 #[test]
 fn renders_at_all() {
     // Check if rendering doesn't hard crash or anything.
@@ -38,10 +142,12 @@ fn renders_at_all() {
         SplatRenderMode::Default,
         Vec3::ZERO,
         true,
+        RenderOptions::default(),
     );
-    aux.validate_values();
+    assert_eq!(aux.validate_values(), Ok(()));
 
     let output: Tensor<MainBackend, 3> = Tensor::from_primitive(TensorPrimitive::Float(output));
+    assert_eq!(validate_output(&output), Ok(()));
     let rgb = output.clone().slice([0..32, 0..32, 0..3]);
     let alpha = output.slice([0..32, 0..32, 3..4]);
     let rgb_mean = rgb.mean().to_data().as_slice::<f32>().expect("Wrong type")[0];
@@ -108,6 +214,1864 @@ fn renders_many_splats() {
         SplatRenderMode::Default,
         Vec3::ZERO,
         true,
+        RenderOptions::default(),
+    );
+    assert_eq!(aux.validate_values(), Ok(()));
+}
+
+#[test]
+fn renders_depth() {
+    // A single opaque splat straight in front of the camera should
+    // have a depth equal to its distance.
+    let splats = white_splats(&[[0.0, 0.0, 5.0]], -1.0, 5.0);
+    let options = RenderOptions {
+        depth: true,
+        ..Default::default()
+    };
+    let (output, aux) = render_test_splats(
+        &test_camera(),
+        glam::uvec2(32, 32),
+        &splats,
+        Vec3::ZERO,
+        options,
+    );
+    assert_eq!(aux.validate_values(), Ok(()));
+
+    let depth = aux.depth().expect("Depth was requested");
+    assert_eq!(depth.dims(), [32, 32, 1]);
+
+    let alpha = output.slice([16..17, 16..17, 3..4]).into_scalar();
+    let corner = depth.clone().slice([0..1, 0..1, 0..1]).into_scalar();
+    let depth = depth.slice([16..17, 16..17, 0..1]).into_scalar();
+    assert!(alpha > 0.5, "Center pixel should be covered");
+    assert_approx_eq!(depth / alpha, 5.0, 1e-3);
+    // Uncovered pixels get the sentinel.
+    assert!(corner.is_infinite(), "Uncovered pixel has depth {corner}");
+
+    let mut nan_aux = aux;
+    let nan_depth =
+        Tensor::<MainBackend, 3>::full([32, 32, 1], f32::NAN, &WgpuDevice::DefaultDevice);
+    nan_aux.depth = Some(nan_depth.into_primitive().tensor());
+    assert_eq!(
+        nan_aux.validate_values(),
+        Err(SplatRenderError::NanInBuffer {
+            buffer: "depth",
+            index: 0,
+        })
+    );
+}
+
+#[test]
+fn validate_values_reports_invalid_global_id() {
+    let splats = white_splats(&[[0.0, 0.0, 5.0]], -1.0, 5.0);
+    let (_, aux) = render_test_splats(
+        &test_camera(),
+        glam::uvec2(32, 32),
+        &splats,
+        Vec3::ZERO,
+        RenderOptions::default(),
+    );
+    assert_eq!(aux.validate_values(), Ok(()));
+
+    let mut bad_aux = aux;
+    let bad_ids = Tensor::<MainBackend, 1, Int>::full([1], 7, &WgpuDevice::DefaultDevice);
+    bad_aux.global_from_compact_gid = bad_ids.into_primitive();
+    assert_eq!(
+        bad_aux.validate_values(),
+        Err(SplatRenderError::InvalidGlobalId {
+            id: 7,
+            num_points: 1
+        })
+    );
+}
+
+#[test]
+fn records_hit_counts() {
+    // Low opacity so no pixel terminates early.
+    let splats = white_splats(&[[0.0, 0.0, 5.0]; 4], -1.0, -4.0);
+    let options = RenderOptions {
+        record_hit_counts: true,
+        ..Default::default()
+    };
+    let (_, aux) = render_test_splats(
+        &test_camera(),
+        glam::uvec2(32, 32),
+        &splats,
+        Vec3::ZERO,
+        options,
+    );
+    assert_eq!(aux.validate_values(), Ok(()));
+
+    let hit_counts = aux.tile_hit_counts().expect("Hit counts were requested");
+    assert_eq!(hit_counts.dims(), [32, 32]);
+    let hit_counts = hit_counts
+        .into_data()
+        .into_vec::<u32>()
+        .expect("Failed to read hit counts");
+    // Every splat overlaps the center.
+    assert_eq!(hit_counts[16 * 32 + 16], 4);
+}
+
+#[test]
+fn records_screen_radii() {
+    let cam = test_camera();
+    let img_size = glam::uvec2(64, 64);
+    // One splat in view, one behind the camera and one twice as far away.
+    let splats = white_splats(
+        &[[0.0, 0.0, 5.0], [0.0, 0.0, -5.0], [0.0, 0.0, 10.0]],
+        -2.0,
+        2.0,
+    );
+    let render = |record_screen_radii: bool| {
+        let options = RenderOptions {
+            record_screen_radii,
+            ..Default::default()
+        };
+        render_test_splats(&cam, img_size, &splats, Vec3::ZERO, options).1
+    };
+
+    assert!(render(false).screen_radii().is_none());
+
+    let aux = render(true);
+    assert_eq!(aux.validate_values(), Ok(()));
+    let radii = aux
+        .screen_radii()
+        .expect("Screen radii were requested")
+        .into_data()
+        .into_vec::<f32>()
+        .expect("Wrong type");
+    // 3 sigma of the projected scale, slightly widened by the 2D blur.
+    let focal = cam.focal(img_size).x;
+    let expected = 3.0 * focal * (-2.0f32).exp() / 5.0;
+    assert!(
+        radii[0] > expected && radii[0] < expected * 1.05,
+        "{radii:?}"
+    );
+    assert_eq!(radii[1], 0.0);
+    assert!(
+        radii[2] > radii[0] * 0.45 && radii[2] < radii[0] * 0.55,
+        "{radii:?}"
+    );
+}
+
+#[test]
+fn records_tile_coverage() {
+    let img_size = glam::uvec2(64, 40);
+    let splats = white_splats(
+        &[[0.0, 0.0, 5.0], [0.5, 0.2, 5.0], [-0.6, -0.1, 4.0]],
+        -2.0,
+        0.0,
+    );
+    let render = |record_tile_coverage: bool| {
+        let options = RenderOptions {
+            record_tile_coverage,
+            ..Default::default()
+        };
+        render_test_splats(&test_camera(), img_size, &splats, Vec3::ZERO, options).1
+    };
+
+    assert!(render(false).tile_coverage().is_none());
+
+    let aux = render(true);
+    assert_eq!(aux.validate_values(), Ok(()));
+    let coverage = aux.tile_coverage().expect("Tile coverage was requested");
+    assert_eq!(
+        coverage.dims(),
+        [
+            img_size.y.div_ceil(TILE_WIDTH) as usize,
+            img_size.x.div_ceil(TILE_WIDTH) as usize
+        ]
+    );
+    // Every intersection belongs to exactly one tile.
+    let total = coverage.sum().into_scalar().elem::<i32>();
+    assert!(total > 0);
+    assert_eq!(total, aux.num_intersections().into_scalar().elem::<i32>());
+}
+
+#[test]
+fn renders_orthographic() {
+    // With an orthographic camera a splat lands on the same pixel at any distance.
+    let cam = test_camera().with_projection(ProjectionMode::Orthographic {
+        width: 4.0,
+        height: 4.0,
+    });
+
+    let mut footprints = vec![];
+    for z in [2.0, 50.0] {
+        let splats = white_splats(&[[1.0, 0.0, z]], -2.0, 5.0);
+        let (output, aux) = render_test_splats(
+            &cam,
+            glam::uvec2(32, 32),
+            &splats,
+            Vec3::ZERO,
+            RenderOptions::default(),
+        );
+        assert_eq!(aux.validate_values(), Ok(()));
+
+        // x = 1 world unit is 8 pixels right of the center.
+        let alpha = output.clone().slice([16..17, 24..25, 3..4]).into_scalar();
+        assert!(alpha > 0.5, "Splat at depth {z} not covering its pixel");
+        footprints.push(
+            output
+                .slice(s![16..17, .., 3..4])
+                .into_data()
+                .into_vec::<f32>()
+                .unwrap(),
+        );
+    }
+
+    // Identical splats also have the same screen space size at any depth.
+    for (near, far) in footprints[0].iter().zip(&footprints[1]) {
+        assert_approx_eq!(*near, *far, 1e-4);
+    }
+}
+
+#[test]
+fn renders_equirectangular() {
+    // Splats to the right and left of the camera land at 3/4 and 1/4 of the panorama.
+    let cam = test_camera().with_projection(ProjectionMode::Equirectangular);
+    let splats = white_splats(&[[5.0, 0.0, 0.0], [-5.0, 0.0, 0.0]], -1.0, 5.0);
+    let (output, aux) = render_test_splats(
+        &cam,
+        glam::uvec2(64, 32),
+        &splats,
+        Vec3::ZERO,
+        RenderOptions::default(),
+    );
+    assert_eq!(aux.validate_values(), Ok(()));
+
+    for x in [16, 48] {
+        let alpha = output.clone().slice([16..17, x..x + 1, 3..4]).into_scalar();
+        assert!(alpha > 0.5, "Expected a splat at pixel {x}, 16");
+    }
+}
+
+#[test]
+fn renders_fisheye() {
+    // A 240° fisheye puts a splat 90° to the right 3/8 of the image width from the center.
+    let cam = test_camera().with_projection(ProjectionMode::Fisheye {
+        max_fov_rad: 240f32.to_radians(),
+        model: FisheyeModel::Equidistant,
+    });
+    let render = |means: &[[f32; 3]], log_scale: f32| {
+        let splats = white_splats(means, log_scale, 5.0);
+        let (output, aux) = render_test_splats(
+            &cam,
+            glam::uvec2(64, 64),
+            &splats,
+            Vec3::ZERO,
+            RenderOptions::default(),
+        );
+        assert_eq!(aux.validate_values(), Ok(()));
+        assert_eq!(validate_output(&output), Ok(()));
+        output
+    };
+    let alpha_at = |output: &Tensor<MainBackend, 3>, x: usize, y: usize| {
+        output
+            .clone()
+            .slice([y..y + 1, x..x + 1, 3..4])
+            .into_scalar()
+    };
+
+    // In front, to the right and behind the camera. The last one is outside the view cone.
+    let output = render(&[[0.0, 0.0, 5.0], [5.0, 0.0, 0.0], [0.0, 0.0, -5.0]], -2.0);
+    assert!(alpha_at(&output, 32, 32) > 0.5, "Expected a splat in front");
+    assert!(alpha_at(&output, 55, 31) > 0.5, "Expected a splat at 90°");
+    assert!(alpha_at(&output, 8, 32) < 1e-3, "Expected nothing at -90°");
+
+    // A big splat covering the whole view still leaves the corners outside the cone empty.
+    let output = render(&[[0.0, 0.0, 5.0]], 3.0);
+    assert!(alpha_at(&output, 32, 32) > 0.5);
+    assert_eq!(alpha_at(&output, 0, 0), 0.0);
+    assert_eq!(alpha_at(&output, 63, 63), 0.0);
+}
+
+#[test]
+fn renders_equisolid_fisheye() {
+    // A 190° equisolid fisheye sees splats slightly behind the camera, at r = 2f sin(θ / 2).
+    let max_fov_rad = 190f32.to_radians();
+    let model = FisheyeModel::Equisolid;
+    let cam = test_camera().with_projection(ProjectionMode::Fisheye { max_fov_rad, model });
+    let img_size = glam::uvec2(64, 64);
+    let focal = cam.focal(img_size).x;
+
+    // 60° and 93° to the right, and 100° to the left which is outside the view cone.
+    let angles = [60f32, 93.0, -100.0].map(f32::to_radians);
+    let splats = white_splats(
+        &angles.map(|a| [5.0 * a.sin(), 0.0, 5.0 * a.cos()]),
+        -2.5,
+        5.0,
+    );
+    let (output, aux) = render_test_splats(
+        &cam,
+        img_size,
+        &splats,
+        Vec3::ZERO,
+        RenderOptions::default(),
+    );
+    assert_eq!(aux.validate_values(), Ok(()));
+    assert_eq!(validate_output(&output), Ok(()));
+    let alpha_at = |x: f32| {
+        let x = x as usize;
+        output.clone().slice([32..33, x..x + 1, 3..4]).into_scalar()
+    };
+
+    for angle in &angles[..2] {
+        let x = 32.0 + focal * model.radius(*angle);
+        assert!(alpha_at(x) > 0.5, "Expected a splat at x = {x}");
+    }
+    // The splat at -100° would land left of the image circle, and isn't projected at all.
+    assert!(alpha_at(1.0) < 1e-3, "Expected nothing at the left edge");
+    assert_eq!(aux.num_visible().into_scalar().elem::<i32>(), 2);
+}
+
+#[test]
+fn culls_offscreen_splats() {
+    // One splat in view, one far off to the side and one behind the camera.
+    let splats = white_splats(
+        &[[0.0, 0.0, 5.0], [50.0, 0.0, 5.0], [0.0, 0.0, -5.0]],
+        -2.0,
+        4.0,
+    );
+    let (_, aux) = render_test_splats(
+        &test_camera(),
+        glam::uvec2(32, 32),
+        &splats,
+        Vec3::ZERO,
+        RenderOptions::default(),
+    );
+    assert_eq!(aux.validate_values(), Ok(()));
+
+    let num_culled = aux.num_culled().into_scalar().elem::<i32>();
+    let num_visible = aux.num_visible().into_scalar().elem::<i32>();
+    assert_eq!(num_culled, 2);
+    assert_eq!(num_visible, 1);
+}
+
+#[test]
+fn culls_below_min_opacity() {
+    let splats = Splats::<MainBackend>::from_raw(
+        [0.0, 0.0, 5.0].repeat(4),
+        [1.0, 0.0, 0.0, 0.0].repeat(4),
+        vec![-2.0; 12],
+        vec![1.0; 12],
+        // Opacities of roughly 0.12, 0.38, 0.62 and 0.88.
+        vec![-2.0, -0.5, 0.5, 2.0],
+        SplatRenderMode::Default,
+        &WgpuDevice::DefaultDevice,
+    );
+
+    let num_visible = |min_opacity: f32| {
+        let options = RenderOptions {
+            min_opacity,
+            ..Default::default()
+        };
+        let (_, aux) = render_test_splats(
+            &test_camera(),
+            glam::uvec2(32, 32),
+            &splats,
+            Vec3::ZERO,
+            options,
+        );
+        assert_eq!(aux.validate_values(), Ok(()));
+        aux.num_visible().into_scalar().elem::<i32>()
+    };
+
+    assert_eq!(num_visible(0.0), 4);
+    assert_eq!(num_visible(0.5), 2);
+    assert_eq!(num_visible(0.9), 0);
+}
+
+#[test]
+fn culls_before_near_plane() {
+    let splats = white_splats(
+        &[[0.0, 0.0, 0.5], [0.0, 0.0, 2.0], [0.0, 0.0, 5.0]],
+        -4.0,
+        2.0,
+    );
+
+    let num_visible = |z_near: f32| {
+        let options = RenderOptions {
+            z_near,
+            ..Default::default()
+        };
+        let (_, aux) = render_test_splats(
+            &test_camera(),
+            glam::uvec2(32, 32),
+            &splats,
+            Vec3::ZERO,
+            options,
+        );
+        assert_eq!(aux.validate_values(), Ok(()));
+        aux.num_visible().into_scalar().elem::<i32>()
+    };
+
+    assert_eq!(num_visible(RenderOptions::default().z_near), 3);
+    assert_eq!(num_visible(1.0), 2);
+    assert_eq!(num_visible(3.0), 1);
+    assert_eq!(num_visible(10.0), 0);
+}
+
+#[test]
+fn premultiplied_alpha_ignores_background() {
+    let splats = Splats::<MainBackend>::from_raw(
+        vec![0.0, 0.0, 5.0],
+        vec![1.0, 0.0, 0.0, 0.0],
+        vec![-1.0; 3],
+        vec![1.0, 0.5, 0.0],
+        vec![0.0],
+        SplatRenderMode::Default,
+        &WgpuDevice::DefaultDevice,
+    );
+    let render = |background: Vec3, premultiplied_alpha: bool| {
+        let options = RenderOptions {
+            premultiplied_alpha,
+            ..Default::default()
+        };
+        render_test_splats(
+            &test_camera(),
+            glam::uvec2(32, 32),
+            &splats,
+            background,
+            options,
+        )
+        .0
+    };
+
+    let premultiplied = render(Vec3::ONE, true);
+    let straight = render(Vec3::ONE, false);
+    let on_black = render(Vec3::ZERO, false);
+    let diff = |a: Tensor<MainBackend, 3>, b: Tensor<MainBackend, 3>| {
+        (a - b).abs().max().into_scalar().elem::<f32>()
+    };
+    assert!(diff(premultiplied.clone(), on_black) < 1e-6);
+    assert!(diff(premultiplied.clone(), straight) > 0.1);
+
+    // Color never exceeds alpha.
+    let data = premultiplied.into_data().into_vec::<f32>().unwrap();
+    for px in data.chunks_exact(4) {
+        assert!(px[0] <= px[3] + 1e-6, "{px:?}");
+    }
+}
+
+#[test]
+fn renders_normals() {
+    // A single disc facing the camera, flat along Z.
+    let splats = Splats::<MainBackend>::from_raw(
+        vec![0.0, 0.0, 5.0],
+        vec![1.0, 0.0, 0.0, 0.0],
+        vec![-3.0, -3.0, -8.0],
+        vec![1.0; 3],
+        vec![10.0],
+        SplatRenderMode::NormalsWorldSpace,
+        &WgpuDevice::DefaultDevice,
+    );
+    let (img, aux) = render_test_splats(
+        &test_camera(),
+        glam::uvec2(32, 32),
+        &splats,
+        Vec3::ONE,
+        RenderOptions::default(),
+    );
+    assert_eq!(aux.validate_values(), Ok(()));
+
+    assert_eq!(img.dims(), [32, 32, 3]);
+    let data = img.into_data().into_vec::<f32>().expect("Wrong type");
+
+    // The normal points back towards the camera.
+    let center = &data[(16 * 32 + 16) * 3..][..3];
+    assert_approx_eq!(center[0], 0.0, 1e-5);
+    assert_approx_eq!(center[1], 0.0, 1e-5);
+    assert_approx_eq!(center[2], -0.999, 1e-2);
+
+    // The background isn't composited in.
+    assert!(data[..3].iter().all(|v| v.abs() < 1e-6));
+
+    // A degenerate splat, far smaller than a pixel, is still drawn by the 2D blur but has no
+    // normal.
+    let mut splats = white_splats(&[[0.0, 0.0, 5.0]], -40.0, 10.0);
+    splats.render_mode = SplatRenderMode::NormalsWorldSpace;
+    let (img, aux) = render_test_splats(
+        &test_camera(),
+        glam::uvec2(32, 32),
+        &splats,
+        Vec3::ONE,
+        RenderOptions::default(),
+    );
+    assert_eq!(aux.num_visible().into_scalar().elem::<i32>(), 1);
+    let data = img.into_data().into_vec::<f32>().expect("Wrong type");
+    let center = &data[(16 * 32 + 16) * 3..][..3];
+    assert!(center.iter().all(|v| *v == 0.0), "{center:?}");
+}
+
+#[test]
+fn renders_camera_space_normals() {
+    // Looking down +X.
+    let cam = Camera::new(
+        glam::vec3(0.0, 0.0, 0.0),
+        glam::Quat::from_rotation_y(std::f32::consts::FRAC_PI_2),
+        0.5,
+        0.5,
+        glam::vec2(0.5, 0.5),
+    );
+    let img_size = glam::uvec2(32, 32);
+    let device = WgpuDevice::DefaultDevice;
+    // A disc flat along X, facing the camera.
+    let splats = Splats::<MainBackend>::from_raw(
+        vec![5.0, 0.0, 0.0],
+        glam::Quat::IDENTITY.to_array().to_vec(),
+        vec![-8.0, -3.0, -3.0],
+        vec![1.0, 1.0, 1.0],
+        vec![10.0],
+        SplatRenderMode::NormalsWorldSpace,
+        &device,
+    );
+    let center_normal = |mode: SplatRenderMode| {
+        let mut splats = splats.clone();
+        splats.render_mode = mode;
+        let (img, _) = render_splats(&splats, &cam, img_size, Vec3::ZERO, None);
+        assert_eq!(img.dims(), [32, 32, 3]);
+        let data = img.into_data().into_vec::<f32>().expect("Wrong type");
+        Vec3::from_slice(&data[(16 * 32 + 16) * 3..][..3])
+    };
+
+    let world = center_normal(SplatRenderMode::NormalsWorldSpace);
+    assert!(world.abs_diff_eq(Vec3::NEG_X, 1e-2), "{world}");
+    let camera = center_normal(SplatRenderMode::NormalsCameraSpace);
+    assert!(camera.abs_diff_eq(Vec3::NEG_Z, 1e-2), "{camera}");
+}
+
+#[test]
+fn renders_world_positions() {
+    let cam = Camera::new(
+        glam::vec3(0.0, 0.0, -5.0),
+        glam::Quat::IDENTITY,
+        0.5,
+        0.5,
+        glam::vec2(0.5, 0.5),
+    );
+    let img_size = glam::uvec2(32, 32);
+    let device = WgpuDevice::DefaultDevice;
+    let mean = glam::vec3(0.3, -0.2, 0.5);
+    let splats = Splats::<MainBackend>::from_raw(
+        mean.to_array().to_vec(),
+        glam::Quat::IDENTITY.to_array().to_vec(),
+        vec![-1.0, -1.0, -1.0],
+        vec![1.0, 1.0, 1.0],
+        vec![0.0],
+        SplatRenderMode::WorldPosition,
+        &device,
+    );
+    let (img, _) = render_splats(&splats, &cam, img_size, Vec3::ONE, None);
+    assert_eq!(img.dims(), [32, 32, 3]);
+    let data = img.into_data().into_vec::<f32>().expect("Wrong type");
+
+    // The positions lie on the pixel rays at the depth of the single splat, not at its center,
+    // which is off the rays of these pixels.
+    let focal = cam.focal(img_size);
+    let center = cam.center(img_size);
+    for pixel in [(16, 16), (20, 15), (12, 19)] {
+        let position = Vec3::from_slice(&data[(pixel.1 * 32 + pixel.0) * 3..][..3]);
+        let depth = mean.z - cam.position.z;
+        let plane = (glam::vec2(pixel.0 as f32, pixel.1 as f32) + 0.5 - center) / focal;
+        let expected = cam.position + (plane * depth).extend(depth);
+        assert!(
+            position.abs_diff_eq(expected, 1e-3),
+            "{position} {expected}"
+        );
+        assert!(!position.abs_diff_eq(mean, 1e-2), "{position}");
+    }
+    // Empty pixels are zero, without the background.
+    assert!(data[..3].iter().all(|v| *v == 0.0));
+}
+
+#[test]
+fn alpha_only_matches_color_alpha() {
+    let cam = Camera::new(
+        glam::vec3(0.0, 0.0, -3.0),
+        glam::Quat::IDENTITY,
+        0.8,
+        0.8,
+        glam::vec2(0.5, 0.5),
+    );
+    let device = WgpuDevice::DefaultDevice;
+    let num_points = 64;
+    // Degree 1 SH, which the alpha render should never need.
+    let splats = Splats::<MainBackend>::from_tensor_data(
+        Tensor::random([num_points, 3], Distribution::Uniform(-1.0, 1.0), &device),
+        Tensor::from_floats([[1.0, 0.0, 0.0, 0.0]], &device).repeat_dim(0, num_points),
+        Tensor::random([num_points, 3], Distribution::Uniform(-3.0, -1.5), &device),
+        Tensor::random(
+            [num_points, 4, 3],
+            Distribution::Uniform(-0.5, 0.5),
+            &device,
+        ),
+        Tensor::random([num_points], Distribution::Uniform(-2.0, 2.0), &device),
+        SplatRenderMode::Default,
+    );
+
+    let render = |mode: SplatRenderMode| {
+        let mut splats = splats.clone();
+        splats.render_mode = mode;
+        let background = Vec3::new(0.1, 0.2, 0.3);
+        let img_size = glam::uvec2(48, 32);
+        render_test_splats(
+            &cam,
+            img_size,
+            &splats,
+            background,
+            RenderOptions::default(),
+        )
+        .0
+    };
+
+    let alpha = render(SplatRenderMode::AlphaOnly);
+    assert_eq!(alpha.dims(), [32, 48, 1]);
+    let color_alpha = render(SplatRenderMode::Default).slice(s![.., .., 3..4]);
+    let max_diff = (alpha.clone() - color_alpha).abs().max().into_scalar();
+    assert!(max_diff < 1e-6, "Alpha differs by {max_diff}");
+    assert!(alpha.max().into_scalar() > 0.1);
+}
+
+#[test]
+fn limits_active_sh_degree() {
+    let device = WgpuDevice::DefaultDevice;
+
+    let render = |sh_coeffs: Tensor<MainBackend, 3>, sh_degree_active: u8| {
+        let splats = Splats::from_tensor_data(
+            Tensor::from_floats([[0.0, 0.0, 5.0]], &device),
+            Tensor::from_floats([[1.0, 0.0, 0.0, 0.0]], &device),
+            Tensor::from_floats([[-1.0, -1.0, -1.0]], &device),
+            sh_coeffs,
+            Tensor::from_floats([10.0], &device),
+            SplatRenderMode::Default,
+        );
+        let options = RenderOptions {
+            sh_degree_active,
+            ..Default::default()
+        };
+        let (img, aux) = render_test_splats(
+            &test_camera(),
+            glam::uvec2(32, 32),
+            &splats,
+            Vec3::ZERO,
+            options,
+        );
+        assert_eq!(aux.validate_values(), Ok(()));
+        img.into_data().into_vec::<f32>().expect("Wrong type")
+    };
+
+    // DC only, and the same DC with a strong first band.
+    let dc = Tensor::<MainBackend, 3>::ones([1, 1, 3], &device) * 0.2;
+    let bands = Tensor::cat(
+        vec![
+            dc.clone(),
+            Tensor::<MainBackend, 3>::ones([1, 3, 3], &device) * 0.5,
+        ],
+        1,
+    );
+
+    let dc_img = render(dc, u8::MAX);
+    let degree_0 = render(bands.clone(), 0);
+    let degree_1 = render(bands.clone(), 1);
+    let clamped = render(bands, 3);
+
+    let max_diff = |a: &[f32], b: &[f32]| {
+        a.iter()
+            .zip(b)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f32::max)
+    };
+    assert!(max_diff(&dc_img, &degree_0) < 1e-6);
+    assert!(max_diff(&dc_img, &degree_1) > 1e-2);
+    assert!(max_diff(&degree_1, &clamped) < 1e-6);
+}
+
+#[test]
+fn tiled_render_matches_full_render() {
+    let cam = Camera::new(
+        glam::vec3(0.0, 0.0, -4.0),
+        glam::Quat::IDENTITY,
+        0.9,
+        0.9,
+        glam::vec2(0.45, 0.55),
+    );
+    let img_size = glam::uvec2(256, 256);
+    // Many of the splats cross tile borders.
+    let splats = scattered_splats(200, glam::vec3(-2.0, -2.0, 0.0), glam::vec3(4.0, 4.0, 2.0));
+
+    let (full, _) = render_test_splats(
+        &cam,
+        img_size,
+        &splats,
+        Vec3::ONE * 0.2,
+        RenderOptions::default(),
+    );
+    let tiled = render_splats_tiled(
+        &splats,
+        &cam,
+        img_size,
+        glam::uvec2(64, 64),
+        Vec3::ONE * 0.2,
+        None,
+    );
+    assert_eq!(tiled.dims(), full.dims());
+
+    let max_diff = (full - tiled).abs().max().into_scalar();
+    assert!(max_diff < 1e-4, "Tiled render differs by {max_diff}");
+}
+
+#[test]
+fn roi_render_matches_crop() {
+    let cam = Camera::new(
+        glam::vec3(0.0, 0.0, -4.0),
+        glam::Quat::IDENTITY,
+        0.9,
+        0.7,
+        glam::vec2(0.5, 0.5),
+    );
+    let img_size = glam::uvec2(200, 150);
+    let splats = scattered_splats(100, glam::vec3(-2.0, -1.5, 0.0), glam::vec3(4.0, 3.0, 2.0));
+
+    // An offset and size that don't line up with the tiles.
+    let (offset, size) = (glam::uvec2(37, 21), glam::uvec2(101, 59));
+    let roi_cam = cam.sub_view(img_size, offset, size).unwrap();
+    let (full, _) = render_splats(&splats, &cam, img_size, Vec3::ONE * 0.2, None);
+    let (roi, _) = render_splats(&splats, &roi_cam, size, Vec3::ONE * 0.2, None);
+    assert_eq!(roi.dims()[..2], [size.y as usize, size.x as usize]);
+
+    let (x, y) = (offset.x as usize, offset.y as usize);
+    let crop = full.slice(s![y..y + size.y as usize, x..x + size.x as usize, ..]);
+    let max_diff = (crop - roi).abs().max().into_scalar();
+    assert!(max_diff < 1e-4, "ROI render differs by {max_diff}");
+}
+
+#[test]
+fn stereo_render_matches_separate_eyes() {
+    let img_size = glam::uvec2(128, 96);
+
+    // Overlapping splats at different depths, some of them only visible to one eye.
+    let splats = scattered_splats(200, glam::vec3(-2.5, -1.5, 0.0), glam::vec3(5.0, 3.0, 3.0));
+    let options = RenderOptions {
+        depth: true,
+        ..Default::default()
+    };
+    let render =
+        |cam: &Camera| render_test_splats(cam, img_size, &splats, Vec3::ONE * 0.2, options);
+    let render_stereo = |[left, right]: &[Camera; 2]| {
+        <MainBackend as SplatForward<MainBackend>>::render_splats_stereo(
+            left,
+            right,
+            img_size,
+            splats.means.val().into_primitive().tensor(),
+            splats.log_scales.val().into_primitive().tensor(),
+            splats.rotations.val().into_primitive().tensor(),
+            splats.sh_coeffs.val().into_primitive().tensor(),
+            splats.raw_opacities.val().into_primitive().tensor(),
+            SplatRenderMode::Default,
+            Vec3::ONE * 0.2,
+            true,
+            options,
+        )
+    };
+
+    let cam = Camera::new(
+        glam::vec3(0.1, 0.0, -3.0),
+        glam::Quat::from_rotation_y(0.1),
+        0.9,
+        0.7,
+        glam::vec2(0.45, 0.5),
+    );
+    // Parallel eyes share the depth sort, toed in eyes are rendered separately.
+    for eyes in [cam.stereo_eyes(0.3, None), cam.stereo_eyes(0.3, Some(2.0))] {
+        for (eye, (img, aux)) in eyes.iter().zip(render_stereo(&eyes)) {
+            let (expected_img, expected_aux) = render(eye);
+            let img = Tensor::<MainBackend, 3>::from_primitive(TensorPrimitive::Float(img));
+            assert!(expected_img.clone().max().into_scalar() > 0.3);
+            let max_diff = (img - expected_img).abs().max().into_scalar();
+            assert!(max_diff < 1e-4, "Stereo render differs by {max_diff}");
+
+            let depth = aux.depth().expect("Depth was requested");
+            let expected_depth = expected_aux.depth().expect("Depth was requested");
+            let uncovered = expected_depth.clone().is_inf();
+            let max_diff = (depth - expected_depth)
+                .abs()
+                .mask_fill(uncovered, 0.0)
+                .max()
+                .into_scalar();
+            assert!(max_diff < 1e-3, "Stereo depth differs by {max_diff}");
+        }
+    }
+}
+
+#[test]
+fn f16_splats_match_f32() {
+    let device = WgpuDevice::DefaultDevice;
+    let cam = Camera::new(
+        glam::vec3(0.0, 0.0, -4.0),
+        glam::Quat::IDENTITY,
+        0.9,
+        0.9,
+        glam::vec2(0.5, 0.5),
+    );
+    let img_size = glam::uvec2(128, 128);
+
+    // An odd count, so the last splat's halves don't fill a whole word.
+    let num_points = 201;
+    let means = (0..num_points * 3)
+        .map(|i| hash(i, 0) * 4.0 - 2.0)
+        .collect();
+    let rotations = (0..num_points * 4).map(|i| hash(i, 1) - 0.5).collect();
+    let log_scales = (0..num_points * 3).map(|i| -2.5 + hash(i, 2)).collect();
+    // Degree 1, so the coefficients of a splat don't start on a word boundary either.
+    let sh_coeffs = (0..num_points * 4 * 3).map(|i| hash(i, 3) - 0.5).collect();
+    let raw_opacities = (0..num_points).map(|i| hash(i, 4) * 4.0 - 1.0).collect();
+    let splats = Splats::<MainBackend>::from_raw(
+        means,
+        rotations,
+        log_scales,
+        sh_coeffs,
+        raw_opacities,
+        SplatRenderMode::Default,
+        &device,
+    );
+    let f32_bytes = splats.size_bytes();
+    let (full, _) = render_splats(&splats, &cam, img_size, Vec3::ZERO, None);
+
+    let splats = splats.with_precision(SplatPrecision::F16);
+    if splats.precision() != SplatPrecision::F16 {
+        // The device doesn't support f16.
+        return;
+    }
+    // Everything but the opacities is halved.
+    assert_eq!(splats.size_bytes(), (f32_bytes + num_points * 4) / 2);
+    let (half, _) = render_splats(&splats, &cam, img_size, Vec3::ZERO, None);
+
+    let max_diff = (full - half).abs().max().into_scalar();
+    assert!(max_diff < 0.05, "F16 render differs by {max_diff}");
+}
+
+#[test]
+fn composites_over_background_image() {
+    let device = WgpuDevice::DefaultDevice;
+    let cam = Camera::new(
+        glam::vec3(0.0, 0.0, -4.0),
+        glam::Quat::IDENTITY,
+        0.9,
+        0.9,
+        glam::vec2(0.5, 0.5),
+    );
+    let img_size = glam::uvec2(64, 48);
+    let splats = scattered_splats(20, Vec3::splat(-1.0), Vec3::splat(2.0));
+
+    // A constant background image matches a solid background color.
+    let background = Vec3::new(0.2, 0.6, 0.9);
+    let (solid, _) = render_test_splats(
+        &cam,
+        img_size,
+        &splats,
+        background,
+        RenderOptions::default(),
+    );
+    let image = Tensor::<MainBackend, 1>::from_floats(background.to_array(), &device)
+        .reshape([1, 1, 3])
+        .repeat_dim(0, 48)
+        .repeat_dim(1, 64);
+    let (over, _) = render_splats_over_image(&splats, &cam, img_size, image, None).unwrap();
+    let max_diff = (solid - over).abs().max().into_scalar();
+    assert!(max_diff < 1e-5, "Composite differs by {max_diff}");
+
+    let wrong_size = Tensor::<MainBackend, 3>::zeros([64, 48, 3], &device);
+    let err = render_splats_over_image(&splats, &cam, img_size, wrong_size, None).unwrap_err();
+    assert_eq!(err.actual, [64, 48, 3]);
+}
+
+#[test]
+fn renders_feature_vectors() {
+    let device = WgpuDevice::DefaultDevice;
+    let cam = Camera::new(
+        glam::vec3(0.0, 0.0, -4.0),
+        glam::Quat::IDENTITY,
+        0.9,
+        0.9,
+        glam::vec2(0.5, 0.5),
+    );
+    let img_size = glam::uvec2(64, 48);
+    let num_points = 20;
+    let splats = scattered_splats(num_points, Vec3::splat(-1.0), Vec3::splat(2.0));
+    let (color, _) = render_splats(&splats, &cam, img_size, Vec3::ZERO, None);
+    let alpha = color.slice(s![.., .., 3..4]);
+
+    assert_eq!(
+        splats
+            .clone()
+            .with_features(Tensor::zeros([num_points, MAX_FEATURE_DIM + 1], &device))
+            .unwrap_err(),
+        FeatureError::InvalidDim(MAX_FEATURE_DIM + 1)
+    );
+
+    // With the same (signed) features for every splat, the blended features are the features
+    // weighted by the accumulated alpha.
+    let feature = [1.0, -2.0, 0.5, 3.0, -0.25];
+    let features = Tensor::<MainBackend, 1>::from_floats(feature, &device)
+        .unsqueeze_dim::<2>(0)
+        .repeat_dim(0, num_points);
+    let splats = splats.with_features(features).unwrap();
+    assert_eq!(splats.render_mode, SplatRenderMode::Feature { dim: 5 });
+    let (rendered, _) = render_splats(&splats, &cam, img_size, Vec3::ONE, None);
+    assert_eq!(rendered.dims(), [48, 64, 5]);
+    let expected = alpha.repeat_dim(2, 5)
+        * Tensor::<MainBackend, 1>::from_floats(feature, &device).reshape([1, 1, 5]);
+    let max_diff = (rendered - expected).abs().max().into_scalar();
+    assert!(max_diff < 1e-5, "Features differ by {max_diff}");
+}
+
+#[tokio::test]
+async fn computes_splat_aabb() {
+    let device = WgpuDevice::DefaultDevice;
+    let means = Tensor::<MainBackend, 2>::from_floats([[0.0, 0.0, 0.0], [2.0, -1.0, 4.0]], &device);
+    // Radius 1 for the first splat (largest axis), 0.5 for the second.
+    let log_scales =
+        Tensor::<MainBackend, 2>::from_floats([[-3.0, 0.0, -2.0], [0.5f32.ln(); 3]], &device);
+
+    let (min, max) = splat_aabb(&means, &log_scales, 2.0).await;
+    assert!(min.abs_diff_eq(glam::vec3(-2.0, -2.0, -2.0), 1e-5));
+    assert!(max.abs_diff_eq(glam::vec3(3.0, 2.0, 5.0), 1e-5));
+}
+
+#[tokio::test]
+async fn auto_camera_frames_splats() {
+    let device = WgpuDevice::DefaultDevice;
+    let points = [
+        [4.0, 1.0, -3.0],
+        [-2.0, 5.0, 1.0],
+        [0.0, -1.0, 7.0],
+        [3.0, 3.0, 3.0],
+    ];
+    let means = Tensor::<MainBackend, 2>::from_floats(points, &device);
+    let img_size = glam::uvec2(64, 32);
+    let camera = auto_camera_for_splats(&means, 90f64.to_radians(), img_size).await;
+
+    assert_eq!(camera.rotation, glam::Quat::IDENTITY);
+    assert!((camera.fov_x - 90f64.to_radians()).abs() < 1e-6);
+    let focal = camera.focal(img_size);
+    let center = camera.center(img_size);
+    for point in points {
+        let local = camera
+            .world_to_local()
+            .transform_point3(glam::Vec3::from(point));
+        assert!(local.z > 0.0, "{point:?} is behind the camera");
+        let pixel = local.truncate() / local.z * focal + center;
+        assert!(
+            pixel.cmpge(glam::Vec2::ZERO).all() && pixel.cmple(img_size.as_vec2()).all(),
+            "{point:?} projects outside the image to {pixel}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn evaluates_motion_at_time() {
+    let device = WgpuDevice::DefaultDevice;
+    let splats = Splats::<MainBackend>::from_raw(
+        vec![0.0, 0.0, 0.0, 1.0, 2.0, 3.0],
+        [1.0, 0.0, 0.0, 0.0].repeat(2),
+        vec![-2.0; 6],
+        vec![0.5; 6],
+        vec![0.0; 2],
+        SplatRenderMode::Default,
+        &device,
+    );
+    let read = |t: Tensor<MainBackend, 2>| t.into_data().into_vec::<f32>().unwrap();
+
+    // Static splats are unchanged.
+    let still = splats.at_time(0.3);
+    assert_eq!(read(still.means.val()), read(splats.means.val()));
+    assert!(still.motion.is_none());
+
+    let motion = Tensor::<MainBackend, 2>::from_floats(
+        [
+            [0.5, 0.1f32.ln(), 1.0, 0.0, -2.0],
+            [0.7, 0.0, 0.0, 0.0, 0.0],
+        ],
+        &device,
+    );
+    let splats = splats.with_motion(motion);
+    let at = splats.at_time(0.7);
+    assert!(at.motion.is_none());
+    let means = read(at.means.val());
+    let expected = [0.2, 0.0, -0.4, 1.0, 2.0, 3.0];
+    assert!(
+        means
+            .iter()
+            .zip(expected)
+            .all(|(a, b)| (a - b).abs() < 1e-5)
+    );
+    // Two time scales away from its center, the first splat fades to exp(-2) of its opacity.
+    let opacities = at.opacities().into_data().into_vec::<f32>().unwrap();
+    assert!((opacities[0] - 0.5 * (-2.0f32).exp()).abs() < 1e-5);
+    assert!((opacities[1] - 0.5).abs() < 1e-5);
+
+    // Times outside of [0, 1] clamp.
+    assert_eq!(
+        read(splats.at_time(3.0).means.val()),
+        read(splats.at_time(1.0).means.val())
+    );
+}
+
+#[tokio::test]
+async fn voxelises_density() {
+    let device = WgpuDevice::DefaultDevice;
+    // The box spans 0..4 on x and 0..2 on y, z is flat.
+    let means = Tensor::<MainBackend, 2>::from_floats(
+        [
+            [0.0, 0.0, 1.0],
+            [0.5, 0.5, 1.0],
+            [3.0, 1.5, 1.0],
+            [4.0, 2.0, 1.0],
+            [2.0, 0.0, 1.0],
+        ],
+        &device,
+    );
+    let counts = density_voxelise(means, glam::uvec3(2, 2, 1));
+    assert_eq!(counts.dims(), [2, 2, 1]);
+    let counts = counts
+        .into_data()
+        .convert::<u32>()
+        .into_vec::<u32>()
+        .unwrap();
+    // Indexed as [x][y][z]. The maximum lands in the last voxel.
+    assert_eq!(counts, [2, 0, 1, 2]);
+
+    let empty = Tensor::<MainBackend, 2>::zeros([0, 3], &device);
+    let counts = density_voxelise(empty, glam::uvec3(3, 1, 2));
+    assert_eq!(counts.sum().into_scalar(), 0);
+}
+
+#[tokio::test]
+async fn crops_to_aabb() {
+    let device = WgpuDevice::DefaultDevice;
+    let means = vec![
+        0.0, 0.0, 0.0, // Inside
+        1.0, -1.0, 0.5, // On the boundary
+        1.5, 0.0, 0.0, // Outside on x
+        0.0, 0.0, -2.0, // Outside on z
+    ];
+    let num_points = 4;
+    let splats = Splats::<MainBackend>::from_raw(
+        means,
+        [1.0, 0.0, 0.0, 0.0].repeat(num_points),
+        vec![-2.0; num_points * 3],
+        vec![0.5; num_points * 3],
+        vec![0.0; num_points],
+        SplatRenderMode::Default,
+        &device,
+    );
+
+    let cropped = splats.crop_aabb(Vec3::splat(-1.0), Vec3::splat(1.0)).await;
+    assert_eq!(cropped.num_splats(), 2);
+    let means = cropped
+        .means
+        .val()
+        .into_data()
+        .into_vec::<f32>()
+        .expect("Wrong type");
+    assert_eq!(means, [0.0, 0.0, 0.0, 1.0, -1.0, 0.5]);
+    assert_eq!(cropped.sh_coeffs.dims(), [2, 1, 3]);
+}
+
+#[tokio::test]
+async fn prunes_by_opacity() {
+    let device = WgpuDevice::DefaultDevice;
+    let num_points = 4;
+    let splats = Splats::<MainBackend>::from_raw(
+        (0..num_points * 3).map(|i| i as f32).collect(),
+        [1.0, 0.0, 0.0, 0.0].repeat(num_points),
+        vec![-2.0; num_points * 3],
+        vec![0.5; num_points * 3],
+        // Opacities of roughly 0.018, 0.5, 0.12 and 0.98.
+        vec![-4.0, 0.0, -2.0, 4.0],
+        SplatRenderMode::Default,
+        &device,
+    );
+
+    let pruned = splats.prune_by_opacity(0.1).await;
+    assert_eq!(pruned.num_splats(), 3);
+    let means = pruned.means.val().into_data().into_vec::<f32>().unwrap();
+    assert_eq!(means, [3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0]);
+    assert_eq!(pruned.prune_by_opacity(0.99).await.num_splats(), 0);
+}
+
+#[tokio::test]
+async fn filters_by_opacity() {
+    let device = WgpuDevice::DefaultDevice;
+    let num_points = 4;
+    let splats = Splats::<MainBackend>::from_raw(
+        (0..num_points * 3).map(|i| i as f32).collect(),
+        [1.0, 0.0, 0.0, 0.0].repeat(num_points),
+        vec![-2.0; num_points * 3],
+        (0..num_points * 3).map(|i| i as f32).collect(),
+        // Opacities of roughly 0.018, 0.5, 0.12 and 0.98.
+        vec![-4.0, 0.0, -2.0, 4.0],
+        SplatRenderMode::Default,
+        &device,
+    );
+
+    let (filtered, removed) = splats.clone().filter_by_opacity(0.1).await;
+    assert_eq!(removed, 1);
+    assert_eq!(filtered.num_splats(), 3);
+    let means = filtered.means.val().into_data().into_vec::<f32>().unwrap();
+    assert_eq!(means, [3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0]);
+    let coeffs = filtered
+        .sh_coeffs
+        .val()
+        .into_data()
+        .into_vec::<f32>()
+        .unwrap();
+    assert_eq!(coeffs, means);
+
+    // Only opacities above the threshold are kept.
+    let (filtered, removed) = splats.clone().filter_by_opacity(0.5).await;
+    assert_eq!((filtered.num_splats(), removed), (1, 3));
+    let (filtered, removed) = splats.filter_by_opacity(0.0).await;
+    assert_eq!((filtered.num_splats(), removed), (4, 0));
+}
+
+#[tokio::test]
+async fn summarizes_splats() {
+    let device = WgpuDevice::DefaultDevice;
+    let splats = Splats::<MainBackend>::from_raw(
+        vec![0.0, 1.0, 2.0, -3.0, 4.0, 0.5, 1.0, -1.0, 6.0],
+        [1.0, 0.0, 0.0, 0.0].repeat(3),
+        vec![0.0, -1.0, -2.0, -3.0, -2.0, -4.0, 1.0, 0.0, 0.0],
+        vec![0.5; 3 * 3],
+        vec![-4.0, 0.0, 4.0],
+        SplatRenderMode::Mip,
+        &device,
+    );
+    let stats = splats.statistics().await;
+    assert_eq!((stats.num_splats, stats.sh_degree), (3, 0));
+    assert_eq!(stats.render_mode, SplatRenderMode::Mip);
+    assert_eq!(stats.means_min, Vec3::new(-3.0, -1.0, 0.5));
+    assert_eq!(stats.means_max, Vec3::new(1.0, 4.0, 6.0));
+    assert_approx_eq!(stats.opacity.min, 0.018, 1e-3);
+    assert_approx_eq!(stats.opacity.median, 0.5, 1e-6);
+    assert_approx_eq!(stats.opacity.max, 0.982, 1e-3);
+    assert_approx_eq!(stats.scale.min, (-2.0f32).exp(), 1e-5);
+    assert_approx_eq!(stats.scale.median, 1.0, 1e-5);
+    assert_approx_eq!(stats.scale.max, 1.0f32.exp(), 1e-5);
+    assert_eq!(stats.size_bytes, splats.size_bytes());
+    assert!(stats.render_bytes > stats.size_bytes);
+}
+
+#[test]
+fn concats_splats() {
+    let device = WgpuDevice::DefaultDevice;
+    let make = |means: Vec<f32>, coeffs_per_splat: usize, mode: SplatRenderMode| {
+        let num_points = means.len() / 3;
+        Splats::<MainBackend>::from_raw(
+            means,
+            [1.0, 0.0, 0.0, 0.0].repeat(num_points),
+            vec![-2.0; num_points * 3],
+            vec![0.5; num_points * coeffs_per_splat * 3],
+            vec![0.0; num_points],
+            mode,
+            &device,
+        )
+    };
+    let dc_only = make(vec![0.0, 1.0, 2.0], 1, SplatRenderMode::Default);
+    let degree_1 = make(
+        vec![3.0, 4.0, 5.0, 6.0, 7.0, 8.0],
+        4,
+        SplatRenderMode::Default,
+    );
+
+    let merged = Splats::concat(&[dc_only.clone(), degree_1]).unwrap();
+    assert_eq!(merged.num_splats(), 3);
+    assert_eq!(merged.sh_degree(), 1);
+    let means = merged.means.val().into_data().into_vec::<f32>().unwrap();
+    assert_eq!(means, [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]);
+    // The higher bands of the degree 0 splat are zero padded.
+    let coeffs = merged
+        .sh_coeffs
+        .val()
+        .into_data()
+        .into_vec::<f32>()
+        .unwrap();
+    assert_eq!(
+        &coeffs[..12],
+        [0.5, 0.5, 0.5, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]
+    );
+
+    let mip = make(vec![0.0; 3], 1, SplatRenderMode::Mip);
+    assert!(matches!(
+        Splats::concat(&[dc_only, mip]),
+        Err(ConcatError::MismatchedRenderModes(..))
+    ));
+    assert_eq!(
+        Splats::<MainBackend>::concat(&[]).unwrap_err(),
+        ConcatError::Empty
+    );
+}
+
+#[test]
+fn merges_splats() {
+    let device = WgpuDevice::DefaultDevice;
+    let a = Splats::<MainBackend>::from_raw(
+        vec![0.0, 1.0, 2.0],
+        vec![1.0, 0.0, 0.0, 0.0],
+        vec![-2.0; 3],
+        vec![0.5; 3],
+        vec![0.0],
+        SplatRenderMode::Default,
+        &device,
+    );
+    let b = Splats::<MainBackend>::from_raw(
+        vec![3.0, 4.0, 5.0],
+        vec![1.0, 0.0, 0.0, 0.0],
+        vec![-2.0; 3],
+        vec![0.25; 9 * 3],
+        vec![0.0],
+        SplatRenderMode::Default,
+        &device,
+    );
+
+    let mut mip = b.clone();
+    mip.render_mode = SplatRenderMode::Mip;
+    assert!(matches!(
+        Splats::merge(a.clone(), mip),
+        Err(ConcatError::MismatchedRenderModes(
+            SplatRenderMode::Default,
+            SplatRenderMode::Mip
+        ))
+    ));
+
+    let merged = Splats::merge(a.clone(), b).unwrap();
+    assert_eq!(merged.num_splats(), 2);
+    assert_eq!(merged.sh_degree(), 2);
+    assert_eq!(merged.render_mode, SplatRenderMode::Default);
+    let means = merged.means.val().into_data().into_vec::<f32>().unwrap();
+    assert_eq!(means, [0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+    let coeffs = merged
+        .sh_coeffs
+        .val()
+        .into_data()
+        .into_vec::<f32>()
+        .unwrap();
+    assert_eq!(&coeffs[..3], [0.5; 3]);
+    assert_eq!(&coeffs[3..27], [0.0; 24]);
+
+    // The clone that was merged doesn't affect the original.
+    assert_eq!(a.num_splats(), 1);
+    assert_eq!(a.sh_degree(), 0);
+}
+
+#[test]
+fn transformed_splats_match_transformed_camera() {
+    let device = WgpuDevice::DefaultDevice;
+    let num_points = 64;
+    let rotations = (0..num_points)
+        .flat_map(|i| {
+            let q = glam::Quat::from_euler(
+                glam::EulerRot::XYZ,
+                hash(i, 0) * 6.0,
+                hash(i, 1) * 6.0,
+                hash(i, 2) * 6.0,
+            );
+            [q.w, q.x, q.y, q.z]
+        })
+        .collect();
+    // Degree 2 SH, so the view dependent color has to be rotated as well.
+    let splats = Splats::<MainBackend>::from_raw(
+        (0..num_points * 3)
+            .map(|i| hash(i, 3) * 2.0 - 1.0)
+            .collect(),
+        rotations,
+        (0..num_points * 3).map(|i| -2.5 + hash(i, 4)).collect(),
+        (0..num_points * 9 * 3).map(|i| hash(i, 5) - 0.5).collect(),
+        (0..num_points).map(|i| hash(i, 6) * 4.0 - 1.0).collect(),
+        SplatRenderMode::Default,
+        &device,
+    );
+
+    let cam = Camera::new(
+        glam::vec3(0.3, -0.2, -3.0),
+        glam::Quat::from_rotation_y(0.1),
+        0.9,
+        0.9,
+        glam::vec2(0.5, 0.5),
+    );
+    let rotation = glam::Quat::from_euler(glam::EulerRot::YXZ, 0.8, -0.4, 1.3);
+    let translation = glam::vec3(2.0, -1.0, 0.5);
+    let scale = 1.7;
+    let moved_cam = Camera::new(
+        scale * (rotation * cam.position) + translation,
+        rotation * cam.rotation,
+        cam.fov_x,
+        cam.fov_y,
+        cam.center_uv,
+    );
+
+    let render = |splats: &Splats<MainBackend>, cam: &Camera| {
+        let img_size = glam::uvec2(64, 64);
+        render_test_splats(cam, img_size, splats, Vec3::ZERO, RenderOptions::default()).0
+    };
+
+    let original = render(&splats, &cam);
+    let moved = render(&splats.transform(translation, rotation, scale), &moved_cam);
+    let max_diff = (original.clone() - moved).abs().max().into_scalar();
+    assert!(max_diff < 1e-3, "Transformed render differs by {max_diff}");
+    assert!(original.max().into_scalar() > 0.1);
+}
+
+#[test]
+fn transform_updates_attributes() {
+    let device = WgpuDevice::DefaultDevice;
+    let start = glam::Quat::from_rotation_x(0.3);
+    let splats = Splats::<MainBackend>::from_raw(
+        vec![1.0, 2.0, 3.0],
+        vec![start.w, start.x, start.y, start.z],
+        vec![-1.0, 0.0, 0.5],
+        vec![0.5; 3],
+        vec![0.0],
+        SplatRenderMode::Default,
+        &device,
+    );
+    let rotation = glam::Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+    let translation = glam::vec3(0.5, -1.0, 2.0);
+    let moved = splats.transform(translation, rotation, 2.0);
+
+    let read = |t: Tensor<MainBackend, 2>| t.into_data().into_vec::<f32>().unwrap();
+    let mean = Vec3::from_slice(&read(moved.means.val()));
+    assert!(mean.abs_diff_eq(glam::vec3(-3.5, 1.0, 8.0), 1e-5), "{mean}");
+    let log_scales = read(moved.log_scales.val());
+    for (s, expected) in log_scales.iter().zip([-1.0, 0.0, 0.5]) {
+        assert_approx_eq!(s, expected + 2.0f32.ln(), 1e-5);
+    }
+    let [w, x, y, z] = read(moved.rotations.val()).try_into().unwrap();
+    let composed = glam::Quat::from_xyzw(x, y, z, w);
+    assert!(composed.angle_between(rotation * start) < 1e-4);
+}
+
+#[tokio::test]
+async fn centers_and_scales_splats() {
+    let device = WgpuDevice::DefaultDevice;
+    // Nine points on a circle of radius 4 around (10, 0, -2), and a far away outlier.
+    let mut means: Vec<f32> = (0..9)
+        .flat_map(|i| {
+            let angle = i as f32 * std::f32::consts::TAU / 9.0;
+            [10.0 + 4.0 * angle.cos(), 0.0, -2.0 + 4.0 * angle.sin()]
+        })
+        .collect();
+    means.extend([10.0, 100.0, -2.0]);
+    let splats = Splats::<MainBackend>::from_raw(
+        means.clone(),
+        [1.0, 0.0, 0.0, 0.0].repeat(10),
+        vec![0.0; 30],
+        vec![0.5; 30],
+        vec![0.0; 10],
+        SplatRenderMode::Default,
+        &device,
+    );
+
+    let (normalized, translation, scale) = splats.center_and_scale().await;
+    // The outlier moves the center, but not the scale.
+    let center = glam::vec3(10.0, 10.0, -2.0);
+    assert!(translation.abs_diff_eq(-center, 1e-4), "{translation}");
+    let radius = glam::vec3(4.0, -10.0, 0.0).length();
+    assert_approx_eq!(scale, 1.0 / radius, 1e-5);
+
+    let read = |t: Tensor<MainBackend, 2>| t.into_data().into_vec::<f32>().unwrap();
+    let normalized_means = read(normalized.means.val());
+    for (p, q) in means.chunks_exact(3).zip(normalized_means.chunks_exact(3)) {
+        let expected = (Vec3::from_slice(p) + translation) * scale;
+        assert!(Vec3::from_slice(q).abs_diff_eq(expected, 1e-4));
+    }
+    for s in read(normalized.log_scales.val()) {
+        assert_approx_eq!(s, scale.ln(), 1e-5);
+    }
+}
+
+#[test]
+fn max_sh_degree_truncates_coeffs() {
+    let device = WgpuDevice::DefaultDevice;
+    let num_points = 3;
+    // Degree 3 has 16 coefficients per channel.
+    let coeffs: Vec<f32> = (0..num_points * 16 * 3).map(|i| i as f32 * 0.01).collect();
+    let splats = Splats::<MainBackend>::from_raw(
+        vec![0.0; num_points * 3],
+        [1.0, 0.0, 0.0, 0.0].repeat(num_points),
+        vec![-2.0; num_points * 3],
+        coeffs.clone(),
+        vec![0.0; num_points],
+        SplatRenderMode::Default,
+        &device,
+    );
+
+    // Truncating to the stored degree (or above) is a no-op.
+    let splats = splats.with_max_sh_degree(3).with_max_sh_degree(4);
+    let full = splats
+        .sh_coeffs
+        .val()
+        .into_data()
+        .into_vec::<f32>()
+        .unwrap();
+    assert_eq!(full, coeffs);
+
+    let dc = splats.with_max_sh_degree(0);
+    assert_eq!(dc.sh_degree(), 0);
+    let dc = dc.sh_coeffs.val().into_data().into_vec::<f32>().unwrap();
+    let expected: Vec<f32> = coeffs
+        .chunks_exact(16 * 3)
+        .flat_map(|c| c[..3].to_vec())
+        .collect();
+    assert_eq!(dc, expected);
+}
+
+#[cfg(feature = "distributed-render")]
+#[tokio::test]
+async fn distributed_render_matches_single_device() {
+    use crate::distributed::render_splats_distributed;
+    use crate::{MainBackendBase, gaussian_splats::render_splats};
+
+    let device = WgpuDevice::DefaultDevice;
+    let cam = Camera::new(
+        glam::vec3(0.0, 0.0, -4.0),
+        glam::Quat::IDENTITY,
+        0.9,
+        0.9,
+        glam::vec2(0.5, 0.5),
+    );
+    let img_size = glam::uvec2(128, 128);
+
+    // Some splats are behind the camera or nearly transparent, so the slices get culled.
+    let num_points = 300;
+    let means = (0..num_points)
+        .flat_map(|i| {
+            [
+                hash(i, 0) * 4.0 - 2.0,
+                hash(i, 1) * 4.0 - 2.0,
+                hash(i, 2) * 8.0 - 6.0,
+            ]
+        })
+        .collect();
+    let log_scales = (0..num_points * 3).map(|i| -2.5 + hash(i, 3)).collect();
+    let sh_coeffs = (0..num_points * 3).map(|i| hash(i, 4) - 0.5).collect();
+    let raw_opacities = (0..num_points).map(|i| hash(i, 5) * 12.0 - 8.0).collect();
+    let splats = Splats::<MainBackendBase>::from_raw(
+        means,
+        [1.0, 0.0, 0.0, 0.0].repeat(num_points),
+        log_scales,
+        sh_coeffs,
+        raw_opacities,
+        SplatRenderMode::Default,
+        &device,
+    );
+
+    let (single, single_aux) = render_splats(&splats, &cam, img_size, Vec3::ONE * 0.2, None);
+    let parts = [
+        (device.clone(), 0..100),
+        (device.clone(), 100..250),
+        (device.clone(), 250..num_points),
+    ];
+    let (distributed, aux) =
+        render_splats_distributed(&splats, &parts, &device, &cam, img_size, Vec3::ONE * 0.2).await;
+
+    let num_visible = aux.num_visible().into_scalar().elem::<i32>();
+    assert!(num_visible > 0, "Some splats should be visible");
+    assert_eq!(
+        num_visible,
+        single_aux.num_visible().into_scalar().elem::<i32>()
+    );
+
+    assert_eq!(
+        single.into_data_async().await.unwrap(),
+        distributed.into_data_async().await.unwrap()
     );
-    aux.validate_values();
+}
+
+#[test]
+fn fisheye_rays_match_projection() {
+    let device = WgpuDevice::DefaultDevice;
+    let img_size = glam::uvec2(8, 8);
+    let camera = Camera::new(
+        Vec3::ZERO,
+        glam::Quat::from_rotation_y(0.3),
+        1.0,
+        1.0,
+        glam::vec2(0.5, 0.5),
+    )
+    .with_projection(ProjectionMode::Fisheye {
+        max_fov_rad: std::f32::consts::PI,
+        model: FisheyeModel::Equidistant,
+    });
+    let dirs = ray_directions::<MainBackend>(&camera, img_size, &device);
+    let dirs = dirs.into_data().into_vec::<f32>().unwrap();
+
+    let focal = camera.focal(img_size);
+    let center = camera.center(img_size);
+    for (i, dir) in dirs.chunks_exact(3).enumerate() {
+        let dir = Vec3::from_slice(dir);
+        assert!((dir.length() - 1.0).abs() < 1e-5);
+        // The distance of the pixel from the center is the angle from the optical axis.
+        let local = camera.world_to_local().transform_vector3(dir);
+        let rho = local.truncate().length();
+        let pixel = focal * local.truncate() * (rho.atan2(local.z) / rho) + center;
+        let expected = glam::vec2((i % 8) as f32 + 0.5, (i / 8) as f32 + 0.5);
+        assert!(pixel.abs_diff_eq(expected, 1e-3));
+    }
+}
+
+#[test]
+fn rays_match_projection() {
+    let device = WgpuDevice::DefaultDevice;
+    let img_size = glam::uvec2(8, 6);
+    let camera = Camera::new(
+        Vec3::ZERO,
+        glam::Quat::from_rotation_y(0.3),
+        1.0,
+        0.8,
+        glam::vec2(0.4, 0.5),
+    );
+    let dirs = ray_directions::<MainBackend>(&camera, img_size, &device);
+    let dirs = dirs.into_data().into_vec::<f32>().unwrap();
+
+    let focal = camera.focal(img_size);
+    let center = camera.center(img_size);
+    for (i, dir) in dirs.chunks_exact(3).enumerate() {
+        let dir = Vec3::from_slice(dir);
+        assert!((dir.length() - 1.0).abs() < 1e-5);
+        // Projecting the ray back gives the pixel center.
+        let local = camera.world_to_local().transform_vector3(dir);
+        let pixel = focal * local.truncate() / local.z + center;
+        let expected = glam::vec2((i % 8) as f32 + 0.5, (i / 8) as f32 + 0.5);
+        assert!(pixel.abs_diff_eq(expected, 1e-3));
+    }
+}
+
+#[test]
+fn samples_env_map_directions() {
+    let device = WgpuDevice::DefaultDevice;
+    // A 4x2 map holding the index of each texel.
+    let env_map = Tensor::<MainBackend, 1, Int>::arange(0..8, &device)
+        .float()
+        .reshape([2, 4, 1]);
+
+    // The center of the texel at column 2 of the bottom row.
+    let (lon, lat) = (
+        0.5 / 4.0 * std::f32::consts::TAU,
+        0.25 * std::f32::consts::PI,
+    );
+    let dir = Vec3::new(lat.cos() * lon.sin(), lat.sin(), lat.cos() * lon.cos());
+    // The seam between the last and first column of the top row.
+    let seam = Vec3::new(-0.001, -1.0, -1.0).normalize();
+    let dirs = Tensor::<MainBackend, 1>::from_floats(
+        [dir.to_array(), seam.to_array()].concat().as_slice(),
+        &device,
+    )
+    .reshape([1, 2, 3]);
+
+    let values = sample_env_map(env_map, dirs)
+        .into_data()
+        .into_vec::<f32>()
+        .unwrap();
+    assert!((values[0] - 6.0).abs() < 1e-3);
+    // Halfway between texels 3 and 0.
+    assert!((values[1] - 1.5).abs() < 1e-2);
+}
+
+#[test]
+fn env_map_background_matches_constant_image() {
+    let device = WgpuDevice::DefaultDevice;
+    let cam = Camera::new(
+        glam::vec3(0.0, 0.0, -3.0),
+        glam::Quat::IDENTITY,
+        0.8,
+        0.6,
+        glam::vec2(0.5, 0.5),
+    );
+    let img_size = glam::uvec2(64, 48);
+    let splats = Splats::<MainBackend>::from_raw(
+        vec![0.0, 0.0, 0.0, 0.3, 0.2, 0.5],
+        [1.0, 0.0, 0.0, 0.0].repeat(2),
+        vec![-1.5; 6],
+        vec![0.5, -0.2, 0.1, -0.4, 0.3, 0.0],
+        vec![1.0, 0.5],
+        SplatRenderMode::Default,
+        &device,
+    );
+
+    // A constant environment map gives the same background for every ray.
+    let color = Tensor::<MainBackend, 1>::from_floats([0.2, 0.5, 0.9], &device);
+    let env_map = color.clone().reshape([1, 1, 3]).expand([8, 16, 3]);
+    let image = color.reshape([1, 1, 3]).expand([48, 64, 3]);
+    let (over_env, _) = render_splats_over_env_map(&splats, &cam, img_size, env_map, None);
+    let (over_image, _) = render_splats_over_image(&splats, &cam, img_size, image, None).unwrap();
+    let max_diff = (over_env - over_image).abs().max().into_scalar();
+    assert!(
+        max_diff < 1e-5,
+        "Environment map background differs by {max_diff}"
+    );
+}
+
+/// Cancels `token` once the depth sort of a render finished, and records whether the
+/// rasterizer ran afterwards.
+struct CancelAfterSort {
+    token: tokio_util::sync::CancellationToken,
+    rasterized: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+impl<S> tracing_subscriber::Layer<S> for CancelAfterSort
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    fn on_enter(&self, id: &tracing::span::Id, ctx: tracing_subscriber::layer::Context<'_, S>) {
+        if ctx.span(id).is_some_and(|span| span.name() == "Rasterize") {
+            self.rasterized
+                .store(true, std::sync::atomic::Ordering::Relaxed);
+        }
+    }
+
+    fn on_close(&self, id: tracing::span::Id, ctx: tracing_subscriber::layer::Context<'_, S>) {
+        if ctx.span(&id).is_some_and(|span| span.name() == "DepthSort") {
+            self.token.cancel();
+        }
+    }
+}
+
+#[tokio::test]
+async fn cancels_render_between_stages() {
+    use crate::RenderCancelled;
+    use crate::gaussian_splats::render_splats_cancellable;
+    use std::sync::{Arc, atomic::AtomicBool, atomic::Ordering};
+    use tokio_util::sync::CancellationToken;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let cam = test_camera();
+    let img_size = glam::uvec2(64, 48);
+    let splats = scattered_splats(64, glam::vec3(-1.0, -1.0, 2.0), glam::vec3(2.0, 2.0, 2.0));
+
+    let token = CancellationToken::new();
+    let rasterized = Arc::new(AtomicBool::new(false));
+    let layer = CancelAfterSort {
+        token: token.clone(),
+        rasterized: rasterized.clone(),
+    };
+    // The test runs on a single thread, so the fused render runs with this subscriber.
+    let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(layer));
+
+    let result =
+        render_splats_cancellable(&splats, &cam, img_size, Vec3::ZERO, None, token.clone()).await;
+    assert!(matches!(result, Err(RenderCancelled)));
+    assert!(token.is_cancelled());
+    assert!(
+        !rasterized.load(Ordering::Relaxed),
+        "Rasterized after the render was cancelled"
+    );
+}
+
+#[test]
+fn antialias_changes_thin_splats() {
+    let device = WgpuDevice::DefaultDevice;
+    let cam = Camera::new(
+        glam::vec3(0.0, 0.0, -3.0),
+        glam::Quat::IDENTITY,
+        0.8,
+        0.6,
+        glam::vec2(0.5, 0.5),
+    );
+    let img_size = glam::uvec2(64, 48);
+    // Needle like splats, thinner than a pixel along y.
+    let splats = Splats::<MainBackend>::from_raw(
+        vec![0.0, 0.0, 0.0, 0.3, 0.2, 0.5, -0.4, -0.1, 0.2],
+        [1.0, 0.0, 0.0, 0.0].repeat(3),
+        [-1.0, -7.0, -1.0].repeat(3),
+        vec![0.5, -0.2, 0.1, -0.4, 0.3, 0.0, 0.2, 0.2, -0.3],
+        vec![2.0, 1.0, 3.0],
+        SplatRenderMode::Default,
+        &device,
+    );
+    let render = |antialias| {
+        let options = RenderOptions {
+            antialias,
+            ..Default::default()
+        };
+        let (img, aux) = render_test_splats(&cam, img_size, &splats, Vec3::ZERO, options);
+        assert_eq!(aux.antialiased, antialias);
+        assert_eq!(validate_output(&img), Ok(()));
+        img
+    };
+
+    let classic = render(false);
+    let antialiased = render(true);
+    let max_diff = (classic - antialiased).abs().max().into_scalar();
+    assert!(
+        max_diff > 1e-3,
+        "Anti-aliasing didn't change the render, max difference {max_diff}"
+    );
+}
+
+#[test]
+fn render_mode_string_round_trip() {
+    use clap::ValueEnum;
+
+    let modes = [
+        SplatRenderMode::Default,
+        SplatRenderMode::Mip,
+        SplatRenderMode::NormalsWorldSpace,
+        SplatRenderMode::NormalsCameraSpace,
+        SplatRenderMode::AlphaOnly,
+        SplatRenderMode::WorldPosition,
+        SplatRenderMode::Feature { dim: 8 },
+    ];
+    for mode in modes {
+        let name = mode.to_string();
+        assert_eq!(name.parse::<SplatRenderMode>(), Ok(mode), "{name}");
+    }
+    // The names match the CLI values.
+    for value in SplatRenderMode::value_variants() {
+        let name = value.to_possible_value().unwrap().get_name().to_owned();
+        assert_eq!(value.to_string(), name);
+    }
+
+    assert_eq!("MIP".parse(), Ok(SplatRenderMode::Mip));
+    assert!("feature-".parse::<SplatRenderMode>().is_err());
+    assert!("normals".parse::<SplatRenderMode>().is_err());
+}
+
+#[test]
+fn median_depth_picks_one_plane() {
+    // A 3x3 grid of splats at z = 2 in front of a wider, opaque grid at z = 6.
+    let render_planes = |front_opacity: f32, depth_mode: DepthMode| {
+        let mut means = vec![];
+        let mut log_scales = vec![];
+        let mut raw_opacities = vec![];
+        for (z, spacing, opacity) in [(2.0, 0.5, front_opacity), (6.0, 1.5, 0.99)] {
+            for i in -1..=1 {
+                for j in -1..=1 {
+                    means.extend([i as f32 * spacing, j as f32 * spacing, z]);
+                    log_scales.extend([(spacing * 0.6f32).ln(), (spacing * 0.6f32).ln(), -4.6]);
+                    raw_opacities.push((opacity / (1.0 - opacity)).ln());
+                }
+            }
+        }
+        let n = raw_opacities.len();
+        let splats = Splats::<MainBackend>::from_raw(
+            means,
+            [1.0, 0.0, 0.0, 0.0].repeat(n),
+            log_scales,
+            vec![1.0; n * 3],
+            raw_opacities,
+            SplatRenderMode::Default,
+            &WgpuDevice::DefaultDevice,
+        );
+        let options = RenderOptions {
+            depth: true,
+            depth_mode,
+            ..Default::default()
+        };
+        let (output, aux) = render_test_splats(
+            &test_camera(),
+            glam::uvec2(32, 32),
+            &splats,
+            Vec3::ZERO,
+            options,
+        );
+        assert_eq!(aux.validate_values(), Ok(()));
+        let alpha = output.slice([16..17, 16..17, 3..4]).into_scalar();
+        let depth = aux.depth().expect("Depth was requested");
+        (depth.slice([16..17, 16..17, 0..1]).into_scalar(), alpha)
+    };
+
+    for (front_opacity, surface) in [(0.7, 2.0), (0.1, 6.0)] {
+        let (median, _) = render_planes(front_opacity, DepthMode::Median);
+        assert_approx_eq!(median, surface, 1e-3);
+
+        // The expected depth is pulled towards the other plane.
+        let (weighted, alpha) = render_planes(front_opacity, DepthMode::Expected);
+        assert!(
+            alpha > 0.99,
+            "Center pixel should be covered, alpha {alpha}"
+        );
+        let expected = weighted / alpha;
+        assert!(
+            expected > 2.2 && expected < 5.8 && (expected - surface).abs() > 0.2,
+            "Expected depth {expected} with front opacity {front_opacity}"
+        );
+    }
+}
+
+#[test]
+fn reinhard_tonemapping() {
+    let device = WgpuDevice::DefaultDevice;
+    let img =
+        Tensor::<MainBackend, 1>::from_floats([0.0, 1.0, 3.0, 0.5], &device).reshape([1, 1, 4]);
+
+    let mapped = apply_reinhard_tonemapping(img.clone(), 1.0)
+        .into_data()
+        .into_vec::<f32>()
+        .unwrap();
+    for (v, expected) in mapped.iter().zip([0.0, 0.5, 0.75, 0.5]) {
+        assert_approx_eq!(v, expected, 1e-6);
+    }
+
+    // Larger white points compress less, and alpha stays untouched.
+    let mapped = apply_reinhard_tonemapping(img, 2.0)
+        .into_data()
+        .into_vec::<f32>()
+        .unwrap();
+    for (v, expected) in mapped.iter().zip([0.0, 0.8, 3.0 / 1.75, 0.5]) {
+        assert_approx_eq!(v, expected, 1e-6);
+    }
+}
+
+#[test]
+fn aces_tonemapping() {
+    let device = WgpuDevice::DefaultDevice;
+    let img =
+        Tensor::<MainBackend, 1>::from_floats([0.0, 0.18, 100.0, 0.25], &device).reshape([1, 1, 4]);
+    let mapped = apply_aces_tonemapping(img)
+        .into_data()
+        .into_vec::<f32>()
+        .unwrap();
+
+    let aces = |x: f32| (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14);
+    assert_approx_eq!(mapped[0], 0.0, 1e-6);
+    assert_approx_eq!(mapped[1], aces(0.18), 1e-6);
+    // Bright highlights approach white without passing it, alpha is untouched.
+    assert!(mapped[2] > 0.99 && mapped[2] <= 1.0);
+    assert_approx_eq!(mapped[3], 0.25, 1e-6);
+}
+
+#[test]
+fn srgb_transfer_functions() {
+    let device = WgpuDevice::DefaultDevice;
+    let linear = [0.0, 0.002, 0.18, 1.0, 0.5, 0.25, 2.0, 0.75];
+    let img = Tensor::<MainBackend, 1>::from_floats(linear, &device).reshape([1, 2, 4]);
+
+    let encoded = linear_to_srgb(img);
+    let values = encoded.clone().into_data().into_vec::<f32>().unwrap();
+    let expected = [
+        0.0,
+        0.002 * 12.92,
+        1.055 * 0.18f32.powf(1.0 / 2.4) - 0.055,
+        1.0,
+        1.055 * 0.5f32.powf(1.0 / 2.4) - 0.055,
+        1.055 * 0.25f32.powf(1.0 / 2.4) - 0.055,
+        1.055 * 2.0f32.powf(1.0 / 2.4) - 0.055,
+        // Alpha isn't encoded.
+        0.75,
+    ];
+    for (v, e) in values.iter().zip(expected) {
+        assert_approx_eq!(v, e, 1e-5);
+    }
+    // 18% gray is code value 118.
+    assert_eq!((values[2] * 255.0).round(), 118.0);
+
+    let decoded = srgb_to_linear(encoded)
+        .into_data()
+        .into_vec::<f32>()
+        .unwrap();
+    for (v, e) in decoded.iter().zip(linear) {
+        assert_approx_eq!(v, e, 1e-5);
+    }
 }
@@ -0,0 +1,46 @@
+use burn::{Tensor, prelude::Backend, tensor::s};
+
+/// Compress the linear HDR colors of an `[H, W, C]` image with the Reinhard curve
+/// `L / (1 + L / white_point²)`, applied to each channel in one element-wise pass.
+///
+/// A white point of 1 gives the classic `L / (1 + L)`, larger white points keep more of the
+/// highlight contrast and approach `white_point²` for very bright values. The fourth channel
+/// of an RGBA image is alpha and left alone.
+pub fn apply_reinhard_tonemapping<B: Backend>(img: Tensor<B, 3>, white_point: f32) -> Tensor<B, 3> {
+    assert!(
+        white_point > 0.0 && white_point.is_finite(),
+        "White point must be positive, got {white_point}"
+    );
+    map_color(img, |color| {
+        color.clone() / (color / (white_point * white_point) + 1.0)
+    })
+}
+
+/// Map the linear HDR colors of an `[H, W, C]` image, from 0 to infinity, to SDR colors
+/// from 0 to 1 with Narkowicz's fit of the ACES filmic curve (RRT + ODT):
+/// `(L (2.51 L + 0.03)) / (L (2.43 L + 0.59) + 0.14)`, clamped to 0..1.
+///
+/// Mid gray stays roughly in place while the highlights roll off smoothly. The output is
+/// still linear, so encode it with the sRGB transfer function afterwards. The fourth channel
+/// of an RGBA image is alpha and left alone.
+pub fn apply_aces_tonemapping<B: Backend>(img: Tensor<B, 3>) -> Tensor<B, 3> {
+    map_color(img, |color| {
+        let numerator = color.clone() * (color.clone() * 2.51 + 0.03);
+        let denominator = color.clone() * (color * 2.43 + 0.59) + 0.14;
+        (numerator / denominator).clamp(0.0, 1.0)
+    })
+}
+
+/// Apply `map` to the color channels of `img`, keeping the alpha of an RGBA image.
+pub(crate) fn map_color<B: Backend>(
+    img: Tensor<B, 3>,
+    map: impl Fn(Tensor<B, 3>) -> Tensor<B, 3>,
+) -> Tensor<B, 3> {
+    match img.dims()[2] {
+        4 => {
+            let alpha = img.clone().slice(s![.., .., 3..4]);
+            Tensor::cat(vec![map(img.slice(s![.., .., 0..3])), alpha], 2)
+        }
+        _ => map(img),
+    }
+}
@@ -1,5 +1,74 @@
 use burn::{prelude::Backend, tensor::Tensor};
 
+use crate::render_aux::SplatRenderError;
+
+/// Index and value of the first NaN or infinite value in `values`.
+fn first_non_finite(values: &[f32]) -> Option<(usize, f32)> {
+    values
+        .iter()
+        .position(|v| !v.is_finite())
+        .map(|i| (i, values[i]))
+}
+
+/// Check that a `[h, w, c]` render output only contains finite values, reporting the first
+/// offending pixel otherwise.
+pub fn validate_output<B: Backend>(output: &Tensor<B, 3>) -> Result<(), SplatRenderError> {
+    let [_, w, c] = output.dims();
+    let values = output
+        .clone()
+        .into_data()
+        .into_vec::<f32>()
+        .expect("Failed to convert tensor to f32 vec");
+    let Some((i, value)) = first_non_finite(&values) else {
+        return Ok(());
+    };
+    let channel = i % c;
+    let pixel = [(i / c) % w, i / (c * w)];
+    Err(if value.is_nan() {
+        SplatRenderError::NanInOutput { channel, pixel }
+    } else {
+        SplatRenderError::InfInOutput { channel, pixel }
+    })
+}
+
+/// Check that a tensor only contains finite values, reporting the first offending (flat)
+/// index otherwise.
+pub fn validate_finite<B: Backend, const D: usize>(
+    tensor: &Tensor<B, D>,
+    buffer: &'static str,
+) -> Result<(), SplatRenderError> {
+    let values = tensor
+        .clone()
+        .into_data()
+        .into_vec::<f32>()
+        .expect("Failed to convert tensor to f32 vec");
+    let Some((index, value)) = first_non_finite(&values) else {
+        return Ok(());
+    };
+    Err(if value.is_nan() {
+        SplatRenderError::NanInBuffer { buffer, index }
+    } else {
+        SplatRenderError::InfInBuffer { buffer, index }
+    })
+}
+
+/// Like [`validate_finite`], but allows infinite values, eg. the depth sentinel of pixels
+/// without coverage.
+pub fn validate_not_nan<B: Backend, const D: usize>(
+    tensor: &Tensor<B, D>,
+    buffer: &'static str,
+) -> Result<(), SplatRenderError> {
+    let values = tensor
+        .clone()
+        .into_data()
+        .into_vec::<f32>()
+        .expect("Failed to convert tensor to f32 vec");
+    match values.iter().position(|v| v.is_nan()) {
+        Some(index) => Err(SplatRenderError::NanInBuffer { buffer, index }),
+        None => Ok(()),
+    }
+}
+
 pub fn validate_tensor_val<B: Backend, const D: usize>(
     tensor: &Tensor<B, D>,
     name: &str,
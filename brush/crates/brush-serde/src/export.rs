@@ -1,31 +1,42 @@
+use std::path::Path;
 use std::vec;
 
 use brush_render::gaussian_splats::{SplatRenderMode, Splats};
 use brush_render::sh::sh_coeffs_for_degree;
 use burn::prelude::Backend;
-use burn::tensor::Transaction;
+use burn::tensor::{TensorData, Transaction};
 use serde::ser::SerializeStruct;
 use serde::{Serialize, Serializer};
 use serde_ply::{SerializeError, SerializeOptions};
 
+#[derive(Debug, thiserror::Error)]
+pub enum SaveError {
+    #[error("Failed to serialize splats to PLY: {0}")]
+    Serialize(#[from] SerializeError),
+    #[error("Failed to write PLY file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Can only quantise to 8 or 16 bits, got {0}")]
+    UnsupportedBits(u8),
+}
+
 // Dynamic PLY structure that only includes needed SH coefficients
 #[derive(Debug)]
-struct DynamicPlyGaussian {
-    x: f32,
-    y: f32,
-    z: f32,
-    scale_0: f32,
-    scale_1: f32,
-    scale_2: f32,
-    opacity: f32,
-    rot_0: f32,
-    rot_1: f32,
-    rot_2: f32,
-    rot_3: f32,
-    f_dc_0: f32,
-    f_dc_1: f32,
-    f_dc_2: f32,
-    rest_coeffs: Vec<f32>,
+pub(crate) struct DynamicPlyGaussian {
+    pub(crate) x: f32,
+    pub(crate) y: f32,
+    pub(crate) z: f32,
+    pub(crate) scale_0: f32,
+    pub(crate) scale_1: f32,
+    pub(crate) scale_2: f32,
+    pub(crate) opacity: f32,
+    pub(crate) rot_0: f32,
+    pub(crate) rot_1: f32,
+    pub(crate) rot_2: f32,
+    pub(crate) rot_3: f32,
+    pub(crate) f_dc_0: f32,
+    pub(crate) f_dc_1: f32,
+    pub(crate) f_dc_2: f32,
+    pub(crate) rest_coeffs: Vec<f32>,
 }
 
 impl Serialize for DynamicPlyGaussian {
@@ -65,23 +76,32 @@ impl Serialize for DynamicPlyGaussian {
 }
 
 #[derive(Serialize)]
-struct DynamicPly {
-    vertex: Vec<DynamicPlyGaussian>,
+pub(crate) struct DynamicPly {
+    pub(crate) vertex: Vec<DynamicPlyGaussian>,
 }
 pub use burn_cubecl::{CubeRuntime, cubecl::Compiler, tensor::CubeTensor};
 
-async fn read_splat_data<B: Backend>(splats: Splats<B>) -> DynamicPly {
-    let [means, log_scales, rotations, raw_opacities, sh_coeffs] = Transaction::default()
+pub(crate) fn splat_transaction<B: Backend>(splats: &Splats<B>) -> Transaction<B> {
+    Transaction::default()
         .register(splats.means.val())
         .register(splats.log_scales.val())
         .register(splats.rotations.val())
         .register(splats.raw_opacities.val())
         .register(splats.sh_coeffs.val().permute([0, 2, 1])) // Permute to inria format ([n, channel, coeffs]).)
+}
+
+async fn read_splat_data<B: Backend>(splats: Splats<B>) -> DynamicPly {
+    let data = splat_transaction(&splats)
         .execute_async()
         .await
-        .expect("Failed to fetch splat data")
+        .expect("Failed to fetch splat data");
+    ply_from_data(&splats, data)
+}
+
+pub(crate) fn ply_from_data<B: Backend>(splats: &Splats<B>, data: Vec<TensorData>) -> DynamicPly {
+    let [means, log_scales, rotations, raw_opacities, sh_coeffs] = data
         .into_iter()
-        .map(|x| x.into_vec().unwrap())
+        .map(|x| x.into_vec::<f32>().unwrap())
         .collect::<Vec<_>>()
         .try_into()
         .unwrap();
@@ -155,31 +175,66 @@ async fn read_splat_data<B: Backend>(splats: Splats<B>) -> DynamicPly {
     DynamicPly { vertex: vertices }
 }
 
+pub(crate) fn ply_comments<B: Backend>(splats: &Splats<B>) -> Vec<String> {
+    let render_mode = match splats.render_mode {
+        SplatRenderMode::Mip => SplatRenderMode::Mip,
+        // Normals are a way to view the splats, the splats themselves are regular. Features
+        // aren't exported.
+        SplatRenderMode::Default
+        | SplatRenderMode::NormalsWorldSpace
+        | SplatRenderMode::NormalsCameraSpace
+        | SplatRenderMode::AlphaOnly
+        | SplatRenderMode::WorldPosition
+        | SplatRenderMode::Feature { .. } => SplatRenderMode::Default,
+    };
+
+    vec![
+        "Exported from Brush".to_owned(),
+        "Vertical axis: y".to_owned(),
+        format!("SH degree: {}", splats.sh_degree()),
+        format!("SplatRenderMode: {render_mode}"),
+    ]
+}
+
 pub async fn splat_to_ply<B: Backend>(splats: Splats<B>) -> Result<Vec<u8>, SerializeError> {
     let splats = splats.with_normed_rotations();
-    let sh_degree = splats.sh_degree();
-    let render_mode = splats.render_mode;
     let ply = read_splat_data(splats.clone()).await;
+    serde_ply::to_bytes(
+        &ply,
+        SerializeOptions::binary_le().with_comments(ply_comments(&splats)),
+    )
+}
 
-    let render_mode_str = match render_mode {
-        SplatRenderMode::Default => "default",
-        SplatRenderMode::Mip => "mip",
-    };
+/// Save splats to a binary little endian PLY file, using the same properties as
+/// [`crate::load_splat_from_ply`] reads. Blocks until the splat data is read back.
+pub fn save_splat_to_ply<B: Backend>(splats: &Splats<B>, path: &Path) -> Result<(), SaveError> {
+    let splats = splats.clone().with_normed_rotations();
+    let data = splat_transaction(&splats).execute();
+    let ply = ply_from_data(&splats, data);
+    let bytes = serde_ply::to_bytes(
+        &ply,
+        SerializeOptions::binary_le().with_comments(ply_comments(&splats)),
+    )?;
+    std::fs::write(path, bytes)?;
+    Ok(())
+}
 
-    let comments = vec![
-        "Exported from Brush".to_owned(),
-        "Vertical axis: y".to_owned(),
-        format!("SH degree: {}", sh_degree),
-        format!("SplatRenderMode: {}", render_mode_str),
-    ];
-    serde_ply::to_bytes(&ply, SerializeOptions::binary_le().with_comments(comments))
+/// Async version of [`save_splat_to_ply`].
+#[cfg(not(target_family = "wasm"))]
+pub async fn save_splat_to_ply_async<B: Backend>(
+    splats: &Splats<B>,
+    path: &Path,
+) -> Result<(), SaveError> {
+    let bytes = splat_to_ply(splats.clone()).await?;
+    tokio::fs::write(path, bytes).await?;
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::import::load_splat_from_ply;
-    use crate::test_utils::create_test_splats;
+    use crate::test_utils::{create_test_splats, create_test_splats_with_count};
     use brush_render::MainBackend;
     use brush_render::gaussian_splats::SplatRenderMode;
     use burn::backend::wgpu::WgpuDevice;
@@ -280,4 +335,61 @@ mod tests {
             assert_coeffs_match(&original_splats, &imported_splats).await;
         }
     }
+
+    async fn tensor_values(tensor: burn::Tensor<MainBackend, 2>) -> Vec<f32> {
+        tensor.into_data_async().await.unwrap().into_vec().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_save_roundtrip() {
+        let device = WgpuDevice::default();
+        let dir = tempfile::tempdir().unwrap();
+
+        let ply_bytes = splat_to_ply(create_test_splats_with_count(2, 8))
+            .await
+            .unwrap();
+        let loaded = load_splat_from_ply(Cursor::new(ply_bytes), None)
+            .await
+            .unwrap()
+            .data
+            .into_splats(&device, SplatRenderMode::Default);
+
+        let path = dir.path().join("saved.ply");
+        save_splat_to_ply(&loaded, &path).unwrap();
+        let async_path = dir.path().join("saved_async.ply");
+        save_splat_to_ply_async(&loaded, &async_path).await.unwrap();
+        assert_eq!(
+            std::fs::read(&path).unwrap(),
+            std::fs::read(&async_path).unwrap()
+        );
+
+        let file = tokio::fs::File::open(&path).await.unwrap();
+        let reloaded = load_splat_from_ply(file, None)
+            .await
+            .unwrap()
+            .data
+            .into_splats(&device, SplatRenderMode::Default);
+
+        assert_eq!(reloaded.num_splats(), loaded.num_splats());
+        assert_eq!(reloaded.sh_degree(), loaded.sh_degree());
+        let pairs = [
+            (loaded.means.val(), reloaded.means.val()),
+            (loaded.log_scales.val(), reloaded.log_scales.val()),
+            (loaded.rotations.val(), reloaded.rotations.val()),
+            (
+                loaded.raw_opacities.val().unsqueeze_dim(1),
+                reloaded.raw_opacities.val().unsqueeze_dim(1),
+            ),
+        ];
+        for (orig, saved) in pairs {
+            for (a, b) in tensor_values(orig)
+                .await
+                .into_iter()
+                .zip(tensor_values(saved).await)
+            {
+                assert!((a - b).abs() <= f32::EPSILON, "mismatch: {a} != {b}");
+            }
+        }
+        assert_coeffs_match(&loaded, &reloaded).await;
+    }
 }
@@ -1,16 +1,18 @@
-use std::pin::pin;
+use std::pin::{Pin, pin};
+use std::task::{Context, Poll};
 use std::time::Duration;
 
 use async_fn_stream::{TryStreamEmitter, try_fn_stream};
 use brush_render::gaussian_splats::{SplatRenderMode, Splats, inverse_sigmoid};
 use brush_render::sh::rgb_to_sh;
 use brush_vfs::SendNotWasm;
+use burn::tensor::TensorData;
 use glam::{Vec3, Vec4Swizzles};
+use rand::{Rng, SeedableRng, rngs::StdRng};
 use serde::Deserialize;
 use serde::de::{DeserializeSeed, Error};
 use serde_ply::{DeserializeError, PlyChunkedReader, RowVisitor};
-use tokio::io::AsyncRead;
-use tokio::io::AsyncReadExt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, ReadBuf};
 use tokio_stream::{Stream, StreamExt};
 use tokio_with_wasm::alias as tokio_wasm;
 
@@ -35,6 +37,9 @@ pub struct SplatData {
     pub log_scales: Option<Vec<f32>>,
     pub sh_coeffs: Option<Vec<f32>>,
     pub raw_opacities: Option<Vec<f32>>,
+    /// Time center, log time scale and xyz velocity of dynamic splats, from the `t`,
+    /// `t_scale` and `velocity_0..2` properties.
+    pub motion: Option<Vec<f32>>,
 }
 
 impl SplatData {
@@ -42,7 +47,89 @@ impl SplatData {
         self.means.len() / 3
     }
 
-    /// Convert into Splats using simple defaults for missing fields.
+    /// Remove the first `count` splats and return them.
+    fn split_front(&mut self, count: usize) -> Self {
+        let total = self.num_splats();
+        let split = |values: &mut Vec<f32>| {
+            let per_splat = values.len() / total.max(1);
+            values.drain(..count * per_splat).collect()
+        };
+        Self {
+            means: split(&mut self.means),
+            rotations: self.rotations.as_mut().map(split),
+            log_scales: self.log_scales.as_mut().map(split),
+            sh_coeffs: self.sh_coeffs.as_mut().map(split),
+            raw_opacities: self.raw_opacities.as_mut().map(split),
+            motion: self.motion.as_mut().map(split),
+        }
+    }
+
+    /// Keep only the splats at `indices`, in that order.
+    pub fn select(&self, indices: &[usize]) -> Self {
+        let total = self.num_splats();
+        let gather = |values: &Vec<f32>| {
+            let per_splat = values.len() / total.max(1);
+            indices
+                .iter()
+                .flat_map(|&i| &values[i * per_splat..(i + 1) * per_splat])
+                .copied()
+                .collect()
+        };
+        Self {
+            means: gather(&self.means),
+            rotations: self.rotations.as_ref().map(gather),
+            log_scales: self.log_scales.as_ref().map(gather),
+            sh_coeffs: self.sh_coeffs.as_ref().map(gather),
+            raw_opacities: self.raw_opacities.as_ref().map(gather),
+            motion: self.motion.as_ref().map(gather),
+        }
+    }
+
+    /// Pick `count` splats at random, the same ones for the same `seed`. Unlike taking every
+    /// nth splat this isn't biased by the order of the file. The picked splats keep their
+    /// original order.
+    pub fn random_subset(&self, count: usize, seed: u64) -> Self {
+        let total = self.num_splats();
+        let count = count.min(total);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut indices: Vec<usize> = (0..total).collect();
+        // Partial Fisher-Yates shuffle, only the first `count` entries have to be shuffled.
+        for i in 0..count {
+            let j = rng.random_range(i..total);
+            indices.swap(i, j);
+        }
+        indices.truncate(count);
+        indices.sort_unstable();
+        self.select(&indices)
+    }
+
+    /// PLY properties that rendering with `mode` needs but the data doesn't have. Missing
+    /// fields would silently be filled with defaults by [`Self::into_splats`].
+    pub fn missing_for_render_mode(&self, mode: SplatRenderMode) -> Vec<&'static str> {
+        let mut missing = vec![];
+        match mode {
+            SplatRenderMode::Default | SplatRenderMode::Mip => {
+                if self.sh_coeffs.is_none() {
+                    missing.push("f_dc_0..2");
+                }
+            }
+            SplatRenderMode::NormalsWorldSpace | SplatRenderMode::NormalsCameraSpace => {
+                if self.rotations.is_none() {
+                    missing.push("rot_0..3");
+                }
+                if self.log_scales.is_none() {
+                    missing.push("scale_0..2");
+                }
+            }
+            SplatRenderMode::AlphaOnly
+            | SplatRenderMode::WorldPosition
+            | SplatRenderMode::Feature { .. } => {}
+        }
+        missing
+    }
+
+    /// Convert into Splats using simple defaults for missing fields. Splats with a time
+    /// center become dynamic, missing time scales and velocities default to zero.
     pub fn into_splats<B: burn::prelude::Backend>(
         self,
         device: &B::Device,
@@ -58,9 +145,16 @@ impl SplatData {
             .raw_opacities
             .unwrap_or_else(|| vec![inverse_sigmoid(0.5); n_splats]);
 
-        Splats::from_raw(
+        let splats = Splats::from_raw(
             self.means, rotations, log_scales, sh_coeffs, opacities, mode, device,
-        )
+        );
+        match self.motion {
+            Some(motion) => {
+                let motion = TensorData::new(motion, [n_splats, 5]);
+                splats.with_motion(burn::Tensor::from_data(motion, device))
+            }
+            None => splats,
+        }
     }
 }
 
@@ -103,7 +197,38 @@ impl TimedUpdate {
     }
 }
 
-fn interleave_coeffs(sh_dc: Vec3, sh_rest: &[f32], result: &mut Vec<f32>) {
+/// The up axis and render mode written in the comments of a PLY header.
+pub(crate) fn comment_metadata(comments: &[String]) -> (Option<Vec3>, Option<SplatRenderMode>) {
+    let up_axis = comments
+        .iter()
+        .filter_map(|c| {
+            match c
+                .to_lowercase()
+                .strip_prefix("vertical axis: ")
+                .map(|s| s.trim())
+            {
+                Some("x") => Some(Vec3::X),
+                Some("y") => Some(Vec3::NEG_Y),
+                Some("z") => Some(Vec3::NEG_Z),
+                _ => None,
+            }
+        })
+        .next_back();
+
+    let render_mode = comments
+        .iter()
+        .filter_map(|c| {
+            c.strip_prefix("SplatRenderMode: ")?
+                .parse::<SplatRenderMode>()
+                .ok()
+        })
+        // Only the way the splats are stored, other modes are ways to view them.
+        .filter(|mode| matches!(mode, SplatRenderMode::Default | SplatRenderMode::Mip))
+        .next_back();
+    (up_axis, render_mode)
+}
+
+pub(crate) fn interleave_coeffs(sh_dc: Vec3, sh_rest: &[f32], result: &mut Vec<f32>) {
     let channels = 3;
     let coeffs_per_channel = sh_rest.len() / channels;
 
@@ -154,48 +279,87 @@ pub async fn load_splat_from_ply<T: AsyncRead + SendNotWasm + Unpin>(
 }
 
 pub fn stream_splat_from_ply<T: AsyncRead + SendNotWasm + Unpin>(
+    reader: T,
+    subsample_points: Option<u32>,
+    streaming: bool,
+) -> impl Stream<Item = Result<SplatMessage, DeserializeError>> {
+    stream_ply(reader, subsample_points, streaming, None)
+}
+
+/// Reader which reports how many bytes have been read so far.
+struct ProgressReader<T, F> {
+    inner: T,
+    bytes_read: u64,
+    total_bytes: u64,
+    progress_cb: F,
+}
+
+impl<T: AsyncRead + Unpin, F: FnMut(u64, u64) + Unpin> AsyncRead for ProgressReader<T, F> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let before = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        let read = (buf.filled().len() - before) as u64;
+        if read > 0 {
+            self.bytes_read += read;
+            let (bytes_read, total_bytes) = (self.bytes_read, self.total_bytes);
+            (self.progress_cb)(bytes_read, total_bytes);
+        }
+        poll
+    }
+}
+
+/// Load a PLY file in chunks of (at most) `chunk_size` splats.
+///
+/// Unlike [`stream_splat_from_ply`], which emits all splats loaded so far with each
+/// update, every message only holds the splats parsed since the last one. This means
+/// the caller can upload the splats incrementally without ever holding the whole file in
+/// memory. `progress_cb` is called with `(bytes_read, total_bytes)` as the file is read.
+///
+/// Nb: Compressed PLYs store the SH coefficients after all splats, so those are only
+/// split into chunks after the whole file is parsed.
+pub async fn load_splat_from_ply_streaming<T, F>(
+    mut reader: T,
+    chunk_size: usize,
+    subsample_points: Option<u32>,
+    progress_cb: F,
+) -> Result<impl Stream<Item = Result<SplatMessage, DeserializeError>>, DeserializeError>
+where
+    T: AsyncRead + AsyncSeek + SendNotWasm + Unpin,
+    F: FnMut(u64, u64) + SendNotWasm + Unpin,
+{
+    if chunk_size == 0 {
+        return Err(DeserializeError::custom("Chunk size must be at least 1"));
+    }
+
+    let start = reader.stream_position().await?;
+    let total_bytes = reader.seek(std::io::SeekFrom::End(0)).await? - start;
+    reader.seek(std::io::SeekFrom::Start(start)).await?;
+
+    let reader = ProgressReader {
+        inner: reader,
+        bytes_read: 0,
+        total_bytes,
+        progress_cb,
+    };
+    Ok(stream_ply(reader, subsample_points, true, Some(chunk_size)))
+}
+
+fn stream_ply<T: AsyncRead + SendNotWasm + Unpin>(
     mut reader: T,
     subsample_points: Option<u32>,
     streaming: bool,
+    chunk_size: Option<usize>,
 ) -> impl Stream<Item = Result<SplatMessage, DeserializeError>> {
-    try_fn_stream(|emitter| async move {
+    try_fn_stream(move |emitter| async move {
         let mut file = PlyChunkedReader::new();
         read_chunk(&mut reader, file.buffer_mut()).await?;
 
         let header = file.header().expect("Must have header");
-        // Parse some metadata.
-        let up_axis = header
-            .comments
-            .iter()
-            .filter_map(|c| {
-                match c
-                    .to_lowercase()
-                    .strip_prefix("vertical axis: ")
-                    .map(|s| s.trim())
-                {
-                    Some("x") => Some(Vec3::X),
-                    Some("y") => Some(Vec3::NEG_Y),
-                    Some("z") => Some(Vec3::NEG_Z),
-                    _ => None,
-                }
-            })
-            .next_back();
-
-        let render_mode = header
-            .comments
-            .iter()
-            .filter_map(|c| {
-                match c
-                    .to_lowercase()
-                    .strip_prefix("SplatRenderMode: ")
-                    .map(|s| s.trim())
-                {
-                    Some("mip") => Some(SplatRenderMode::Mip),
-                    Some("default") => Some(SplatRenderMode::Default),
-                    _ => None,
-                }
-            })
-            .next_back();
+        let (up_axis, render_mode) = comment_metadata(&header.comments);
 
         // Check whether there is a vertex header that has at least XYZ.
         let has_vertex = header.elem_defs.iter().any(|el| el.name == "vertex");
@@ -214,7 +378,10 @@ pub fn stream_splat_from_ply<T: AsyncRead + SendNotWasm + Unpin>(
         };
 
         let subsample = subsample_points.unwrap_or(1) as usize;
-        let mut updater = TimedUpdate::new(streaming.then(|| Duration::from_millis(1500)));
+        // Chunks are emitted as soon as they're full, rather than on a timer.
+        let mut updater = TimedUpdate::new(
+            (streaming && chunk_size.is_none()).then(|| Duration::from_millis(1500)),
+        );
 
         match ply_type {
             PlyFormat::Ply => {
@@ -226,6 +393,7 @@ pub fn stream_splat_from_ply<T: AsyncRead + SendNotWasm + Unpin>(
                     &emitter,
                     render_mode,
                     &mut updater,
+                    chunk_size,
                 )
                 .await?;
             }
@@ -238,6 +406,7 @@ pub fn stream_splat_from_ply<T: AsyncRead + SendNotWasm + Unpin>(
                     emitter,
                     render_mode,
                     updater,
+                    chunk_size,
                 )
                 .await?;
             }
@@ -250,6 +419,27 @@ fn progress(index: usize, len: usize) -> f32 {
     ((index + 1) as f32) / len as f32
 }
 
+/// Emit chunks of `chunk_size` splats from `data`. When `all` is set any remaining splats
+/// are emitted as well, otherwise they're left in `data`.
+async fn emit_chunks(
+    emitter: &StreamEmitter,
+    data: &mut SplatData,
+    chunk_size: usize,
+    all: bool,
+    meta: impl Fn() -> ParseMetadata,
+) {
+    while data.num_splats() >= chunk_size || (all && data.num_splats() > 0) {
+        let count = data.num_splats().min(chunk_size);
+        let chunk = data.split_front(count);
+        emitter
+            .emit(SplatMessage {
+                meta: meta(),
+                data: chunk,
+            })
+            .await;
+    }
+}
+
 fn vec_exact(cap: usize) -> Vec<f32> {
     let mut r = vec![];
     r.reserve_exact(cap);
@@ -264,6 +454,7 @@ async fn parse_ply<T: AsyncRead + Unpin>(
     emitter: &StreamEmitter,
     render_mode: Option<SplatRenderMode>,
     update: &mut TimedUpdate,
+    chunk_size: Option<usize>,
 ) -> Result<(), DeserializeError> {
     let header = file.header().expect("Must have header");
     let vertex = header
@@ -282,18 +473,21 @@ async fn parse_ply<T: AsyncRead + Unpin>(
         })
         .count();
 
+    // When emitting chunks, only reserve space for a single chunk (and some slack
+    // as a read can parse more rows than that).
+    let capacity = chunk_size.map_or(max_splats, |size| size.min(max_splats));
+
     let mut data = SplatData {
-        means: vec_exact(max_splats * 3),
+        means: vec_exact(capacity * 3),
         rotations: vertex
             .has_property("rot_0")
-            .then(|| vec_exact(max_splats * 4)),
+            .then(|| vec_exact(capacity * 4)),
         log_scales: vertex
             .has_property("scale_0")
-            .then(|| vec_exact(max_splats * 3)),
-        sh_coeffs: (sh_count > 0).then(|| vec_exact(max_splats * sh_count)),
-        raw_opacities: vertex
-            .has_property("opacity")
-            .then(|| vec_exact(max_splats)),
+            .then(|| vec_exact(capacity * 3)),
+        sh_coeffs: (sh_count > 0).then(|| vec_exact(capacity * sh_count)),
+        raw_opacities: vertex.has_property("opacity").then(|| vec_exact(capacity)),
+        motion: vertex.has_property("t").then(|| vec_exact(capacity * 5)),
     };
 
     let mut row_index: usize = 0;
@@ -336,9 +530,33 @@ async fn parse_ply<T: AsyncRead + Unpin>(
             if let Some(opacity) = &mut data.raw_opacities {
                 opacity.push(gauss.opacity);
             }
+            if let Some(motion) = &mut data.motion {
+                motion.extend([
+                    gauss.t,
+                    gauss.t_scale,
+                    gauss.velocity_0,
+                    gauss.velocity_1,
+                    gauss.velocity_2,
+                ]);
+            }
         })
         .deserialize(&mut *file)?;
 
+        if let Some(chunk_size) = chunk_size {
+            let done = row_index == total_splats;
+            let meta = || ParseMetadata {
+                total_splats: max_splats as u32,
+                up_axis,
+                progress: progress(row_index, total_splats),
+                render_mode,
+            };
+            emit_chunks(emitter, &mut data, chunk_size, done, meta).await;
+            if done {
+                return Ok(());
+            }
+            continue;
+        }
+
         if update.should_update(row_index as f32 / total_splats as f32) || row_index == total_splats
         {
             let meta = ParseMetadata {
@@ -371,6 +589,7 @@ async fn parse_compressed_ply<T: AsyncRead + Unpin>(
     emitter: StreamEmitter,
     render_mode: Option<SplatRenderMode>,
     mut update: TimedUpdate,
+    chunk_size: Option<usize>,
 ) -> Result<(), DeserializeError> {
     #[derive(Default, Deserialize)]
     struct QuantMeta {
@@ -480,9 +699,10 @@ async fn parse_compressed_ply<T: AsyncRead + Unpin>(
         })
         .deserialize(&mut file)?;
 
-        // Occasionally send some updated splats.
-        if update.should_update(row_count as f32 / total_splats as f32) || row_count == total_splats
-        {
+        // Occasionally send some updated splats. When emitting chunks, wait for the SH
+        // coefficients so every splat is only sent once.
+        let last_update = row_count == total_splats && (chunk_size.is_none() || sh_vals.is_none());
+        if update.should_update(row_count as f32 / total_splats as f32) || last_update {
             // Leave 20% of progress for loading the SH's, just an estimate.
             let max_time = if sh_vals.is_some() { 0.8 } else { 1.0 };
             let progress = progress(row_count, total_splats) * max_time;
@@ -493,14 +713,25 @@ async fn parse_compressed_ply<T: AsyncRead + Unpin>(
                 render_mode,
             };
 
-            let data = SplatData {
+            let mut data = SplatData {
                 means: means.clone(),
                 rotations: Some(rotations.clone()),
                 log_scales: Some(log_scales.clone()),
                 sh_coeffs: Some(sh_coeffs.clone()),
                 raw_opacities: Some(opacity.clone()),
+                motion: None,
             };
-            emitter.emit(SplatMessage { meta, data }).await;
+            if let Some(chunk_size) = chunk_size {
+                let meta = || ParseMetadata {
+                    total_splats: max_splats as u32,
+                    up_axis,
+                    progress,
+                    render_mode,
+                };
+                emit_chunks(&emitter, &mut data, chunk_size, true, meta).await;
+            } else {
+                emitter.emit(SplatMessage { meta, data }).await;
+            }
         }
     }
 
@@ -536,20 +767,26 @@ async fn parse_compressed_ply<T: AsyncRead + Unpin>(
             .deserialize(&mut file)?;
         }
 
-        let meta = ParseMetadata {
-            total_splats: (means.len() / 3) as u32,
+        let total_splats = (means.len() / 3) as u32;
+        let meta = || ParseMetadata {
+            total_splats,
             up_axis,
             progress: 1.0,
             render_mode,
         };
-        let data = SplatData {
+        let mut data = SplatData {
             means,
             rotations: Some(rotations),
             log_scales: Some(log_scales),
             sh_coeffs: Some(total_coeffs),
             raw_opacities: Some(opacity),
+            motion: None,
         };
-        emitter.emit(SplatMessage { meta, data }).await;
+        if let Some(chunk_size) = chunk_size {
+            emit_chunks(&emitter, &mut data, chunk_size, true, meta).await;
+        } else {
+            emitter.emit(SplatMessage { meta: meta(), data }).await;
+        }
     }
 
     Ok(())
@@ -580,6 +817,129 @@ mod tests {
         assert!(imported_message.data.raw_opacities.is_some());
     }
 
+    #[tokio::test]
+    async fn test_import_render_mode() {
+        for mode in [SplatRenderMode::Default, SplatRenderMode::Mip] {
+            let mut splats = create_test_splats(0);
+            splats.render_mode = mode;
+            let ply_bytes = splat_to_ply(splats).await.unwrap();
+            assert!(
+                String::from_utf8_lossy(&ply_bytes).contains(&format!("SplatRenderMode: {mode}"))
+            );
+
+            let imported = load_splat_from_ply(Cursor::new(ply_bytes), None)
+                .await
+                .unwrap();
+            assert_eq!(imported.meta.render_mode, Some(mode));
+        }
+    }
+
+    #[test]
+    fn test_missing_for_render_mode() {
+        let points = SplatData {
+            means: vec![0.0; 6],
+            rotations: None,
+            log_scales: None,
+            sh_coeffs: Some(vec![0.5; 6]),
+            raw_opacities: None,
+            motion: None,
+        };
+        assert!(
+            points
+                .missing_for_render_mode(SplatRenderMode::Default)
+                .is_empty()
+        );
+        assert!(
+            points
+                .missing_for_render_mode(SplatRenderMode::AlphaOnly)
+                .is_empty()
+        );
+        assert_eq!(
+            points.missing_for_render_mode(SplatRenderMode::NormalsWorldSpace),
+            ["rot_0..3", "scale_0..2"]
+        );
+
+        let uncolored = SplatData {
+            sh_coeffs: None,
+            ..points
+        };
+        assert_eq!(
+            uncolored.missing_for_render_mode(SplatRenderMode::Mip),
+            ["f_dc_0..2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_random_subset() {
+        let splats = create_test_splats_with_count(1, 20);
+        let data = load_splat_from_ply(Cursor::new(splat_to_ply(splats).await.unwrap()), None)
+            .await
+            .unwrap()
+            .data;
+
+        let subset = data.random_subset(5, 7);
+        assert_eq!(subset.num_splats(), 5);
+        assert_eq!(subset.sh_coeffs.as_ref().unwrap().len(), 5 * 12);
+        assert_eq!(subset.means, data.random_subset(5, 7).means);
+        assert_ne!(subset.means, data.random_subset(5, 8).means);
+
+        // Picked splats are distinct and keep their order, the test splats have increasing x.
+        let xs: Vec<f32> = subset.means.chunks(3).map(|m| m[0]).collect();
+        assert!(xs.windows(2).all(|w| w[0] < w[1]));
+
+        assert_eq!(data.random_subset(100, 7).num_splats(), 20);
+    }
+
+    #[tokio::test]
+    async fn test_import_motion() {
+        let props = [
+            "x",
+            "y",
+            "z",
+            "t",
+            "t_scale",
+            "velocity_0",
+            "velocity_1",
+            "velocity_2",
+        ];
+        let mut ply = b"ply\nformat binary_little_endian 1.0\nelement vertex 2\n".to_vec();
+        for prop in props {
+            ply.extend(format!("property float {prop}\n").bytes());
+        }
+        ply.extend(b"end_header\n");
+        let rows: [[f32; 8]; 2] = [
+            [1.0, 2.0, 3.0, 0.25, -1.0, 0.5, 0.0, -0.5],
+            [4.0, 5.0, 6.0, 0.75, -2.0, 0.0, 1.0, 0.0],
+        ];
+        for value in rows.iter().flatten() {
+            ply.extend(value.to_le_bytes());
+        }
+
+        let data = load_splat_from_ply(Cursor::new(ply), None)
+            .await
+            .unwrap()
+            .data;
+        assert_eq!(
+            data.motion,
+            Some(vec![
+                0.25, -1.0, 0.5, 0.0, -0.5, //
+                0.75, -2.0, 0.0, 1.0, 0.0,
+            ])
+        );
+        assert_eq!(
+            data.select(&[1]).motion,
+            Some(vec![0.75, -2.0, 0.0, 1.0, 0.0])
+        );
+
+        // Static files have no motion.
+        let static_ply = splat_to_ply(create_test_splats(0)).await.unwrap();
+        let data = load_splat_from_ply(Cursor::new(static_ply), None)
+            .await
+            .unwrap()
+            .data;
+        assert!(data.motion.is_none());
+    }
+
     #[tokio::test]
     async fn test_import_different_sh_degrees() {
         for degree in [0, 1, 2] {
@@ -614,4 +974,47 @@ mod tests {
         let imported_message = load_splat_from_ply(cursor, Some(2)).await.unwrap();
         assert_eq!(imported_message.data.num_splats(), 2);
     }
+
+    #[tokio::test]
+    async fn test_import_streaming_chunks() {
+        let original_splats = create_test_splats_with_count(1, 10);
+        let ply_bytes = splat_to_ply(original_splats).await.unwrap();
+        let file_size = ply_bytes.len() as u64;
+
+        let full = load_splat_from_ply(Cursor::new(ply_bytes.clone()), None)
+            .await
+            .unwrap();
+
+        let mut last_progress = (0, 0);
+        let stream = load_splat_from_ply_streaming(
+            Cursor::new(ply_bytes.clone()),
+            4,
+            None,
+            |read, total| {
+                last_progress = (read, total);
+            },
+        )
+        .await
+        .unwrap();
+        let chunks: Vec<_> = stream.collect::<Result<_, _>>().await.unwrap();
+
+        let sizes: Vec<_> = chunks.iter().map(|c| c.data.num_splats()).collect();
+        assert_eq!(sizes, [4, 4, 2]);
+        let means: Vec<f32> = chunks.iter().flat_map(|c| c.data.means.clone()).collect();
+        assert_eq!(means, full.data.means);
+        let coeffs: Vec<f32> = chunks
+            .iter()
+            .flat_map(|c| c.data.sh_coeffs.clone().unwrap())
+            .collect();
+        assert_eq!(Some(coeffs), full.data.sh_coeffs);
+        assert_eq!(last_progress, (file_size, file_size));
+
+        // Subsampling applies before chunking.
+        let stream = load_splat_from_ply_streaming(Cursor::new(ply_bytes), 4, Some(2), |_, _| {})
+            .await
+            .unwrap();
+        let chunks: Vec<_> = stream.collect::<Result<_, _>>().await.unwrap();
+        let sizes: Vec<_> = chunks.iter().map(|c| c.data.num_splats()).collect();
+        assert_eq!(sizes, [4, 1]);
+    }
 }
@@ -6,15 +6,28 @@ pub mod export;
 pub mod import;
 pub mod ply_gaussian;
 pub mod quant;
+#[cfg(any(feature = "export", feature = "import"))]
+pub mod quantised;
+#[cfg(feature = "import")]
+pub mod splat_binary;
 
 // Re-export main functionality
+#[cfg(all(feature = "export", not(target_family = "wasm")))]
+pub use export::save_splat_to_ply_async;
 #[cfg(feature = "export")]
-pub use export::splat_to_ply;
+pub use export::{SaveError, save_splat_to_ply, splat_to_ply};
 #[cfg(feature = "import")]
 pub use import::{
-    ParseMetadata, SplatData, SplatMessage, load_splat_from_ply, stream_splat_from_ply,
+    ParseMetadata, SplatData, SplatMessage, load_splat_from_ply, load_splat_from_ply_streaming,
+    stream_splat_from_ply,
 };
 pub use ply_gaussian::PlyGaussian;
+#[cfg(feature = "import")]
+pub use quantised::load_splat_from_ply_quantised;
+#[cfg(feature = "export")]
+pub use quantised::{save_splat_to_ply_quantised, splat_to_ply_quantised};
+#[cfg(feature = "import")]
+pub use splat_binary::load_splat_from_binary;
 
 // Re-export serde-ply types for compatibility
 #[cfg(feature = "import")]
@@ -90,6 +90,18 @@ pub struct PlyGaussian {
     #[serde(default)]
     pub(crate) _sh_rest_fields: (),
 
+    // Motion of dynamic (4D) splats, see `Splats::motion`.
+    #[serde(default)]
+    pub(crate) t: f32,
+    #[serde(default)]
+    pub(crate) t_scale: f32,
+    #[serde(default)]
+    pub(crate) velocity_0: f32,
+    #[serde(default)]
+    pub(crate) velocity_1: f32,
+    #[serde(default)]
+    pub(crate) velocity_2: f32,
+
     // Color overrides. Potentially quantized.
     #[serde(default, alias = "r", skip_serializing, deserialize_with = "de_quant")]
     pub(crate) red: Option<f32>,
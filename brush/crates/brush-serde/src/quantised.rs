@@ -0,0 +1,629 @@
+//! Lossy PLYs with the splat attributes stored as 8 or 16 bit fixed point integers, which
+//! map linearly from their integers to a range.
+//!
+//! Like compressed PLYs, the splats are sorted along a Morton curve and grouped in chunks of
+//! [`CHUNK_SIZE`]. The positions and log scales are quantised to the range of their chunk,
+//! stored in a `chunk` element as `min_x`, `max_x`, etc. Nearby splats share a chunk, so these
+//! ranges are much tighter than the bounds of the whole scene. The other properties have
+//! their range in a header comment like `Quantised range: opacity -1.5 2.25`. Rotations are
+//! stored as their three smallest components, and the index of the largest one.
+//!
+//! Regular PLY readers don't know about the ranges, so read these files with
+//! [`load_splat_from_ply_quantised`].
+
+#[cfg(feature = "export")]
+use std::path::Path;
+
+#[cfg(feature = "export")]
+use brush_render::gaussian_splats::Splats;
+#[cfg(feature = "export")]
+use burn::prelude::Backend;
+#[cfg(feature = "import")]
+use glam::Vec3;
+#[cfg(feature = "import")]
+use serde::de::Error;
+#[cfg(feature = "import")]
+use serde_ply::DeserializeError;
+#[cfg(feature = "import")]
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+#[cfg(feature = "export")]
+use crate::export::{
+    DynamicPlyGaussian, SaveError, ply_comments, ply_from_data, splat_transaction,
+};
+#[cfg(feature = "import")]
+use crate::import::{ParseMetadata, SplatData, SplatMessage, comment_metadata, interleave_coeffs};
+
+const BITS_COMMENT: &str = "Quantised bits: ";
+const CHUNK_COMMENT: &str = "Quantised chunk size: ";
+const RANGE_COMMENT: &str = "Quantised range: ";
+
+/// Number of splats sharing the ranges of their positions and scales.
+const CHUNK_SIZE: usize = 256;
+/// Properties quantised to the range of their chunk.
+const CHUNKED: [&str; 6] = ["x", "y", "z", "scale_0", "scale_1", "scale_2"];
+/// Index of the largest rotation component, stored as is.
+const ROT_LARGEST: &str = "rot_largest";
+/// The other rotation components, in order.
+const ROT_SMALL: [&str; 3] = ["rot_small_0", "rot_small_1", "rot_small_2"];
+
+/// Largest integer of a `bits` wide property.
+fn max_level(bits: u8) -> f32 {
+    ((1u32 << bits) - 1) as f32
+}
+
+/// Quantise the splats to `bits_per_attribute` (8 or 16) bit integers and encode them as a
+/// binary little endian PLY, see the [module docs](self).
+#[cfg(feature = "export")]
+pub async fn splat_to_ply_quantised<B: Backend>(
+    splats: Splats<B>,
+    bits_per_attribute: u8,
+) -> Result<Vec<u8>, SaveError> {
+    let splats = splats.with_normed_rotations();
+    let data = splat_transaction(&splats)
+        .execute_async()
+        .await
+        .expect("Failed to fetch splat data");
+    encode_quantised(&splats, data, bits_per_attribute)
+}
+
+/// Save splats to a quantised PLY file, see [`splat_to_ply_quantised`]. Blocks until the
+/// splat data is read back.
+#[cfg(feature = "export")]
+pub fn save_splat_to_ply_quantised<B: Backend>(
+    splats: &Splats<B>,
+    path: &Path,
+    bits_per_attribute: u8,
+) -> Result<(), SaveError> {
+    let splats = splats.clone().with_normed_rotations();
+    let data = splat_transaction(&splats).execute();
+    let bytes = encode_quantised(&splats, data, bits_per_attribute)?;
+    std::fs::write(path, bytes)?;
+    Ok(())
+}
+
+/// Order of the splats along a Morton curve through the bounds of their positions.
+#[cfg(feature = "export")]
+fn morton_order(vertices: &[DynamicPlyGaussian]) -> Vec<usize> {
+    let position = |v: &DynamicPlyGaussian| [v.x, v.y, v.z];
+    let mut min = [f32::INFINITY; 3];
+    let mut max = [f32::NEG_INFINITY; 3];
+    for v in vertices {
+        for (axis, value) in position(v).into_iter().enumerate() {
+            min[axis] = min[axis].min(value);
+            max[axis] = max[axis].max(value);
+        }
+    }
+    // Interleave the bits of the 10 bit cell of each axis.
+    let code = |v: &DynamicPlyGaussian| {
+        let mut code = 0u32;
+        for (axis, value) in position(v).into_iter().enumerate() {
+            let extent = (max[axis] - min[axis]).max(f32::MIN_POSITIVE);
+            let cell = ((value - min[axis]) / extent * 1023.0) as u32;
+            for bit in 0..10 {
+                code |= ((cell >> bit) & 1) << (bit * 3 + axis);
+            }
+        }
+        code
+    };
+    let mut order: Vec<usize> = (0..vertices.len()).collect();
+    order.sort_by_key(|&i| code(&vertices[i]));
+    order
+}
+
+/// The three smallest components of a unit quaternion and the index of the largest one,
+/// flipped so the largest is positive and can be recomputed from the others.
+#[cfg(feature = "export")]
+fn smallest_three(rotation: [f32; 4]) -> (usize, [f32; 3]) {
+    let largest = (0..4)
+        .max_by(|&a, &b| rotation[a].abs().total_cmp(&rotation[b].abs()))
+        .expect("Quaternions have four components");
+    let sign = rotation[largest].signum();
+    let mut small = [0.0; 3];
+    for (value, i) in small.iter_mut().zip((0..4).filter(|&i| i != largest)) {
+        *value = rotation[i] * sign;
+    }
+    (largest, small)
+}
+
+/// How a property of a vertex is stored.
+#[cfg(feature = "export")]
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Storage {
+    /// Quantised to the range of its chunk.
+    Chunk,
+    /// Quantised to the range of the whole scene.
+    Global,
+    /// A small integer stored as is.
+    Index,
+}
+
+#[cfg(feature = "export")]
+fn encode_quantised<B: Backend>(
+    splats: &Splats<B>,
+    data: Vec<burn::tensor::TensorData>,
+    bits: u8,
+) -> Result<Vec<u8>, SaveError> {
+    if bits != 8 && bits != 16 {
+        return Err(SaveError::UnsupportedBits(bits));
+    }
+    let vertices = ply_from_data(splats, data).vertex;
+    let order = morton_order(&vertices);
+    let num_rest = vertices.first().map_or(0, |v| v.rest_coeffs.len());
+    let rotations: Vec<_> = order
+        .iter()
+        .map(|&i| {
+            let v = &vertices[i];
+            smallest_three([v.rot_0, v.rot_1, v.rot_2, v.rot_3])
+        })
+        .collect();
+
+    // One column of values per property, in the sorted order of the rows.
+    let mut columns: Vec<(String, Storage, Vec<f32>)> = vec![];
+    let mut column = |name: &str, storage: Storage, value: &dyn Fn(usize) -> f32| {
+        let values = (0..order.len()).map(value).collect();
+        columns.push((name.to_owned(), storage, values));
+    };
+    let vertex = |i: usize| &vertices[order[i]];
+    column("x", Storage::Chunk, &|i| vertex(i).x);
+    column("y", Storage::Chunk, &|i| vertex(i).y);
+    column("z", Storage::Chunk, &|i| vertex(i).z);
+    column("scale_0", Storage::Chunk, &|i| vertex(i).scale_0);
+    column("scale_1", Storage::Chunk, &|i| vertex(i).scale_1);
+    column("scale_2", Storage::Chunk, &|i| vertex(i).scale_2);
+    column("opacity", Storage::Global, &|i| vertex(i).opacity);
+    column(ROT_LARGEST, Storage::Index, &|i| rotations[i].0 as f32);
+    for (c, name) in ROT_SMALL.iter().enumerate() {
+        column(name, Storage::Global, &|i| rotations[i].1[c]);
+    }
+    column("f_dc_0", Storage::Global, &|i| vertex(i).f_dc_0);
+    column("f_dc_1", Storage::Global, &|i| vertex(i).f_dc_1);
+    column("f_dc_2", Storage::Global, &|i| vertex(i).f_dc_2);
+    for c in 0..num_rest {
+        column(&format!("f_rest_{c}"), Storage::Global, &|i| {
+            vertex(i).rest_coeffs[c]
+        });
+    }
+
+    let range = |values: &[f32]| {
+        if values.is_empty() {
+            return (0.0, 0.0);
+        }
+        let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        (min, max)
+    };
+    let num_chunks = order.len().div_ceil(CHUNK_SIZE);
+    // The range of every chunked column, for each chunk.
+    let chunk_ranges: Vec<Vec<(f32, f32)>> = (0..num_chunks)
+        .map(|chunk| {
+            let rows = chunk * CHUNK_SIZE..((chunk + 1) * CHUNK_SIZE).min(order.len());
+            columns
+                .iter()
+                .filter(|(_, storage, _)| *storage == Storage::Chunk)
+                .map(|(_, _, values)| range(&values[rows.clone()]))
+                .collect()
+        })
+        .collect();
+
+    let int_type = if bits == 8 { "uchar" } else { "ushort" };
+    let mut header = String::from("ply\nformat binary_little_endian 1.0\n");
+    for comment in ply_comments(splats) {
+        header += &format!("comment {comment}\n");
+    }
+    header += &format!("comment {BITS_COMMENT}{bits}\n");
+    header += &format!("comment {CHUNK_COMMENT}{CHUNK_SIZE}\n");
+    let mut global_ranges = vec![];
+    for (name, storage, values) in &columns {
+        let range = range(values);
+        if *storage == Storage::Global {
+            header += &format!("comment {RANGE_COMMENT}{name} {} {}\n", range.0, range.1);
+        }
+        global_ranges.push(range);
+    }
+    header += &format!("element chunk {num_chunks}\n");
+    for name in CHUNKED {
+        header += &format!("property float min_{name}\nproperty float max_{name}\n");
+    }
+    header += &format!("element vertex {}\n", order.len());
+    for (name, storage, _) in &columns {
+        let ty = if *storage == Storage::Index {
+            "uchar"
+        } else {
+            int_type
+        };
+        header += &format!("property {ty} {name}\n");
+    }
+    header += "end_header\n";
+
+    let mut bytes = header.into_bytes();
+    for ranges in &chunk_ranges {
+        for (min, max) in ranges {
+            bytes.extend(min.to_le_bytes());
+            bytes.extend(max.to_le_bytes());
+        }
+    }
+
+    let levels = max_level(bits);
+    for i in 0..order.len() {
+        let mut chunk = chunk_ranges[i / CHUNK_SIZE].iter();
+        for ((_, storage, values), global_range) in columns.iter().zip(&global_ranges) {
+            let value = values[i];
+            let (min, max) = match storage {
+                Storage::Index => {
+                    bytes.push(value as u8);
+                    continue;
+                }
+                Storage::Chunk => *chunk.next().expect("Range of every chunked column"),
+                Storage::Global => *global_range,
+            };
+            let t = if max > min {
+                (value - min) / (max - min)
+            } else {
+                0.0
+            };
+            let level = (t * levels).round().clamp(0.0, levels);
+            if bits == 8 {
+                bytes.push(level as u8);
+            } else {
+                bytes.extend((level as u16).to_le_bytes());
+            }
+        }
+    }
+    Ok(bytes)
+}
+
+/// Read a PLY written by [`splat_to_ply_quantised`], mapping the integers back to floats.
+#[cfg(feature = "import")]
+pub async fn load_splat_from_ply_quantised<T: AsyncRead + Unpin>(
+    mut reader: T,
+) -> Result<SplatMessage, DeserializeError> {
+    let mut bytes = vec![];
+    reader
+        .read_to_end(&mut bytes)
+        .await
+        .map_err(DeserializeError::custom)?;
+    decode_quantised(&bytes)
+}
+
+/// How to read a property of a vertex.
+#[cfg(feature = "import")]
+enum Source {
+    Float,
+    Index,
+    /// Quantised to the range of the chunk, the columns of its min and max in the chunk rows.
+    Chunk(usize, usize),
+    Global(f32, f32),
+}
+
+#[cfg(feature = "import")]
+fn decode_quantised(bytes: &[u8]) -> Result<SplatMessage, DeserializeError> {
+    const END: &[u8] = b"end_header\n";
+    let header_len = bytes
+        .windows(END.len())
+        .position(|w| w == END)
+        .ok_or_else(|| DeserializeError::custom("Missing PLY header"))?
+        + END.len();
+    let header = std::str::from_utf8(&bytes[..header_len]).map_err(DeserializeError::custom)?;
+
+    let mut comments = vec![];
+    // The name, count and (type, name) properties of each element.
+    let mut elements: Vec<(&str, usize, Vec<(&str, &str)>)> = vec![];
+    for line in header.lines() {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("format") => {
+                if words.next() != Some("binary_little_endian") {
+                    return Err(DeserializeError::custom(
+                        "Quantised PLYs must be binary little endian",
+                    ));
+                }
+            }
+            Some("comment") => comments.push(line["comment ".len()..].to_owned()),
+            Some("element") => {
+                let name = words.next().unwrap_or_default();
+                if name != "vertex" && name != "chunk" {
+                    return Err(DeserializeError::custom(format!(
+                        "Unexpected element {name} in a quantised PLY"
+                    )));
+                }
+                let count = words.next().and_then(|n| n.parse::<usize>().ok());
+                let count = count.ok_or_else(|| DeserializeError::custom("Bad count"))?;
+                elements.push((name, count, vec![]));
+            }
+            Some("property") => {
+                if let (Some(ty), Some(name), Some(element)) =
+                    (words.next(), words.next(), elements.last_mut())
+                {
+                    element.2.push((ty, name));
+                }
+            }
+            _ => {}
+        }
+    }
+    let element = |wanted: &str| elements.iter().find(|(name, _, _)| *name == wanted);
+    let (_, num_splats, properties) =
+        element("vertex").ok_or_else(|| DeserializeError::custom("No vertex element"))?;
+    let num_splats = *num_splats;
+    let (num_chunks, chunk_properties) = element("chunk")
+        .map_or((0, &[][..]), |(_, count, properties)| {
+            (*count, properties.as_slice())
+        });
+    if chunk_properties.iter().any(|(ty, _)| *ty != "float") {
+        return Err(DeserializeError::custom("Chunk ranges must be floats"));
+    }
+
+    let comment_value = |prefix: &str| {
+        comments
+            .iter()
+            .find_map(|c| c.strip_prefix(prefix)?.trim().parse::<usize>().ok())
+    };
+    let bits = comment_value(BITS_COMMENT)
+        .and_then(|bits| u8::try_from(bits).ok())
+        .filter(|bits| *bits == 8 || *bits == 16)
+        .ok_or_else(|| DeserializeError::custom("Not a quantised PLY"))?;
+    let levels = max_level(bits);
+    let range = |name: &str| {
+        comments.iter().find_map(|c| {
+            let mut words = c.strip_prefix(RANGE_COMMENT)?.split_whitespace();
+            if words.next()? != name {
+                return None;
+            }
+            Some((
+                words.next()?.parse::<f32>().ok()?,
+                words.next()?.parse::<f32>().ok()?,
+            ))
+        })
+    };
+    let chunk_column = |name: String| chunk_properties.iter().position(|(_, n)| *n == name);
+
+    // How to read each property: its size and where its range comes from.
+    let mut layout = vec![];
+    for &(ty, name) in properties {
+        let size = match ty {
+            "float" => 4,
+            "uchar" => 1,
+            "ushort" => 2,
+            _ => return Err(DeserializeError::custom(format!("Unsupported type {ty}"))),
+        };
+        let source = if ty == "float" {
+            Source::Float
+        } else if name == ROT_LARGEST {
+            Source::Index
+        } else if let (Some(min), Some(max)) = (
+            chunk_column(format!("min_{name}")),
+            chunk_column(format!("max_{name}")),
+        ) {
+            Source::Chunk(min, max)
+        } else {
+            let (min, max) = range(name).ok_or_else(|| {
+                DeserializeError::custom(format!("Missing quantisation range of {name}"))
+            })?;
+            Source::Global(min, max)
+        };
+        layout.push((name, size, source));
+    }
+    let row_size: usize = layout.iter().map(|(_, size, _)| size).sum();
+    if row_size == 0 {
+        return Err(DeserializeError::custom(
+            "Quantised PLY has no vertex properties",
+        ));
+    }
+
+    let chunked = layout
+        .iter()
+        .any(|(_, _, source)| matches!(source, Source::Chunk(..)));
+    let chunk_size = if chunked {
+        let chunk_size = comment_value(CHUNK_COMMENT)
+            .filter(|size| *size > 0)
+            .ok_or_else(|| DeserializeError::custom("Missing quantised chunk size"))?;
+        if num_chunks < num_splats.div_ceil(chunk_size) {
+            return Err(DeserializeError::custom("Missing quantised chunks"));
+        }
+        chunk_size
+    } else {
+        // No property needs the chunk ranges.
+        usize::MAX
+    };
+
+    // The chunk element comes first when it's written before the vertices.
+    let chunk_row_size = 4 * chunk_properties.len();
+    let chunks_first = elements
+        .first()
+        .is_some_and(|(name, _, _)| *name == "chunk");
+    let body = &bytes[header_len..];
+    let chunks_len = chunk_row_size.saturating_mul(num_chunks);
+    let vertices_len = row_size.saturating_mul(num_splats);
+    if body.len() < chunks_len.saturating_add(vertices_len) {
+        return Err(DeserializeError::custom("Truncated quantised PLY"));
+    }
+    let (chunk_bytes, vertex_bytes) = if chunks_first {
+        let (chunks, vertices) = body.split_at(chunks_len);
+        (chunks, vertices)
+    } else {
+        let (vertices, chunks) = body.split_at(vertices_len);
+        (chunks, vertices)
+    };
+    let chunk_ranges: Vec<f32> = chunk_bytes[..chunks_len]
+        .chunks_exact(4)
+        .map(|raw| f32::from_le_bytes(raw.try_into().expect("4 bytes")))
+        .collect();
+
+    let column = |wanted: &str| layout.iter().position(|(name, _, _)| *name == wanted);
+    let num_rest = properties
+        .iter()
+        .filter(|(_, name)| name.starts_with("f_rest_"))
+        .count();
+    let mut data = SplatData {
+        means: Vec::with_capacity(num_splats * 3),
+        rotations: Some(Vec::with_capacity(num_splats * 4)),
+        log_scales: Some(Vec::with_capacity(num_splats * 3)),
+        sh_coeffs: Some(Vec::with_capacity(num_splats * (3 + num_rest))),
+        raw_opacities: Some(Vec::with_capacity(num_splats)),
+        motion: None,
+    };
+    let mut row = vec![0.0; layout.len()];
+    for (i, vertex) in vertex_bytes
+        .chunks_exact(row_size)
+        .take(num_splats)
+        .enumerate()
+    {
+        let chunk = &chunk_ranges[i / chunk_size * chunk_properties.len()..];
+        let mut offset = 0;
+        for (value, (_, size, source)) in row.iter_mut().zip(&layout) {
+            let raw = &vertex[offset..offset + size];
+            offset += size;
+            let level = match size {
+                1 => raw[0] as f32,
+                2 => u16::from_le_bytes(raw.try_into().expect("2 bytes")) as f32,
+                _ => f32::from_le_bytes(raw.try_into().expect("4 bytes")),
+            };
+            let (min, max) = match *source {
+                Source::Float | Source::Index => {
+                    *value = level;
+                    continue;
+                }
+                Source::Chunk(min, max) => (chunk[min], chunk[max]),
+                Source::Global(min, max) => (min, max),
+            };
+            *value = min + level / levels * (max - min);
+        }
+        let get = |name: &str| column(name).map_or(0.0, |c| row[c]);
+        data.means.extend([get("x"), get("y"), get("z")]);
+        if let Some(scales) = &mut data.log_scales {
+            scales.extend([get("scale_0"), get("scale_1"), get("scale_2")]);
+        }
+        if let Some(rotations) = &mut data.rotations {
+            // The largest component is positive, and makes the quaternion unit length.
+            let small = ROT_SMALL.map(get);
+            let largest = (1.0 - small.iter().map(|v| v * v).sum::<f32>())
+                .max(0.0)
+                .sqrt();
+            let index = (get(ROT_LARGEST) as usize).min(3);
+            let mut small = small.into_iter();
+            rotations.extend((0..4).map(|c| {
+                if c == index {
+                    largest
+                } else {
+                    small.next().expect("Three small components")
+                }
+            }));
+        }
+        if let Some(opacities) = &mut data.raw_opacities {
+            opacities.push(get("opacity"));
+        }
+        if let Some(coeffs) = &mut data.sh_coeffs {
+            let rest: Vec<f32> = (0..num_rest).map(|c| get(&format!("f_rest_{c}"))).collect();
+            let dc = Vec3::new(get("f_dc_0"), get("f_dc_1"), get("f_dc_2"));
+            interleave_coeffs(dc, &rest, coeffs);
+        }
+    }
+
+    let (up_axis, render_mode) = comment_metadata(&comments);
+    Ok(SplatMessage {
+        meta: ParseMetadata {
+            up_axis,
+            render_mode,
+            total_splats: num_splats as u32,
+            progress: 1.0,
+        },
+        data,
+    })
+}
+
+#[cfg(all(test, feature = "export", feature = "import"))]
+mod tests {
+    use super::*;
+    use crate::export::splat_to_ply;
+    use brush_render::MainBackend;
+    use brush_render::camera::Camera;
+    use brush_render::gaussian_splats::{SplatRenderMode, render_splats};
+    use brush_render::sh::sh_coeffs_for_degree;
+    use burn::backend::wgpu::WgpuDevice;
+    use std::io::Cursor;
+
+    /// A cloud of small splats in front of the camera, with deterministic noisy attributes.
+    fn test_scene(device: &WgpuDevice) -> Splats<MainBackend> {
+        let n = 4096;
+        let mut state = 1u32;
+        let mut noise = move || {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 8) as f32 / (1 << 24) as f32
+        };
+        let mut means = vec![];
+        for _ in 0..n {
+            means.extend([noise() * 2.0 - 1.0, noise() * 2.0 - 1.0, 4.0 + noise()]);
+        }
+        let rotations = (0..n * 4).map(|_| noise() * 2.0 - 1.0).collect();
+        let log_scales = (0..n * 3).map(|_| -3.5 + noise()).collect();
+        let coeffs = (0..n * 3 * sh_coeffs_for_degree(2) as usize)
+            .map(|_| noise() - 0.5)
+            .collect();
+        let opacities = (0..n).map(|_| noise() * 4.0 - 2.0).collect();
+        Splats::from_raw(
+            means,
+            rotations,
+            log_scales,
+            coeffs,
+            opacities,
+            SplatRenderMode::Default,
+            device,
+        )
+    }
+
+    #[tokio::test]
+    async fn quantised_round_trip() {
+        let device = WgpuDevice::default();
+        let splats = test_scene(&device);
+        let full_size = splat_to_ply(splats.clone()).await.unwrap().len();
+
+        let camera = Camera::new(
+            glam::Vec3::ZERO,
+            glam::Quat::IDENTITY,
+            0.8,
+            0.8,
+            glam::vec2(0.5, 0.5),
+        );
+        let img_size = glam::uvec2(128, 128);
+        let render = |splats: &Splats<MainBackend>| {
+            render_splats(splats, &camera, img_size, glam::Vec3::ZERO, None).0
+        };
+        let original = render(&splats);
+
+        for bits in [8, 16] {
+            let bytes = splat_to_ply_quantised(splats.clone(), bits).await.unwrap();
+            let ratio = full_size as f32 / bytes.len() as f32;
+            assert!(
+                (2.0..=4.0).contains(&ratio),
+                "{bits} bit PLY is {ratio} times smaller"
+            );
+
+            let message = load_splat_from_ply_quantised(Cursor::new(bytes))
+                .await
+                .unwrap();
+            assert_eq!(message.meta.total_splats, 4096);
+            assert_eq!(message.meta.render_mode, Some(SplatRenderMode::Default));
+            let loaded = message.data.into_splats(&device, SplatRenderMode::Default);
+            assert_eq!(loaded.sh_degree(), 2);
+
+            let mse = (render(&loaded) - original.clone())
+                .powi_scalar(2)
+                .mean()
+                .into_scalar();
+            let psnr = -10.0 * mse.max(1e-12).log10();
+            assert!(psnr > 40.0, "PSNR of {bits} bit splats is {psnr} dB");
+        }
+
+        assert!(matches!(
+            splat_to_ply_quantised(splats, 12).await,
+            Err(SaveError::UnsupportedBits(12))
+        ));
+    }
+
+    #[test]
+    fn rejects_empty_rows() {
+        let bytes = b"ply\nformat binary_little_endian 1.0\ncomment Quantised bits: 8\n\
+            element vertex 4\nend_header\n\x01\x02";
+        assert!(decode_quantised(bytes).is_err());
+    }
+}
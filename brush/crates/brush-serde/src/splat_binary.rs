@@ -0,0 +1,162 @@
+use brush_render::gaussian_splats::inverse_sigmoid;
+use brush_render::sh::rgb_to_sh;
+use glam::{Vec3, Vec4};
+use serde::de::Error;
+use serde_ply::DeserializeError;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::import::{ParseMetadata, SplatData, SplatMessage};
+
+/// Bytes per splat in a `.splat` file.
+pub const SPLAT_BINARY_STRIDE: usize = 32;
+
+/// Load a `.splat` file, the compact format of the antimatter15 web viewer.
+///
+/// Each splat takes 32 bytes: the position and scale as 3 little endian f32 each, the color
+/// and opacity as 4 u8, and the rotation as 4 u8 (w, x, y, z) mapped from `[-1, 1]` to
+/// `[0, 255]`. Colors are only stored as the base color, so the splats have SH degree 0.
+///
+/// The format has no header, magic bytes or version. To still catch wrong inputs, PLY files
+/// are rejected by their `ply` magic, and the size must be a whole number of splats.
+pub async fn load_splat_from_binary<T: AsyncRead + Unpin>(
+    mut reader: T,
+) -> Result<SplatMessage, DeserializeError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).await?;
+    decode_splat_binary(&bytes)
+}
+
+fn decode_splat_binary(bytes: &[u8]) -> Result<SplatMessage, DeserializeError> {
+    if bytes.starts_with(b"ply\n") || bytes.starts_with(b"ply\r\n") {
+        return Err(DeserializeError::custom(
+            "This is a PLY file, not a .splat file",
+        ));
+    }
+    if bytes.is_empty() || bytes.len() % SPLAT_BINARY_STRIDE != 0 {
+        return Err(DeserializeError::custom(format!(
+            "A .splat file holds {SPLAT_BINARY_STRIDE} bytes per splat, got {} bytes",
+            bytes.len()
+        )));
+    }
+
+    let num_splats = bytes.len() / SPLAT_BINARY_STRIDE;
+    let mut means = Vec::with_capacity(num_splats * 3);
+    let mut log_scales = Vec::with_capacity(num_splats * 3);
+    let mut sh_coeffs = Vec::with_capacity(num_splats * 3);
+    let mut raw_opacities = Vec::with_capacity(num_splats);
+    let mut rotations = Vec::with_capacity(num_splats * 4);
+
+    let bytes_at = |splat: &[u8], offset: usize| -> [u8; 4] {
+        splat[offset..offset + 4].try_into().expect("4 bytes")
+    };
+    let f32_at = |splat: &[u8], i: usize| f32::from_le_bytes(bytes_at(splat, i * 4));
+    for splat in bytes.chunks_exact(SPLAT_BINARY_STRIDE) {
+        means.extend((0..3).map(|i| f32_at(splat, i)));
+        log_scales.extend((3..6).map(|i| f32_at(splat, i).ln()));
+
+        let [r, g, b, a] = bytes_at(splat, 24).map(f32::from);
+        let color = Vec3::new(r, g, b) / 255.0;
+        sh_coeffs.extend(rgb_to_sh(color).to_array());
+        // Fully opaque or transparent splats would give an infinite raw opacity.
+        raw_opacities.push(inverse_sigmoid((a / 255.0).clamp(1e-4, 1.0 - 1e-4)));
+
+        let rotation =
+            Vec4::from_array(bytes_at(splat, 28).map(|v| (f32::from(v) - 128.0) / 128.0));
+        rotations.extend(rotation.normalize_or(Vec4::X).to_array());
+    }
+
+    Ok(SplatMessage {
+        meta: ParseMetadata {
+            up_axis: None,
+            render_mode: None,
+            total_splats: num_splats as u32,
+            progress: 1.0,
+        },
+        data: SplatData {
+            means,
+            rotations: Some(rotations),
+            log_scales: Some(log_scales),
+            sh_coeffs: Some(sh_coeffs),
+            raw_opacities: Some(raw_opacities),
+            motion: None,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use brush_render::shaders::SH_C0;
+    use std::io::Cursor;
+
+    fn encode(position: [f32; 3], scale: [f32; 3], rgba: [u8; 4], rot: [u8; 4]) -> Vec<u8> {
+        let mut bytes: Vec<u8> = position
+            .iter()
+            .chain(&scale)
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        bytes.extend(rgba);
+        bytes.extend(rot);
+        bytes
+    }
+
+    #[tokio::test]
+    async fn decodes_splat_binary() {
+        let mut blob = encode(
+            [1.0, -2.0, 3.5],
+            [0.5, 1.0, 2.0],
+            [255, 128, 0, 191],
+            [255, 128, 128, 128],
+        );
+        // 90° around Z: (w, z) = (0.707, 0.707).
+        blob.extend(encode(
+            [0.0; 3],
+            [0.01; 3],
+            [10, 20, 30, 64],
+            [218, 128, 128, 218],
+        ));
+        assert_eq!(blob.len(), 2 * SPLAT_BINARY_STRIDE);
+
+        let message = load_splat_from_binary(Cursor::new(blob)).await.unwrap();
+        assert_eq!(message.meta.total_splats, 2);
+        let data = message.data;
+        assert_eq!(data.num_splats(), 2);
+        assert_eq!(data.means, [1.0, -2.0, 3.5, 0.0, 0.0, 0.0]);
+
+        let log_scales = data.log_scales.unwrap();
+        for (log_scale, scale) in log_scales.iter().zip([0.5, 1.0, 2.0, 0.01, 0.01, 0.01]) {
+            assert!((log_scale.exp() - scale).abs() < 1e-6);
+        }
+
+        let sh = data.sh_coeffs.unwrap();
+        let color = (Vec3::from_slice(&sh[..3]) * SH_C0 + 0.5) * 255.0;
+        assert!(color.abs_diff_eq(Vec3::new(255.0, 128.0, 0.0), 1e-3));
+
+        let opacities = data.raw_opacities.unwrap();
+        let alpha = 1.0 / (1.0 + (-opacities[0]).exp());
+        assert!((alpha * 255.0 - 191.0).abs() < 1e-3);
+
+        let rotations = data.rotations.unwrap();
+        assert!(Vec4::from_slice(&rotations[..4]).abs_diff_eq(Vec4::X, 1e-6));
+        let half = std::f32::consts::FRAC_1_SQRT_2;
+        assert!(
+            Vec4::from_slice(&rotations[4..]).abs_diff_eq(Vec4::new(half, 0.0, 0.0, half), 1e-6)
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_splat_binary() {
+        let ply = b"ply\nformat binary_little_endian 1.0\n".to_vec();
+        assert!(load_splat_from_binary(Cursor::new(ply)).await.is_err());
+        assert!(
+            load_splat_from_binary(Cursor::new(vec![0u8; 33]))
+                .await
+                .is_err()
+        );
+        assert!(
+            load_splat_from_binary(Cursor::new(Vec::new()))
+                .await
+                .is_err()
+        );
+    }
+}
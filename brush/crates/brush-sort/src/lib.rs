@@ -230,6 +230,54 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_sorting_full_range() {
+        // Keys using the top bit are sorted as u32, not as negative i32.
+        let keys_inp: [u32; 8] = [
+            u32::MAX,
+            0,
+            1 << 31,
+            (1 << 31) - 1,
+            0xdead_beef,
+            7,
+            1 << 28,
+            u32::MAX - 1,
+        ];
+        let device = Default::default();
+        let keys = Tensor::<Backend, 1, Int>::from_ints(keys_inp.map(|k| k as i32), &device)
+            .into_primitive();
+        let values =
+            Tensor::<Backend, 1, Int>::arange(0..keys_inp.len() as i64, &device).into_primitive();
+        let num_points =
+            Tensor::<Backend, 1, Int>::from_ints([keys_inp.len() as i32], &device).into_primitive();
+        let (ret_keys, ret_values) = radix_argsort(keys, values, &num_points, 32);
+
+        let ret_keys = Tensor::<Backend, 1, Int>::from_primitive(ret_keys).into_data();
+        let ret_values = Tensor::<Backend, 1, Int>::from_primitive(ret_values).into_data();
+        let ret_keys: Vec<u32> = ret_keys
+            .as_slice::<i32>()
+            .expect("Wrong type")
+            .iter()
+            .map(|&k| k as u32)
+            .collect();
+        let ret_values = ret_values.as_slice::<i32>().expect("Wrong type");
+
+        assert_eq!(
+            ret_keys,
+            [
+                0,
+                7,
+                1 << 28,
+                (1 << 31) - 1,
+                1 << 31,
+                0xdead_beef,
+                u32::MAX - 1,
+                u32::MAX
+            ]
+        );
+        assert_eq!(ret_values, [1, 5, 6, 3, 2, 4, 7, 0]);
+    }
+
     #[test]
     fn test_sorting_big() {
         // Simulate some data as one might find for a bunch of gaussians.
@@ -6,7 +6,7 @@ use brush_dataset::scene::{sample_to_tensor_data, view_to_sample_image};
 use brush_render::camera::Camera;
 use brush_render::gaussian_splats::Splats;
 use brush_render::render_aux::RenderAux;
-use brush_render::{AlphaMode, SplatForward};
+use brush_render::{AlphaMode, RenderOptions, SplatForward};
 use burn::prelude::Backend;
 use burn::tensor::{Tensor, TensorPrimitive, s};
 use glam::Vec3;
@@ -49,6 +49,7 @@ pub fn eval_stats<B: Backend + SplatForward<B>>(
             splats.render_mode,
             Vec3::ZERO,
             true,
+            RenderOptions::default(),
         );
         (Tensor::from_primitive(TensorPrimitive::Float(img)), aux)
     };
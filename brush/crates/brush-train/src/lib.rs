@@ -32,6 +32,14 @@ pub fn splats_into_autodiff<B: Backend, BDiff: AutodiffBackend<InnerBackend = B>
     let (log_scales_id, log_scales, _) = splats.log_scales.consume();
     let (sh_coeffs_id, sh_coeffs, _) = splats.sh_coeffs.consume();
     let (raw_opacity_id, raw_opacity, _) = splats.raw_opacities.consume();
+    let features = splats.features.map(|features| {
+        let (id, features, _) = features.consume();
+        Param::initialized(id, Tensor::from_inner(features).require_grad())
+    });
+    let motion = splats.motion.map(|motion| {
+        let (id, motion, _) = motion.consume();
+        Param::initialized(id, Tensor::from_inner(motion).require_grad())
+    });
 
     Splats::<BDiff> {
         means: Param::initialized(means_id, Tensor::from_inner(means).require_grad()),
@@ -45,6 +53,8 @@ pub fn splats_into_autodiff<B: Backend, BDiff: AutodiffBackend<InnerBackend = B>
             raw_opacity_id,
             Tensor::from_inner(raw_opacity).require_grad(),
         ),
+        features,
+        motion,
         render_mode: mode,
     }
 }
@@ -10,7 +10,7 @@ use crate::{
 };
 
 use brush_dataset::scene::SceneBatch;
-use brush_render::{AlphaMode, MainBackend, gaussian_splats::Splats};
+use brush_render::{AlphaMode, MainBackend, RenderOptions, gaussian_splats::Splats};
 use brush_render::{bounding_box::BoundingBox, sh::sh_coeffs_for_degree};
 use brush_render_bwd::render_splats;
 use burn::{
@@ -126,6 +126,7 @@ impl SplatTrainer {
                 camera,
                 glam::uvec2(img_w as u32, img_h as u32),
                 background,
+                RenderOptions::default(),
             );
 
             let img = Tensor::from_primitive(TensorPrimitive::Float(diff_out.img));
@@ -0,0 +1,37 @@
+//! Build script picking the rasterizer tile size.
+//!
+//! The tile width is set by the `tile8`, `tile16` or `tile32` features, and can be overridden
+//! with the `BRUSH_TILE_SIZE` environment variable when building. It's passed on to the
+//! proc macro as `BRUSH_TILE_WIDTH`, which sets it as the `TILE_WIDTH` shader define of all
+//! kernels.
+
+const TILE_WIDTHS: [u32; 3] = [8, 16, 32];
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=BRUSH_TILE_SIZE");
+
+    let from_features: Vec<u32> = TILE_WIDTHS
+        .into_iter()
+        .filter(|w| std::env::var_os(format!("CARGO_FEATURE_TILE{w}")).is_some())
+        .collect();
+    assert!(
+        from_features.len() <= 1,
+        "Only one of the tile8, tile16 and tile32 features can be enabled, got {from_features:?}"
+    );
+
+    let tile_width = match std::env::var("BRUSH_TILE_SIZE") {
+        Ok(value) => {
+            let width = value
+                .trim()
+                .parse()
+                .ok()
+                .filter(|w| TILE_WIDTHS.contains(w));
+            width.unwrap_or_else(|| {
+                panic!("BRUSH_TILE_SIZE must be one of {TILE_WIDTHS:?}, got '{value}'")
+            })
+        }
+        Err(_) => from_features.first().copied().unwrap_or(16),
+    };
+    println!("cargo:rustc-env=BRUSH_TILE_WIDTH={tile_width}");
+}
@@ -179,6 +179,12 @@ struct IncludeInfo {
     module_name: String,
 }
 
+/// Shader defines set for every kernel. `TILE_WIDTH` comes from build.rs.
+fn global_shader_defs() -> HashMap<String, ShaderDefValue> {
+    let tile_width = env!("BRUSH_TILE_WIDTH").parse().unwrap();
+    HashMap::from([("TILE_WIDTH".to_owned(), ShaderDefValue::UInt(tile_width))])
+}
+
 fn create_composer(includes: &[IncludeInfo]) -> Result<Composer, String> {
     let mut composer = Composer::default().with_capabilities(Capabilities::all());
     for inc in includes {
@@ -186,6 +192,7 @@ fn create_composer(includes: &[IncludeInfo]) -> Result<Composer, String> {
             source: &inc.source,
             file_path: &inc.file_path,
             as_name: Some(inc.module_name.clone()),
+            shader_defs: global_shader_defs(),
             ..Default::default()
         }) {
             return Err(format!(
@@ -240,6 +247,7 @@ fn extract_shader_info(
         .make_naga_module(NagaModuleDescriptor {
             source,
             file_path: source_path,
+            shader_defs: global_shader_defs(),
             ..Default::default()
         })
         .map_err(|e| e.emit_to_string(&composer))?;
@@ -312,8 +320,9 @@ fn extract_shader_info(
 
     // Compile all define combinations
     let mut variants = Vec::new();
-    for combo in generate_define_combinations(defines) {
+    for mut combo in generate_define_combinations(defines) {
         let suffix = variant_suffix(defines, &combo);
+        combo.extend(global_shader_defs());
         let mut variant_composer = create_composer(includes)?;
         let module = variant_composer
             .make_naga_module(NagaModuleDescriptor {